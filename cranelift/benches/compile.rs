@@ -0,0 +1,119 @@
+//! Measure compile time of the IR corpus in `benches/corpus`, one phase at a time, for each
+//! supported target.
+//!
+//! The same corpus can be measured outside of `cargo bench` with `clif-util bench`.
+
+use cranelift_codegen::control::ControlPlane;
+use cranelift_codegen::isa::{self, OwnedTargetIsa};
+use cranelift_codegen::settings::{self, Configurable};
+use cranelift_codegen::timing::{self, Pass};
+use cranelift_codegen::Context;
+use cranelift_reader::{parse_functions, parse_test, ParseOptions};
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::str::FromStr;
+use target_lexicon::Triple;
+
+const CORPUS: &[(&str, &str)] = &[
+    ("small", include_str!("corpus/small.clif")),
+    ("medium", include_str!("corpus/medium.clif")),
+    ("large", include_str!("corpus/large.clif")),
+];
+
+const TRIPLES: &[&str] = &[
+    "x86_64-unknown-linux-gnu",
+    "aarch64-unknown-linux-gnu",
+    "riscv64gc-unknown-linux-gnu",
+    "s390x-unknown-linux-gnu",
+];
+
+/// Build an ISA for each target compiled into this crate.
+fn isas() -> Vec<OwnedTargetIsa> {
+    let mut flag_builder = settings::builder();
+    flag_builder.set("opt_level", "speed").unwrap();
+    let flags = settings::Flags::new(flag_builder);
+
+    TRIPLES
+        .iter()
+        .filter_map(|triple| isa::lookup(Triple::from_str(triple).unwrap()).ok())
+        .map(|builder| builder.finish(flags.clone()).unwrap())
+        .collect()
+}
+
+fn compile_benchmarks(c: &mut Criterion) {
+    for (name, source) in CORPUS {
+        c.bench_function(&format!("parse/{name}"), |b| {
+            b.iter(|| parse_test(source, ParseOptions::default()).unwrap())
+        });
+    }
+
+    for isa in isas() {
+        let isa = &*isa;
+        for (name, source) in CORPUS {
+            let funcs = parse_functions(source).unwrap();
+            let mut group = c.benchmark_group(format!("{}/{name}", isa.triple().architecture));
+
+            group.bench_function("verify", |b| {
+                let mut ctx = Context::new();
+                b.iter(|| {
+                    for func in &funcs {
+                        ctx.clear();
+                        ctx.func = func.clone();
+                        ctx.verify(isa).unwrap();
+                    }
+                })
+            });
+
+            // Legalization and the mid-end optimizations.
+            group.bench_function("optimize", |b| {
+                let mut ctx = Context::new();
+                b.iter(|| {
+                    for func in &funcs {
+                        ctx.clear();
+                        ctx.func = func.clone();
+                        ctx.optimize(isa, &mut ControlPlane::default()).unwrap();
+                    }
+                })
+            });
+
+            // Lowering, register allocation and emission of already optimized functions, each
+            // measured on its own using the pass timings the backend records.
+            let optimized = funcs
+                .iter()
+                .map(|func| {
+                    let mut ctx = Context::new();
+                    ctx.func = func.clone();
+                    ctx.optimize(isa, &mut ControlPlane::default()).unwrap();
+                    ctx
+                })
+                .collect::<Vec<_>>();
+            for (phase, pass) in [
+                ("lower", Pass::vcode_lower),
+                ("regalloc", Pass::regalloc),
+                ("emit", Pass::vcode_emit),
+            ] {
+                group.bench_function(phase, |b| {
+                    b.iter_custom(|iters| {
+                        let _ = timing::take_current();
+                        for _ in 0..iters {
+                            for ctx in &optimized {
+                                isa.compile_function(
+                                    &ctx.func,
+                                    &ctx.domtree,
+                                    false,
+                                    &mut ControlPlane::default(),
+                                )
+                                .unwrap();
+                            }
+                        }
+                        timing::take_current().get(pass)
+                    })
+                });
+            }
+
+            group.finish();
+        }
+    }
+}
+
+criterion_group!(benches, compile_benchmarks);
+criterion_main!(benches);
@@ -356,6 +356,7 @@ where
 mod tests {
     use super::*;
     use alloc::vec::Vec;
+    use core::cmp::Ordering;
     use core::mem;
 
     #[test]
@@ -593,4 +594,59 @@ mod tests {
         let mut s = dense4l(&mut f);
         s.clear(&mut f);
     }
+
+    /// Orders keys from largest to smallest.
+    struct Reverse;
+
+    impl Comparator<u32> for Reverse {
+        fn cmp(&self, a: u32, b: u32) -> Ordering {
+            b.cmp(&a)
+        }
+    }
+
+    #[test]
+    fn random_against_btreeset() {
+        use alloc::collections::BTreeSet;
+
+        // A small xorshift generator, so the test is deterministic and needs no dependencies.
+        let mut state = 0x2545_f491_u32;
+        let mut random = move |bound: u32| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state % bound
+        };
+
+        // Several sets share the same forest, so nodes freed by one set get reused by another.
+        let mut f = SetForest::<u32>::new();
+        let mut sets: Vec<(Set<u32>, BTreeSet<u32>)> =
+            (0..4).map(|_| (Set::new(), BTreeSet::new())).collect();
+
+        for round in 0..20_000 {
+            let (set, reference) = &mut sets[random(4) as usize];
+            let key = random(1000);
+            match random(10) {
+                0..=4 => assert_eq!(set.insert(key, &mut f, &Reverse), reference.insert(key)),
+                5..=7 => assert_eq!(set.remove(key, &mut f, &Reverse), reference.remove(&key)),
+                8 => assert_eq!(set.contains(key, &f, &Reverse), reference.contains(&key)),
+                _ => {
+                    let modulus = random(5) + 2;
+                    set.retain(&mut f, |k| k % modulus != 0);
+                    reference.retain(|k| k % modulus != 0);
+                }
+            }
+
+            if round % 1000 == 0 {
+                for (set, reference) in &mut sets {
+                    assert!(set.iter(&f).eq(reference.iter().rev().copied()));
+                    SetCursor::new(set, &mut f, &Reverse).verify();
+                }
+            }
+        }
+
+        for (set, reference) in &mut sets {
+            assert!(set.iter(&f).eq(reference.iter().rev().copied()));
+            set.clear(&mut f);
+        }
+    }
 }
@@ -7,7 +7,7 @@ use cranelift_codegen_shared::constant_hash;
 use crate::cdsl::camel_case;
 use crate::cdsl::formats::InstructionFormat;
 use crate::cdsl::instructions::{AllInstructions, Instruction};
-use crate::cdsl::operands::Operand;
+use crate::cdsl::operands::{Operand, OperandKindFields};
 use crate::cdsl::typevar::{TypeSet, TypeVar};
 
 use crate::error;
@@ -59,6 +59,84 @@ fn gen_formats(formats: &[Rc<InstructionFormat>], fmt: &mut Formatter) {
     });
     fmt.line("}");
     fmt.empty_line();
+
+    // Emit the operand layout of each format, for table-driven parsers and printers.
+    fmt.line("impl InstructionFormat {");
+    fmt.indent(|fmt| {
+        fmt.doc_comment("Get the number of fixed value operands, not counting any value list.");
+        fmt.line("pub fn num_value_operands(self) -> usize {");
+        fmt.indent(|fmt| {
+            let mut m = Match::new("self");
+            for format in formats {
+                m.arm_no_fields(
+                    format!("Self::{}", format.name),
+                    format.num_value_operands.to_string(),
+                );
+            }
+            fmt.add_match(m);
+        });
+        fmt.line("}");
+        fmt.empty_line();
+
+        fmt.doc_comment("Does this format keep a list of variable value operands?");
+        fmt.line("pub fn has_value_list(self) -> bool {");
+        fmt.indent(|fmt| {
+            let mut m = Match::new("self");
+            for format in formats {
+                m.arm_no_fields(
+                    format!("Self::{}", format.name),
+                    format.has_value_list.to_string(),
+                );
+            }
+            fmt.add_match(m);
+        });
+        fmt.line("}");
+        fmt.empty_line();
+
+        fmt.doc_comment("Get the number of block operands.");
+        fmt.line("pub fn num_block_operands(self) -> usize {");
+        fmt.indent(|fmt| {
+            let mut m = Match::new("self");
+            for format in formats {
+                m.arm_no_fields(
+                    format!("Self::{}", format.name),
+                    format.num_block_operands.to_string(),
+                );
+            }
+            fmt.add_match(m);
+        });
+        fmt.line("}");
+        fmt.empty_line();
+
+        fmt.doc_comment(
+            "Get the immediate operands and entity references stored in the instruction data, in \
+             operand order.",
+        );
+        fmt.line("pub fn immediates(self) -> &'static [FormatImmediate] {");
+        fmt.indent(|fmt| {
+            let mut m = Match::new("self");
+            for format in formats {
+                let fields = format
+                    .imm_fields
+                    .iter()
+                    .map(|field| {
+                        format!(
+                            "FormatImmediate {{ member: \"{}\", rust_type: \"{}\", is_entity: {} }}",
+                            field.member,
+                            field.kind.rust_type,
+                            matches!(field.kind.fields, OperandKindFields::EntityRef),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                m.arm_no_fields(format!("Self::{}", format.name), format!("&[{fields}]"));
+            }
+            fmt.add_match(m);
+        });
+        fmt.line("}");
+    });
+    fmt.line("}");
+    fmt.empty_line();
 }
 
 /// Generate the InstructionData enum.
@@ -462,6 +540,246 @@ fn gen_instruction_data_impl(formats: &[Rc<InstructionFormat>], fmt: &mut Format
             fmt.line("}");
         });
         fmt.line("}");
+        fmt.empty_line();
+
+        fmt.doc_comment(r#"
+            Clone an `InstructionData` whose lists live in `src_pool` into
+            `dst_pool`.
+
+            Entity references other than values and blocks are copied
+            unchanged, so they must be remapped by the caller if `dst_pool`
+            belongs to a different function.
+        "#);
+        fmt.line("pub fn clone_to(&self, src_pool: &ir::ValueListPool, dst_pool: &mut ir::ValueListPool) -> Self {");
+        fmt.indent(|fmt| {
+            fmt.line("let mut data = *self;");
+            fmt.line("match data {");
+            fmt.indent(|fmt| {
+                for format in formats {
+                    let mut members = vec![];
+                    let mut clones = vec![];
+                    if format.has_value_list {
+                        members.push("ref mut args");
+                        clones.push("*args = args.clone_to(src_pool, dst_pool);".to_string());
+                    }
+                    match format.num_block_operands {
+                        0 => {}
+                        1 => {
+                            members.push("ref mut destination");
+                            clones.push("*destination = destination.clone_to(src_pool, dst_pool);".to_string());
+                        }
+                        n => {
+                            members.push("ref mut blocks");
+                            for i in 0..n {
+                                clones.push(format!("blocks[{i}] = blocks[{i}].clone_to(src_pool, dst_pool);"));
+                            }
+                        }
+                    }
+                    if clones.is_empty() {
+                        continue;
+                    }
+
+                    fmtln!(fmt, "Self::{} {{ {}, .. }} => {{", format.name, members.join(", "));
+                    fmt.indent(|fmt| {
+                        for clone in &clones {
+                            fmt.line(clone);
+                        }
+                    });
+                    fmt.line("}");
+                }
+                fmt.line("_ => {}");
+            });
+            fmt.line("}");
+            fmt.line("data");
+        });
+        fmt.line("}");
+    });
+    fmt.line("}");
+}
+
+/// Generate accessors for the immediate operands of `InstructionData`.
+///
+/// For each immediate kind below, this generates a getter returning the immediate of every
+/// format that has one, and a `_mut` variant returning a mutable reference to it. New formats
+/// are covered automatically. In particular, `trap_code` returns the code of every trapping
+/// format, `uadd_overflow_trap`'s `IntAddTrap` included, not only `trap` and its conditional
+/// variants.
+fn gen_immediate_accessors(formats: &[Rc<InstructionFormat>], fmt: &mut Formatter) {
+    let accessors = [
+        (
+            "ir::immediates::Imm64",
+            "imm64",
+            "its 64-bit integer immediate",
+        ),
+        (
+            "ir::immediates::Ieee16",
+            "ieee16",
+            "its 16-bit floating point immediate",
+        ),
+        (
+            "ir::immediates::Ieee32",
+            "ieee32",
+            "its 32-bit floating point immediate",
+        ),
+        (
+            "ir::immediates::Ieee64",
+            "ieee64",
+            "its 64-bit floating point immediate",
+        ),
+        ("ir::immediates::Offset32", "offset32", "its address offset"),
+        (
+            "ir::condcodes::IntCC",
+            "cond_code",
+            "its integer condition code",
+        ),
+        (
+            "ir::condcodes::FloatCC",
+            "fp_cond_code",
+            "its floating point condition code",
+        ),
+        (
+            "ir::TrapCode",
+            "trap_code",
+            "the trap code of a `trap`, `trapz`, `trapnz` or `uadd_overflow_trap`",
+        ),
+    ];
+
+    fmt.line("impl InstructionData {");
+    fmt.indent(|fmt| {
+        for (i, (rust_type, name, what)) in accessors.into_iter().enumerate() {
+            let fields: Vec<(&str, &str)> = formats
+                .iter()
+                .filter_map(|format| {
+                    let mut members = format
+                        .imm_fields
+                        .iter()
+                        .filter(|field| field.kind.rust_type == rust_type);
+                    let member = members.next()?.member;
+                    assert!(
+                        members.next().is_none(),
+                        "format {} has several `{}` immediates",
+                        format.name,
+                        rust_type
+                    );
+                    Some((format.name, member))
+                })
+                .collect();
+
+            for is_mut in [false, true] {
+                if i != 0 || is_mut {
+                    fmt.empty_line();
+                }
+                let (method, self_ty, ret_ty, scrutinee) = if is_mut {
+                    (
+                        format!("{name}_mut"),
+                        "&mut self",
+                        format!("&mut {rust_type}"),
+                        "self",
+                    )
+                } else {
+                    (name.to_string(), "&self", rust_type.to_string(), "*self")
+                };
+                if is_mut {
+                    fmt.doc_comment(format!(
+                        "Get a mutable reference to {what}, if this instruction has one."
+                    ));
+                } else {
+                    fmt.doc_comment(format!("Get {what}, if this instruction has one."));
+                }
+                fmtln!(
+                    fmt,
+                    "pub fn {}({}) -> Option<{}> {{",
+                    method,
+                    self_ty,
+                    ret_ty
+                );
+                fmt.indent(|fmt| {
+                    fmtln!(fmt, "match {} {{", scrutinee);
+                    fmt.indent(|fmt| {
+                        for (format, member) in &fields {
+                            fmtln!(
+                                fmt,
+                                "Self::{} {{ {}, .. }} => Some({}),",
+                                format,
+                                member,
+                                member
+                            );
+                        }
+                        fmt.line("_ => None,");
+                    });
+                    fmt.line("}");
+                });
+                fmt.line("}");
+            }
+        }
+    });
+    fmt.line("}");
+}
+
+/// Generate `InstructionData::map_entity_refs`, which rewrites every entity reference stored in
+/// the immediate fields of a format through an `EntityRefMapper`.
+///
+/// Values and blocks aren't immediate fields, so they aren't covered. Any other entity kind must
+/// have a method in `EntityRefMapper`, so that a new format can't silently escape renumbering.
+fn gen_entity_ref_mapper(formats: &[Rc<InstructionFormat>], fmt: &mut Formatter) {
+    let mapper_method = |rust_type: &str| match rust_type {
+        "ir::SigRef" => "map_sig_ref",
+        "ir::FuncRef" => "map_func_ref",
+        "ir::JumpTable" => "map_jump_table",
+        "ir::GlobalValue" => "map_global_value",
+        "ir::StackSlot" => "map_stack_slot",
+        "ir::DynamicStackSlot" => "map_dynamic_stack_slot",
+        _ => panic!("no `EntityRefMapper` method for entity references of type `{rust_type}`"),
+    };
+
+    fmt.line("impl InstructionData {");
+    fmt.indent(|fmt| {
+        fmt.doc_comment(
+            r#"
+            Replace each entity reference in the immediate fields of this instruction, such as
+            its `SigRef` or `FuncRef`, by what `mapper` maps it to.
+
+            Values and blocks, including those in value lists and jump tables, are left as-is.
+        "#,
+        );
+        fmt.line("pub fn map_entity_refs(&mut self, mapper: &mut impl EntityRefMapper) {");
+        fmt.indent(|fmt| {
+            fmt.line("match self {");
+            fmt.indent(|fmt| {
+                for format in formats {
+                    let fields: Vec<_> = format
+                        .imm_fields
+                        .iter()
+                        .filter(|field| matches!(field.kind.fields, OperandKindFields::EntityRef))
+                        .collect();
+                    if fields.is_empty() {
+                        continue;
+                    }
+                    let members: Vec<_> = fields.iter().map(|field| field.member).collect();
+                    fmtln!(
+                        fmt,
+                        "Self::{} {{ {}, .. }} => {{",
+                        format.name,
+                        members.join(", ")
+                    );
+                    fmt.indent(|fmt| {
+                        for field in &fields {
+                            fmtln!(
+                                fmt,
+                                "*{} = mapper.{}(*{});",
+                                field.member,
+                                mapper_method(field.kind.rust_type),
+                                field.member
+                            );
+                        }
+                    });
+                    fmt.line("}");
+                }
+                fmt.line("_ => {}");
+            });
+            fmt.line("}");
+        });
+        fmt.line("}");
     });
     fmt.line("}");
 }
@@ -1260,6 +1578,10 @@ pub(crate) fn generate(
     fmt.empty_line();
     gen_instruction_data_impl(&formats, &mut fmt);
     fmt.empty_line();
+    gen_immediate_accessors(&formats, &mut fmt);
+    fmt.empty_line();
+    gen_entity_ref_mapper(&formats, &mut fmt);
+    fmt.empty_line();
     gen_opcodes(all_inst, &mut fmt);
     fmt.empty_line();
     gen_type_constraints(all_inst, &mut fmt);
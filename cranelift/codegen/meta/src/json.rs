@@ -0,0 +1,188 @@
+//! Export of the instruction definitions as JSON, for tools outside of Cranelift.
+//!
+//! The output is an object with a `version` field, incremented whenever the schema changes
+//! incompatibly, and an `opcodes` array with one object per line, in the same order as the
+//! `Opcode` enum. Each opcode object has the following fields:
+//!
+//! - `name`: the opcode mnemonic, as in the textual IR.
+//! - `format`: the name of its `InstructionFormat`.
+//! - `num_fixed_args`: the number of fixed value operands.
+//! - `variable_args`: whether it takes a variable number of value operands after them.
+//! - `immediates`: the immediate and entity operands, with their `name` and `kind`.
+//! - `args` and `results`: the type constraint of each fixed value operand and result. This is
+//!   a concrete type such as `"i8"`, `"same"` for the controlling type variable, the name of the
+//!   function deriving it from the controlling type variable, such as `"half_width"`, or
+//!   `"free"` for a type variable of its own.
+//! - `polymorphic`: whether it has a controlling type variable.
+//! - The boolean attributes `is_terminator`, `is_branch`, `is_call`, `is_return`, `can_load`,
+//!   `can_store`, `can_trap` and `other_side_effects`.
+
+use crate::cdsl::instructions::{AllInstructions, Instruction};
+use crate::cdsl::operands::Operand;
+use std::fmt::Write;
+
+/// The version of the schema of the exported JSON.
+const VERSION: u32 = 1;
+
+/// Describe all of the instructions in `all_inst` as JSON.
+pub(crate) fn export(all_inst: &AllInstructions) -> String {
+    let mut out = format!("{{\n  \"version\": {VERSION},\n  \"opcodes\": [\n");
+    for (i, inst) in all_inst.iter().enumerate() {
+        let separator = if i + 1 < all_inst.len() { "," } else { "" };
+        writeln!(out, "    {}{}", opcode(inst), separator).unwrap();
+    }
+    out.push_str("  ]\n}\n");
+    out
+}
+
+/// Describe `inst` as a single-line JSON object.
+fn opcode(inst: &Instruction) -> String {
+    let values = |operands: &[Operand]| {
+        let constraints: Vec<_> = operands
+            .iter()
+            .filter(|op| op.is_value())
+            .map(|op| string(&constraint(inst, op)))
+            .collect();
+        format!("[{}]", constraints.join(", "))
+    };
+    let immediates: Vec<_> = inst
+        .operands_in
+        .iter()
+        .filter(|op| op.is_immediate_or_entityref())
+        .map(|op| {
+            // The kind is the last component of the Rust type, such as `Imm64` or `IntCC`.
+            let kind = op.kind.rust_type.rsplit("::").next().unwrap();
+            format!(
+                "{{\"name\": {}, \"kind\": {}}}",
+                string(op.name),
+                string(kind)
+            )
+        })
+        .collect();
+
+    let mut out = format!(
+        "{{\"name\": {}, \"format\": {}, \"num_fixed_args\": {}, \"variable_args\": {}, \
+         \"immediates\": [{}], \"args\": {}, \"results\": {}, \"polymorphic\": {}",
+        string(&inst.name),
+        string(inst.format.name),
+        inst.value_opnums.len(),
+        inst.operands_in.iter().any(|op| op.is_varargs()),
+        immediates.join(", "),
+        values(&inst.operands_in),
+        values(&inst.operands_out),
+        inst.polymorphic_info.is_some(),
+    );
+    for (name, value) in [
+        ("is_terminator", inst.is_terminator),
+        ("is_branch", inst.is_branch),
+        ("is_call", inst.is_call),
+        ("is_return", inst.is_return),
+        ("can_load", inst.can_load),
+        ("can_store", inst.can_store),
+        ("can_trap", inst.can_trap),
+        ("other_side_effects", inst.other_side_effects),
+    ] {
+        write!(out, ", \"{name}\": {value}").unwrap();
+    }
+    out.push('}');
+    out
+}
+
+/// Get the type constraint of the value operand `op` of `inst`.
+fn constraint(inst: &Instruction, op: &Operand) -> String {
+    let type_var = op.type_var().unwrap();
+    if let Some(ty) = type_var.singleton_type() {
+        return ty.to_string();
+    }
+    let ctrl_typevar = inst
+        .polymorphic_info
+        .as_ref()
+        .map(|poly| &poly.ctrl_typevar);
+    if let Some(base) = &type_var.base {
+        if Some(&base.type_var) == ctrl_typevar {
+            return base.derived_func.name().to_string();
+        }
+    }
+    if Some(type_var) == ctrl_typevar {
+        "same".to_string()
+    } else {
+        "free".to_string()
+    }
+}
+
+/// Quote `s` as a JSON string.
+fn string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' | '\\' => write!(out, "\\{c}").unwrap(),
+            c if c.is_control() => write!(out, "\\u{:04x}", u32::from(c)).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared;
+
+    #[test]
+    fn export_opcodes() {
+        let json = export(&shared::define().all_instructions);
+        let lines: Vec<_> = json.lines().collect();
+        assert_eq!(lines[..3], ["{", "  \"version\": 1,", "  \"opcodes\": ["]);
+        assert_eq!(lines[lines.len() - 2..], ["  ]", "}"]);
+
+        let entry = |name: &str| {
+            let prefix = format!("    {{\"name\": \"{name}\", ");
+            let line = lines.iter().find(|line| line.starts_with(&prefix)).unwrap();
+            line.trim().trim_end_matches(',').to_string()
+        };
+        assert_eq!(
+            entry("iadd"),
+            "{\"name\": \"iadd\", \"format\": \"Binary\", \"num_fixed_args\": 2, \
+             \"variable_args\": false, \"immediates\": [], \"args\": [\"same\", \"same\"], \
+             \"results\": [\"same\"], \"polymorphic\": true, \"is_terminator\": false, \
+             \"is_branch\": false, \"is_call\": false, \"is_return\": false, \
+             \"can_load\": false, \"can_store\": false, \"can_trap\": false, \
+             \"other_side_effects\": false}"
+        );
+        assert_eq!(
+            entry("icmp_imm"),
+            "{\"name\": \"icmp_imm\", \"format\": \"IntCompareImm\", \"num_fixed_args\": 1, \
+             \"variable_args\": false, \"immediates\": [{\"name\": \"Cond\", \"kind\": \"IntCC\"}, \
+             {\"name\": \"Y\", \"kind\": \"Imm64\"}], \"args\": [\"same\"], \
+             \"results\": [\"i8\"], \"polymorphic\": true, \"is_terminator\": false, \
+             \"is_branch\": false, \"is_call\": false, \"is_return\": false, \
+             \"can_load\": false, \"can_store\": false, \"can_trap\": false, \
+             \"other_side_effects\": false}"
+        );
+        assert_eq!(
+            entry("jump"),
+            "{\"name\": \"jump\", \"format\": \"Jump\", \"num_fixed_args\": 0, \
+             \"variable_args\": false, \"immediates\": [{\"name\": \"block_call\", \
+             \"kind\": \"BlockCall\"}], \"args\": [], \"results\": [], \
+             \"polymorphic\": false, \"is_terminator\": true, \"is_branch\": true, \
+             \"is_call\": false, \"is_return\": false, \"can_load\": false, \
+             \"can_store\": false, \"can_trap\": false, \"other_side_effects\": false}"
+        );
+        assert_eq!(
+            entry("load"),
+            "{\"name\": \"load\", \"format\": \"Load\", \"num_fixed_args\": 1, \
+             \"variable_args\": false, \"immediates\": [{\"name\": \"MemFlags\", \
+             \"kind\": \"MemFlags\"}, {\"name\": \"Offset\", \"kind\": \"Offset32\"}], \
+             \"args\": [\"free\"], \"results\": [\"same\"], \"polymorphic\": true, \
+             \"is_terminator\": false, \"is_branch\": false, \"is_call\": false, \
+             \"is_return\": false, \"can_load\": true, \"can_store\": false, \
+             \"can_trap\": false, \"other_side_effects\": false}"
+        );
+    }
+
+    #[test]
+    fn quote_strings() {
+        assert_eq!(string("a\"b\\c\n"), "\"a\\\"b\\\\c\\u000a\"");
+    }
+}
@@ -17,6 +17,7 @@ mod gen_settings;
 mod gen_types;
 
 mod constant_hash;
+mod json;
 mod shared;
 mod unique_table;
 
@@ -28,6 +29,14 @@ pub fn isa_from_arch(arch: &str) -> Result<isa::Isa, String> {
     isa::Isa::from_arch(arch).ok_or_else(|| format!("no supported isa found for arch `{arch}`"))
 }
 
+/// Describe all of the instructions as JSON, for tools outside of Cranelift.
+///
+/// This is built from the same definitions as the generated Rust sources. See the `json` module
+/// for the schema.
+pub fn export_json() -> String {
+    json::export(&shared::define().all_instructions)
+}
+
 /// Generates all the Rust source files used in Cranelift from the meta-language.
 pub fn generate_rust(isas: &[isa::Isa], out_dir: &std::path::Path) -> Result<(), error::Error> {
     let shared_defs = shared::define();
@@ -33,6 +33,8 @@ pub(crate) struct Formats {
     pub(crate) ternary: Rc<InstructionFormat>,
     pub(crate) ternary_imm8: Rc<InstructionFormat>,
     pub(crate) trap: Rc<InstructionFormat>,
+    pub(crate) try_call: Rc<InstructionFormat>,
+    pub(crate) try_call_indirect: Rc<InstructionFormat>,
     pub(crate) unary: Rc<InstructionFormat>,
     pub(crate) unary_const: Rc<InstructionFormat>,
     pub(crate) unary_global_value: Rc<InstructionFormat>,
@@ -133,6 +135,19 @@ impl Formats {
                 .varargs()
                 .build(),
 
+            try_call: Builder::new("TryCall")
+                .imm(&entities.func_ref)
+                .imm(&entities.jump_table)
+                .varargs()
+                .build(),
+
+            try_call_indirect: Builder::new("TryCallIndirect")
+                .imm(&entities.sig_ref)
+                .imm(&entities.jump_table)
+                .value()
+                .varargs()
+                .build(),
+
             func_addr: Builder::new("FuncAddr").imm(&entities.func_ref).build(),
 
             atomic_rmw: Builder::new("AtomicRmw")
@@ -281,6 +281,61 @@ fn define_control_flow(
         .call(),
     );
 
+    ig.push(
+        Inst::new(
+            "try_call",
+            r#"
+        Direct function call that may unwind.
+
+        Call a function like `call`, then continue at the normal block if it
+        returns, or at the exception block if it unwinds. Both destinations
+        are kept in the jump table ``JT``: its default block is the normal
+        block, and its single entry is the exception block.
+
+        The instruction has no results. The return values of the call are
+        passed to the first parameters of the normal block instead, and the
+        exception value to the first parameter of the exception block. The
+        block call arguments fill the parameters after them.
+
+        This instruction is a block terminator.
+        "#,
+            &formats.try_call,
+        )
+        .operands_in(vec![
+            Operand::new("FN", &entities.func_ref)
+                .with_doc("function to call, declared by `function`"),
+            Operand::new("JT", &entities.jump_table)
+                .with_doc("the normal block, then the exception block"),
+            Operand::new("args", &entities.varargs).with_doc("call arguments"),
+        ])
+        .branches()
+        .call(),
+    );
+
+    ig.push(
+        Inst::new(
+            "try_call_indirect",
+            r#"
+        Indirect function call that may unwind.
+
+        Call the function pointed to by `callee` like `call_indirect`, with
+        the normal and exception destinations of `try_call`.
+
+        This instruction is a block terminator.
+        "#,
+            &formats.try_call_indirect,
+        )
+        .operands_in(vec![
+            Operand::new("SIG", &entities.sig_ref).with_doc("function signature"),
+            Operand::new("JT", &entities.jump_table)
+                .with_doc("the normal block, then the exception block"),
+            Operand::new("callee", iAddr).with_doc("address of function to call"),
+            Operand::new("args", &entities.varargs).with_doc("call arguments"),
+        ])
+        .branches()
+        .call(),
+    );
+
     ig.push(
         Inst::new(
             "func_addr",
@@ -67,6 +67,110 @@ pub(crate) fn define() -> SettingGroup {
         true,
     );
 
+    settings.add_bool(
+        "enable_simplify_cfg",
+        "Clean up the control flow graph before and after the egraph pass.",
+        r#"
+            This folds branches on constants, merges straight-line blocks and
+            removes empty and unreachable blocks. Only effective when
+            `opt_level` is `speed` or `speed_and_size`.
+        "#,
+        false,
+    );
+
+    settings.add_bool(
+        "enable_if_conversion",
+        "Replace small diamonds in the control flow graph by `select`s.",
+        r#"
+            Both arms of a diamond are executed unconditionally when they are
+            short and free of side effects, and a `select` picks the value
+            of the arm that was taken. Only effective when `opt_level` is
+            `speed` or `speed_and_size`.
+        "#,
+        false,
+    );
+
+    settings.add_bool(
+        "enable_tail_duplication",
+        "Copy small blocks into the predecessors that jump to them.",
+        r#"
+            This removes join points in front of small blocks by duplicating
+            their code, which gives the egraph pass longer straight-line
+            sequences to work with at the cost of larger code. Only effective
+            when `opt_level` is `speed`.
+        "#,
+        false,
+    );
+
+    settings.add_bool(
+        "enable_div_const_folding",
+        "Replace integer divisions by constants with multiplications and shifts.",
+        r#"
+            `udiv` and `sdiv` of 32- and 64-bit integers by a constant are
+            rewritten before legalization, using a multiplication by a
+            "magic number" or only shifts for powers of two. This makes the
+            code larger, so it is only effective when `opt_level` is `speed`.
+        "#,
+        false,
+    );
+
+    settings.add_bool(
+        "enable_licm_sinking",
+        "Sink loop-invariant loads whose results are only used after the loop.",
+        r#"
+            Such loads are moved to the exit of their loop after the egraph
+            pass, so they are executed once instead of on every iteration.
+            Only effective when `opt_level` is `speed` or `speed_and_size`.
+        "#,
+        false,
+    );
+
+    settings.add_bool(
+        "enable_code_sinking",
+        "Sink pure instructions towards their only use.",
+        r#"
+            After the egraph pass, an instruction whose result is only used
+            in a block it dominates is moved right before that use, unless
+            that would move it into a loop. Only effective when `opt_level`
+            is `speed` or `speed_and_size`.
+        "#,
+        false,
+    );
+
+    settings.add_bool(
+        "enable_load_merging",
+        "Merge runs of adjacent scalar loads into vector loads.",
+        r#"
+            This runs after the egraph pass on targets with vector support.
+            Only effective when `opt_level` is `speed` or `speed_and_size`.
+        "#,
+        false,
+    );
+
+    settings.add_bool(
+        "enable_instruction_scheduling",
+        "Reorder the instructions in each block to hide their latencies.",
+        r#"
+            This runs a list scheduler over each block after the other
+            optimizations, using the target's instruction latencies. Only
+            effective when `opt_level` is `speed` or `speed_and_size`.
+        "#,
+        false,
+    );
+
+    settings.add_bool(
+        "enable_cold_block_outlining",
+        "Move cold blocks to the end of the function.",
+        r#"
+            Blocks that end in a `trap`, and blocks that are already marked
+            cold, are moved after all of the other blocks once the other
+            optimizations are done, so they don't take up instruction cache
+            space between hot blocks. Only effective when `opt_level` is
+            `speed` or `speed_and_size`.
+        "#,
+        false,
+    );
+
     settings.add_bool(
         "enable_verifier",
         "Run the Cranelift IR verifier at strategic times during compilation.",
@@ -398,6 +502,67 @@ pub(crate) fn define() -> SettingGroup {
         0,
     );
 
+    settings.add_num(
+        "loop_align_log2",
+        "The log2 of the alignment of loop headers, in bytes",
+        r#"
+            When `opt_level` is `speed`, the first block of each loop is
+            padded with no-op instructions so that its offset is a multiple
+            of two raised to the power of this value. Each function that
+            contains an aligned loop is aligned to at least as much. Values
+            above 6 are treated as 6, which aligns loops to 64 bytes.
+
+            The default for this option is 0 to leave loops unaligned.
+        "#,
+        0,
+    );
+
+    settings.add_num(
+        "max_insts_log2",
+        "The log2 of the maximum number of instructions in a function.",
+        r#"
+            Functions with `2^max_insts_log2` or more instructions are rejected
+            with `CodegenError::ImplLimitExceeded` before any other compilation
+            work is done. Values larger than 24 are clamped to 24, which is the
+            limit imposed by the in-memory representation of the IR.
+        "#,
+        24,
+    );
+
+    settings.add_num(
+        "max_blocks_log2",
+        "The log2 of the maximum number of basic blocks in a function.",
+        r#"
+            Functions with `2^max_blocks_log2` or more blocks are rejected with
+            `CodegenError::ImplLimitExceeded`. Values larger than 24 are clamped
+            to 24.
+        "#,
+        24,
+    );
+
+    settings.add_num(
+        "max_values_log2",
+        "The log2 of the maximum number of SSA values in a function.",
+        r#"
+            Functions with `2^max_values_log2` or more values are rejected with
+            `CodegenError::ImplLimitExceeded`. Values larger than 24 are clamped
+            to 24.
+        "#,
+        24,
+    );
+
+    settings.add_num(
+        "max_params_log2",
+        "The log2 of the maximum number of block parameters and call arguments.",
+        r#"
+            Blocks with `2^max_params_log2` or more parameters, and calls with
+            that many arguments, are rejected with
+            `CodegenError::ImplLimitExceeded`. Values larger than 16 are clamped
+            to 16.
+        "#,
+        16,
+    );
+
     // When adding new settings please check if they can also be added
     // in cranelift/fuzzgen/src/lib.rs for fuzzing.
     settings.build()
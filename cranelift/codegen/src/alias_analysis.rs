@@ -206,7 +206,7 @@ impl<'a> AliasAnalysis<'a> {
     fn compute_block_input_states(&mut self, func: &Function) {
         let mut queue = vec![];
         let mut queue_set = FxHashSet::default();
-        let entry = func.layout.entry_block().unwrap();
+        let entry = func.entry_block();
         queue.push(entry);
         queue_set.insert(entry);
 
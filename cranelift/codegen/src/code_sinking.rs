@@ -0,0 +1,185 @@
+//! Sinking of instructions towards their only use.
+//!
+//! A pure instruction whose result is only used in a block it dominates keeps its result live
+//! on every path from the definition, even the ones that never reach the use. Moving the
+//! instruction right before its use shortens the live range and skips the computation on those
+//! other paths:
+//!
+//! ```plain
+//! block0(v0: i32, v1: i32):
+//!     v2 = imul v0, v1
+//!     brif v0, block1, block2
+//! block1:
+//!     return v2
+//! block2:
+//!     return v1
+//! ```
+//!
+//! becomes
+//!
+//! ```plain
+//! block0(v0: i32, v1: i32):
+//!     brif v0, block1, block2
+//! block1:
+//!     v2 = imul v0, v1
+//!     return v2
+//! block2:
+//!     return v1
+//! ```
+//!
+//! Instructions are never sunk into a loop they aren't already in, since that would execute them
+//! on every iteration. Results with several uses stay where they are, even if all the uses are in
+//! the same block. This complements LICM, which hoists instructions out of loops.
+
+use crate::dominator_tree::DominatorTree;
+use crate::entity::SecondaryMap;
+use crate::inst_predicates::is_pure_for_egraph;
+use crate::ir::{Block, Function, Inst};
+use crate::loop_analysis::LoopAnalysis;
+use crate::packed_option::PackedOption;
+use crate::{timing, trace};
+use alloc::vec::Vec;
+
+/// Move the pure instructions of `func` whose result has a single use in another block they
+/// dominate right before that use. Returns `true` if any instruction was moved.
+///
+/// `domtree` and `loop_analysis` must be valid, and stay valid since only instructions are moved.
+pub fn sink_instructions(
+    func: &mut Function,
+    domtree: &DominatorTree,
+    loop_analysis: &LoopAnalysis,
+) -> bool {
+    let _tt = timing::code_sinking();
+
+    // The number of uses of each value, and the instruction using it if there is only one.
+    let mut num_uses = SecondaryMap::<_, u32>::new();
+    let mut user = SecondaryMap::<_, PackedOption<Inst>>::new();
+    for block in func.layout.blocks() {
+        for inst in func.layout.block_insts(block) {
+            for arg in func.dfg.inst_values(inst) {
+                let arg = func.dfg.resolve_aliases(arg);
+                num_uses[arg] += 1;
+                user[arg] = inst.into();
+            }
+        }
+    }
+
+    // Visit the uses before the definitions, so chains of instructions sink together.
+    let mut changed = false;
+    for &block in domtree.cfg_postorder() {
+        let insts: Vec<Inst> = func.layout.block_insts(block).collect();
+        for inst in insts.into_iter().rev() {
+            if !is_pure_for_egraph(func, inst) {
+                continue;
+            }
+            let result = func.dfg.first_result(inst);
+            if num_uses[result] != 1 {
+                continue;
+            }
+            let use_inst = user[result].unwrap();
+            let use_block = func.layout.inst_block(use_inst).unwrap();
+            if use_block == block
+                || !domtree.dominates(block, use_block, &func.layout)
+                || enters_loop(loop_analysis, block, use_block)
+            {
+                continue;
+            }
+
+            trace!("Sinking {} from {} to {}", inst, block, use_block);
+            func.layout.remove_inst(inst);
+            func.layout.insert_inst(inst, use_inst);
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Is `to` in a loop that doesn't contain `from`?
+fn enters_loop(loop_analysis: &LoopAnalysis, from: Block, to: Block) -> bool {
+    let mut lp = loop_analysis.innermost_loop(to);
+    while let Some(l) = lp {
+        if !loop_analysis.is_in_loop(from, l) {
+            return true;
+        }
+        lp = loop_analysis.loop_parent(l);
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cursor::{Cursor, FuncCursor};
+    use crate::flowgraph::ControlFlowGraph;
+    use crate::ir::{types, AbiParam, InstBuilder};
+    use crate::settings;
+    use crate::verifier::verify_function;
+
+    fn sink(func: &mut Function) -> bool {
+        let cfg = ControlFlowGraph::with_function(func);
+        let domtree = DominatorTree::with_function(func, &cfg);
+        let mut loop_analysis = LoopAnalysis::new();
+        loop_analysis.compute(func, &cfg, &domtree);
+        let changed = sink_instructions(func, &domtree, &loop_analysis);
+        let flags = settings::Flags::new(settings::builder());
+        verify_function(&*func, &flags).unwrap();
+        changed
+    }
+
+    #[test]
+    fn sink_chain() {
+        let mut func = Function::new();
+        func.signature.params.push(AbiParam::new(types::I32));
+        func.signature.returns.push(AbiParam::new(types::I32));
+        let [block0, block1, block2] = [(); 3].map(|_| func.dfg.make_block());
+        let v0 = func.dfg.append_block_param(block0, types::I32);
+
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let v1 = pos.ins().iconst(types::I32, 3);
+        let v2 = pos.ins().imul(v0, v1);
+        let v3 = pos.ins().iadd(v0, v0);
+        pos.ins().brif(v0, block1, &[], block2, &[]);
+        pos.insert_block(block1);
+        let ret = pos.ins().return_(&[v2]);
+        pos.insert_block(block2);
+        let v4 = pos.ins().iadd(v3, v3);
+        pos.ins().return_(&[v4]);
+
+        let mul = func.dfg.value_def(v2).unwrap_inst();
+        let iconst = func.dfg.value_def(v1).unwrap_inst();
+        let add = func.dfg.value_def(v3).unwrap_inst();
+        assert!(sink(&mut func));
+        assert_eq!(
+            func.layout.block_insts(block1).collect::<Vec<_>>(),
+            [iconst, mul, ret]
+        );
+        // `v3` has two uses, so it stays in `block0`.
+        assert_eq!(func.layout.inst_block(add), Some(block0));
+        assert!(!sink(&mut func));
+    }
+
+    #[test]
+    fn keep_out_of_loop() {
+        let mut func = Function::new();
+        func.signature.params.push(AbiParam::new(types::I32));
+        func.signature.returns.push(AbiParam::new(types::I32));
+        let [block0, block1, block2] = [(); 3].map(|_| func.dfg.make_block());
+        let v0 = func.dfg.append_block_param(block0, types::I32);
+        let v1 = func.dfg.append_block_param(block1, types::I32);
+
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let v2 = pos.ins().imul(v0, v0);
+        pos.ins().jump(block1, &[v0]);
+        pos.insert_block(block1);
+        let v3 = pos.ins().isub(v1, v2);
+        pos.ins().brif(v3, block1, &[v3], block2, &[]);
+        pos.insert_block(block2);
+        pos.ins().return_(&[v1]);
+
+        assert!(!sink(&mut func));
+        let mul = func.dfg.value_def(v2).unwrap_inst();
+        assert_eq!(func.layout.inst_block(mul), Some(block0));
+    }
+}
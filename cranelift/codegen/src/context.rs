@@ -10,31 +10,49 @@
 //! single ISA instance.
 
 use crate::alias_analysis::AliasAnalysis;
+use crate::code_sinking::sink_instructions;
+use crate::div_const::fold_divs_by_constants;
 use crate::dominator_tree::DominatorTree;
 use crate::egraph::EgraphPass;
 use crate::flowgraph::ControlFlowGraph;
-use crate::ir::Function;
+use crate::if_conversion::if_convert;
+use crate::impl_limits::check_impl_limits;
+use crate::ir::{Block, Function};
 use crate::isa::TargetIsa;
 use crate::legalizer::simple_legalize;
+use crate::licm_sinking::sink_loop_invariant_loads;
 use crate::loop_analysis::LoopAnalysis;
 use crate::machinst::{CompiledCode, CompiledCodeStencil};
 use crate::nan_canonicalization::do_nan_canonicalization;
+use crate::outline_cold::{outline_cold, trapping_blocks};
 use crate::remove_constant_phis::do_remove_constant_phis;
 use crate::result::{CodegenResult, CompileResult};
+use crate::scheduling::list_schedule;
 use crate::settings::{FlagsOrIsa, OptLevel};
+use crate::simplify_cfg::simplify_cfg;
+use crate::taildup::{self, duplicate_tails};
 use crate::trace;
-use crate::unreachable_code::eliminate_unreachable_code;
+use crate::unreachable_code::{
+    cleanup_unreachable, eliminate_unreachable_code, remove_unreachable_blocks,
+};
+use crate::vectorize_memory::merge_loads;
 use crate::verifier::{verify_context, VerifierErrors, VerifierResult};
 use crate::{timing, CompileError};
+use alloc::boxed::Box;
 #[cfg(feature = "souper-harvest")]
 use alloc::string::String;
 use alloc::vec::Vec;
 use cranelift_control::ControlPlane;
-use target_lexicon::Architecture;
 
 #[cfg(feature = "souper-harvest")]
 use crate::souper_harvest::do_souper_harvest;
 
+/// A callback that is given the name of each pass in the compilation pipeline and the function
+/// right after that pass has run.
+///
+/// See `Context::set_pass_observer`.
+pub type PassObserver = Box<dyn FnMut(&str, &Function) + Send>;
+
 /// Persistent data structures and compilation pipeline.
 pub struct Context {
     /// The function we're compiling.
@@ -54,6 +72,9 @@ pub struct Context {
 
     /// Flag: do we want a disassembly with the CompiledCode?
     pub want_disasm: bool,
+
+    /// Callback to observe the function after each pass, if any.
+    pass_observer: Option<PassObserver>,
 }
 
 impl Context {
@@ -77,6 +98,7 @@ impl Context {
             loop_analysis: LoopAnalysis::new(),
             compiled_code: None,
             want_disasm: false,
+            pass_observer: None,
         }
     }
 
@@ -108,6 +130,31 @@ impl Context {
         self.want_disasm = val;
     }
 
+    /// Register a callback to observe the function after each pass that transforms it.
+    ///
+    /// This makes it possible to capture intermediate IR programmatically, e.g. to print the
+    /// function after a particular pass. The observer is kept when the context is cleared.
+    pub fn set_pass_observer(&mut self, observer: PassObserver) {
+        self.pass_observer = Some(observer);
+    }
+
+    /// Remove the pass observer, returning it if there was one.
+    pub fn take_pass_observer(&mut self) -> Option<PassObserver> {
+        self.pass_observer.take()
+    }
+
+    /// Report that the pass `name` has finished transforming the function.
+    ///
+    /// This is called before verifying the result of the pass, so observers get to see invalid
+    /// IR produced by a buggy pass.
+    fn after_pass(&mut self, name: &str) {
+        log::debug!("Finished pass {name}");
+        trace!("After {name}:\n{}", self.func.display());
+        if let Some(observer) = &mut self.pass_observer {
+            observer(name, &self.func);
+        }
+    }
+
     /// Compile the function, and emit machine code into a `Vec<u8>`.
     #[deprecated = "use Context::compile"]
     pub fn compile_and_emit(
@@ -131,6 +178,7 @@ impl Context {
     ) -> CodegenResult<CompiledCodeStencil> {
         let _tt = timing::compile();
 
+        self.check_impl_limits(isa)?;
         self.verify_if(isa)?;
 
         self.optimize(isa, ctrl_plane)?;
@@ -142,6 +190,15 @@ impl Context {
     /// but not including machine-code lowering and register
     /// allocation.
     ///
+    /// With `opt_level` set to `none`, this only runs the passes needed
+    /// to lower the function: legalization, unreachable code elimination
+    /// and constant-phi removal. With `speed` and `speed_and_size`, it
+    /// also runs the egraph pass, which performs GVN, LICM, DCE and the
+    /// mid-end rewrite rules. Passes that are off by default, such as tail
+    /// duplication, run around the egraph pass when their `enable_*`
+    /// setting is set. The ones that grow the code, tail duplication and
+    /// division-by-constant folding, are skipped with `speed_and_size`.
+    ///
     /// Public only for testing purposes.
     pub fn optimize(
         &mut self,
@@ -169,6 +226,12 @@ impl Context {
             self.canonicalize_nans(isa)?;
         }
 
+        // Fold divisions before legalization, which expands the `_imm` instructions that the
+        // folding introduces.
+        if opt_level == OptLevel::Speed && isa.flags().enable_div_const_folding() {
+            self.fold_divs_by_constants(isa)?;
+        }
+
         self.legalize(isa)?;
 
         self.compute_domtree();
@@ -178,12 +241,57 @@ impl Context {
         self.func.dfg.resolve_all_aliases();
 
         if opt_level != OptLevel::None {
+            let flags = isa.flags();
+            if flags.enable_simplify_cfg() {
+                self.simplify_cfg(isa)?;
+            }
+            if flags.enable_if_conversion() {
+                self.if_convert(isa)?;
+            }
+            if opt_level == OptLevel::Speed && flags.enable_tail_duplication() {
+                self.duplicate_tails(isa)?;
+            }
+            self.revalidate();
+
             self.egraph_pass(isa, ctrl_plane)?;
+
+            if flags.enable_simplify_cfg() {
+                self.simplify_cfg(isa)?;
+            }
+            if flags.enable_licm_sinking() {
+                self.sink_loop_invariant_loads(isa)?;
+            }
+            if flags.enable_code_sinking() {
+                self.sink_instructions(isa)?;
+            }
+            if flags.enable_load_merging() {
+                self.merge_loads(isa)?;
+            }
+            if flags.enable_instruction_scheduling() {
+                self.list_schedule(isa)?;
+            }
+            if flags.enable_cold_block_outlining() {
+                let cold_blocks = trapping_blocks(&self.func);
+                self.outline_cold(&cold_blocks, isa)?;
+            }
+            self.revalidate();
         }
 
         Ok(())
     }
 
+    /// Resolve the aliases introduced by the optional passes in `optimize`, and recompute the
+    /// CFG and dominator tree if they invalidated them.
+    fn revalidate(&mut self) {
+        self.func.dfg.resolve_all_aliases();
+        if !self.cfg.is_valid() {
+            self.compute_cfg();
+        }
+        if !self.domtree.is_valid() {
+            self.compute_domtree();
+        }
+    }
+
     /// Compile the function,
     ///
     /// Run the function through all the passes necessary to generate
@@ -248,6 +356,13 @@ impl Context {
         }
     }
 
+    /// Check that the function is within the configured implementation limits.
+    ///
+    /// Returns `CodegenError::ImplLimitExceeded` for functions that are too large to compile.
+    pub fn check_impl_limits<'a, FOI: Into<FlagsOrIsa<'a>>>(&self, fisa: FOI) -> CodegenResult<()> {
+        check_impl_limits(&self.func, fisa.into().flags)
+    }
+
     /// Run the verifier only if the `enable_verifier` setting is true.
     pub fn verify_if<'a, FOI: Into<FlagsOrIsa<'a>>>(&self, fisa: FOI) -> CodegenResult<()> {
         let fisa = fisa.into();
@@ -263,24 +378,65 @@ impl Context {
         fisa: FOI,
     ) -> CodegenResult<()> {
         do_remove_constant_phis(&mut self.func, &mut self.domtree);
+        self.after_pass("remove_constant_phis");
         self.verify_if(fisa)?;
         Ok(())
     }
 
     /// Perform NaN canonicalizing rewrites on the function.
     pub fn canonicalize_nans(&mut self, isa: &dyn TargetIsa) -> CodegenResult<()> {
-        // Currently only RiscV64 is the only arch that may not have vector support.
-        let has_vector_support = match isa.triple().architecture {
-            Architecture::Riscv64(_) => match isa.isa_flags().iter().find(|f| f.name == "has_v") {
-                Some(value) => value.as_bool().unwrap_or(false),
-                None => false,
-            },
-            _ => true,
-        };
-        do_nan_canonicalization(&mut self.func, has_vector_support);
+        do_nan_canonicalization(&mut self.func, isa.has_vector_support());
+        self.after_pass("nan_canonicalization");
         self.verify_if(isa)
     }
 
+    /// Merge runs of adjacent scalar loads into vector loads. Returns `true` if anything changed.
+    ///
+    /// No blocks are changed, so the CFG, dominator tree and loop analysis stay valid.
+    pub fn merge_loads(&mut self, isa: &dyn TargetIsa) -> CodegenResult<bool> {
+        let changed = merge_loads(&mut self.func, isa);
+        self.after_pass("vectorize_memory");
+        self.verify_if(isa)?;
+        Ok(changed)
+    }
+
+    /// Reorder the instructions in each block to hide their latencies on `isa`. Returns `true` if
+    /// anything changed.
+    ///
+    /// Instructions only move within their blocks, so the CFG, dominator tree and loop analysis
+    /// stay valid.
+    pub fn list_schedule(&mut self, isa: &dyn TargetIsa) -> CodegenResult<bool> {
+        let changed = list_schedule(&mut self.func, isa);
+        self.after_pass("scheduling");
+        self.verify_if(isa)?;
+        Ok(changed)
+    }
+
+    /// Replace integer divisions by constants with multiplications and shifts. Returns `true` if
+    /// anything changed.
+    pub fn fold_divs_by_constants<'a, FOI: Into<FlagsOrIsa<'a>>>(
+        &mut self,
+        fisa: FOI,
+    ) -> CodegenResult<bool> {
+        let changed = fold_divs_by_constants(&mut self.func);
+        self.after_pass("div_const");
+        self.verify_if(fisa)?;
+        Ok(changed)
+    }
+
+    /// Mark `cold_blocks` as cold and move all cold blocks to the end of the function. Returns
+    /// `true` if anything changed.
+    pub fn outline_cold<'a, FOI: Into<FlagsOrIsa<'a>>>(
+        &mut self,
+        cold_blocks: &[Block],
+        fisa: FOI,
+    ) -> CodegenResult<bool> {
+        let changed = outline_cold(&mut self.func, cold_blocks);
+        self.after_pass("outline_cold");
+        self.verify_if(fisa)?;
+        Ok(changed)
+    }
+
     /// Run the legalizer for `isa` on the function.
     pub fn legalize(&mut self, isa: &dyn TargetIsa) -> CodegenResult<()> {
         // Legalization invalidates the domtree and loop_analysis by mutating the CFG.
@@ -290,6 +446,7 @@ impl Context {
 
         // Run some specific legalizations only.
         simple_legalize(&mut self.func, isa);
+        self.after_pass("legalize");
         self.verify_if(isa)
     }
 
@@ -321,9 +478,158 @@ impl Context {
         FOI: Into<FlagsOrIsa<'a>>,
     {
         eliminate_unreachable_code(&mut self.func, &mut self.cfg, &self.domtree);
+        self.after_pass("unreachable_code");
         self.verify_if(fisa)
     }
 
+    /// Remove blocks that can't be reached from the entry block, and return how many were
+    /// removed.
+    ///
+    /// Unlike `eliminate_unreachable_code`, this doesn't need the dominator tree. The CFG,
+    /// dominator tree and loop analysis are cleared if any blocks are removed.
+    pub fn remove_unreachable_blocks(&mut self) -> usize {
+        let removed = remove_unreachable_blocks(&mut self.func);
+        if removed > 0 {
+            self.cfg.clear();
+            self.domtree.clear();
+            self.loop_analysis.clear();
+        }
+        self.after_pass("remove_unreachable_blocks");
+        removed
+    }
+
+    /// Remove blocks that can't be reached from the entry block along with the jump tables only
+    /// they used, and return how many blocks were removed.
+    ///
+    /// Fails without changing the function if a removed block defines a value that is still used.
+    /// The CFG is computed if needed and kept up to date. The dominator tree and loop analysis
+    /// are cleared if any blocks are removed.
+    pub fn cleanup_unreachable(&mut self) -> CodegenResult<usize> {
+        if !self.cfg.is_valid() {
+            self.compute_cfg();
+        }
+        let removed = cleanup_unreachable(&mut self.func, &mut self.cfg)?;
+        if removed > 0 {
+            self.domtree.clear();
+            self.loop_analysis.clear();
+        }
+        self.after_pass("cleanup_unreachable");
+        Ok(removed)
+    }
+
+    /// Copy every block of at most `taildup::MAX_INSTRUCTIONS` instructions into the predecessors
+    /// that jump to it unconditionally. Returns `true` if anything was duplicated.
+    ///
+    /// The CFG is computed if needed and kept up to date. The dominator tree and loop analysis
+    /// are cleared if anything changed.
+    pub fn duplicate_tails<'a, FOI: Into<FlagsOrIsa<'a>>>(
+        &mut self,
+        fisa: FOI,
+    ) -> CodegenResult<bool> {
+        if !self.cfg.is_valid() {
+            self.compute_cfg();
+        }
+        let changed = duplicate_tails(&mut self.func, &mut self.cfg, taildup::MAX_INSTRUCTIONS);
+        if changed {
+            self.domtree.clear();
+            self.loop_analysis.clear();
+        }
+        self.after_pass("tail_duplication");
+        self.verify_if(fisa)?;
+        Ok(changed)
+    }
+
+    /// Fold constant branches, merge straight-line blocks and remove empty and unreachable
+    /// blocks until nothing changes. Returns `true` if anything changed.
+    ///
+    /// The CFG, dominator tree and loop analysis are cleared if anything changed.
+    pub fn simplify_cfg<'a, FOI: Into<FlagsOrIsa<'a>>>(
+        &mut self,
+        fisa: FOI,
+    ) -> CodegenResult<bool> {
+        let changed = simplify_cfg(&mut self.func);
+        if changed {
+            self.cfg.clear();
+            self.domtree.clear();
+            self.loop_analysis.clear();
+        }
+        self.after_pass("simplify_cfg");
+        self.verify_if(fisa)?;
+        Ok(changed)
+    }
+
+    /// Replace diamonds with short arms that are free of side effects by `select`s. Returns
+    /// `true` if anything changed.
+    ///
+    /// The CFG is computed if needed and kept up to date. The dominator tree and loop analysis
+    /// are cleared if anything changed.
+    pub fn if_convert<'a, FOI: Into<FlagsOrIsa<'a>>>(&mut self, fisa: FOI) -> CodegenResult<bool> {
+        if !self.cfg.is_valid() {
+            self.compute_cfg();
+        }
+        let changed = if_convert(&mut self.func, &mut self.cfg);
+        if changed {
+            self.domtree.clear();
+            self.loop_analysis.clear();
+        }
+        self.after_pass("if_conversion");
+        self.verify_if(fisa)?;
+        Ok(changed)
+    }
+
+    /// Move loads from loop-invariant addresses whose results are only used after their loop to
+    /// the loop's exit. Returns `true` if anything changed.
+    ///
+    /// The CFG, dominator tree and loop analysis are computed if needed. Since only instructions
+    /// are moved, they all stay valid.
+    pub fn sink_loop_invariant_loads<'a, FOI: Into<FlagsOrIsa<'a>>>(
+        &mut self,
+        fisa: FOI,
+    ) -> CodegenResult<bool> {
+        if !self.cfg.is_valid() {
+            self.compute_cfg();
+        }
+        if !self.domtree.is_valid() {
+            self.compute_domtree();
+        }
+        if !self.loop_analysis.is_valid() {
+            self.compute_loop_analysis();
+        }
+        let changed = sink_loop_invariant_loads(
+            &mut self.func,
+            &self.cfg,
+            &self.domtree,
+            &self.loop_analysis,
+        );
+        self.after_pass("licm_sinking");
+        self.verify_if(fisa)?;
+        Ok(changed)
+    }
+
+    /// Move pure instructions whose result has a single use in a dominated block right before
+    /// that use. Returns `true` if anything changed.
+    ///
+    /// The CFG, dominator tree and loop analysis are computed if needed. Since only instructions
+    /// are moved, they all stay valid.
+    pub fn sink_instructions<'a, FOI: Into<FlagsOrIsa<'a>>>(
+        &mut self,
+        fisa: FOI,
+    ) -> CodegenResult<bool> {
+        if !self.cfg.is_valid() {
+            self.compute_cfg();
+        }
+        if !self.domtree.is_valid() {
+            self.compute_domtree();
+        }
+        if !self.loop_analysis.is_valid() {
+            self.compute_loop_analysis();
+        }
+        let changed = sink_instructions(&mut self.func, &self.domtree, &self.loop_analysis);
+        self.after_pass("code_sinking");
+        self.verify_if(fisa)?;
+        Ok(changed)
+    }
+
     /// Replace all redundant loads with the known values in
     /// memory. These are loads whose values were already loaded by
     /// other loads earlier, as well as loads whose values were stored
@@ -332,6 +638,7 @@ impl Context {
     pub fn replace_redundant_loads(&mut self) -> CodegenResult<()> {
         let mut analysis = AliasAnalysis::new(&self.func, &self.domtree);
         analysis.compute_and_update_aliases(&mut self.func);
+        self.after_pass("redundant_loads");
         Ok(())
     }
 
@@ -374,8 +681,120 @@ impl Context {
         pass.run();
         log::debug!("egraph stats: {:?}", pass.stats);
         trace!("pinned_union_count: {}", pass.eclasses.pinned_union_count);
-        trace!("After egraph optimization:\n{}", self.func.display());
+        self.after_pass("egraph");
 
         self.verify_if(fisa)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cursor::{Cursor, FuncCursor};
+    use crate::ir::InstBuilder;
+    use crate::settings;
+    use alloc::string::{String, ToString};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn pass_observer() {
+        let mut func = Function::new();
+        let block0 = func.dfg.make_block();
+        let block1 = func.dfg.make_block();
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        pos.ins().return_(&[]);
+        pos.insert_block(block1);
+        pos.ins().return_(&[]);
+        let before = func.display().to_string();
+
+        let seen: Arc<Mutex<Vec<(String, String)>>> = Default::default();
+        let mut ctx = Context::for_function(func);
+        let observed = seen.clone();
+        ctx.set_pass_observer(Box::new(move |name, func| {
+            let ir = func.display().to_string();
+            observed.lock().unwrap().push((name.to_string(), ir));
+        }));
+
+        let flags = settings::Flags::new(settings::builder());
+        ctx.flowgraph();
+        ctx.eliminate_unreachable_code(&flags).unwrap();
+        ctx.remove_constant_phis(&flags).unwrap();
+
+        let seen = seen.lock().unwrap();
+        let names: Vec<_> = seen.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["unreachable_code", "remove_constant_phis"]);
+        assert_ne!(seen[0].1, before);
+        assert!(!seen[0].1.contains("block1"));
+        assert!(ctx.take_pass_observer().is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "x86")]
+    fn opt_level_pipelines() {
+        use crate::ir::{types, AbiParam};
+        use settings::Configurable;
+
+        // Increment the pinned register in place, and divide by a constant.
+        let mut func = Function::new();
+        func.signature.params.push(AbiParam::new(types::I64));
+        func.signature.returns.push(AbiParam::new(types::I64));
+        let block0 = func.dfg.make_block();
+        let v0 = func.dfg.append_block_param(block0, types::I64);
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let v1 = pos.ins().get_pinned_reg(types::I64);
+        let v2 = pos.ins().iadd_imm(v1, 1);
+        pos.ins().set_pinned_reg(v2);
+        let v3 = pos.ins().imul_imm(v0, 3);
+        let v4 = pos.ins().imul_imm(v0, 3);
+        let v5 = pos.ins().iadd(v3, v4);
+        let v6 = pos.ins().iconst(types::I64, 7);
+        let v7 = pos.ins().udiv(v5, v6);
+        pos.ins().return_(&[v7]);
+
+        let compile = |opt_level| {
+            let mut builder = settings::builder();
+            builder.set("opt_level", opt_level).unwrap();
+            builder.enable("enable_pinned_reg").unwrap();
+            builder.enable("enable_div_const_folding").unwrap();
+            builder.enable("enable_tail_duplication").unwrap();
+            let isa = crate::isa::lookup_by_name("x86_64")
+                .unwrap()
+                .finish(settings::Flags::new(builder))
+                .unwrap();
+            let passes: Arc<Mutex<Vec<String>>> = Default::default();
+            let observed = passes.clone();
+            let mut ctx = Context::for_function(func.clone());
+            ctx.set_pass_observer(Box::new(move |name, _| {
+                observed.lock().unwrap().push(name.to_string());
+            }));
+            let size = ctx
+                .compile(&*isa, &mut Default::default())
+                .unwrap()
+                .code_buffer()
+                .len();
+            let passes = passes.lock().unwrap().clone();
+            (passes, size, isa.opt_function_alignment())
+        };
+
+        let (none, none_size, none_align) = compile("none");
+        let (speed, speed_size, speed_align) = compile("speed");
+        let (size, size_size, size_align) = compile("speed_and_size");
+        let ran = |passes: &[String], name: &str| passes.iter().any(|pass| pass == name);
+        assert!(!ran(&none, "egraph"), "{none:?}");
+        assert!(!ran(&none, "div_const"), "{none:?}");
+        assert!(ran(&speed, "egraph"), "{speed:?}");
+        assert!(ran(&speed, "div_const"), "{speed:?}");
+        assert!(ran(&speed, "tail_duplication"), "{speed:?}");
+        // `speed_and_size` skips the passes that grow the code.
+        assert!(ran(&size, "egraph"), "{size:?}");
+        assert!(!ran(&size, "div_const"), "{size:?}");
+        assert!(!ran(&size, "tail_duplication"), "{size:?}");
+        assert_ne!(size, speed);
+        assert!(size_size < none_size);
+        assert!(size_size < speed_size);
+        assert_eq!(none_align, speed_align);
+        assert!(size_align < speed_align);
+    }
+}
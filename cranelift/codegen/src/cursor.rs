@@ -596,6 +596,16 @@ impl<'f> FuncCursor<'f> {
     pub fn ins(&mut self) -> ir::InsertBuilder<'_, &mut FuncCursor<'f>> {
         ir::InsertBuilder::new(self)
     }
+
+    /// Create an instruction builder that type checks an instruction, and then inserts it at the
+    /// current position.
+    ///
+    /// This is like `ins()`, except that the builder panics if the instruction's arguments have
+    /// the wrong types, or if a branch passes arguments that don't match the destination block's
+    /// parameters.
+    pub fn ins_checked(&mut self) -> ir::CheckedInsertBuilder<'_, &mut FuncCursor<'f>> {
+        ir::CheckedInsertBuilder::new(self)
+    }
 }
 
 impl<'f> Cursor for FuncCursor<'f> {
@@ -0,0 +1,169 @@
+//! The `DFGPrinter` utility.
+
+use alloc::collections::BTreeSet;
+use alloc::string::{String, ToString};
+use core::fmt::{Display, Formatter, Result, Write};
+
+use crate::ir::{Block, Function, Value, ValueDef};
+
+/// A utility for pretty-printing the value dependence graph of a `Function`.
+///
+/// Every instruction becomes a node labeled with its text, and every block parameter becomes a
+/// node in a cluster for its block. There is an edge from the definition of each operand to the
+/// instruction using it; operands that are aliases are drawn dashed.
+pub struct DFGPrinter<'a> {
+    func: &'a Function,
+    block: Option<Block>,
+}
+
+impl<'a> DFGPrinter<'a> {
+    /// Create a new DFGPrinter for the whole function.
+    pub fn new(func: &'a Function) -> Self {
+        Self { func, block: None }
+    }
+
+    /// Only print the instructions and parameters of `block`.
+    ///
+    /// Operands defined outside of `block` are shown as plain value nodes.
+    pub fn with_block(func: &'a Function, block: Block) -> Self {
+        Self {
+            func,
+            block: Some(block),
+        }
+    }
+
+    /// Write the value dependence graph for this function to `w`.
+    pub fn write(&self, w: &mut dyn Write) -> Result {
+        writeln!(w, "digraph \"{}\" {{", self.func.name)?;
+        for block in self.blocks() {
+            self.block_cluster(w, block)?;
+        }
+        self.operand_edges(w)?;
+        writeln!(w, "}}")
+    }
+
+    fn blocks(&self) -> impl Iterator<Item = Block> + '_ {
+        self.func
+            .layout
+            .blocks()
+            .filter(move |&block| self.block.map_or(true, |b| b == block))
+    }
+
+    fn block_cluster(&self, w: &mut dyn Write, block: Block) -> Result {
+        writeln!(w, "    subgraph cluster_{block} {{")?;
+        writeln!(w, "        label=\"{block}\"")?;
+        for &param in self.func.dfg.block_params(block) {
+            let ty = self.func.dfg.value_type(param);
+            writeln!(w, "        {param} [label=\"{param}: {ty}\"]")?;
+        }
+        for inst in self.func.layout.block_insts(block) {
+            let text = self.func.dfg.display_inst(inst).to_string();
+            writeln!(w, "        {inst} [shape=box, label=\"{}\"]", escape(&text))?;
+        }
+        writeln!(w, "    }}")
+    }
+
+    fn operand_edges(&self, w: &mut dyn Write) -> Result {
+        // Values used in this graph without a node of their own, because their definition was
+        // filtered out.
+        let mut external = BTreeSet::new();
+
+        for block in self.blocks() {
+            for inst in self.func.layout.block_insts(block) {
+                for arg in self.func.dfg.inst_values(inst) {
+                    let original = self.func.dfg.resolve_aliases(arg);
+                    let source = match self.def_node(original) {
+                        Some(node) => node,
+                        None => {
+                            if external.insert(original) {
+                                writeln!(w, "    {original} [shape=plaintext]")?;
+                            }
+                            original.to_string()
+                        }
+                    };
+                    if arg == original {
+                        writeln!(w, "    {source} -> {inst}")?;
+                    } else {
+                        writeln!(w, "    {source} -> {inst} [style=dashed, label=\"{arg}\"]")?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the name of the node defining `value`, if it is part of this graph.
+    fn def_node(&self, value: Value) -> Option<String> {
+        let (block, node) = match self.func.dfg.value_def(value) {
+            ValueDef::Result(inst, _) => (self.func.layout.inst_block(inst)?, inst.to_string()),
+            ValueDef::Param(block, _) => (block, value.to_string()),
+            ValueDef::Union(..) => return None,
+        };
+        if !self.func.layout.is_block_inserted(block) || self.block.map_or(false, |b| b != block) {
+            return None;
+        }
+        Some(node)
+    }
+}
+
+impl<'a> Display for DFGPrinter<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        self.write(f)
+    }
+}
+
+/// Escape `text` for use in a quoted graphviz label.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cursor::{Cursor, FuncCursor};
+    use crate::ir::{types, InstBuilder};
+
+    #[test]
+    fn edges() {
+        let mut func = Function::new();
+        let block0 = func.dfg.make_block();
+        let block1 = func.dfg.make_block();
+        let v0 = func.dfg.append_block_param(block0, types::I32);
+        let v1 = func.dfg.append_block_param(block1, types::I32);
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let v2 = pos.ins().iadd(v0, v0);
+        let add = pos.func.dfg.value_def(v2).unwrap_inst();
+        let jump = pos.ins().jump(block1, &[v2]);
+        pos.insert_block(block1);
+        let ret = pos.ins().return_(&[v1]);
+
+        let dot = DFGPrinter::new(&func).to_string();
+        assert!(dot.contains("subgraph cluster_block0"));
+        assert!(dot.contains(&format!("{v0} -> {add}")));
+        assert!(dot.contains(&format!("{add} -> {jump}")));
+        assert!(dot.contains(&format!("{v1} -> {ret}")));
+
+        // With a block filter, operands from other blocks become plain value nodes.
+        let dot = DFGPrinter::with_block(&func, block1).to_string();
+        assert!(!dot.contains("cluster_block0"));
+        assert!(!dot.contains(&format!("{add}")));
+        assert!(dot.contains(&format!("{v1} -> {ret}")));
+    }
+
+    #[test]
+    fn alias_edges() {
+        let mut func = Function::new();
+        let block0 = func.dfg.make_block();
+        let v0 = func.dfg.append_block_param(block0, types::I32);
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let v1 = pos.ins().iconst(types::I32, 1);
+        let ret = pos.ins().return_(&[v1]);
+        pos.func.dfg.replace_result(v1, types::I32);
+        pos.func.dfg.change_to_alias(v1, v0);
+
+        let dot = DFGPrinter::new(&func).to_string();
+        assert!(dot.contains(&format!("{v0} -> {ret} [style=dashed, label=\"{v1}\"]")));
+    }
+}
@@ -0,0 +1,398 @@
+//! Strength reduction of integer division by constants.
+//!
+//! Dividing by a constant is replaced by a multiplication by a "magic number" followed by shifts,
+//! as described in chapter 10 of Hacker's Delight and in Granlund and Montgomery, "Division by
+//! Invariant Integers using Multiplication". Division by a power of two only needs shifts.
+//!
+//! Only `udiv` and `sdiv` of `i32` and `i64` values are rewritten. Division by zero is left alone
+//! so it still traps, and so is signed division by -1, which traps on overflow.
+
+use crate::cursor::{Cursor, FuncCursor};
+use crate::ir::{types, Function, Inst, InstBuilder, InstructionData, Opcode, Value};
+use crate::{timing, trace};
+
+/// Replace every division by a constant in `func` with a cheaper sequence of instructions.
+/// Returns `true` if anything changed.
+pub fn fold_divs_by_constants(func: &mut Function) -> bool {
+    let _tt = timing::div_const();
+    let mut changed = false;
+    let mut pos = FuncCursor::new(func);
+    while let Some(_block) = pos.next_block() {
+        while let Some(inst) = pos.next_inst() {
+            changed |= fold_div_by_constant(&mut pos, inst);
+        }
+    }
+    changed
+}
+
+/// Replace `inst` with a cheaper sequence of instructions if it is a `udiv` or `sdiv` by a
+/// constant. Returns `true` if it was replaced.
+///
+/// The new instructions are inserted before `inst`, which is overwritten with the last of them so
+/// its result value is kept. Division by one makes the result an alias of the dividend instead,
+/// and `inst` is removed. `pos` is left at the last instruction of the replacement sequence.
+pub fn fold_div_by_constant(pos: &mut FuncCursor, inst: Inst) -> bool {
+    let (opcode, [x, y]) = match pos.func.dfg.insts[inst] {
+        InstructionData::Binary {
+            opcode: opcode @ (Opcode::Udiv | Opcode::Sdiv),
+            args,
+        } => (opcode, args),
+        _ => return false,
+    };
+    let ty = pos.func.dfg.ctrl_typevar(inst);
+    if ty != types::I32 && ty != types::I64 {
+        return false;
+    }
+    let Some(divisor) = constant(pos.func, y) else {
+        return false;
+    };
+    let bits = ty.bits();
+    let divisor = divisor & (u64::MAX >> (64 - bits));
+    if divisor == 0 {
+        return false;
+    }
+    pos.goto_inst(inst);
+
+    if opcode == Opcode::Udiv {
+        trace!("Reducing {} by constant {}", inst, divisor);
+        if divisor == 1 {
+            replace_with_dividend(pos, inst, x);
+        } else if divisor.is_power_of_two() {
+            let k = divisor.trailing_zeros();
+            pos.func.dfg.replace(inst).ushr_imm(x, i64::from(k));
+        } else {
+            match magic_u(divisor, bits) {
+                UnsignedMagic::Shift { multiplier, shift } => {
+                    let m = pos.ins().iconst(ty, multiplier as i64);
+                    let q = pos.ins().umulhi(x, m);
+                    pos.func.dfg.replace(inst).ushr_imm(q, i64::from(shift));
+                }
+                UnsignedMagic::AddShift { multiplier, shift } => {
+                    let m = pos.ins().iconst(ty, multiplier as i64);
+                    let t = pos.ins().umulhi(x, m);
+                    let d = pos.ins().isub(x, t);
+                    let d = pos.ins().ushr_imm(d, 1);
+                    let q = pos.ins().iadd(d, t);
+                    pos.func.dfg.replace(inst).ushr_imm(q, i64::from(shift - 1));
+                }
+            }
+        }
+    } else {
+        // Sign-extend the divisor from `bits` bits.
+        let divisor = ((divisor << (64 - bits)) as i64) >> (64 - bits);
+        trace!("Reducing {} by constant {}", inst, divisor);
+        if divisor == -1 {
+            return false;
+        } else if divisor == 1 {
+            replace_with_dividend(pos, inst, x);
+        } else if divisor.unsigned_abs().is_power_of_two() {
+            // Round towards zero by adding `2^k - 1` to negative dividends before shifting.
+            let k = divisor.unsigned_abs().trailing_zeros();
+            let sign = pos.ins().sshr_imm(x, i64::from(k - 1));
+            let bias = pos.ins().ushr_imm(sign, i64::from(bits - k));
+            let biased = pos.ins().iadd(x, bias);
+            if divisor > 0 {
+                pos.func.dfg.replace(inst).sshr_imm(biased, i64::from(k));
+            } else {
+                let q = pos.ins().sshr_imm(biased, i64::from(k));
+                pos.func.dfg.replace(inst).ineg(q);
+            }
+        } else {
+            let (multiplier, shift) = magic_s(divisor, bits);
+            let m = pos.ins().iconst(ty, multiplier);
+            let mut q = pos.ins().smulhi(x, m);
+            if divisor > 0 && multiplier < 0 {
+                q = pos.ins().iadd(q, x);
+            } else if divisor < 0 && multiplier > 0 {
+                q = pos.ins().isub(q, x);
+            }
+            if shift > 0 {
+                q = pos.ins().sshr_imm(q, i64::from(shift));
+            }
+            // Add one to negative quotients to round towards zero.
+            let sign = pos.ins().ushr_imm(q, i64::from(bits - 1));
+            pos.func.dfg.replace(inst).iadd(q, sign);
+        }
+    }
+    true
+}
+
+/// Get the value of `v` if it is defined by an `iconst`, possibly through aliases.
+fn constant(func: &Function, v: Value) -> Option<u64> {
+    let inst = func.dfg.value_def(func.dfg.resolve_aliases(v)).inst()?;
+    match func.dfg.insts[inst] {
+        InstructionData::UnaryImm {
+            opcode: Opcode::Iconst,
+            imm,
+        } => Some(imm.bits() as u64),
+        _ => None,
+    }
+}
+
+/// Turn the result of `inst` into an alias of `x`, and remove `inst`.
+fn replace_with_dividend(pos: &mut FuncCursor, inst: Inst, x: Value) {
+    let result = pos.func.dfg.first_result(inst);
+    pos.func.dfg.clear_results(inst);
+    pos.func.dfg.change_to_alias(result, x);
+    pos.remove_inst_and_step_back();
+}
+
+/// How to divide by a constant with an unsigned multiplication.
+#[derive(Debug, PartialEq, Eq)]
+enum UnsignedMagic {
+    /// `q = umulhi(x, multiplier) >> shift`.
+    Shift { multiplier: u64, shift: u32 },
+    /// `t = umulhi(x, multiplier); q = (((x - t) >> 1) + t) >> (shift - 1)`, for divisors whose
+    /// multiplier needs `bits + 1` bits.
+    AddShift { multiplier: u64, shift: u32 },
+}
+
+/// Compute the magic number for unsigned division of `bits`-bit integers by `d`, which must not
+/// be zero or a power of two.
+fn magic_u(d: u64, bits: u32) -> UnsignedMagic {
+    debug_assert!(d != 0 && !d.is_power_of_two());
+    let d = u128::from(d);
+    // `l = ceil(log2(d))`.
+    let l = 128 - (d - 1).leading_zeros();
+
+    // Look for the smallest `p` for which `m = ceil(2^p / d)` fits in `bits` bits and is precise
+    // enough: `m * d - 2^p <= 2^(p - bits)` guarantees `floor(x * m / 2^p) = floor(x / d)` for
+    // every `bits`-bit `x`.
+    for p in bits..(bits + l).min(128) {
+        let two_p = 1u128 << p;
+        let m = two_p.div_ceil(d);
+        if m >> bits == 0 && m * d - two_p <= 1 << (p - bits) {
+            return UnsignedMagic::Shift {
+                multiplier: m as u64,
+                shift: p - bits,
+            };
+        }
+    }
+
+    // Otherwise use the low `bits` bits of the `bits + 1`-bit multiplier
+    // `floor(2^(bits + l) / d) + 1`, and add the missing high bit back with `x`.
+    let m = ((1u128 << bits) * ((1u128 << l) - d)) / d + 1;
+    UnsignedMagic::AddShift {
+        multiplier: m as u64,
+        shift: l,
+    }
+}
+
+/// Compute the magic number and shift for signed division of `bits`-bit integers by `d`, whose
+/// absolute value must not be 0, 1 or a power of two.
+///
+/// This is the algorithm from figure 10-1 of Hacker's Delight. The multiplier is returned
+/// sign-extended from `bits` bits.
+fn magic_s(d: i64, bits: u32) -> (i64, u32) {
+    debug_assert!(d.unsigned_abs() > 1 && !d.unsigned_abs().is_power_of_two());
+    let ad = u128::from(d.unsigned_abs());
+    let t = (1u128 << (bits - 1)) + u128::from(d < 0);
+    // The absolute value of the largest dividend that is one less than a multiple of `d`.
+    let anc = t - 1 - t % ad;
+    let mut p = bits - 1;
+    let two_p = 1u128 << p;
+    let (mut q1, mut r1) = (two_p / anc, two_p % anc);
+    let (mut q2, mut r2) = (two_p / ad, two_p % ad);
+    loop {
+        p += 1;
+        q1 *= 2;
+        r1 *= 2;
+        if r1 >= anc {
+            q1 += 1;
+            r1 -= anc;
+        }
+        q2 *= 2;
+        r2 *= 2;
+        if r2 >= ad {
+            q2 += 1;
+            r2 -= ad;
+        }
+        let delta = ad - r2;
+        if q1 > delta || (q1 == delta && r1 != 0) {
+            break;
+        }
+    }
+
+    let mask = u128::MAX >> (128 - bits);
+    let mut m = (q2 + 1) & mask;
+    if d < 0 {
+        m = m.wrapping_neg() & mask;
+    }
+    // Sign-extend from `bits` bits.
+    let m = ((m << (128 - bits)) as i128 >> (128 - bits)) as i64;
+    (m, p - bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::AbiParam;
+    use crate::settings;
+    use crate::verifier::verify_function;
+    use alloc::vec::Vec;
+
+    /// Evaluate the unsigned division sequence for `x`.
+    fn udiv(x: u64, magic: &UnsignedMagic, bits: u32) -> u64 {
+        let mulhi = |m: u64| ((u128::from(x) * u128::from(m)) >> bits) as u64;
+        match *magic {
+            UnsignedMagic::Shift { multiplier, shift } => mulhi(multiplier) >> shift,
+            UnsignedMagic::AddShift { multiplier, shift } => {
+                let t = mulhi(multiplier);
+                (((x - t) >> 1) + t) >> (shift - 1)
+            }
+        }
+    }
+
+    /// Evaluate the signed division sequence for `x`.
+    fn sdiv(x: i64, d: i64, bits: u32) -> i64 {
+        let (m, s) = magic_s(d, bits);
+        let mut q = ((i128::from(x) * i128::from(m)) >> bits) as i64;
+        if d > 0 && m < 0 {
+            q += x;
+        } else if d < 0 && m > 0 {
+            q -= x;
+        }
+        q >>= s;
+        q + i64::from(q < 0)
+    }
+
+    fn samples(bits: u32) -> Vec<u64> {
+        let max = u64::MAX >> (64 - bits);
+        let mut samples = Vec::new();
+        for x in 0..1000 {
+            samples.extend([x, max - x, max / 2 - x, max / 2 + x]);
+        }
+        samples.extend((0..1000).map(|i: u64| i.wrapping_mul(0x9e37_79b9_7f4a_7c15) & max));
+        samples
+    }
+
+    #[test]
+    fn magic_numbers() {
+        for bits in [32, 64] {
+            let max = u64::MAX >> (64 - bits);
+            let min = -1i64 << (bits - 1);
+            for d in [
+                3,
+                5,
+                6,
+                7,
+                10,
+                11,
+                25,
+                100,
+                641,
+                1000,
+                1 << 20 | 1,
+                max - 1,
+                max,
+            ] {
+                let magic = magic_u(d, bits);
+                for x in samples(bits) {
+                    assert_eq!(udiv(x, &magic, bits), x / d, "{x} / {d} ({bits} bits)");
+                }
+            }
+            for d in [
+                3,
+                -3,
+                5,
+                -5,
+                7,
+                -7,
+                10,
+                25,
+                -100,
+                641,
+                i64::from(i32::MAX),
+                min + 1,
+            ] {
+                for x in samples(bits) {
+                    let x = ((x << (64 - bits)) as i64) >> (64 - bits);
+                    assert_eq!(
+                        sdiv(x, d, bits),
+                        x.wrapping_div(d),
+                        "{x} / {d} ({bits} bits)"
+                    );
+                }
+            }
+        }
+        // Known values from Hacker's Delight.
+        assert_eq!(
+            magic_u(7, 32),
+            UnsignedMagic::AddShift {
+                multiplier: 0x2492_4925,
+                shift: 3
+            }
+        );
+        assert_eq!(magic_s(7, 32), (0x9249_2493_u32 as i32 as i64, 2));
+        assert_eq!(magic_s(-5, 32), (0x9999_9999_u32 as i32 as i64, 1));
+    }
+
+    #[test]
+    fn fold_divs() {
+        let mut func = Function::new();
+        func.signature.params.push(AbiParam::new(types::I32));
+        func.signature.returns.push(AbiParam::new(types::I32));
+        let block0 = func.dfg.make_block();
+        let x = func.dfg.append_block_param(block0, types::I32);
+
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let mut divs = Vec::new();
+        for (opcode, d) in [
+            (Opcode::Udiv, 8),
+            (Opcode::Udiv, 7),
+            (Opcode::Sdiv, -4),
+            (Opcode::Sdiv, 7),
+            (Opcode::Sdiv, 1),
+            (Opcode::Sdiv, -1),
+            (Opcode::Udiv, 0),
+        ] {
+            let d = pos.ins().iconst(types::I32, d);
+            let (inst, dfg) = pos.ins().Binary(opcode, types::I32, x, d);
+            divs.push((inst, dfg.first_result(inst)));
+        }
+        let sum = divs
+            .iter()
+            .fold(x, |sum, &(_, result)| pos.ins().iadd(sum, result));
+        pos.ins().return_(&[sum]);
+
+        assert!(fold_divs_by_constants(&mut func));
+        verify_function(&func, &settings::Flags::new(settings::builder())).unwrap();
+        let opcode = |i: usize| func.dfg.insts[divs[i].0].opcode();
+        assert_eq!(opcode(0), Opcode::UshrImm);
+        assert_eq!(opcode(1), Opcode::UshrImm);
+        assert_eq!(opcode(2), Opcode::Ineg);
+        assert_eq!(opcode(3), Opcode::Iadd);
+        // Division by one is removed, and the divisions that can trap are kept.
+        assert!(func.layout.inst_block(divs[4].0).is_none());
+        assert_eq!(func.dfg.resolve_aliases(divs[4].1), x);
+        assert_eq!(opcode(5), Opcode::Sdiv);
+        assert_eq!(opcode(6), Opcode::Udiv);
+    }
+
+    #[test]
+    fn fold_div_by_aliased_constant() {
+        let mut func = Function::new();
+        func.signature.params.push(AbiParam::new(types::I32));
+        func.signature.returns.push(AbiParam::new(types::I32));
+        let block0 = func.dfg.make_block();
+        let x = func.dfg.append_block_param(block0, types::I32);
+
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let seven = pos.ins().iconst(types::I32, 7);
+        // Make `d` an alias of `seven`, as earlier passes may leave behind.
+        let d = pos.ins().iconst(types::I32, 0);
+        let d_inst = pos.func.dfg.value_def(d).unwrap_inst();
+        pos.func.dfg.clear_results(d_inst);
+        pos.func.dfg.change_to_alias(d, seven);
+        pos.func.layout.remove_inst(d_inst);
+        let q = pos.ins().udiv(x, d);
+        pos.ins().return_(&[q]);
+
+        assert!(fold_divs_by_constants(&mut func));
+        verify_function(&func, &settings::Flags::new(settings::builder())).unwrap();
+        let div = func.dfg.value_def(q).unwrap_inst();
+        assert_eq!(func.dfg.insts[div].opcode(), Opcode::UshrImm);
+    }
+}
@@ -786,7 +786,7 @@ impl<'a> Elaborator<'a> {
 
     fn elaborate_domtree(&mut self, domtree: &DominatorTreePreorder) {
         self.block_stack.push(BlockStackEntry::Elaborate {
-            block: self.func.layout.entry_block().unwrap(),
+            block: self.func.entry_block(),
             idom: None,
         });
 
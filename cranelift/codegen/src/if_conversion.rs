@@ -0,0 +1,170 @@
+//! If conversion.
+//!
+//! A `brif` to two short blocks that both jump to the same block can be replaced by executing
+//! both blocks unconditionally and using `select` to pick the arguments for the join block:
+//!
+//! ```plain
+//! block0:
+//!     brif v0, block1, block2
+//! block1:
+//!     v1 = iadd_imm v9, 1
+//!     jump block3(v1)
+//! block2:
+//!     jump block3(v9)
+//! ```
+//!
+//! becomes
+//!
+//! ```plain
+//! block0:
+//!     v1 = iadd_imm v9, 1
+//!     v2 = select v0, v1, v9
+//!     jump block3(v2)
+//! ```
+//!
+//! This is only done when both arms are free of side effects and can't trap, since their
+//! instructions are executed even when their arm wasn't taken.
+
+use crate::cursor::{Cursor, FuncCursor};
+use crate::flowgraph::ControlFlowGraph;
+use crate::inst_predicates::is_pure_for_egraph;
+use crate::ir::{Block, Function, Inst, InstBuilder, InstructionData, Opcode, Value};
+use crate::{timing, trace};
+use alloc::vec::Vec;
+use smallvec::SmallVec;
+
+/// The largest number of instructions, not counting the final jump, that an arm may have to be
+/// executed unconditionally.
+const MAX_ARM_INSTRUCTIONS: usize = 4;
+
+/// A `brif` whose arms can be converted to `select`s.
+struct Diamond {
+    branch: Inst,
+    cond: Value,
+    arms: [Block; 2],
+    join: Block,
+}
+
+/// Convert all diamonds in `func` whose arms are short and free of side effects.
+///
+/// `cfg` must be valid on entry, and is kept up to date. Returns `true` if anything was
+/// converted, in which case the arms are removed and any dominator tree is invalid.
+pub fn if_convert(func: &mut Function, cfg: &mut ControlFlowGraph) -> bool {
+    let _tt = timing::if_conversion();
+    let mut changed = false;
+    let blocks: Vec<Block> = func.layout.blocks().collect();
+    for block in blocks {
+        // Arms of previous diamonds have been removed.
+        if !func.layout.is_block_inserted(block) {
+            continue;
+        }
+        if let Some(diamond) = find_diamond(func, cfg, block) {
+            convert(func, cfg, block, diamond);
+            changed = true;
+        }
+    }
+    changed
+}
+
+fn find_diamond(func: &Function, cfg: &ControlFlowGraph, block: Block) -> Option<Diamond> {
+    let branch = func.layout.last_inst(block)?;
+    let InstructionData::Brif {
+        arg: cond,
+        blocks: [then_dest, else_dest],
+        ..
+    } = func.dfg.insts[branch]
+    else {
+        return None;
+    };
+    let pool = &func.dfg.value_lists;
+    if !then_dest.args_slice(pool).is_empty() || !else_dest.args_slice(pool).is_empty() {
+        return None;
+    }
+
+    let arms = [then_dest.block(pool), else_dest.block(pool)];
+    if arms[0] == arms[1] {
+        return None;
+    }
+    let join = arm_successor(func, cfg, arms[0])?;
+    if arm_successor(func, cfg, arms[1])? != join {
+        return None;
+    }
+    Some(Diamond {
+        branch,
+        cond,
+        arms,
+        join,
+    })
+}
+
+/// If `arm` may be executed unconditionally by its only predecessor, get the block it jumps to.
+fn arm_successor(func: &Function, cfg: &ControlFlowGraph, arm: Block) -> Option<Block> {
+    if func.layout.entry_block() == Some(arm)
+        || func.dfg.num_block_params(arm) != 0
+        || cfg.pred_iter(arm).nth(1).is_some()
+    {
+        return None;
+    }
+    let jump = func.layout.last_inst(arm)?;
+    if func.dfg.insts[jump].opcode() != Opcode::Jump {
+        return None;
+    }
+    let mut body = func.layout.block_insts(arm).filter(|&inst| inst != jump);
+    if func.layout.block_insts(arm).count() - 1 > MAX_ARM_INSTRUCTIONS
+        || !body.all(|inst| is_pure_for_egraph(func, inst))
+    {
+        return None;
+    }
+    let dest = func.dfg.insts[jump].branch_destination(&func.dfg.jump_tables)[0];
+    Some(dest.block(&func.dfg.value_lists)).filter(|&dest| dest != arm)
+}
+
+fn convert(func: &mut Function, cfg: &mut ControlFlowGraph, block: Block, diamond: Diamond) {
+    let Diamond {
+        branch,
+        cond,
+        arms,
+        join,
+    } = diamond;
+    trace!(
+        "If-converting {} with arms {} and {}",
+        block,
+        arms[0],
+        arms[1]
+    );
+
+    // Hoist the arms above the branch, and collect their arguments for `join`.
+    let mut args: [SmallVec<[Value; 8]>; 2] = Default::default();
+    for (arm, args) in arms.into_iter().zip(&mut args) {
+        let jump = func.layout.last_inst(arm).unwrap();
+        while let Some(inst) = func.layout.first_inst(arm).filter(|&inst| inst != jump) {
+            func.layout.remove_inst(inst);
+            func.layout.insert_inst(inst, branch);
+        }
+        let dest = func.dfg.insts[jump].branch_destination(&func.dfg.jump_tables)[0];
+        args.extend(dest.args_slice(&func.dfg.value_lists).iter().copied());
+        func.layout.remove_inst(jump);
+    }
+
+    let srcloc = func.srcloc(branch);
+    let mut pos = FuncCursor::new(func).at_inst(branch).with_srcloc(srcloc);
+    let [then_args, else_args] = args;
+    let args: SmallVec<[Value; 8]> = then_args
+        .into_iter()
+        .zip(else_args)
+        .map(|(then_arg, else_arg)| {
+            if pos.func.dfg.resolve_aliases(then_arg) == pos.func.dfg.resolve_aliases(else_arg) {
+                then_arg
+            } else {
+                pos.ins().select(cond, then_arg, else_arg)
+            }
+        })
+        .collect();
+    pos.func.dfg.replace(branch).jump(join, &args);
+
+    for arm in arms {
+        cfg.recompute_block(func, arm);
+        func.layout.remove_block(arm);
+    }
+    cfg.recompute_block(func, block);
+}
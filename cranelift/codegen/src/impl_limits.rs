@@ -0,0 +1,203 @@
+//! Implementation limit checks.
+//!
+//! The in-memory IR packs entity indices into narrow fields (see `ValueDataPacked` in the DFG),
+//! so functions that are too large can't be represented faithfully. Rather than panicking deep
+//! inside the compiler, these checks reject oversized functions up front with
+//! `CodegenError::ImplLimitExceeded`. The limits are configurable through the `max_*_log2`
+//! settings.
+
+use crate::ir::instructions::CallInfo;
+use crate::ir::Function;
+use crate::result::{CodegenError, CodegenResult};
+use crate::settings::Flags;
+
+/// Hard limit on the number of instructions, blocks, and values, imposed by the 24-bit index
+/// fields in the packed value representation.
+const MAX_ENTITIES_LOG2: u8 = 24;
+
+/// Hard limit on the number of block parameters and call arguments, imposed by the 16-bit
+/// parameter number stored for each value.
+const MAX_PARAMS_LOG2: u8 = 16;
+
+/// Compute the largest count allowed by a `log2` setting, clamped to `hard_log2`.
+fn limit(log2: u8, hard_log2: u8) -> usize {
+    (1usize << log2.min(hard_log2)) - 1
+}
+
+/// Check that `func` stays within the implementation limits configured in `flags`.
+///
+/// Returns `CodegenError::ImplLimitExceeded` if any limit is exceeded.
+pub fn check_impl_limits(func: &Function, flags: &Flags) -> CodegenResult<()> {
+    let max_insts = limit(flags.max_insts_log2(), MAX_ENTITIES_LOG2);
+    let max_blocks = limit(flags.max_blocks_log2(), MAX_ENTITIES_LOG2);
+    let max_values = limit(flags.max_values_log2(), MAX_ENTITIES_LOG2);
+    let max_params = limit(flags.max_params_log2(), MAX_PARAMS_LOG2);
+
+    if func.dfg.num_insts() > max_insts
+        || func.dfg.num_blocks() > max_blocks
+        || func.dfg.num_values() > max_values
+        || func.signature.params.len() > max_params
+        || func.signature.returns.len() > max_params
+    {
+        return Err(CodegenError::ImplLimitExceeded);
+    }
+
+    for block in func.layout.blocks() {
+        if func.dfg.num_block_params(block) > max_params {
+            return Err(CodegenError::ImplLimitExceeded);
+        }
+        for inst in func.layout.block_insts(block) {
+            let args = match func.dfg.insts[inst].analyze_call(&func.dfg.value_lists) {
+                CallInfo::NotACall => continue,
+                CallInfo::Direct(_, args) | CallInfo::Indirect(_, args) => args,
+            };
+            if args.len() > max_params {
+                return Err(CodegenError::ImplLimitExceeded);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cursor::{Cursor, FuncCursor};
+    use crate::ir::{types, AbiParam, InstBuilder, Signature};
+    use crate::isa::CallConv;
+    use crate::settings::{self, Configurable};
+    use alloc::string::ToString;
+    use alloc::vec::Vec;
+
+    fn flags(name: &str, value: &str) -> Flags {
+        let mut b = settings::builder();
+        b.set(name, value).unwrap();
+        Flags::new(b)
+    }
+
+    #[test]
+    fn defaults_accept_small_function() {
+        let mut func = Function::new();
+        let block0 = func.dfg.make_block();
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        pos.ins().return_(&[]);
+
+        let flags = Flags::new(settings::builder());
+        assert!(check_impl_limits(&func, &flags).is_ok());
+    }
+
+    #[test]
+    fn too_many_insts() {
+        let mut func = Function::new();
+        let block0 = func.dfg.make_block();
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        for _ in 0..8 {
+            pos.ins().iconst(types::I32, 0);
+        }
+        pos.ins().return_(&[]);
+
+        assert!(check_impl_limits(&func, &flags("max_insts_log2", "4")).is_ok());
+        assert!(matches!(
+            check_impl_limits(&func, &flags("max_insts_log2", "3")),
+            Err(CodegenError::ImplLimitExceeded)
+        ));
+    }
+
+    #[test]
+    fn too_many_block_params() {
+        let mut func = Function::new();
+        let block0 = func.dfg.make_block();
+        for _ in 0..4 {
+            func.dfg.append_block_param(block0, types::I32);
+        }
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        pos.ins().return_(&[]);
+
+        assert!(check_impl_limits(&func, &flags("max_params_log2", "3")).is_ok());
+        assert!(matches!(
+            check_impl_limits(&func, &flags("max_params_log2", "2")),
+            Err(CodegenError::ImplLimitExceeded)
+        ));
+    }
+
+    #[test]
+    fn too_many_call_args() {
+        let mut func = Function::new();
+        let mut sig = Signature::new(CallConv::SystemV);
+        sig.params = (0..4).map(|_| AbiParam::new(types::I32)).collect();
+        let sig = func.import_signature(sig);
+        let block0 = func.dfg.make_block();
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let callee = pos.ins().iconst(types::I64, 0);
+        let arg = pos.ins().iconst(types::I32, 0);
+        let args: Vec<_> = (0..4).map(|_| arg).collect();
+        pos.ins().call_indirect(sig, callee, &args);
+        pos.ins().return_(&[]);
+
+        assert!(check_impl_limits(&func, &flags("max_params_log2", "3")).is_ok());
+        assert!(matches!(
+            check_impl_limits(&func, &flags("max_params_log2", "2")),
+            Err(CodegenError::ImplLimitExceeded)
+        ));
+    }
+
+    #[test]
+    fn random_functions() {
+        // Functions of random shapes, checked against random limits, from a fixed xorshift
+        // sequence so the test is deterministic.
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = |n: u64| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state % n
+        };
+        for _ in 0..200 {
+            let mut func = Function::new();
+            let mut max_block_params = 0;
+            let mut pos = FuncCursor::new(&mut func);
+            for _ in 0..1 + next(8) {
+                let block = pos.func.dfg.make_block();
+                let num_params = next(6) as usize;
+                for _ in 0..num_params {
+                    pos.func.dfg.append_block_param(block, types::I32);
+                }
+                max_block_params = max_block_params.max(num_params);
+                pos.insert_block(block);
+                for _ in 0..next(6) {
+                    pos.ins().iconst(types::I32, 0);
+                }
+                pos.ins().return_(&[]);
+            }
+
+            let log2 = [1 + next(5), 1 + next(5), 1 + next(5), 1 + next(5)];
+            let mut b = settings::builder();
+            let names = [
+                "max_insts_log2",
+                "max_blocks_log2",
+                "max_values_log2",
+                "max_params_log2",
+            ];
+            for (name, log2) in names.into_iter().zip(log2) {
+                b.set(name, &log2.to_string()).unwrap();
+            }
+            let flags = Flags::new(b);
+
+            let fits = |count: usize, log2: u64| count < 1 << log2;
+            let expected = fits(func.dfg.num_insts(), log2[0])
+                && fits(func.dfg.num_blocks(), log2[1])
+                && fits(func.dfg.num_values(), log2[2])
+                && fits(max_block_params, log2[3]);
+            match check_impl_limits(&func, &flags) {
+                Ok(()) => assert!(expected),
+                Err(CodegenError::ImplLimitExceeded) => assert!(!expected),
+                Err(e) => panic!("unexpected error: {e}"),
+            }
+        }
+    }
+}
@@ -3,8 +3,11 @@ use crate::ir::immediates::Offset32;
 use crate::ir::{self, Block, Function, Inst, InstructionData, Opcode, Type, Value};
 
 /// Test whether the given opcode is unsafe to even consider as side-effect-free.
+///
+/// Loads aren't included, since some of them can be removed; `Opcode::is_pure` is this plus
+/// every load.
 #[inline(always)]
-fn trivially_has_side_effects(opcode: Opcode) -> bool {
+pub(crate) fn trivially_has_side_effects(opcode: Opcode) -> bool {
     opcode.is_call()
         || opcode.is_branch()
         || opcode.is_terminator()
@@ -61,11 +64,11 @@ pub fn is_pure_for_egraph(func: &Function, inst: Inst) -> bool {
     // is "not pure". Similarly, ops with zero results can be used
     // only for their side-effects, so are never pure. (Or if they
     // are, we can always trivially eliminate them with no effect.)
-    let has_one_result = func.dfg.inst_results(inst).len() == 1;
+    let has_one_result = func.dfg.num_results(inst) == 1;
 
     let op = func.dfg.insts[inst].opcode();
 
-    has_one_result && (is_readonly_load || (!op.can_load() && !trivially_has_side_effects(op)))
+    has_one_result && (is_readonly_load || op.is_pure())
 }
 
 /// Can the given instruction be merged into another copy of itself?
@@ -77,7 +80,7 @@ pub fn is_mergeable_for_egraph(func: &Function, inst: Inst) -> bool {
     let op = func.dfg.insts[inst].opcode();
     // We can only merge one-result operators due to the way that GVN
     // is structured in the egraph implementation.
-    let has_one_result = func.dfg.inst_results(inst).len() == 1;
+    let has_one_result = func.dfg.num_results(inst) == 1;
     has_one_result
         // Loads/stores are handled by alias analysis and not
         // otherwise mergeable.
@@ -201,6 +204,15 @@ pub(crate) fn visit_block_succs<F: FnMut(Inst, Block, bool)>(
                 }
             }
 
+            ir::InstructionData::TryCall { table, .. }
+            | ir::InstructionData::TryCallIndirect { table, .. } => {
+                // Both destinations are direct branches, even though they are kept in a jump
+                // table.
+                for dest in f.stencil.dfg.jump_tables[*table].all_branches() {
+                    visit(inst, dest.block(&f.dfg.value_lists), false);
+                }
+            }
+
             inst => debug_assert!(!inst.opcode().is_branch()),
         }
     }
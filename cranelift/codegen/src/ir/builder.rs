@@ -133,6 +133,50 @@ impl<'f, IIB: InstInserterBase<'f>> InstBuilderBase<'f> for InsertBuilder<'f, II
     }
 }
 
+/// Builder that inserts a new instruction like `InsertBuilder`, but type checks it first.
+///
+/// The arguments of each instruction are checked with `DataFlowGraph::type_check_inst` before it
+/// is inserted, and the builder panics if they are wrong. This catches mistakes like branch
+/// arguments that don't match the parameters of the destination block where they are made,
+/// rather than when the verifier eventually runs.
+pub struct CheckedInsertBuilder<'f, IIB: InstInserterBase<'f>> {
+    inserter: IIB,
+    unused: PhantomData<&'f u32>,
+}
+
+impl<'f, IIB: InstInserterBase<'f>> CheckedInsertBuilder<'f, IIB> {
+    /// Create a new builder which type checks instructions and inserts them at `pos`.
+    pub fn new(inserter: IIB) -> Self {
+        Self {
+            inserter,
+            unused: PhantomData,
+        }
+    }
+}
+
+impl<'f, IIB: InstInserterBase<'f>> InstBuilderBase<'f> for CheckedInsertBuilder<'f, IIB> {
+    fn data_flow_graph(&self) -> &DataFlowGraph {
+        self.inserter.data_flow_graph()
+    }
+
+    fn data_flow_graph_mut(&mut self) -> &mut DataFlowGraph {
+        self.inserter.data_flow_graph_mut()
+    }
+
+    fn build(mut self, data: InstructionData, ctrl_typevar: Type) -> (Inst, &'f mut DataFlowGraph) {
+        let inst;
+        {
+            let dfg = self.inserter.data_flow_graph_mut();
+            inst = dfg.make_inst(data);
+            dfg.make_inst_results(inst, ctrl_typevar);
+            if let Err(err) = dfg.type_check_inst(inst) {
+                panic!("ill-typed {}: {err}", dfg.insts[inst].opcode());
+            }
+        }
+        (inst, self.inserter.insert_built_inst(inst))
+    }
+}
+
 /// Builder that inserts a new instruction like `InsertBuilder`, but reusing result values.
 pub struct InsertReuseBuilder<'f, IIB, Array>
 where
@@ -264,6 +308,46 @@ mod tests {
         assert_eq!(pos.func.dfg.value_def(v0), ValueDef::Result(iconst, 0));
     }
 
+    #[test]
+    #[should_panic(
+        expected = "ill-typed jump: arg 0 (v1) has type i64, expected i32 (passed to block1)"
+    )]
+    fn checked_branch_args() {
+        let mut func = Function::new();
+        let block0 = func.dfg.make_block();
+        let block1 = func.dfg.make_block();
+        func.dfg.append_block_param(block1, I32);
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+
+        let v0 = pos.ins_checked().iconst(I64, 0);
+        pos.ins_checked().jump(block1, &[v0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ill-typed call: call has 1 arguments, but the signature has 2")]
+    fn checked_call_args() {
+        use crate::ir::{AbiParam, ExtFuncData, ExternalName, Signature};
+        use crate::isa::CallConv;
+
+        let mut func = Function::new();
+        let mut sig = Signature::new(CallConv::SystemV);
+        sig.params.push(AbiParam::new(I32));
+        sig.params.push(AbiParam::new(I32));
+        let signature = func.import_signature(sig);
+        let callee = func.import_function(ExtFuncData {
+            name: ExternalName::testcase("callee"),
+            signature,
+            colocated: false,
+        });
+        let block0 = func.dfg.make_block();
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+
+        let v0 = pos.ins_checked().iconst(I32, 0);
+        pos.ins_checked().call(callee, &[v0]);
+    }
+
     #[test]
     #[should_panic]
     #[cfg(debug_assertions)]
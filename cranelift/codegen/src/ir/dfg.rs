@@ -1,19 +1,25 @@
 //! Data flow graph tracking Instructions, Values, and blocks.
 
-use crate::entity::{self, PrimaryMap, SecondaryMap};
+use crate::dominator_tree::{DominatorTree, DominatorTreePreorder};
+use crate::entity::{self, EntityRef, EntitySet, PrimaryMap, SecondaryMap};
 use crate::ir;
 use crate::ir::builder::ReplaceBuilder;
 use crate::ir::dynamic_type::{DynamicTypeData, DynamicTypes};
-use crate::ir::instructions::{CallInfo, InstructionData};
+use crate::ir::instructions::{
+    CallInfo, EntityRefMapper, InstructionData, InstructionFormat, ResolvedConstraint, ValueTypeSet,
+};
 use crate::ir::pcc::Fact;
 use crate::ir::user_stack_maps::{UserStackMapEntry, UserStackMapEntryVec};
 use crate::ir::{
     types, Block, BlockCall, ConstantData, ConstantPool, DynamicType, ExtFuncData, FuncRef,
-    Immediate, Inst, JumpTables, RelSourceLoc, SigRef, Signature, Type, Value,
-    ValueLabelAssignments, ValueList, ValueListPool,
+    Immediate, Inst, JumpTables, Layout, ProgramPoint, RelSourceLoc, SigRef, Signature, Type,
+    Value, ValueLabelAssignments, ValueList, ValueListPool,
 };
+use crate::loop_analysis::LoopAnalysis;
 use crate::packed_option::ReservedValue;
+use crate::result::{CodegenError, CodegenResult};
 use crate::write::write_operands;
+use core::cmp::Ordering;
 use core::fmt;
 use core::iter;
 use core::mem;
@@ -21,10 +27,16 @@ use core::ops::{Index, IndexMut};
 use core::u16;
 
 use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use rustc_hash::FxHashMap;
 #[cfg(feature = "enable-serde")]
 use serde_derive::{Deserialize, Serialize};
 use smallvec::SmallVec;
 
+/// The maximum number of parameters a block can have, since `ValueData::Param` stores the
+/// parameter number in a `u16`.
+const MAX_BLOCK_PARAMS: usize = 1 << 16;
+
 /// Storage for instructions within the DFG.
 #[derive(Clone, PartialEq, Hash)]
 #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
@@ -181,6 +193,61 @@ impl DataFlowGraph {
         }
     }
 
+    /// Forget every instruction, value and block beyond the given counts, and shrink the value
+    /// list pool back to `value_lists` elements.
+    ///
+    /// This is how `FunctionSnapshot` rolls back append-only changes. Entities below the counts
+    /// are left alone, so anything that still refers to the removed entities must be cleaned up
+    /// separately, e.g. in the layout.
+    pub(crate) fn truncate(
+        &mut self,
+        insts: usize,
+        values: usize,
+        blocks: usize,
+        value_lists: usize,
+    ) {
+        self.insts.0.truncate(insts);
+        self.results.resize(insts);
+        self.user_stack_maps.retain(|inst, _| inst.index() < insts);
+        self.values.truncate(values);
+        self.facts.resize(values);
+        if let Some(labels) = &mut self.values_labels {
+            labels.retain(|value, _| value.index() < values);
+        }
+        self.blocks.0.truncate(blocks);
+        self.value_lists.truncate(value_lists);
+    }
+
+    /// Hash the instructions, values and blocks below the given counts, including the contents
+    /// of their value lists.
+    ///
+    /// Used by `FunctionSnapshot` to detect changes that it can't roll back.
+    pub(crate) fn hash_entities<H: core::hash::Hasher>(
+        &self,
+        insts: usize,
+        values: usize,
+        blocks: usize,
+        state: &mut H,
+    ) {
+        use core::hash::Hash;
+        for inst in self.insts.0.keys().take(insts) {
+            self.insts[inst].hash(state, &self.value_lists, |value| value);
+            self.inst_results(inst).hash(state);
+            for value in self.inst_values(inst) {
+                value.hash(state);
+            }
+            for block in self.instruction_successors(inst) {
+                block.hash(state);
+            }
+        }
+        for value in self.values.keys().take(values) {
+            self.values[value].hash(state);
+        }
+        for block in self.blocks.0.keys().take(blocks) {
+            self.block_params(block).hash(state);
+        }
+    }
+
     /// Clear everything.
     pub fn clear(&mut self) {
         self.insts.0.clear();
@@ -320,6 +387,38 @@ impl<'a> Iterator for Values<'a> {
     }
 }
 
+/// Iterator over the blocks reachable from an entry block, in depth-first pre-order.
+struct ReachableBlocks<'a> {
+    dfg: &'a DataFlowGraph,
+    layout: &'a Layout,
+    stack: Vec<Block>,
+    seen: EntitySet<Block>,
+}
+
+impl<'a> Iterator for ReachableBlocks<'a> {
+    type Item = Block;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(block) = self.stack.pop() {
+            if !self.seen.insert(block) {
+                continue;
+            }
+            if let Some(inst) = self.layout.last_inst(block) {
+                // Push successors in reverse so the first one is visited first.
+                let seen = &self.seen;
+                self.stack.extend(
+                    self.dfg
+                        .instruction_successors(inst)
+                        .rev()
+                        .filter(|&succ| !seen.contains(succ)),
+                );
+            }
+            return Some(block);
+        }
+        None
+    }
+}
+
 /// Handling values.
 ///
 /// Values are either block parameters or instruction results.
@@ -386,6 +485,33 @@ impl DataFlowGraph {
         }
     }
 
+    /// Get an iterator over the aliases of `original`, including the aliases of its aliases.
+    ///
+    /// `original` doesn't have to be the end of an alias chain: for `v1 -> v2 -> v3`, the aliases
+    /// of `v2` are just `v1`. This scans every value in the function, so it is meant for debugging
+    /// rather than for use in passes.
+    pub fn aliases_of(&self, original: Value) -> impl Iterator<Item = Value> + '_ {
+        self.values().filter(move |&value| {
+            let mut v = value;
+            // Bound the walk like `maybe_resolve_aliases`, in case of an alias cycle.
+            for _ in 0..=self.values.len() {
+                match ValueData::from(self.values[v]) {
+                    ValueData::Alias { original: next, .. } if next == original => return true,
+                    ValueData::Alias { original: next, .. } => v = next,
+                    _ => return false,
+                }
+            }
+            false
+        })
+    }
+
+    /// Get the number of values that are aliases of other values.
+    pub fn num_aliases(&self) -> usize {
+        self.values()
+            .filter(|&v| matches!(self.values[v].into(), ValueData::Alias { .. }))
+            .count()
+    }
+
     /// Resolve value aliases.
     ///
     /// Find the original SSA value that `value` aliases.
@@ -528,6 +654,119 @@ impl DataFlowGraph {
         self.values[dest] = ValueData::Alias { ty, original }.into();
     }
 
+    /// Turn a value into an alias of another, checking that the result is still valid SSA.
+    ///
+    /// This is like `change_to_alias`, but when debug assertions are enabled it also checks that
+    /// the definition of `src` is in the layout and strictly dominates every use of `dest` in the
+    /// layout.
+    /// If `domtree` hasn't been computed, only the first check is made. This catches mistakes
+    /// right away instead of leaving them for the verifier to report much later.
+    ///
+    /// Finding the uses of `dest` requires a scan of all instructions in the function.
+    pub fn change_to_alias_checked(
+        &mut self,
+        dest: Value,
+        src: Value,
+        layout: &Layout,
+        domtree: &DominatorTree,
+    ) {
+        if cfg!(debug_assertions) {
+            let original = self.resolve_aliases(src);
+            let def = match self.value_def(original) {
+                ValueDef::Result(inst, _) => {
+                    assert!(
+                        layout.inst_block(inst).is_some(),
+                        "Aliasing {dest} to {src}, which is defined by `{}` outside the layout",
+                        self.display_inst(inst)
+                    );
+                    Some(ProgramPoint::Inst(inst))
+                }
+                ValueDef::Param(block, _) => {
+                    assert!(
+                        layout.is_block_inserted(block),
+                        "Aliasing {dest} to {src}, a parameter of {block} outside the layout"
+                    );
+                    Some(ProgramPoint::Block(block))
+                }
+                // Unions only exist inside the egraph pass, outside of any layout.
+                ValueDef::Union(..) => None,
+            };
+
+            if let Some(def) = def.filter(|_| domtree.is_valid()) {
+                self.walk_uses(dest, |user, _| {
+                    if layout.inst_block(user).is_none() {
+                        return;
+                    }
+                    // An instruction can't use its own result, so dominance must be strict.
+                    assert!(
+                        def != ProgramPoint::Inst(user) && domtree.dominates(def, user, layout),
+                        "Aliasing {dest} to {src} is invalid: the definition of {src} ({}) \
+                         doesn't dominate the use of {dest} in `{}`",
+                        self.display_def(original),
+                        self.display_inst(user)
+                    );
+                });
+            }
+        }
+
+        self.change_to_alias(dest, src);
+    }
+
+    /// Give the definition of `old` a new identity, `new`.
+    ///
+    /// `new` must be a detached value with the same type as `old`, which must be an instruction
+    /// result or a block parameter. `new` takes the place of `old` in the list of results or
+    /// parameters it belongs to, and every use of `old` by an instruction in `layout` is replaced
+    /// by a use of `new`. Finally, `old` is turned into an alias of `new`, so any remaining
+    /// references to it, such as uses in instructions outside the layout, still see the same
+    /// value.
+    ///
+    /// This is the renaming step of SSA destruction, which gives each copy of a value its own
+    /// name. It scans every instruction in the layout.
+    pub fn rename_value(&mut self, old: Value, new: Value, layout: &Layout) {
+        debug_assert!(!self.value_is_attached(new));
+        debug_assert_eq!(
+            self.value_type(old),
+            self.value_type(new),
+            "Renaming {old} to {new} would change its type"
+        );
+
+        for block in layout.blocks() {
+            for inst in layout.block_insts(block) {
+                self.map_inst_values(inst, |arg| if arg == old { new } else { arg });
+            }
+        }
+
+        let data = ValueData::from(self.values[old]);
+        match data {
+            ValueData::Inst { inst, num, .. } => {
+                self.results[inst].as_mut_slice(&mut self.value_lists)[usize::from(num)] = new;
+            }
+            ValueData::Param { block, num, .. } => {
+                self.blocks[block]
+                    .params
+                    .as_mut_slice(&mut self.value_lists)[usize::from(num)] = new;
+            }
+            ValueData::Alias { .. } | ValueData::Union { .. } => {
+                panic!("{old} must be an instruction result or a block parameter")
+            }
+        }
+        self.values[new] = data.into();
+        if self.facts[new].is_none() {
+            self.facts[new] = self.facts[old].clone();
+        }
+        self.change_to_alias(old, new);
+    }
+
+    /// Describe where `value` is defined, for use in error messages.
+    fn display_def(&self, value: Value) -> alloc::string::String {
+        match self.value_def(value) {
+            ValueDef::Result(inst, _) => format!("`{}`", self.display_inst(inst)),
+            ValueDef::Param(block, num) => format!("parameter {num} of {block}"),
+            ValueDef::Union(x, y) => format!("union of {x} and {y}"),
+        }
+    }
+
     /// Replace the results of one instruction with aliases to the results of another.
     ///
     /// Change all the results of `dest_inst` to behave as aliases of
@@ -816,6 +1055,106 @@ impl DataFlowGraph {
         self.insts.0.push(data)
     }
 
+    /// Create a new instruction and insert it in `layout` immediately before `before`.
+    ///
+    /// This is a shorthand for `make_inst` followed by `Layout::insert_inst`. Like `make_inst`,
+    /// it doesn't create any result values, so call `make_inst_results` afterwards if the
+    /// instruction has results.
+    pub fn insert_before(
+        &mut self,
+        layout: &mut Layout,
+        before: Inst,
+        data: InstructionData,
+    ) -> Inst {
+        let inst = self.make_inst(data);
+        layout.insert_inst(inst, before);
+        inst
+    }
+
+    /// Create a new instruction and insert it in `layout` immediately after `after`.
+    ///
+    /// Like `insert_before`, this doesn't create any result values. Panics if `after` is a
+    /// terminator, since the new instruction would end up after the end of its block.
+    pub fn insert_after(
+        &mut self,
+        layout: &mut Layout,
+        after: Inst,
+        data: InstructionData,
+    ) -> Inst {
+        assert!(
+            !self.insts[after].opcode().is_terminator(),
+            "can't insert an instruction after the terminator `{}`",
+            self.display_inst(after)
+        );
+        let block = layout
+            .inst_block(after)
+            .expect("instruction not in the layout");
+        let inst = self.make_inst(data);
+        match layout.next_inst(after) {
+            Some(next) => layout.insert_inst(inst, next),
+            None => layout.append_inst(inst, block),
+        }
+        inst
+    }
+
+    /// Create an instruction that materializes the constant `bytes` of type `ty`, and return its
+    /// result. Like `make_inst`, the instruction isn't inserted in the layout.
+    ///
+    /// `bytes` holds the constant in little-endian order, and must be exactly as long as `ty`.
+    /// Integers of up to 64 bits become an `iconst`, and floats up to `f64` become an
+    /// `f16const`, `f32const` or `f64const` with the bits as an immediate. Wider constants,
+    /// `f128` and vectors, are added to the constant pool and referenced by an `f128const` or
+    /// `vconst`.
+    ///
+    /// Panics for types that have no constant instruction, such as `i128`.
+    pub fn make_const(&mut self, ty: Type, bytes: &[u8]) -> Value {
+        assert_eq!(
+            bytes.len(),
+            ty.bytes() as usize,
+            "wrong number of bytes for a constant of type {ty}"
+        );
+        let data = match ty {
+            types::I8 | types::I16 | types::I32 | types::I64 => {
+                let mut imm = [0; 8];
+                imm[..bytes.len()].copy_from_slice(bytes);
+                InstructionData::UnaryImm {
+                    opcode: ir::Opcode::Iconst,
+                    imm: ir::immediates::Imm64::new(i64::from_le_bytes(imm)),
+                }
+            }
+            types::F16 => InstructionData::UnaryIeee16 {
+                opcode: ir::Opcode::F16const,
+                imm: ir::immediates::Ieee16::with_bits(u16::from_le_bytes(
+                    bytes.try_into().unwrap(),
+                )),
+            },
+            types::F32 => InstructionData::UnaryIeee32 {
+                opcode: ir::Opcode::F32const,
+                imm: ir::immediates::Ieee32::with_bits(u32::from_le_bytes(
+                    bytes.try_into().unwrap(),
+                )),
+            },
+            types::F64 => InstructionData::UnaryIeee64 {
+                opcode: ir::Opcode::F64const,
+                imm: ir::immediates::Ieee64::with_bits(u64::from_le_bytes(
+                    bytes.try_into().unwrap(),
+                )),
+            },
+            types::F128 => InstructionData::UnaryConst {
+                opcode: ir::Opcode::F128const,
+                constant_handle: self.constants.insert(bytes.into()),
+            },
+            ty if ty.is_vector() => InstructionData::UnaryConst {
+                opcode: ir::Opcode::Vconst,
+                constant_handle: self.constants.insert(bytes.into()),
+            },
+            _ => panic!("there is no constant instruction for type {ty}"),
+        };
+        let inst = self.make_inst(data);
+        self.make_inst_results(inst, ty);
+        self.first_result(inst)
+    }
+
     /// Declares a dynamic vector type
     pub fn make_dynamic_ty(&mut self, data: DynamicTypeData) -> DynamicType {
         self.dynamic_types.push(data)
@@ -838,6 +1177,52 @@ impl DataFlowGraph {
         }
     }
 
+    /// Print `domtree`, the dominator tree of the function with this DFG and `layout`, to `out`.
+    ///
+    /// This is meant for debugging. Each block is printed on its own line, indented below its
+    /// immediate dominator, followed by its index in the CFG reverse post-order and its
+    /// immediate dominator. The blocks in `layout` that aren't reachable are listed at the end.
+    pub fn print_domtree(
+        &self,
+        domtree: &DominatorTree,
+        layout: &Layout,
+        out: &mut dyn fmt::Write,
+    ) -> fmt::Result {
+        let mut rpo = SecondaryMap::new();
+        for (index, &block) in domtree.cfg_rpo().enumerate() {
+            rpo[block] = index;
+        }
+        let mut preorder = DominatorTreePreorder::new();
+        preorder.compute(domtree);
+
+        let mut stack: Vec<(Block, usize)> = layout
+            .entry_block()
+            .filter(|&entry| domtree.is_reachable(entry))
+            .map(|entry| (entry, 0))
+            .into_iter()
+            .collect();
+        while let Some((block, depth)) = stack.pop() {
+            debug_assert!(self.block_is_valid(block));
+            let indent = 2 * depth;
+            write!(out, "{:indent$}{block} rpo={}", "", rpo[block])?;
+            if let Some(idom) = domtree.idom(block) {
+                write!(out, " idom={idom}")?;
+            }
+            writeln!(out)?;
+            // Push the children in reverse, so they are printed in reverse post-order.
+            let first_child = stack.len();
+            stack.extend(preorder.children(block).map(|child| (child, depth + 1)));
+            stack[first_child..].reverse();
+        }
+
+        for block in layout.blocks() {
+            if !domtree.is_reachable(block) {
+                writeln!(out, "{block} unreachable")?;
+            }
+        }
+        Ok(())
+    }
+
     /// Construct a read-only visitor context for the values of this instruction.
     pub fn inst_values<'dfg>(
         &'dfg self,
@@ -854,6 +1239,177 @@ impl DataFlowGraph {
             .copied()
     }
 
+    /// Get the blocks targeted by `inst`, or nothing if it isn't a branch.
+    ///
+    /// For `br_table`, the default block comes first, followed by every entry of the jump table
+    /// in order, so the same block may be yielded more than once.
+    pub fn instruction_successors<'dfg>(
+        &'dfg self,
+        inst: Inst,
+    ) -> impl DoubleEndedIterator<Item = Block> + 'dfg {
+        self.insts[inst]
+            .branch_destination(&self.jump_tables)
+            .iter()
+            .map(|call| call.block(&self.value_lists))
+    }
+
+    /// Iterate over the blocks reachable from `entry`, in depth-first pre-order.
+    ///
+    /// The successors of each block are the destinations of its last instruction in `layout`.
+    /// Every reachable block is yielded exactly once, and blocks that can't be reached from
+    /// `entry` are never yielded.
+    pub fn reachable_blocks<'a>(
+        &'a self,
+        entry: Block,
+        layout: &'a Layout,
+    ) -> impl Iterator<Item = Block> + 'a {
+        ReachableBlocks {
+            dfg: self,
+            layout,
+            stack: vec![entry],
+            seen: EntitySet::with_capacity(self.num_blocks()),
+        }
+    }
+
+    /// Call `f` on each instruction in `layout`, in layout order.
+    ///
+    /// This is an alternative to iterating over `layout` and indexing `self.insts` that is
+    /// convenient when building analysis results in a single pass.
+    pub fn visit_instructions<F>(&self, layout: &Layout, mut f: F)
+    where
+        F: FnMut(Inst, &InstructionData),
+    {
+        for block in layout.blocks() {
+            for inst in layout.block_insts(block) {
+                f(inst, &self.insts[inst]);
+            }
+        }
+    }
+
+    /// Call `f` on each value defined in `layout`, with its type.
+    ///
+    /// Values are visited in layout order: the parameters of each block come first, followed by
+    /// the results of each instruction in the block. Aliases are not visited since they aren't
+    /// defined anywhere in the layout.
+    pub fn visit_values<F>(&self, layout: &Layout, mut f: F)
+    where
+        F: FnMut(Value, Type),
+    {
+        for block in layout.blocks() {
+            for &param in self.block_params(block) {
+                f(param, self.value_type(param));
+            }
+            for inst in layout.block_insts(block) {
+                for &result in self.inst_results(inst) {
+                    f(result, self.value_type(result));
+                }
+            }
+        }
+    }
+
+    /// Call `f(inst, index)` for each use of `v` as an operand of an instruction.
+    ///
+    /// `index` is the position of the use in the sequence yielded by `inst_values(inst)`, so
+    /// branch arguments are numbered after the instruction's fixed and variable arguments.
+    ///
+    /// This is a linear scan over every instruction in the DFG, including instructions that
+    /// have not been inserted in the layout. Uses of aliases of `v` are not reported.
+    pub fn walk_uses(&self, v: Value, mut f: impl FnMut(Inst, usize)) {
+        for inst in self.insts.0.keys() {
+            for (index, arg) in self.inst_values(inst).enumerate() {
+                if arg == v {
+                    f(inst, index);
+                }
+            }
+        }
+    }
+
+    /// Estimate the cost of spilling `v` to the stack, to rank spill candidates. Values with a
+    /// lower cost are better candidates.
+    ///
+    /// Each use of `v` or one of its aliases by an instruction in `layout` costs 1, multiplied by
+    /// 10 for each loop that contains the instruction according to `loops`. Like `walk_uses`,
+    /// this is a linear scan over the function.
+    pub fn estimate_spill_cost(&self, v: Value, layout: &Layout, loops: &LoopAnalysis) -> f64 {
+        let v = self.resolve_aliases(v);
+        let mut cost = 0.0;
+        for block in layout.blocks() {
+            let uses = layout
+                .block_insts(block)
+                .flat_map(|inst| self.inst_values(inst))
+                .filter(|&arg| self.resolve_aliases(arg) == v)
+                .count();
+            if uses > 0 {
+                let level = loops.loop_level(block).level();
+                let weight = (0..level).fold(1.0, |weight, _| weight * 10.0);
+                cost += uses as f64 * weight;
+            }
+        }
+        cost
+    }
+
+    /// Is `v` live across the call instruction `call_inst`, so that it has to survive the call
+    /// in a callee-saved register or on the stack?
+    ///
+    /// This is the case when the definition of `v` strictly dominates `call_inst`, and `v` or one
+    /// of its aliases is used after `call_inst`: later in the same block, or in a block that is
+    /// reachable from it without passing through the definition of `v` again. The uses are
+    /// searched on every query, so this is linear in the size of the function.
+    pub fn is_live_through_call(
+        &self,
+        v: Value,
+        call_inst: Inst,
+        layout: &Layout,
+        domtree: &DominatorTree,
+    ) -> bool {
+        debug_assert!(self.insts[call_inst].opcode().is_call());
+        let v = self.resolve_aliases(v);
+        let (def, def_block): (ProgramPoint, Block) = match self.value_def(v) {
+            ValueDef::Result(inst, _) => match layout.inst_block(inst) {
+                Some(block) => (inst.into(), block),
+                None => return false,
+            },
+            ValueDef::Param(block, _) => (block.into(), block),
+            ValueDef::Union(..) => return false,
+        };
+        if def == ProgramPoint::Inst(call_inst) || !domtree.dominates(def, call_inst, layout) {
+            return false;
+        }
+
+        let uses_v = |inst| {
+            self.inst_values(inst)
+                .any(|arg| self.resolve_aliases(arg) == v)
+        };
+        let mut next = layout.next_inst(call_inst);
+        while let Some(inst) = next {
+            if uses_v(inst) {
+                return true;
+            }
+            next = layout.next_inst(inst);
+        }
+
+        let call_block = layout
+            .inst_block(call_inst)
+            .expect("call instruction not in the layout");
+        let mut visited = EntitySet::with_capacity(self.num_blocks());
+        let mut stack = vec![call_block];
+        while let Some(block) = stack.pop() {
+            let Some(last) = layout.last_inst(block) else {
+                continue;
+            };
+            for succ in self.instruction_successors(last) {
+                if succ == def_block || !visited.insert(succ) {
+                    continue;
+                }
+                if layout.block_insts(succ).any(uses_v) {
+                    return true;
+                }
+                stack.push(succ);
+            }
+        }
+        false
+    }
+
     /// Map a function over the values of the instruction.
     pub fn map_inst_values<F>(&mut self, inst: Inst, body: F)
     where
@@ -874,6 +1430,11 @@ impl DataFlowGraph {
         });
     }
 
+    /// Get the instruction format of `inst`.
+    pub fn inst_format(&self, inst: Inst) -> InstructionFormat {
+        self.insts[inst].opcode().format()
+    }
+
     /// Get all value arguments on `inst` as a slice.
     pub fn inst_args(&self, inst: Inst) -> &[Value] {
         self.insts[inst].arguments(&self.value_lists)
@@ -932,10 +1493,39 @@ impl DataFlowGraph {
     /// The type of the first result value is also set, even if it was already set in the
     /// `InstructionData` passed to `make_inst`. If this function is called with a single-result
     /// instruction, that is the only effect.
-    pub fn make_inst_results(&mut self, inst: Inst, ctrl_typevar: Type) -> usize {
+    ///
+    /// Any results `inst` already has are detached and replaced by new values. Use
+    /// `remake_inst_results` to keep them instead.
+    ///
+    /// Returns the new list of results.
+    pub fn make_inst_results(&mut self, inst: Inst, ctrl_typevar: Type) -> &[Value] {
         self.make_inst_results_reusing(inst, ctrl_typevar, iter::empty())
     }
 
+    /// Recreate the result values of `inst` after its result types may have changed, for
+    /// example because the signature of a call was replaced.
+    ///
+    /// This is like `make_inst_results`, except that every existing result whose type is
+    /// unchanged is kept at its position instead of being replaced by a new value, so its uses
+    /// stay valid. The other old results are left detached.
+    ///
+    /// Returns the new list of results.
+    pub fn remake_inst_results(&mut self, inst: Inst, ctrl_typevar: Type) -> &[Value] {
+        // Detach the old results, so they can be reused.
+        let mut old_results = mem::take(&mut self.results[inst]);
+        let reuse: SmallVec<[_; 16]> = self
+            .inst_result_types(inst, ctrl_typevar)
+            .enumerate()
+            .map(|(i, ty)| {
+                old_results
+                    .get(i, &self.value_lists)
+                    .filter(|&v| self.value_type(v) == ty)
+            })
+            .collect();
+        old_results.clear(&mut self.value_lists);
+        self.make_inst_results_reusing(inst, ctrl_typevar, reuse.into_iter())
+    }
+
     /// Create result values for `inst`, reusing the provided detached values.
     ///
     /// Create a new set of result values for `inst` using `ctrl_typevar` to determine the result
@@ -946,7 +1536,7 @@ impl DataFlowGraph {
         inst: Inst,
         ctrl_typevar: Type,
         reuse: I,
-    ) -> usize
+    ) -> &[Value]
     where
         I: Iterator<Item = Option<Value>>,
     {
@@ -960,7 +1550,11 @@ impl DataFlowGraph {
             let value_data = ValueData::Inst { ty, num, inst };
             let v = if let Some(Some(v)) = reuse.next() {
                 debug_assert_eq!(self.value_type(v), ty, "Reused {ty} is wrong type");
-                debug_assert!(!self.value_is_attached(v));
+                debug_assert!(
+                    !self.value_is_attached(v),
+                    "Reused {v} is still attached to {:?}",
+                    self.value_def(v)
+                );
                 self.values[v] = value_data.into();
                 v
             } else {
@@ -970,7 +1564,45 @@ impl DataFlowGraph {
             debug_assert_eq!(expected, actual);
         }
 
-        result_tys.len()
+        debug_assert!(
+            self.inst_results(inst).iter().enumerate().all(|(i, &v)| {
+                self.value_def(v) == ValueDef::Result(inst, i) && self.value_is_attached(v)
+            }),
+            "inconsistent results for {inst}"
+        );
+
+        self.inst_results(inst)
+    }
+
+    /// Move the result values of `from` to `to`, keeping their order.
+    ///
+    /// `to` must not have any results yet, and its result types must match the types of the
+    /// values. Afterwards the values are defined by `to`, and `from` has no results.
+    pub fn transfer_results(&mut self, from: Inst, to: Inst) {
+        debug_assert_ne!(from, to, "Transferring the results of {from} to itself");
+        debug_assert!(!self.has_results(to), "{to} already has results");
+
+        let results: SmallVec<[Value; 4]> = self.inst_results(from).iter().copied().collect();
+        self.clear_results(from);
+        // If the controlling type variable comes from the first result, it's the type of the
+        // first value being transferred.
+        let constraints = self.insts[to].opcode().constraints();
+        let ctrl_typevar = match results.first() {
+            Some(&first)
+                if constraints.is_polymorphic() && !constraints.requires_typevar_operand() =>
+            {
+                self.value_type(first)
+            }
+            _ => self.ctrl_typevar(to),
+        };
+        let num_results = self
+            .make_inst_results_reusing(to, ctrl_typevar, results.iter().map(|&v| Some(v)))
+            .len();
+        debug_assert_eq!(
+            num_results,
+            results.len(),
+            "{to} doesn't have the same number of results as {from}"
+        );
     }
 
     /// Create a `ReplaceBuilder` that will replace `inst` with a new instruction in place.
@@ -1033,7 +1665,7 @@ impl DataFlowGraph {
         // Get the controlling type variable.
         let ctrl_typevar = self.ctrl_typevar(inst);
         // Create new result values.
-        let num_results = self.make_inst_results(new_inst, ctrl_typevar);
+        let num_results = self.make_inst_results(new_inst, ctrl_typevar).len();
         // Copy over PCC facts, if any.
         for i in 0..num_results {
             let old_result = self.inst_results(inst)[i];
@@ -1043,6 +1675,117 @@ impl DataFlowGraph {
         new_inst
     }
 
+    /// Copy the instructions `insts` into `target`, which may belong to a different function.
+    ///
+    /// Each instruction gets a new counterpart in `target` with new result values, and every
+    /// operand defined by one of `insts` is mapped to the corresponding new value. Signatures,
+    /// external functions, jump tables, constants and the dynamic types of results referenced by
+    /// the instructions are copied to `target` as well. The new instructions aren't inserted in
+    /// any layout.
+    ///
+    /// Operands defined outside of `insts`, such as block parameters, and branch destinations
+    /// are left as-is: they still refer to this DFG's values and blocks, and the caller should
+    /// replace them with their counterparts in the target context. Entities that are owned by
+    /// the `Function` rather than the DFG, like stack slots and global values, aren't copied
+    /// either.
+    ///
+    /// Returns the new instructions in the same order as `insts`, and the mapping from values in
+    /// this DFG to their copies in `target`.
+    pub fn clone_into(
+        &self,
+        insts: &[Inst],
+        target: &mut DataFlowGraph,
+    ) -> (Vec<Inst>, FxHashMap<Value, Value>) {
+        let mut sigs = FxHashMap::default();
+        let mut clone_sig = |sig: SigRef, target: &mut DataFlowGraph| {
+            *sigs
+                .entry(sig)
+                .or_insert_with(|| target.signatures.push(self.signatures[sig].clone()))
+        };
+        let mut funcs = FxHashMap::default();
+        let clone_table = |table: ir::JumpTable, target: &mut DataFlowGraph| {
+            let blocks: SmallVec<[BlockCall; 16]> = self.jump_tables[table]
+                .all_branches()
+                .iter()
+                .map(|call| call.clone_to(&self.value_lists, &mut target.value_lists))
+                .collect();
+            target
+                .jump_tables
+                .push(ir::JumpTableData::new(blocks[0], &blocks[1..]))
+        };
+
+        let mut new_insts = Vec::with_capacity(insts.len());
+        let mut values = FxHashMap::default();
+        for &inst in insts {
+            let mut data = self.insts[inst].clone_to(&self.value_lists, &mut target.value_lists);
+            match &mut data {
+                InstructionData::Call { func_ref, .. }
+                | InstructionData::TryCall { func_ref, .. }
+                | InstructionData::FuncAddr { func_ref, .. } => {
+                    *func_ref = *funcs.entry(*func_ref).or_insert_with(|| {
+                        let mut ext_func = self.ext_funcs[*func_ref].clone();
+                        ext_func.signature = clone_sig(ext_func.signature, target);
+                        target.ext_funcs.push(ext_func)
+                    });
+                }
+                InstructionData::CallIndirect { sig_ref, .. }
+                | InstructionData::TryCallIndirect { sig_ref, .. } => {
+                    *sig_ref = clone_sig(*sig_ref, target);
+                }
+                InstructionData::BranchTable { table, .. } => {
+                    *table = clone_table(*table, target);
+                }
+                InstructionData::UnaryConst {
+                    constant_handle, ..
+                } => {
+                    *constant_handle = target
+                        .constants
+                        .insert(self.constants.get(*constant_handle).clone());
+                }
+                InstructionData::Shuffle { imm, .. } => {
+                    *imm = target.immediates.push(self.immediates[*imm].clone());
+                }
+                _ => {}
+            }
+            if let InstructionData::TryCall { table, .. }
+            | InstructionData::TryCallIndirect { table, .. } = &mut data
+            {
+                *table = clone_table(*table, target);
+            }
+
+            // Dynamic vector types have to be declared in the DFG that uses them.
+            for &result in self.inst_results(inst) {
+                let ty = self.value_type(result);
+                if ty.is_dynamic_vector() && target.check_dynamic_type(ty).is_none() {
+                    let data = self
+                        .dynamic_types
+                        .values()
+                        .find(|data| data.concrete() == Some(ty))
+                        .expect("dynamic type should be declared");
+                    target.make_dynamic_ty(data.clone());
+                }
+            }
+
+            let new_inst = target.make_inst(data);
+            let new_results = target.make_inst_results(new_inst, self.ctrl_typevar(inst));
+            for (&old, &new) in self.inst_results(inst).iter().zip(new_results) {
+                values.insert(old, new);
+            }
+            new_insts.push(new_inst);
+        }
+
+        // Operands can refer to results of instructions later in `insts`, so they are only
+        // mapped once all the results exist.
+        for &new_inst in &new_insts {
+            target.map_inst_values(new_inst, |arg| {
+                let arg = self.resolve_aliases(arg);
+                values.get(&arg).copied().unwrap_or(arg)
+            });
+        }
+
+        (new_insts, values)
+    }
+
     /// Get the first result of an instruction.
     ///
     /// This function panics if the instruction doesn't have any result.
@@ -1057,6 +1800,18 @@ impl DataFlowGraph {
         !self.results[inst].is_empty()
     }
 
+    /// Test if `inst` has more than one result value currently.
+    pub fn has_multiple_results(&self, inst: Inst) -> bool {
+        self.num_results(inst) > 1
+    }
+
+    /// Get the number of result values of `inst`.
+    ///
+    /// This reads the length of the result list without looking at its values.
+    pub fn num_results(&self, inst: Inst) -> usize {
+        self.results[inst].len(&self.value_lists)
+    }
+
     /// Return all the results of an instruction.
     pub fn inst_results(&self, inst: Inst) -> &[Value] {
         self.results[inst].as_slice(&self.value_lists)
@@ -1075,6 +1830,42 @@ impl DataFlowGraph {
         self.make_value(ValueData::Union { ty, x, y })
     }
 
+    /// Merge identical signatures, so each distinct signature is declared once.
+    ///
+    /// The remaining signatures keep their relative order and are renumbered. Every `SigRef` in
+    /// the external function declarations and in instructions, such as `call_indirect` and
+    /// `try_call_indirect`, is rewritten to match, including in instructions that aren't in the
+    /// layout.
+    ///
+    /// Returns the number of signatures that were removed.
+    pub fn dedup_signatures(&mut self) -> usize {
+        let old = mem::take(&mut self.signatures);
+        let num_old = old.len();
+        let mut canonical = FxHashMap::default();
+        let remap: Vec<SigRef> = old
+            .into_iter()
+            .map(|(_, sig)| {
+                *canonical
+                    .entry(sig.clone())
+                    .or_insert_with(|| self.signatures.push(sig))
+            })
+            .collect();
+
+        for ext_func in self.ext_funcs.values_mut() {
+            ext_func.signature = remap[ext_func.signature.index()];
+        }
+        struct Remap<'a>(&'a [SigRef]);
+        impl EntityRefMapper for Remap<'_> {
+            fn map_sig_ref(&mut self, sig_ref: SigRef) -> SigRef {
+                self.0[sig_ref.index()]
+            }
+        }
+        for inst in self.insts.0.values_mut() {
+            inst.map_entity_refs(&mut Remap(&remap));
+        }
+        num_old - self.signatures.len()
+    }
+
     /// Get the call signature of a direct or indirect call instruction.
     /// Returns `None` if `inst` is not a call instruction.
     pub fn call_signature(&self, inst: Inst) -> Option<SigRef> {
@@ -1085,19 +1876,120 @@ impl DataFlowGraph {
         }
     }
 
-    /// Like `call_signature` but returns none for tail call instructions.
-    fn non_tail_call_signature(&self, inst: Inst) -> Option<SigRef> {
+    /// Like `call_signature` but returns none for the calls that don't define results: tail
+    /// calls, and `try_call`s, which pass their results to the normal block instead.
+    fn call_results_signature(&self, inst: Inst) -> Option<SigRef> {
         let sig = self.call_signature(inst)?;
         match self.insts[inst].opcode() {
-            ir::Opcode::ReturnCall | ir::Opcode::ReturnCallIndirect => None,
+            ir::Opcode::ReturnCall
+            | ir::Opcode::ReturnCallIndirect
+            | ir::Opcode::TryCall
+            | ir::Opcode::TryCallIndirect => None,
             _ => Some(sig),
         }
     }
 
+    /// Get the number of parameters of the `index`th destination of the branch `inst` that the
+    /// branch defines itself, ahead of the arguments of its `BlockCall`.
+    ///
+    /// For `try_call` and `try_call_indirect`, these are the return values of the call on the
+    /// normal edge and the exception value on the exception edge. Other branches define none.
+    pub fn num_implicit_block_args(&self, inst: Inst, index: usize) -> usize {
+        match self.insts[inst].opcode() {
+            ir::Opcode::TryCall | ir::Opcode::TryCallIndirect => match index {
+                0 => {
+                    let sig = self.call_signature(inst).unwrap();
+                    self.signatures[sig].returns.len()
+                }
+                _ => 1,
+            },
+            _ => 0,
+        }
+    }
+
+    /// Turn the direct call `inst` into an indirect call of `callee`.
+    ///
+    /// `call` becomes `call_indirect`, `return_call` becomes `return_call_indirect` and
+    /// `try_call` becomes `try_call_indirect`, using the signature of the original callee. The
+    /// arguments, results and destinations are kept, so `callee` must point to a function with
+    /// that signature.
+    pub fn rewrite_call_to_indirect(
+        &mut self,
+        inst: Inst,
+        callee: Value,
+    ) -> Result<(), RewriteError> {
+        let sig_ref = match self.insts[inst].analyze_call(&self.value_lists) {
+            CallInfo::Direct(func_ref, _) => self.ext_funcs[func_ref].signature,
+            _ => return Err(RewriteError::NotADirectCall),
+        };
+        self.insts[inst] = match self.insts[inst] {
+            InstructionData::Call {
+                opcode, mut args, ..
+            } => {
+                args.insert(0, callee, &mut self.value_lists);
+                let opcode = match opcode {
+                    ir::Opcode::ReturnCall => ir::Opcode::ReturnCallIndirect,
+                    _ => ir::Opcode::CallIndirect,
+                };
+                InstructionData::CallIndirect {
+                    opcode,
+                    sig_ref,
+                    args,
+                }
+            }
+            InstructionData::TryCall {
+                mut args, table, ..
+            } => {
+                args.insert(0, callee, &mut self.value_lists);
+                InstructionData::TryCallIndirect {
+                    opcode: ir::Opcode::TryCallIndirect,
+                    sig_ref,
+                    table,
+                    args,
+                }
+            }
+            _ => unreachable!(),
+        };
+        Ok(())
+    }
+
+    /// Simplify `inst` if it is an `ireduce` of a `uextend` or `sextend`.
+    ///
+    /// If the extended value already has the type that `inst` reduces to, the pair is a no-op and
+    /// that value is returned, so the caller can replace the result of `inst` with it, e.g. with
+    /// `change_to_alias`. Otherwise `inst` is rewritten in place to a single instruction on the
+    /// extended value: an `ireduce` if that value is wider than the result, or the same extension
+    /// if it is narrower. `None` is returned in that case, and when `inst` is anything else.
+    pub fn fold_ext_reduce(&mut self, inst: Inst) -> Option<Value> {
+        let InstructionData::Unary {
+            opcode: ir::Opcode::Ireduce,
+            arg,
+        } = self.insts[inst]
+        else {
+            return None;
+        };
+        let ext = self.value_def(self.resolve_aliases(arg)).inst()?;
+        let (ext_opcode, inner) = match self.insts[ext] {
+            InstructionData::Unary {
+                opcode: opcode @ (ir::Opcode::Uextend | ir::Opcode::Sextend),
+                arg,
+            } => (opcode, self.resolve_aliases(arg)),
+            _ => return None,
+        };
+        let ty = self.value_type(self.first_result(inst));
+        let opcode = match self.value_type(inner).bits().cmp(&ty.bits()) {
+            Ordering::Equal => return Some(inner),
+            Ordering::Greater => ir::Opcode::Ireduce,
+            Ordering::Less => ext_opcode,
+        };
+        self.insts[inst] = InstructionData::Unary { opcode, arg: inner };
+        None
+    }
+
     // Only for use by the verifier. Everyone else should just use
-    // `dfg.inst_results(inst).len()`.
+    // `dfg.num_results(inst)`.
     pub(crate) fn num_expected_results_for_verifier(&self, inst: Inst) -> usize {
-        match self.non_tail_call_signature(inst) {
+        match self.call_results_signature(inst) {
             Some(sig) => self.signatures[sig].returns.len(),
             None => {
                 let constraints = self.insts[inst].opcode().constraints();
@@ -1112,7 +2004,7 @@ impl DataFlowGraph {
         inst: Inst,
         ctrl_typevar: Type,
     ) -> impl iter::ExactSizeIterator<Item = Type> + 'a {
-        return match self.non_tail_call_signature(inst) {
+        return match self.call_results_signature(inst) {
             Some(sig) => InstResultTypes::Signature(self, sig, 0),
             None => {
                 let constraints = self.insts[inst].opcode().constraints();
@@ -1202,13 +2094,284 @@ impl DataFlowGraph {
             self.value_type(self.first_result(inst))
         }
     }
-}
 
-/// basic blocks.
-impl DataFlowGraph {
-    /// Create a new basic block.
-    pub fn make_block(&mut self) -> Block {
-        self.blocks.add()
+    /// Check the types of the arguments of `inst`.
+    ///
+    /// For polymorphic opcodes, the controlling type variable must be in the opcode's type set.
+    /// Each fixed argument must satisfy the opcode's constraint for it, and the arguments of a
+    /// call must match the callee's signature, and the arguments passed to each branch destination
+    /// must match the parameters of the destination block. Result types aren't checked; the
+    /// verifier does that.
+    ///
+    /// Returns the first error found. Use `for_each_type_error` to find all of them.
+    pub fn type_check_inst(&self, inst: Inst) -> Result<(), TypeCheckError> {
+        let mut result = Ok(());
+        self.for_each_type_error(inst, |err| {
+            if result.is_ok() {
+                result = Err(err);
+            }
+        });
+        result
+    }
+
+    /// Check the types of the arguments of `inst` like `type_check_inst`, calling `report` for
+    /// each error found instead of stopping at the first one.
+    ///
+    /// The errors are reported in order: the controlling type variable, the fixed arguments, the
+    /// branch destinations and finally the call arguments.
+    pub fn for_each_type_error(&self, inst: Inst, mut report: impl FnMut(TypeCheckError)) {
+        let constraints = self.insts[inst].opcode().constraints();
+
+        let ctrl_type = match constraints.ctrl_typeset() {
+            Some(allowed) => {
+                let found = self.ctrl_typevar(inst);
+                if !allowed.contains(found) {
+                    report(TypeCheckError::CtrlType { allowed, found });
+                }
+                found
+            }
+            None => types::INVALID,
+        };
+
+        for (index, &arg) in self.inst_fixed_args(inst).iter().enumerate() {
+            let found = self.value_type(arg);
+            let expected = constraints.value_argument_constraint(index, ctrl_type);
+            let ok = match expected {
+                ResolvedConstraint::Bound(ty) => found == ty,
+                ResolvedConstraint::Free(allowed) => allowed.contains(found),
+            };
+            if !ok {
+                report(TypeCheckError::FixedArg {
+                    index,
+                    arg,
+                    expected,
+                    found,
+                });
+            }
+        }
+
+        for (i, &call) in self.insts[inst]
+            .branch_destination(&self.jump_tables)
+            .iter()
+            .enumerate()
+        {
+            let implicit = self.num_implicit_block_args(inst, i);
+            self.for_each_block_call_type_error(call, implicit, &mut report);
+        }
+
+        let (sig_ref, args) = match self.insts[inst].analyze_call(&self.value_lists) {
+            CallInfo::Direct(func_ref, args) => (self.ext_funcs[func_ref].signature, args),
+            CallInfo::Indirect(sig_ref, args) => (sig_ref, args),
+            CallInfo::NotACall => return,
+        };
+        let params = &self.signatures[sig_ref].params;
+        for (index, (&arg, param)) in args.iter().zip(params).enumerate() {
+            let found = self.value_type(arg);
+            if found != param.value_type {
+                report(TypeCheckError::CallArg {
+                    index,
+                    arg,
+                    expected: param.value_type,
+                    found,
+                });
+            }
+        }
+        if args.len() != params.len() {
+            report(TypeCheckError::CallArgCount {
+                expected: params.len(),
+                found: args.len(),
+            });
+        }
+    }
+
+    /// Check that the arguments of `call` match the parameters of the block it branches to.
+    ///
+    /// The argument types are checked before their number, so a `BlockArgCount` error means that
+    /// all the arguments that were passed have the right types.
+    pub fn type_check_block_call(&self, call: BlockCall) -> Result<(), TypeCheckError> {
+        let mut result = Ok(());
+        self.for_each_block_call_type_error(call, 0, &mut |err| {
+            if result.is_ok() {
+                result = Err(err);
+            }
+        });
+        result
+    }
+
+    /// Check the arguments of `call` against the parameters of its block after the first
+    /// `implicit` ones, which the branch defines itself.
+    fn for_each_block_call_type_error(
+        &self,
+        call: BlockCall,
+        implicit: usize,
+        report: &mut impl FnMut(TypeCheckError),
+    ) {
+        let block = call.block(&self.value_lists);
+        let args = call.args_slice(&self.value_lists);
+        let params = self.block_params(block).get(implicit..).unwrap_or_default();
+        for (index, (&arg, &param)) in args.iter().zip(params).enumerate() {
+            let expected = self.value_type(param);
+            let found = self.value_type(arg);
+            if found != expected {
+                report(TypeCheckError::BlockArg {
+                    block,
+                    index,
+                    arg,
+                    expected,
+                    found,
+                });
+            }
+        }
+        if args.len() != params.len() {
+            report(TypeCheckError::BlockArgCount {
+                block,
+                expected: params.len(),
+                found: args.len(),
+            });
+        }
+    }
+}
+
+/// A type error found by `DataFlowGraph::type_check_inst`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TypeCheckError {
+    /// The controlling type variable isn't in the opcode's type set.
+    CtrlType {
+        /// The types allowed by the opcode.
+        allowed: ValueTypeSet,
+        /// The controlling type variable of the instruction.
+        found: Type,
+    },
+    /// A fixed argument doesn't satisfy the opcode's constraint.
+    FixedArg {
+        /// The index of the argument among the fixed arguments.
+        index: usize,
+        /// The argument.
+        arg: Value,
+        /// The constraint on the argument.
+        expected: ResolvedConstraint,
+        /// The type of the argument.
+        found: Type,
+    },
+    /// A call has the wrong number of arguments for the callee's signature.
+    CallArgCount {
+        /// The number of parameters in the signature.
+        expected: usize,
+        /// The number of arguments passed.
+        found: usize,
+    },
+    /// A call argument doesn't have the type of the corresponding signature parameter.
+    CallArg {
+        /// The index of the argument among the call arguments.
+        index: usize,
+        /// The argument.
+        arg: Value,
+        /// The type of the parameter.
+        expected: Type,
+        /// The type of the argument.
+        found: Type,
+    },
+    /// A branch passes the wrong number of arguments to its destination.
+    BlockArgCount {
+        /// The destination block.
+        block: Block,
+        /// The number of parameters of the block.
+        expected: usize,
+        /// The number of arguments passed.
+        found: usize,
+    },
+    /// A branch argument doesn't have the type of the corresponding block parameter.
+    BlockArg {
+        /// The destination block.
+        block: Block,
+        /// The index of the argument among the arguments passed to `block`.
+        index: usize,
+        /// The argument.
+        arg: Value,
+        /// The type of the block parameter.
+        expected: Type,
+        /// The type of the argument.
+        found: Type,
+    },
+}
+
+impl fmt::Display for TypeCheckError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::CtrlType { allowed, found } => write!(
+                f,
+                "invalid controlling type {found} (allowed set is {allowed:?})"
+            ),
+            Self::FixedArg {
+                index,
+                arg,
+                expected: ResolvedConstraint::Bound(expected),
+                found,
+            }
+            | Self::CallArg {
+                index,
+                arg,
+                expected,
+                found,
+            } => write!(
+                f,
+                "arg {index} ({arg}) has type {found}, expected {expected}"
+            ),
+            Self::FixedArg {
+                index,
+                arg,
+                expected: ResolvedConstraint::Free(allowed),
+                found,
+            } => write!(
+                f,
+                "arg {index} ({arg}) with type {found} failed to satisfy type set {allowed:?}"
+            ),
+            Self::CallArgCount { expected, found } => write!(
+                f,
+                "call has {found} arguments, but the signature has {expected} parameters"
+            ),
+            Self::BlockArgCount {
+                block,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{found} arguments passed to {block}, which has {expected} parameters"
+            ),
+            Self::BlockArg {
+                block,
+                index,
+                arg,
+                expected,
+                found,
+            } => write!(
+                f,
+                "arg {index} ({arg}) has type {found}, expected {expected} (passed to {block})"
+            ),
+        }
+    }
+}
+
+/// An error from rewriting an instruction in place.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RewriteError {
+    /// The instruction isn't a direct call.
+    NotADirectCall,
+}
+
+impl fmt::Display for RewriteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::NotADirectCall => write!(f, "instruction is not a direct call"),
+        }
+    }
+}
+
+/// basic blocks.
+impl DataFlowGraph {
+    /// Create a new basic block.
+    pub fn make_block(&mut self) -> Block {
+        self.blocks.add()
     }
 
     /// Get the number of parameters on `block`.
@@ -1226,16 +2389,51 @@ impl DataFlowGraph {
         self.block_params(block).iter().map(|&v| self.value_type(v))
     }
 
+    /// Get the type of the parameter at `index` on `block`.
+    ///
+    /// Panics if `block` has no parameter at `index`.
+    pub fn block_param_type(&self, block: Block, index: usize) -> Type {
+        self.value_type(self.block_params(block)[index])
+    }
+
     /// Append a parameter with type `ty` to `block`.
+    ///
+    /// Panics if `block` already has the maximum number of parameters. Use
+    /// `try_append_block_param` to handle that case gracefully.
     pub fn append_block_param(&mut self, block: Block, ty: Type) -> Value {
+        self.try_append_block_param(block, ty)
+            .expect("Too many parameters on block")
+    }
+
+    /// Append a parameter with type `ty` to `block`.
+    ///
+    /// Returns `CodegenError::ImplLimitExceeded` if `block` already has the maximum number of
+    /// parameters.
+    pub fn try_append_block_param(&mut self, block: Block, ty: Type) -> CodegenResult<Value> {
+        self.append_block_param_with_limit(block, ty, MAX_BLOCK_PARAMS)
+    }
+
+    /// Append a parameter with type `ty` to `block`, unless it already has `max_params`.
+    fn append_block_param_with_limit(
+        &mut self,
+        block: Block,
+        ty: Type,
+        max_params: usize,
+    ) -> CodegenResult<Value> {
+        let num = self.next_block_param_num(block, max_params)?;
         let param = self.values.next_key();
-        let num = self.blocks[block].params.push(param, &mut self.value_lists);
-        debug_assert!(num <= u16::MAX as usize, "Too many parameters on block");
-        self.make_value(ValueData::Param {
-            ty,
-            num: num as u16,
-            block,
-        })
+        self.blocks[block].params.push(param, &mut self.value_lists);
+        Ok(self.make_value(ValueData::Param { ty, num, block }))
+    }
+
+    /// Get the number of the next parameter of `block`, if it has fewer than `max_params`.
+    fn next_block_param_num(&self, block: Block, max_params: usize) -> CodegenResult<u16> {
+        debug_assert!(max_params <= MAX_BLOCK_PARAMS);
+        let num = self.num_block_params(block);
+        if num >= max_params {
+            return Err(CodegenError::ImplLimitExceeded);
+        }
+        Ok(num as u16)
     }
 
     /// Removes `val` from `block`'s parameters by swapping it with the last parameter on `block`.
@@ -1316,16 +2514,21 @@ impl DataFlowGraph {
     ///
     /// In almost all cases, you should be using `append_block_param()` instead of this method.
     pub fn attach_block_param(&mut self, block: Block, param: Value) {
+        self.try_attach_block_param(block, param)
+            .expect("Too many parameters on block")
+    }
+
+    /// Append an existing value to `block`'s parameters.
+    ///
+    /// Like `attach_block_param`, but returns `CodegenError::ImplLimitExceeded` instead of
+    /// panicking if `block` already has the maximum number of parameters.
+    pub fn try_attach_block_param(&mut self, block: Block, param: Value) -> CodegenResult<()> {
         debug_assert!(!self.value_is_attached(param));
-        let num = self.blocks[block].params.push(param, &mut self.value_lists);
-        debug_assert!(num <= u16::MAX as usize, "Too many parameters on block");
+        let num = self.next_block_param_num(block, MAX_BLOCK_PARAMS)?;
+        self.blocks[block].params.push(param, &mut self.value_lists);
         let ty = self.value_type(param);
-        self.values[param] = ValueData::Param {
-            ty,
-            num: num as u16,
-            block,
-        }
-        .into();
+        self.values[param] = ValueData::Param { ty, num, block }.into();
+        Ok(())
     }
 
     /// Replace a block parameter with a new value of type `ty`.
@@ -1501,6 +2704,7 @@ impl DataFlowGraph {
         }
 
         self.make_inst_results_reusing(inst, ctrl_typevar, reuse.iter().map(|x| Some(*x)))
+            .len()
     }
 
     /// Similar to `append_block_param`, append a parameter with type `ty` to
@@ -1637,6 +2841,216 @@ mod tests {
         assert_eq!(dfg.value_type(v2), types::F64);
     }
 
+    #[test]
+    fn insert_before() {
+        use crate::ir::InstBuilder;
+
+        let mut func = Function::new();
+        let block0 = func.dfg.make_block();
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let v0 = pos.ins().iconst(types::I32, 0);
+        let ret = pos.ins().return_(&[]);
+
+        let data = InstructionData::Unary {
+            opcode: Opcode::Ineg,
+            arg: v0,
+        };
+        let inst = func
+            .stencil
+            .dfg
+            .insert_before(&mut func.stencil.layout, ret, data);
+        assert_eq!(func.layout.inst_block(inst), Some(block0));
+        assert_eq!(func.layout.next_inst(inst), Some(ret));
+        assert!(!func.dfg.has_results(inst));
+
+        func.dfg.make_inst_results(inst, types::I32);
+        assert_eq!(
+            func.dfg.display_inst(inst).to_string(),
+            "v1 = ineg.i32 v0  ; v0 = 0"
+        );
+    }
+
+    #[test]
+    fn insert_after() {
+        use crate::ir::InstBuilder;
+
+        let mut func = Function::new();
+        let block0 = func.dfg.make_block();
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let v0 = pos.ins().iconst(types::I32, 0);
+        let iconst = pos.func.dfg.value_def(v0).unwrap_inst();
+        let ret = pos.ins().return_(&[]);
+
+        let data = InstructionData::Unary {
+            opcode: Opcode::Ineg,
+            arg: v0,
+        };
+        let inst = func
+            .stencil
+            .dfg
+            .insert_after(&mut func.stencil.layout, iconst, data);
+        assert_eq!(func.layout.prev_inst(inst), Some(iconst));
+        assert_eq!(func.layout.next_inst(inst), Some(ret));
+
+        // An unterminated block can be appended to.
+        func.layout.remove_inst(ret);
+        let last = func
+            .stencil
+            .dfg
+            .insert_after(&mut func.stencil.layout, inst, data);
+        assert_eq!(func.layout.last_inst(block0), Some(last));
+    }
+
+    #[test]
+    #[should_panic(expected = "after the terminator `return`")]
+    fn insert_after_terminator() {
+        use crate::ir::InstBuilder;
+
+        let mut func = Function::new();
+        let block0 = func.dfg.make_block();
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let ret = pos.ins().return_(&[]);
+
+        let data = InstructionData::NullAry {
+            opcode: Opcode::Nop,
+        };
+        func.stencil
+            .dfg
+            .insert_after(&mut func.stencil.layout, ret, data);
+    }
+
+    #[test]
+    fn type_check_inst() {
+        use crate::ir::{AbiParam, InstBuilder};
+
+        let mut func = Function::new();
+        let mut sig = Signature::new(crate::isa::CallConv::SystemV);
+        sig.params.push(AbiParam::new(types::I32));
+        let sig = func.import_signature(sig);
+        let block0 = func.dfg.make_block();
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let v0 = pos.ins().iconst(types::I32, 0);
+        let v1 = pos.ins().iconst(types::I64, 0);
+        let v2 = pos.ins().f32const(0.0);
+        let sum = pos.ins().iadd(v0, v0);
+        let add = pos.func.dfg.value_def(sum).unwrap_inst();
+        let call = pos.ins().call_indirect(sig, v1, &[v0]);
+        let dfg = &mut pos.func.dfg;
+
+        assert_eq!(dfg.type_check_inst(add), Ok(()));
+        assert_eq!(dfg.type_check_inst(call), Ok(()));
+
+        dfg.inst_args_mut(add)[1] = v1;
+        let err = dfg.type_check_inst(add).unwrap_err();
+        assert_eq!(
+            err,
+            TypeCheckError::FixedArg {
+                index: 1,
+                arg: v1,
+                expected: ResolvedConstraint::Bound(types::I32),
+                found: types::I64,
+            }
+        );
+        assert_eq!(err.to_string(), "arg 1 (v1) has type i64, expected i32");
+
+        // The controlling type of `iadd` is the type of its result.
+        dfg.inst_args_mut(add)[0] = v2;
+        dfg.inst_args_mut(add)[1] = v2;
+        dfg.replace_result(sum, types::F32);
+        assert!(matches!(
+            dfg.type_check_inst(add),
+            Err(TypeCheckError::CtrlType {
+                found: types::F32,
+                ..
+            })
+        ));
+
+        dfg.inst_variable_args_mut(call)[0] = v1;
+        assert_eq!(
+            dfg.type_check_inst(call),
+            Err(TypeCheckError::CallArg {
+                index: 0,
+                arg: v1,
+                expected: types::I32,
+                found: types::I64,
+            })
+        );
+
+        // Every error is reported, with the argument count last.
+        if let InstructionData::CallIndirect { args, .. } = &mut dfg.insts[call] {
+            args.push(v0, &mut dfg.value_lists);
+        }
+        let mut errors = Vec::new();
+        dfg.for_each_type_error(call, |err| errors.push(err));
+        assert_eq!(
+            errors,
+            [
+                TypeCheckError::CallArg {
+                    index: 0,
+                    arg: v1,
+                    expected: types::I32,
+                    found: types::I64,
+                },
+                TypeCheckError::CallArgCount {
+                    expected: 1,
+                    found: 2,
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn type_check_block_args() {
+        use crate::ir::InstBuilder;
+
+        let mut func = Function::new();
+        let block0 = func.dfg.make_block();
+        let block1 = func.dfg.make_block();
+        func.dfg.append_block_param(block1, types::I32);
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let v0 = pos.ins().iconst(types::I32, 0);
+        let v1 = pos.ins().iconst(types::I64, 0);
+        let jump = pos.ins().jump(block1, &[v0]);
+        let dfg = &mut pos.func.dfg;
+
+        assert_eq!(dfg.type_check_inst(jump), Ok(()));
+
+        let call = &mut dfg.insts[jump].branch_destination_mut(&mut dfg.jump_tables)[0];
+        call.args_slice_mut(&mut dfg.value_lists)[0] = v1;
+        let err = dfg.type_check_inst(jump).unwrap_err();
+        assert_eq!(
+            err,
+            TypeCheckError::BlockArg {
+                block: block1,
+                index: 0,
+                arg: v1,
+                expected: types::I32,
+                found: types::I64,
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "arg 0 (v2) has type i64, expected i32 (passed to block1)"
+        );
+
+        let call = &mut dfg.insts[jump].branch_destination_mut(&mut dfg.jump_tables)[0];
+        call.args_slice_mut(&mut dfg.value_lists)[0] = v0;
+        call.append_argument(v0, &mut dfg.value_lists);
+        assert_eq!(
+            dfg.type_check_inst(jump),
+            Err(TypeCheckError::BlockArgCount {
+                block: block1,
+                expected: 1,
+                found: 2,
+            })
+        );
+    }
+
     #[test]
     fn no_results() {
         let mut dfg = DataFlowGraph::new();
@@ -1650,6 +3064,313 @@ mod tests {
 
         // Result slice should be empty.
         assert_eq!(dfg.inst_results(inst), &[]);
+        assert_eq!(dfg.num_results(inst), 0);
+    }
+
+    #[test]
+    fn make_const() {
+        let mut dfg = DataFlowGraph::new();
+        let display = |dfg: &DataFlowGraph, v| dfg.display_value_inst(v).to_string();
+
+        let v = dfg.make_const(types::I8, &[0xff]);
+        assert_eq!(display(&dfg, v), "v0 = iconst.i8 -1");
+        let inst = dfg.value_def(v).unwrap_inst();
+        match dfg.insts[inst] {
+            InstructionData::UnaryImm { imm, .. } => assert_eq!(imm.bits(), 0xff),
+            _ => panic!("expected an iconst"),
+        }
+        let v = dfg.make_const(types::I64, &(-2i64).to_le_bytes());
+        assert_eq!(display(&dfg, v), "v1 = iconst.i64 -2");
+        let v = dfg.make_const(types::F32, &1.0f32.to_le_bytes());
+        assert_eq!(display(&dfg, v), "v2 = f32const 0x1.000000p0");
+        let v = dfg.make_const(types::F64, &(-0.5f64).to_le_bytes());
+        assert_eq!(display(&dfg, v), "v3 = f64const -0x1.0000000000000p-1");
+        assert_eq!(dfg.constants.len(), 0);
+
+        let bytes: Vec<u8> = (0..16).collect();
+        let v = dfg.make_const(types::I32X4, &bytes);
+        assert_eq!(display(&dfg, v), "v4 = vconst.i32x4 const0");
+        assert_eq!(dfg.value_type(v), types::I32X4);
+        let v = dfg.make_const(types::F128, &bytes);
+        assert_eq!(display(&dfg, v), "v5 = f128const const0");
+        assert_eq!(dfg.value_type(v), types::F128);
+        assert_eq!(dfg.constants.len(), 1);
+        assert_eq!(
+            dfg.constants.get(ir::Constant::new(0)).as_slice(),
+            &bytes[..]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "no constant instruction for type i128")]
+    fn make_const_i128() {
+        DataFlowGraph::new().make_const(types::I128, &[0; 16]);
+    }
+
+    #[test]
+    fn remake_inst_results() {
+        use crate::ir::{AbiParam, InstBuilder};
+        use crate::settings;
+
+        let mut func = Function::new();
+        func.signature.returns.push(AbiParam::new(types::I32));
+        let mut sig = Signature::new(crate::isa::CallConv::SystemV);
+        sig.returns.push(AbiParam::new(types::I32));
+        let sig0 = func.import_signature(sig.clone());
+        sig.returns.push(AbiParam::new(types::I64));
+        let sig1 = func.import_signature(sig);
+        let block0 = func.dfg.make_block();
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let callee = pos.ins().iconst(types::I64, 0);
+        let call = pos.ins().call_indirect(sig0, callee, &[]);
+        let v2 = pos.func.dfg.first_result(call);
+        assert!(!pos.func.dfg.has_multiple_results(call));
+        pos.ins().return_(&[v2]);
+        let func = pos.func;
+        let num_values = func.dfg.num_values();
+
+        // Adding a result to the call keeps the existing one, which is still in use.
+        match &mut func.dfg.insts[call] {
+            InstructionData::CallIndirect { sig_ref, .. } => *sig_ref = sig1,
+            _ => unreachable!(),
+        }
+        let results = func.dfg.remake_inst_results(call, types::INVALID);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0], v2);
+        let v3 = results[1];
+        assert_eq!(func.dfg.value_type(v3), types::I64);
+        assert_eq!(func.dfg.value_def(v3), ValueDef::Result(call, 1));
+        assert_eq!(func.dfg.num_values(), num_values + 1);
+
+        // Calling it again with the same signature doesn't create any values.
+        assert_eq!(
+            func.dfg.remake_inst_results(call, types::INVALID),
+            &[v2, v3]
+        );
+        assert_eq!(func.dfg.num_values(), num_values + 1);
+        assert_eq!(func.dfg.num_results(call), 2);
+        assert!(func.dfg.has_multiple_results(call));
+
+        let flags = settings::Flags::new(settings::builder());
+        crate::verifier::verify_function(&*func, &flags).unwrap();
+
+        // `make_inst_results` always makes new values.
+        let results = func.dfg.make_inst_results(call, types::INVALID).to_vec();
+        assert_eq!(func.dfg.num_values(), num_values + 3);
+        assert!(!results.contains(&v2) && !results.contains(&v3));
+        assert!(!func.dfg.value_is_attached(v2));
+    }
+
+    #[test]
+    fn transfer_results() {
+        use crate::ir::{InstBuilder, MemFlags};
+
+        let mut func = Function::new();
+        let block0 = func.dfg.make_block();
+        let v0 = func.dfg.append_block_param(block0, types::I64);
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let (sum, overflow) = pos.ins().uadd_overflow(v0, v0);
+        let loaded = pos.ins().load(types::I32, MemFlags::trusted(), v0, 0);
+        let dfg = &mut pos.func.dfg;
+
+        // The controlling type variable comes from an operand.
+        let from = dfg.value_def(sum).unwrap_inst();
+        let to = dfg.make_inst(dfg.insts[from]);
+        dfg.transfer_results(from, to);
+        assert!(!dfg.has_results(from));
+        assert_eq!(dfg.inst_results(to), &[sum, overflow]);
+        assert_eq!(dfg.value_def(overflow), ValueDef::Result(to, 1));
+
+        // The controlling type variable comes from the first result.
+        let from = dfg.value_def(loaded).unwrap_inst();
+        let to = dfg.make_inst(dfg.insts[from]);
+        dfg.transfer_results(from, to);
+        assert_eq!(dfg.inst_results(to), &[loaded]);
+        assert_eq!(dfg.value_type(loaded), types::I32);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "is still attached")]
+    fn reuse_attached_result() {
+        use crate::ir::InstBuilder;
+
+        let mut func = Function::new();
+        let block0 = func.dfg.make_block();
+        let v0 = func.dfg.append_block_param(block0, types::I32);
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let (sum, overflow) = pos.ins().uadd_overflow(v0, v0);
+        let dfg = &mut pos.func.dfg;
+
+        // Reusing results that weren't detached from the original instruction would leave two
+        // instructions claiming them.
+        let from = dfg.value_def(sum).unwrap_inst();
+        let to = dfg.make_inst(dfg.insts[from]);
+        dfg.make_inst_results_reusing(to, types::I32, [Some(sum), Some(overflow)].into_iter());
+    }
+
+    #[test]
+    fn inst_format() {
+        let mut dfg = DataFlowGraph::new();
+        let v0 = dfg.make_const(types::I32, &[1, 0, 0, 0]);
+        let iconst = dfg.value_def(v0).unwrap_inst();
+        let inst = dfg.make_inst(InstructionData::Binary {
+            opcode: Opcode::Iadd,
+            args: [v0, v0],
+        });
+        assert_eq!(dfg.inst_format(iconst), InstructionFormat::UnaryImm);
+        assert_eq!(dfg.inst_format(inst), InstructionFormat::Binary);
+    }
+
+    #[test]
+    fn fold_ext_reduce() {
+        use crate::ir::{AbiParam, InstBuilder};
+        use crate::settings;
+
+        let mut func = Function::new();
+        func.signature.params.push(AbiParam::new(types::I8));
+        func.signature.params.push(AbiParam::new(types::I32));
+        let block0 = func.dfg.make_block();
+        let v0 = func.dfg.append_block_param(block0, types::I8);
+        let v1 = func.dfg.append_block_param(block0, types::I32);
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let ext0 = pos.ins().uextend(types::I32, v0);
+        let v2 = pos.ins().ireduce(types::I8, ext0);
+        let ext1 = pos.ins().sextend(types::I64, v0);
+        let v3 = pos.ins().ireduce(types::I16, ext1);
+        let ext2 = pos.ins().uextend(types::I64, v1);
+        let v4 = pos.ins().ireduce(types::I16, ext2);
+        let sum = pos.ins().iadd(v1, v1);
+        let v5 = pos.ins().ireduce(types::I8, sum);
+        pos.ins().return_(&[]);
+        let dfg = &mut pos.func.dfg;
+        let inst = |dfg: &DataFlowGraph, v| dfg.value_def(v).unwrap_inst();
+        let display = |dfg: &DataFlowGraph, v| dfg.display_value_inst(v).to_string();
+
+        // The extended value has the reduced type already.
+        assert_eq!(dfg.fold_ext_reduce(inst(dfg, v2)), Some(v0));
+        // It is narrower, so the reduction becomes a narrower extension.
+        assert_eq!(dfg.fold_ext_reduce(inst(dfg, v3)), None);
+        assert_eq!(display(dfg, v3), "v5 = sextend.i16 v0");
+        // It is wider, so the extension is skipped.
+        assert_eq!(dfg.fold_ext_reduce(inst(dfg, v4)), None);
+        assert_eq!(display(dfg, v4), "v7 = ireduce.i16 v1");
+        // Other instructions are left alone.
+        assert_eq!(dfg.fold_ext_reduce(inst(dfg, v5)), None);
+        assert_eq!(dfg.fold_ext_reduce(inst(dfg, ext0)), None);
+        assert_eq!(display(dfg, v5), "v9 = ireduce.i8 v8");
+
+        let flags = settings::Flags::new(settings::builder());
+        crate::verifier::verify_function(&func, &flags).unwrap();
+    }
+
+    #[test]
+    fn rewrite_call_to_indirect() {
+        use crate::ir::{AbiParam, ExtFuncData, ExternalName, InstBuilder};
+        use crate::settings;
+
+        let mut func = Function::new();
+        func.signature.returns.push(AbiParam::new(types::I32));
+        let mut sig = Signature::new(crate::isa::CallConv::SystemV);
+        sig.params.push(AbiParam::new(types::I32));
+        sig.returns.push(AbiParam::new(types::I32));
+        let signature = func.import_signature(sig);
+        let fn0 = func.import_function(ExtFuncData {
+            name: ExternalName::testcase("f"),
+            signature,
+            colocated: false,
+        });
+        let block0 = func.dfg.make_block();
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let v0 = pos.ins().iconst(types::I32, 7);
+        let callee = pos.ins().iconst(types::I64, 0);
+        let call = pos.ins().call(fn0, &[v0]);
+        let v1 = pos.func.dfg.first_result(call);
+        let ret = pos.ins().return_(&[v1]);
+        let func = pos.func;
+
+        assert_eq!(
+            func.dfg.rewrite_call_to_indirect(ret, callee),
+            Err(RewriteError::NotADirectCall)
+        );
+        assert_eq!(func.dfg.rewrite_call_to_indirect(call, callee), Ok(()));
+        assert_eq!(func.dfg.insts[call].opcode(), Opcode::CallIndirect);
+        assert_eq!(func.dfg.call_signature(call), Some(signature));
+        assert_eq!(func.dfg.inst_args(call), &[callee, v0]);
+        assert_eq!(func.dfg.inst_results(call), &[v1]);
+        assert_eq!(
+            func.dfg.rewrite_call_to_indirect(call, callee),
+            Err(RewriteError::NotADirectCall)
+        );
+
+        let flags = settings::Flags::new(settings::builder());
+        crate::verifier::verify_function(&*func, &flags).unwrap();
+    }
+
+    #[test]
+    fn rewrite_try_call_to_indirect() {
+        use crate::ir::{AbiParam, ExtFuncData, ExternalName, InstBuilder, JumpTableData};
+        use crate::settings;
+
+        let mut func = Function::new();
+        func.signature.returns.push(AbiParam::new(types::I32));
+        let mut sig = Signature::new(crate::isa::CallConv::SystemV);
+        sig.params.push(AbiParam::new(types::I32));
+        sig.returns.push(AbiParam::new(types::I32));
+        let signature = func.import_signature(sig);
+        let fn0 = func.import_function(ExtFuncData {
+            name: ExternalName::testcase("f"),
+            signature,
+            colocated: false,
+        });
+        let block0 = func.dfg.make_block();
+        let block1 = func.dfg.make_block();
+        let v1 = func.dfg.append_block_param(block1, types::I32);
+        let block2 = func.dfg.make_block();
+        func.dfg.append_block_param(block2, types::I64);
+        let v3 = func.dfg.append_block_param(block2, types::I32);
+
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let v0 = pos.ins().iconst(types::I32, 7);
+        let callee = pos.ins().iconst(types::I64, 0);
+        let normal = pos.func.dfg.block_call(block1, &[]);
+        let exception = pos.func.dfg.block_call(block2, &[v0]);
+        let table = pos
+            .func
+            .create_jump_table(JumpTableData::new(normal, &[exception]));
+        let try_call = pos.ins().try_call(fn0, table, &[v0]);
+        pos.insert_block(block1);
+        pos.ins().return_(&[v1]);
+        pos.insert_block(block2);
+        pos.ins().return_(&[v3]);
+        let func = pos.func;
+
+        assert_eq!(func.dfg.num_implicit_block_args(try_call, 0), 1);
+        assert_eq!(func.dfg.num_implicit_block_args(try_call, 1), 1);
+        assert_eq!(func.dfg.rewrite_call_to_indirect(try_call, callee), Ok(()));
+        assert_eq!(func.dfg.insts[try_call].opcode(), Opcode::TryCallIndirect);
+        assert_eq!(func.dfg.call_signature(try_call), Some(signature));
+        assert_eq!(func.dfg.inst_args(try_call), &[callee, v0]);
+        assert_eq!(func.dfg.inst_results(try_call), &[]);
+        let dests: Vec<_> = func.dfg.insts[try_call]
+            .branch_destination(&func.dfg.jump_tables)
+            .iter()
+            .map(|call| call.block(&func.dfg.value_lists))
+            .collect();
+        assert_eq!(dests, [block1, block2]);
+        assert_eq!(
+            func.dfg.rewrite_call_to_indirect(try_call, callee),
+            Err(RewriteError::NotADirectCall)
+        );
+
+        let flags = settings::Flags::new(settings::builder());
+        crate::verifier::verify_function(&*func, &flags).unwrap();
     }
 
     #[test]
@@ -1688,6 +3409,38 @@ mod tests {
         let arg3 = dfg.append_block_param(block, types::I32);
         dfg.attach_block_param(block, arg1);
         assert_eq!(dfg.block_params(block), &[arg2, arg3, arg1]);
+        assert_eq!(dfg.block_param_type(block, 0), types::I16);
+        assert_eq!(dfg.block_param_type(block, 1), types::I32);
+        assert_eq!(dfg.block_param_type(block, 2), types::F32);
+    }
+
+    #[test]
+    #[should_panic]
+    fn block_param_type_out_of_bounds() {
+        let mut dfg = DataFlowGraph::new();
+        let block = dfg.make_block();
+        dfg.append_block_param(block, types::I32);
+        dfg.block_param_type(block, 1);
+    }
+
+    #[test]
+    fn too_many_block_params() {
+        let mut dfg = DataFlowGraph::new();
+
+        // Use a lowered limit; reaching `MAX_BLOCK_PARAMS` takes too long in a test.
+        let block = dfg.make_block();
+        for _ in 0..4 {
+            dfg.append_block_param_with_limit(block, types::I32, 4)
+                .unwrap();
+        }
+        assert_eq!(dfg.num_block_params(block), 4);
+        assert!(matches!(
+            dfg.append_block_param_with_limit(block, types::I32, 4),
+            Err(CodegenError::ImplLimitExceeded)
+        ));
+        assert_eq!(dfg.num_block_params(block), 4);
+        let v = dfg.append_block_param(block, types::I32);
+        assert_eq!(dfg.value_def(v).num(), 4);
     }
 
     #[test]
@@ -1779,6 +3532,421 @@ mod tests {
         assert_eq!(pos.func.dfg.resolve_aliases(c), c2);
     }
 
+    #[test]
+    fn rename_value() {
+        use crate::ir::InstBuilder;
+
+        let mut func = Function::new();
+        let block0 = func.dfg.make_block();
+        let block1 = func.dfg.make_block();
+        let v0 = func.dfg.append_block_param(block0, types::I32);
+        let v1 = func.dfg.append_block_param(block1, types::I32);
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let v2 = pos.ins().iadd(v0, v0);
+        let jump = pos.ins().jump(block1, &[v2]);
+        pos.insert_block(block1);
+        let v3 = pos.ins().iadd(v1, v1);
+        pos.ins().return_(&[v3]);
+        let [add0, add1] = [v2, v3].map(|v| func.dfg.value_def(v).unwrap_inst());
+
+        // An instruction that isn't in the layout.
+        let dead = func.dfg.make_inst(InstructionData::Binary {
+            opcode: Opcode::Iadd,
+            args: [v1, v2],
+        });
+        func.dfg.make_inst_results(dead, types::I32);
+
+        // Make two detached values.
+        let spare = func.dfg.make_block();
+        let new1 = func.dfg.append_block_param(spare, types::I32);
+        let new2 = func.dfg.append_block_param(spare, types::I32);
+        func.dfg.detach_block_params(spare);
+
+        let dfg = &mut func.stencil.dfg;
+        dfg.rename_value(v1, new1, &func.stencil.layout);
+        dfg.rename_value(v2, new2, &func.stencil.layout);
+
+        assert_eq!(dfg.block_params(block1), &[new1]);
+        assert_eq!(dfg.value_def(new1), ValueDef::Param(block1, 0));
+        assert_eq!(dfg.inst_args(add1), &[new1, new1]);
+        assert_eq!(dfg.inst_results(add0), &[new2]);
+        assert_eq!(dfg.value_def(new2), ValueDef::Result(add0, 0));
+        let call = dfg.insts[jump].branch_destination(&dfg.jump_tables)[0];
+        assert_eq!(call.args_slice(&dfg.value_lists), &[new2]);
+
+        // Uses outside the layout are left to the aliases.
+        assert_eq!(dfg.inst_args(dead), &[v1, v2]);
+        assert_eq!(dfg.resolve_aliases(v1), new1);
+        assert_eq!(dfg.resolve_aliases(v2), new2);
+        assert!(!dfg.value_is_attached(v1));
+    }
+
+    #[test]
+    fn aliases_of() {
+        let mut dfg = DataFlowGraph::new();
+        let block = dfg.make_block();
+        let v0 = dfg.append_block_param(block, types::I32);
+        let v1 = dfg.make_value(ValueData::Alias {
+            ty: types::I32,
+            original: v0,
+        });
+        let v2 = dfg.make_value(ValueData::Alias {
+            ty: types::I32,
+            original: v1,
+        });
+        let v3 = dfg.make_value(ValueData::Alias {
+            ty: types::I32,
+            original: v2,
+        });
+        let v4 = dfg.make_value(ValueData::Alias {
+            ty: types::I32,
+            original: v0,
+        });
+        let v5 = dfg.make_value(ValueData::Alias {
+            ty: types::I32,
+            original: v0,
+        });
+
+        // A chain `v3 -> v2 -> v1 -> v0`, and a fan-out of `v4` and `v5` to `v0`.
+        let aliases: Vec<_> = dfg.aliases_of(v0).collect();
+        assert_eq!(aliases, [v1, v2, v3, v4, v5]);
+        let aliases: Vec<_> = dfg.aliases_of(v2).collect();
+        assert_eq!(aliases, [v3]);
+        assert_eq!(dfg.aliases_of(v3).next(), None);
+        assert_eq!(dfg.num_aliases(), 5);
+    }
+
+    #[test]
+    fn walk_uses() {
+        use crate::ir::InstBuilder;
+
+        let mut func = Function::new();
+        let block0 = func.dfg.make_block();
+        let block1 = func.dfg.make_block();
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let v0 = pos.ins().iconst(types::I32, 1);
+        let v1 = pos.ins().iconst(types::I32, 2);
+        let add = pos.ins().iadd(v1, v0);
+        let add_inst = pos.func.dfg.value_def(add).unwrap_inst();
+        let jump = pos.ins().jump(block1, &[v0, add, v0]);
+
+        let mut uses = alloc::vec::Vec::new();
+        pos.func
+            .dfg
+            .walk_uses(v0, |inst, index| uses.push((inst, index)));
+        assert_eq!(uses, [(add_inst, 1), (jump, 0), (jump, 2)]);
+
+        uses.clear();
+        pos.func
+            .dfg
+            .walk_uses(add, |inst, index| uses.push((inst, index)));
+        assert_eq!(uses, [(jump, 1)]);
+    }
+
+    #[test]
+    fn instruction_successors() {
+        use crate::ir::{InstBuilder, JumpTableData};
+
+        let mut func = Function::new();
+        let block0 = func.dfg.make_block();
+        let block1 = func.dfg.make_block();
+        let block2 = func.dfg.make_block();
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let v0 = pos.ins().iconst(types::I32, 0);
+        let brif = pos.ins().brif(v0, block1, &[], block2, &[]);
+        assert_eq!(
+            pos.func
+                .dfg
+                .instruction_successors(brif)
+                .collect::<Vec<_>>(),
+            [block1, block2]
+        );
+
+        let iconst = pos.func.dfg.value_def(v0).unwrap_inst();
+        assert_eq!(pos.func.dfg.instruction_successors(iconst).count(), 0);
+
+        pos.insert_block(block1);
+        let def = pos.func.dfg.block_call(block2, &[]);
+        let entries = [
+            pos.func.dfg.block_call(block1, &[]),
+            pos.func.dfg.block_call(block2, &[]),
+        ];
+        let table = pos
+            .func
+            .create_jump_table(JumpTableData::new(def, &entries));
+        let br_table = pos.ins().br_table(v0, table);
+        assert_eq!(
+            pos.func
+                .dfg
+                .instruction_successors(br_table)
+                .collect::<Vec<_>>(),
+            [block2, block1, block2]
+        );
+    }
+
+    #[test]
+    fn reachable_blocks() {
+        use crate::ir::InstBuilder;
+
+        let mut func = Function::new();
+        let blocks: Vec<_> = (0..5).map(|_| func.dfg.make_block()).collect();
+        let mut pos = FuncCursor::new(&mut func);
+        for &block in &blocks {
+            pos.insert_block(block);
+        }
+
+        // block0 -> block2 -> {block3, block0}, block0 -> block3; block1 and block4 are dead.
+        pos.goto_bottom(blocks[0]);
+        let c = pos.ins().iconst(types::I32, 0);
+        pos.ins().brif(c, blocks[2], &[], blocks[3], &[]);
+        pos.goto_bottom(blocks[1]);
+        pos.ins().jump(blocks[4], &[]);
+        pos.goto_bottom(blocks[2]);
+        pos.ins().brif(c, blocks[3], &[], blocks[0], &[]);
+        pos.goto_bottom(blocks[3]);
+        pos.ins().return_(&[]);
+        pos.goto_bottom(blocks[4]);
+        pos.ins().return_(&[]);
+
+        let reachable: Vec<_> = func.dfg.reachable_blocks(blocks[0], &func.layout).collect();
+        assert_eq!(reachable, [blocks[0], blocks[2], blocks[3]]);
+
+        let reachable: Vec<_> = func.dfg.reachable_blocks(blocks[1], &func.layout).collect();
+        assert_eq!(reachable, [blocks[1], blocks[4]]);
+    }
+
+    #[test]
+    fn visitors() {
+        use crate::ir::InstBuilder;
+
+        let mut func = Function::new();
+        let block0 = func.dfg.make_block();
+        let block1 = func.dfg.make_block();
+        let v0 = func.dfg.append_block_param(block0, types::I32);
+        let v1 = func.dfg.append_block_param(block1, types::I64);
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let v2 = pos.ins().iadd(v0, v0);
+        pos.ins().jump(block1, &[v2]);
+        pos.insert_block(block1);
+        let v3 = pos.ins().iconst(types::I8, 0);
+        pos.ins().return_(&[v3]);
+
+        // A detached instruction is never visited.
+        pos.func.dfg.make_inst(InstructionData::UnaryImm {
+            opcode: Opcode::Iconst,
+            imm: 0.into(),
+        });
+
+        let mut opcodes = Vec::new();
+        func.dfg
+            .visit_instructions(&func.layout, |_, data| opcodes.push(data.opcode()));
+        assert_eq!(
+            opcodes,
+            [Opcode::Iadd, Opcode::Jump, Opcode::Iconst, Opcode::Return]
+        );
+
+        let mut values = Vec::new();
+        func.dfg
+            .visit_values(&func.layout, |v, ty| values.push((v, ty)));
+        assert_eq!(
+            values,
+            [
+                (v0, types::I32),
+                (v2, types::I32),
+                (v1, types::I64),
+                (v3, types::I8)
+            ]
+        );
+    }
+
+    fn alias_after_use() -> (Function, Value, Value, Value) {
+        use crate::ir::InstBuilder;
+
+        let mut func = Function::new();
+        let block0 = func.dfg.make_block();
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let v0 = pos.ins().iconst(types::I32, 0);
+        let v1 = pos.ins().iconst(types::I32, 1);
+        pos.ins().iadd(v1, v1);
+        let v3 = pos.ins().iconst(types::I32, 3);
+        pos.ins().return_(&[]);
+
+        // Detach `v1` so it can become an alias.
+        pos.func.dfg.replace_result(v1, types::I32);
+        (func, v0, v1, v3)
+    }
+
+    #[test]
+    fn change_to_alias_checked() {
+        let (mut func, v0, v1, v3) = alias_after_use();
+        let cfg = crate::flowgraph::ControlFlowGraph::with_function(&func);
+        let domtree = DominatorTree::with_function(&func, &cfg);
+        func.stencil
+            .dfg
+            .change_to_alias_checked(v1, v0, &func.stencil.layout, &domtree);
+        assert_eq!(func.dfg.resolve_aliases(v1), v0);
+
+        // The unchecked version silently accepts an alias to a value defined after its use.
+        let (mut func, _, v1, _) = alias_after_use();
+        func.dfg.change_to_alias(v1, v3);
+        assert_eq!(func.dfg.resolve_aliases(v1), v3);
+    }
+
+    #[test]
+    #[should_panic(expected = "doesn't dominate the use of v1")]
+    fn change_to_alias_checked_dominance() {
+        let (mut func, _, v1, v3) = alias_after_use();
+        let cfg = crate::flowgraph::ControlFlowGraph::with_function(&func);
+        let domtree = DominatorTree::with_function(&func, &cfg);
+        func.stencil
+            .dfg
+            .change_to_alias_checked(v1, v3, &func.stencil.layout, &domtree);
+    }
+
+    #[test]
+    #[should_panic(expected = "doesn't dominate the use of v1")]
+    fn change_to_alias_checked_self_use() {
+        let (mut func, _, v1, _) = alias_after_use();
+        let cfg = crate::flowgraph::ControlFlowGraph::with_function(&func);
+        let domtree = DominatorTree::with_function(&func, &cfg);
+
+        // `v2 = iadd v1, v1` would use its own result.
+        let block0 = func.layout.entry_block().unwrap();
+        let iadd = func.layout.block_insts(block0).nth(2).unwrap();
+        let v2 = func.dfg.first_result(iadd);
+        func.stencil
+            .dfg
+            .change_to_alias_checked(v1, v2, &func.stencil.layout, &domtree);
+    }
+
+    #[test]
+    fn estimate_spill_cost() {
+        use crate::flowgraph::ControlFlowGraph;
+        use crate::ir::InstBuilder;
+
+        let mut func = Function::new();
+        let block0 = func.dfg.make_block();
+        let block1 = func.dfg.make_block();
+        let block2 = func.dfg.make_block();
+        let v0 = func.dfg.append_block_param(block0, types::I32);
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let v1 = pos.ins().iconst(types::I32, 1);
+        pos.ins().iadd(v0, v1);
+        pos.ins().jump(block1, &[]);
+        pos.insert_block(block1);
+        let v3 = pos.ins().iadd(v1, v1);
+        pos.ins().brif(v3, block1, &[], block2, &[]);
+        pos.insert_block(block2);
+        pos.ins().iadd(v1, v0);
+        pos.ins().return_(&[]);
+
+        let cfg = ControlFlowGraph::with_function(&func);
+        let domtree = DominatorTree::with_function(&func, &cfg);
+        let mut loops = LoopAnalysis::new();
+        loops.compute(&func, &cfg, &domtree);
+
+        let dfg = &func.dfg;
+        assert_eq!(dfg.estimate_spill_cost(v1, &func.layout, &loops), 22.0);
+        assert_eq!(dfg.estimate_spill_cost(v0, &func.layout, &loops), 2.0);
+        assert_eq!(dfg.estimate_spill_cost(v3, &func.layout, &loops), 10.0);
+    }
+
+    #[test]
+    fn is_live_through_call() {
+        use crate::flowgraph::ControlFlowGraph;
+        use crate::ir::{AbiParam, ExtFuncData, ExternalName, InstBuilder};
+
+        let mut func = Function::new();
+        let mut sig = Signature::new(crate::isa::CallConv::SystemV);
+        sig.params.push(AbiParam::new(types::I32));
+        let signature = func.import_signature(sig);
+        let fn0 = func.import_function(ExtFuncData {
+            name: ExternalName::testcase("f"),
+            signature,
+            colocated: false,
+        });
+        let block0 = func.dfg.make_block();
+        let block1 = func.dfg.make_block();
+        let block2 = func.dfg.make_block();
+        let v0 = func.dfg.append_block_param(block0, types::I32);
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let v1 = pos.ins().iconst(types::I32, 1);
+        let v2 = pos.ins().iconst(types::I32, 2);
+        let call0 = pos.ins().call(fn0, &[v0]);
+        let v3 = pos.ins().iadd(v1, v1);
+        pos.ins().jump(block1, &[]);
+        pos.insert_block(block1);
+        let call1 = pos.ins().call(fn0, &[v3]);
+        pos.ins().brif(v3, block1, &[], block2, &[]);
+        pos.insert_block(block2);
+        pos.ins().iadd(v2, v2);
+        pos.ins().return_(&[]);
+
+        let cfg = ControlFlowGraph::with_function(&func);
+        let domtree = DominatorTree::with_function(&func, &cfg);
+        let live = |v, call| {
+            func.dfg
+                .is_live_through_call(v, call, &func.layout, &domtree)
+        };
+
+        // Used later in the same block, or only by the call itself.
+        assert!(live(v1, call0));
+        assert!(!live(v0, call0));
+        // Used in a later block.
+        assert!(live(v2, call0));
+        // Defined after the call.
+        assert!(!live(v3, call0));
+        // Used by the call itself, but also again on the next loop iteration.
+        assert!(live(v3, call1));
+        assert!(live(v2, call1));
+        assert!(!live(v1, call1));
+    }
+
+    #[test]
+    fn print_domtree() {
+        use crate::ir::InstBuilder;
+
+        let mut func = Function::new();
+        let blocks: Vec<Block> = (0..6).map(|_| func.dfg.make_block()).collect();
+        let v0 = func.dfg.append_block_param(blocks[0], types::I32);
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(blocks[0]);
+        pos.ins().brif(v0, blocks[2], &[], blocks[1], &[]);
+        pos.insert_block(blocks[1]);
+        pos.ins().jump(blocks[3], &[]);
+        pos.insert_block(blocks[2]);
+        pos.ins().brif(v0, blocks[3], &[], blocks[2], &[]);
+        pos.insert_block(blocks[3]);
+        pos.ins().brif(v0, blocks[4], &[], blocks[4], &[]);
+        pos.insert_block(blocks[4]);
+        pos.ins().return_(&[]);
+        pos.insert_block(blocks[5]);
+        pos.ins().jump(blocks[4], &[]);
+
+        let cfg = crate::flowgraph::ControlFlowGraph::with_function(&func);
+        let domtree = DominatorTree::with_function(&func, &cfg);
+        let mut out = alloc::string::String::new();
+        func.dfg
+            .print_domtree(&domtree, &func.layout, &mut out)
+            .unwrap();
+        assert_eq!(
+            out,
+            "block0 rpo=0\n\
+             \x20 block1 rpo=1 idom=block0\n\
+             \x20 block2 rpo=2 idom=block0\n\
+             \x20 block3 rpo=3 idom=block0\n\
+             \x20   block4 rpo=4 idom=block3\n\
+             block5 unreachable\n"
+        );
+    }
+
     #[test]
     fn cloning() {
         use crate::ir::InstBuilder;
@@ -1799,4 +3967,124 @@ mod tests {
         func.dfg.inst_args_mut(call_inst)[0] = v2;
         assert_eq!(v1, func.dfg.inst_args(call_inst_dup)[0]);
     }
+
+    #[test]
+    fn clone_into() {
+        use crate::ir::{ExternalName, InstBuilder, JumpTableData, UserExternalNameRef};
+
+        let mut func = Function::new();
+        let mut sig = Signature::new(crate::isa::CallConv::SystemV);
+        sig.params.push(ir::AbiParam::new(types::I32));
+        sig.returns.push(ir::AbiParam::new(types::I64));
+        let sig = func.import_signature(sig);
+        let fn0 = func.import_function(ExtFuncData {
+            name: ExternalName::User(UserExternalNameRef::new(0)),
+            signature: sig,
+            colocated: false,
+        });
+        let block0 = func.dfg.make_block();
+        let block1 = func.dfg.make_block();
+        let v0 = func.dfg.append_block_param(block0, types::I32);
+        func.dfg.append_block_param(block1, types::I64);
+        let constant = func.dfg.constants.insert(vec![0u8; 16].into());
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let v1 = pos.ins().iadd_imm(v0, 1);
+        let call = pos.ins().call(fn0, &[v1]);
+        let v2 = pos.func.dfg.first_result(call);
+        pos.ins().vconst(types::I8X16, constant);
+        let pool = &mut pos.func.dfg.value_lists;
+        let jt = JumpTableData::new(
+            BlockCall::new(block1, &[v2], pool),
+            &[BlockCall::new(block1, &[v2], pool)],
+        );
+        let jt = pos.func.create_jump_table(jt);
+        pos.ins().br_table(v1, jt);
+        pos.insert_block(block1);
+        let insts: Vec<_> = func.layout.block_insts(block0).collect();
+
+        // Give the target some entities of its own, so that indices have to be remapped.
+        let mut target = Function::new();
+        target.import_signature(Signature::new(crate::isa::CallConv::Fast));
+        target.dfg.make_inst(InstructionData::NullAry {
+            opcode: Opcode::Nop,
+        });
+        target.dfg.constants.insert(vec![1u8; 16].into());
+
+        let (new_insts, values) = func.dfg.clone_into(&insts, &mut target.dfg);
+        let dfg = &target.dfg;
+        assert_eq!(new_insts.len(), insts.len());
+        assert_eq!(values.len(), 3);
+        assert!(new_insts
+            .iter()
+            .all(|&inst| target.layout.inst_block(inst).is_none()));
+
+        // `v0` is a block parameter, so it is left as-is.
+        let new_v1 = values[&v1];
+        assert_eq!(dfg.inst_args(new_insts[0]), &[v0]);
+        assert_eq!(dfg.first_result(new_insts[0]), new_v1);
+
+        let new_v2 = values[&v2];
+        assert_eq!(dfg.value_type(new_v2), types::I64);
+        assert_eq!(dfg.inst_args(new_insts[1]), &[new_v1]);
+        let new_fn0 = match dfg.insts[new_insts[1]] {
+            InstructionData::Call { func_ref, .. } => func_ref,
+            _ => unreachable!(),
+        };
+        let new_sig = dfg.ext_funcs[new_fn0].signature;
+        assert_ne!(new_sig, sig);
+        assert_eq!(dfg.signatures[new_sig], func.dfg.signatures[sig]);
+
+        let new_constant = match dfg.insts[new_insts[2]] {
+            InstructionData::UnaryConst {
+                constant_handle, ..
+            } => constant_handle,
+            _ => unreachable!(),
+        };
+        assert_eq!(dfg.constants.get(new_constant).as_slice(), &[0; 16]);
+
+        assert_eq!(dfg.inst_args(new_insts[3]), &[new_v1]);
+        let branches: Vec<_> = dfg.insts[new_insts[3]]
+            .branch_destination(&dfg.jump_tables)
+            .iter()
+            .map(|call| {
+                (
+                    call.block(&dfg.value_lists),
+                    call.args_slice(&dfg.value_lists),
+                )
+            })
+            .collect();
+        assert_eq!(branches, [(block1, &[new_v2][..]), (block1, &[new_v2][..])]);
+    }
+
+    #[test]
+    fn clone_into_dynamic_types() {
+        use crate::ir::{GlobalValueData, InstBuilder};
+
+        let mut func = Function::new();
+        let gv = func.create_global_value(GlobalValueData::DynScaleTargetConst {
+            vector_type: types::I32X4,
+        });
+        let dt = func
+            .dfg
+            .make_dynamic_ty(DynamicTypeData::new(types::I32X4, gv));
+        let ty = func.get_concrete_dynamic_ty(dt).unwrap();
+        let block0 = func.dfg.make_block();
+        let v0 = func.dfg.append_block_param(block0, types::I32);
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let v1 = pos.ins().splat(ty, v0);
+        let splat = pos.func.dfg.value_def(v1).unwrap_inst();
+
+        let mut target = Function::new();
+        let (new_insts, values) = func.dfg.clone_into(&[splat], &mut target.dfg);
+        assert_eq!(target.dfg.value_type(values[&v1]), ty);
+        assert_eq!(target.dfg.ctrl_typevar(new_insts[0]), ty);
+        assert_eq!(target.dfg.dynamic_types.len(), 1);
+        assert_eq!(target.dfg.check_dynamic_type(ty), Some(ty));
+
+        // Types that are already declared aren't copied again.
+        func.dfg.clone_into(&[splat], &mut target.dfg);
+        assert_eq!(target.dfg.dynamic_types.len(), 1);
+    }
 }
@@ -51,6 +51,26 @@ impl Signature {
         self.call_conv = call_conv;
     }
 
+    /// Add a normal parameter of type `ty` to the signature.
+    ///
+    /// This is a builder-style shorthand for pushing `AbiParam::new(ty)` to `params`.
+    pub fn with_param(mut self, ty: Type) -> Self {
+        self.params.push(AbiParam::new(ty));
+        self
+    }
+
+    /// Add a normal return value of type `ty` to the signature.
+    pub fn with_return(mut self, ty: Type) -> Self {
+        self.returns.push(AbiParam::new(ty));
+        self
+    }
+
+    /// Change the calling convention of the signature.
+    pub fn with_call_conv(mut self, call_conv: CallConv) -> Self {
+        self.call_conv = call_conv;
+        self
+    }
+
     /// Find the index of a presumed unique special-purpose parameter.
     pub fn special_param_index(&self, purpose: ArgumentPurpose) -> Option<usize> {
         self.params.iter().rposition(|arg| arg.purpose == purpose)
@@ -405,4 +425,14 @@ mod tests {
         sig.returns.push(AbiParam::new(I8));
         assert_eq!(sig.to_string(), "(i32, i32x4) -> f32, i8 windows_fastcall");
     }
+
+    #[test]
+    fn signature_builder() {
+        let sig = Signature::new(CallConv::SystemV)
+            .with_param(I32)
+            .with_param(I8)
+            .with_return(F32)
+            .with_call_conv(CallConv::WindowsFastcall);
+        assert_eq!(sig.to_string(), "(i32, i8) -> f32 windows_fastcall");
+    }
 }
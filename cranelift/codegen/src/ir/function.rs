@@ -3,14 +3,15 @@
 //! The `Function` struct defined in this module owns all of its basic blocks and
 //! instructions.
 
+use crate::binemit::CodeOffset;
 use crate::entity::{PrimaryMap, SecondaryMap};
 use crate::ir::{
     self, pcc::Fact, Block, DataFlowGraph, DynamicStackSlot, DynamicStackSlotData,
     DynamicStackSlots, DynamicType, ExtFuncData, FuncRef, GlobalValue, GlobalValueData, Inst,
     JumpTable, JumpTableData, Layout, MemoryType, MemoryTypeData, SigRef, Signature, SourceLocs,
-    StackSlot, StackSlotData, StackSlots, Type,
+    StackSlot, StackSlotData, StackSlots, Type, Value, ValueDef,
 };
-use crate::isa::CallConv;
+use crate::isa::{CallConv, TargetIsa};
 use crate::write::write_function;
 use crate::HashMap;
 #[cfg(feature = "enable-serde")]
@@ -61,6 +62,13 @@ impl<'de> Deserialize<'de> for VersionMarker {
     }
 }
 
+/// The reason `FunctionStencil::move_inst` refused to move an instruction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoveError {
+    /// The value would be used before it is defined.
+    WouldViolateSsa(Value),
+}
+
 /// Function parameters used when creating this function, and that will become applied after
 /// compilation to materialize the final `CompiledCode`.
 #[derive(Clone, PartialEq)]
@@ -234,6 +242,14 @@ impl FunctionStencil {
         self.dfg.signatures.push(signature)
     }
 
+    /// Merge identical signatures, so each distinct signature is declared once.
+    ///
+    /// See [`DataFlowGraph::dedup_signatures`]. Returns the number of signatures that were
+    /// removed.
+    pub fn dedup_signatures(&mut self) -> usize {
+        self.dfg.dedup_signatures()
+    }
+
     /// Declares a global value accessible to the function.
     pub fn create_global_value(&mut self, data: GlobalValueData) -> GlobalValue {
         self.global_values.push(data)
@@ -264,11 +280,19 @@ impl FunctionStencil {
             .concrete()
     }
 
+    /// Get the entry block of the function, which is the first block in the layout.
+    ///
+    /// Panics if the layout is empty. Use `layout.entry_block()` for functions that may not have
+    /// been built yet.
+    pub fn entry_block(&self) -> Block {
+        self.layout.entry_block().expect("Function is empty")
+    }
+
     /// Find a presumed unique special-purpose function parameter value.
     ///
     /// Returns the value of the last `purpose` parameter, or `None` if no such parameter exists.
     pub fn special_param(&self, purpose: ir::ArgumentPurpose) -> Option<ir::Value> {
-        let entry = self.layout.entry_block().expect("Function is empty");
+        let entry = self.entry_block();
         self.signature
             .special_param_index(purpose)
             .map(|i| self.dfg.block_params(entry)[i])
@@ -281,12 +305,71 @@ impl FunctionStencil {
 
     /// Rewrite the branch destination to `new_dest` if the destination matches `old_dest`.
     /// Does nothing if called with a non-jump or non-branch instruction.
-    pub fn rewrite_branch_destination(&mut self, inst: Inst, old_dest: Block, new_dest: Block) {
+    ///
+    /// This covers every destination of `inst`, including the default and all entries of a
+    /// `br_table`'s jump table, and leaves the branch arguments alone. Returns the number of
+    /// destinations that were rewritten.
+    pub fn rewrite_branch_destination(
+        &mut self,
+        inst: Inst,
+        old_dest: Block,
+        new_dest: Block,
+    ) -> usize {
+        let mut rewritten = 0;
         for dest in self.dfg.insts[inst].branch_destination_mut(&mut self.dfg.jump_tables) {
             if dest.block(&self.dfg.value_lists) == old_dest {
-                dest.set_block(new_dest, &mut self.dfg.value_lists)
+                dest.set_block(new_dest, &mut self.dfg.value_lists);
+                rewritten += 1;
+            }
+        }
+        rewritten
+    }
+
+    /// Move `inst` in the layout so it comes right before `before`.
+    ///
+    /// Fails without changing anything if this would put a use of a value before its definition
+    /// in the destination block: if an argument of `inst` is defined at or after `before`, or a
+    /// result of `inst` is used before `before`. Uses and definitions in other blocks aren't
+    /// checked, so the caller must make sure the definitions of the arguments still dominate
+    /// `inst`, and that `inst` still dominates the uses of its results.
+    pub fn move_inst(&mut self, inst: Inst, before: Inst) -> Result<(), MoveError> {
+        if inst == before {
+            return Ok(());
+        }
+        let block = self
+            .layout
+            .inst_block(before)
+            .expect("Destination not in the layout");
+
+        for arg in self.dfg.inst_values(inst) {
+            let arg = self.dfg.resolve_aliases(arg);
+            if let ValueDef::Result(def, _) = self.dfg.value_def(arg) {
+                if self.layout.inst_block(def) == Some(block)
+                    && self.layout.pp_cmp(def, before).is_ge()
+                {
+                    return Err(MoveError::WouldViolateSsa(arg));
+                }
             }
         }
+        let results = self.dfg.inst_results(inst);
+        for user in self
+            .layout
+            .block_insts(block)
+            .take_while(|&user| user != before)
+        {
+            if let Some(result) = self
+                .dfg
+                .inst_values(user)
+                .map(|arg| self.dfg.resolve_aliases(arg))
+                .find(|arg| results.contains(arg))
+            {
+                return Err(MoveError::WouldViolateSsa(result));
+            }
+        }
+
+        self.layout.remove_inst(inst);
+        self.layout.insert_inst(inst, before);
+        Ok(())
     }
 
     /// Checks that the specified block can be encoded as a basic block.
@@ -310,12 +393,10 @@ impl FunctionStencil {
 
     /// Returns an iterator over the blocks succeeding the given block.
     pub fn block_successors(&self, block: Block) -> impl DoubleEndedIterator<Item = Block> + '_ {
-        self.layout.last_inst(block).into_iter().flat_map(|inst| {
-            self.dfg.insts[inst]
-                .branch_destination(&self.dfg.jump_tables)
-                .iter()
-                .map(|block| block.block(&self.dfg.value_lists))
-        })
+        self.layout
+            .last_inst(block)
+            .into_iter()
+            .flat_map(|inst| self.dfg.instruction_successors(inst))
     }
 
     /// Returns true if the function is function that doesn't call any other functions. This is not
@@ -371,6 +452,156 @@ impl FunctionStencil {
     pub(crate) fn rel_srclocs(&self) -> &SecondaryMap<Inst, RelSourceLoc> {
         &self.srclocs
     }
+
+    /// Report the sizes of the main entity tables and pools backing this function.
+    ///
+    /// This is meant for compile-time benchmarking and for tracking down memory growth in the
+    /// IR data structures; it doesn't account for heap memory precisely.
+    pub fn memory_report(&self) -> MemoryReport {
+        MemoryReport {
+            insts: self.dfg.num_insts(),
+            blocks: self.dfg.num_blocks(),
+            values: self.dfg.num_values(),
+            value_list_pool: self.dfg.value_lists.len(),
+            value_list_pool_capacity: self.dfg.value_lists.capacity(),
+            constants: self.dfg.constants.len(),
+            jump_tables: self.dfg.jump_tables.len(),
+            global_values: self.global_values.len(),
+            stack_slots: self.sized_stack_slots.len() + self.dynamic_stack_slots.len(),
+        }
+    }
+
+    /// Report how much of the data flow graph is no longer in use.
+    ///
+    /// This is meant for debugging passes that leave aliases and detached values behind, or that
+    /// fragment the value list pool.
+    pub fn dfg_stats(&self) -> DfgStats {
+        let dfg = &self.dfg;
+        // Each non-empty list takes a length header in addition to its elements.
+        let list_len = |len: usize| if len == 0 { 0 } else { len + 1 };
+        let mut value_list_live = 0;
+        for block in self.layout.blocks() {
+            value_list_live += list_len(dfg.block_params(block).len());
+            for inst in self.layout.block_insts(block) {
+                value_list_live += list_len(dfg.inst_results(inst).len());
+                let data = &dfg.insts[inst];
+                if data.opcode().format().has_value_list() {
+                    value_list_live += list_len(dfg.inst_args(inst).len());
+                }
+                for call in data.branch_destination(&dfg.jump_tables) {
+                    // The list holds the destination block as well as the arguments.
+                    value_list_live += list_len(call.args_slice(&dfg.value_lists).len() + 1);
+                }
+            }
+        }
+
+        DfgStats {
+            aliases: dfg.num_aliases(),
+            detached_values: dfg
+                .values()
+                .filter(|&v| {
+                    dfg.value_is_real(v)
+                        && !matches!(dfg.value_def(v), ValueDef::Union(..))
+                        && !dfg.value_is_attached(v)
+                })
+                .count(),
+            value_list_live,
+            value_list_pool: dfg.value_lists.len(),
+        }
+    }
+}
+
+/// Sizes of the entity tables and pools backing a function, as returned by
+/// `FunctionStencil::memory_report`.
+///
+/// Entities are never deleted from the data flow graph, so these counts include instructions,
+/// blocks and values that are no longer reachable from the layout.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct MemoryReport {
+    /// Number of instructions allocated in the DFG.
+    pub insts: usize,
+    /// Number of blocks allocated in the DFG.
+    pub blocks: usize,
+    /// Number of values allocated in the DFG, including aliases.
+    pub values: usize,
+    /// Number of elements allocated from the value list pool.
+    pub value_list_pool: usize,
+    /// Capacity of the value list pool's backing storage.
+    pub value_list_pool_capacity: usize,
+    /// Number of entries in the constant pool.
+    pub constants: usize,
+    /// Number of jump tables.
+    pub jump_tables: usize,
+    /// Number of global values.
+    pub global_values: usize,
+    /// Number of sized and dynamic stack slots.
+    pub stack_slots: usize,
+}
+
+impl MemoryReport {
+    /// Combine two reports by taking the maximum of each field.
+    ///
+    /// This is useful for recording peak sizes across the phases of a compilation.
+    pub fn max(self, other: Self) -> Self {
+        Self {
+            insts: self.insts.max(other.insts),
+            blocks: self.blocks.max(other.blocks),
+            values: self.values.max(other.values),
+            value_list_pool: self.value_list_pool.max(other.value_list_pool),
+            value_list_pool_capacity: self
+                .value_list_pool_capacity
+                .max(other.value_list_pool_capacity),
+            constants: self.constants.max(other.constants),
+            jump_tables: self.jump_tables.max(other.jump_tables),
+            global_values: self.global_values.max(other.global_values),
+            stack_slots: self.stack_slots.max(other.stack_slots),
+        }
+    }
+}
+
+impl fmt::Display for MemoryReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "insts: {}", self.insts)?;
+        writeln!(f, "blocks: {}", self.blocks)?;
+        writeln!(f, "values: {}", self.values)?;
+        writeln!(
+            f,
+            "value list pool: {} (capacity {})",
+            self.value_list_pool, self.value_list_pool_capacity
+        )?;
+        writeln!(f, "constants: {}", self.constants)?;
+        writeln!(f, "jump tables: {}", self.jump_tables)?;
+        writeln!(f, "global values: {}", self.global_values)?;
+        writeln!(f, "stack slots: {}", self.stack_slots)
+    }
+}
+
+/// Statistics about the unused parts of a function's data flow graph, as returned by
+/// `FunctionStencil::dfg_stats`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct DfgStats {
+    /// Number of values that are aliases of other values.
+    pub aliases: usize,
+    /// Number of instruction results and block parameters that have been detached from their
+    /// instruction or block.
+    pub detached_values: usize,
+    /// Number of value list pool elements used by the blocks and instructions in the layout,
+    /// including list length headers.
+    pub value_list_live: usize,
+    /// Number of elements allocated from the value list pool.
+    pub value_list_pool: usize,
+}
+
+impl fmt::Display for DfgStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "aliases: {}", self.aliases)?;
+        writeln!(f, "detached values: {}", self.detached_values)?;
+        writeln!(
+            f,
+            "value list pool: {} of {} elements live",
+            self.value_list_live, self.value_list_pool
+        )
+    }
 }
 
 /// Functions can be cloned, but it is not a very fast operation.
@@ -471,6 +702,28 @@ impl Function {
     pub fn import_function(&mut self, data: ExtFuncData) -> FuncRef {
         self.stencil.dfg.ext_funcs.push(data)
     }
+
+    /// Estimate the size of the machine code for this function on `isa`, without compiling it.
+    ///
+    /// This adds up `TargetIsa::estimate_inst_size` for the instructions in the layout, plus some
+    /// slack for each branch in case it needs to be relaxed into a longer form. It is meant for
+    /// heuristics such as inlining and tiering decisions; the compiled code can be quite a bit
+    /// smaller or larger.
+    pub fn estimate_size(&self, isa: &dyn TargetIsa) -> CodeOffset {
+        // Room for a veneer or a longer branch form.
+        const BRANCH_SLACK: CodeOffset = 4;
+
+        let mut size = 0;
+        for block in self.layout.blocks() {
+            for inst in self.layout.block_insts(block) {
+                size += isa.estimate_inst_size(&self.dfg, inst);
+                if self.dfg.insts[inst].opcode().is_branch() {
+                    size += BRANCH_SLACK;
+                }
+            }
+        }
+        size
+    }
 }
 
 /// Wrapper type capable of displaying a `Function`.
@@ -493,3 +746,159 @@ impl fmt::Debug for Function {
         write_function(fmt, self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cursor::{Cursor, FuncCursor};
+    use crate::ir::{types, BlockCall, InstBuilder};
+    use alloc::vec::Vec;
+
+    #[test]
+    fn rewrite_br_table_destinations() {
+        let mut func = Function::new();
+        let [block0, block1, block2, block3] = [(); 4].map(|_| func.dfg.make_block());
+        let v0 = func.dfg.append_block_param(block0, types::I32);
+        let pool = &mut func.dfg.value_lists;
+        let jt = JumpTableData::new(
+            BlockCall::new(block1, &[v0], pool),
+            &[
+                BlockCall::new(block1, &[v0], pool),
+                BlockCall::new(block2, &[], pool),
+                BlockCall::new(block1, &[], pool),
+                BlockCall::new(block1, &[v0, v0], pool),
+            ],
+        );
+        let jt = func.create_jump_table(jt);
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let br_table = pos.ins().br_table(v0, jt);
+
+        // The default and three table entries are rewritten.
+        assert_eq!(func.rewrite_branch_destination(br_table, block1, block3), 4);
+        assert_eq!(func.rewrite_branch_destination(br_table, block1, block3), 0);
+
+        let pool = &func.dfg.value_lists;
+        let dests: Vec<_> = func.dfg.jump_tables[jt]
+            .all_branches()
+            .iter()
+            .map(|call| (call.block(pool), call.args_slice(pool).len()))
+            .collect();
+        assert_eq!(
+            dests,
+            [
+                (block3, 1),
+                (block3, 1),
+                (block2, 0),
+                (block3, 0),
+                (block3, 2)
+            ]
+        );
+
+        // Instructions that don't branch are left alone.
+        let mut pos = FuncCursor::new(&mut func).at_first_inst(block0);
+        let nop = pos.ins().nop();
+        assert_eq!(func.rewrite_branch_destination(nop, block2, block3), 0);
+    }
+
+    #[test]
+    fn entry_block() {
+        let mut func = Function::new();
+        let block0 = func.dfg.make_block();
+        let block1 = func.dfg.make_block();
+        func.layout.append_block(block1);
+        func.layout.insert_block(block0, block1);
+        assert_eq!(func.entry_block(), block0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Function is empty")]
+    fn entry_block_of_empty_function() {
+        Function::new().entry_block();
+    }
+
+    #[test]
+    fn dfg_stats() {
+        let mut func = Function::new();
+        let block0 = func.dfg.make_block();
+        let block1 = func.dfg.make_block();
+        let v0 = func.dfg.append_block_param(block0, types::I32);
+        let v1 = func.dfg.append_block_param(block1, types::I32);
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let v2 = pos.ins().iadd(v0, v0);
+        let v3 = pos.ins().iconst(types::I32, 1);
+        let v4 = pos.ins().iconst(types::I32, 2);
+        pos.ins().jump(block1, &[v2]);
+        pos.insert_block(block1);
+        pos.ins().return_(&[v1]);
+
+        for v in [v3, v4] {
+            let inst = func.dfg.value_def(v).unwrap_inst();
+            func.dfg.clear_results(inst);
+        }
+        func.dfg.change_to_alias(v4, v2);
+
+        let stats = func.dfg_stats();
+        assert_eq!(stats.aliases, 1);
+        assert_eq!(stats.detached_values, 1);
+        // Two block parameter lists, the `iadd` results, the jump's block call, and the return
+        // arguments.
+        assert_eq!(stats.value_list_live, 2 + 2 + 2 + 3 + 2);
+        assert!(stats.value_list_pool >= stats.value_list_live);
+    }
+
+    #[test]
+    fn move_inst() {
+        let mut func = Function::new();
+        let block0 = func.dfg.make_block();
+        let v0 = func.dfg.append_block_param(block0, types::I32);
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let v1 = pos.ins().iadd(v0, v0);
+        let v2 = pos.ins().imul(v1, v1);
+        let v3 = pos.ins().isub(v0, v0);
+        let ret = pos.ins().return_(&[v2]);
+        let [iadd, imul, isub] = [v1, v2, v3].map(|v| func.dfg.value_def(v).unwrap_inst());
+
+        assert_eq!(func.move_inst(isub, iadd), Ok(()));
+        let insts: Vec<_> = func.layout.block_insts(block0).collect();
+        assert_eq!(insts, [isub, iadd, imul, ret]);
+
+        // `imul` can't come before the definition of its argument, and `iadd` can't come after
+        // its use.
+        assert_eq!(
+            func.move_inst(imul, iadd),
+            Err(MoveError::WouldViolateSsa(v1))
+        );
+        assert_eq!(
+            func.move_inst(iadd, ret),
+            Err(MoveError::WouldViolateSsa(v1))
+        );
+        let insts: Vec<_> = func.layout.block_insts(block0).collect();
+        assert_eq!(insts, [isub, iadd, imul, ret]);
+    }
+
+    #[test]
+    #[cfg(feature = "x86")]
+    fn estimate_size() {
+        use crate::settings;
+
+        let flags = settings::Flags::new(settings::builder());
+        let isa = crate::isa::lookup_by_name("x86_64")
+            .unwrap()
+            .finish(flags)
+            .unwrap();
+        let mut func = Function::new();
+        let [block0, block1] = [(); 2].map(|_| func.dfg.make_block());
+        let v0 = func.dfg.append_block_param(block0, types::I32);
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        pos.ins().brif(v0, block1, &[], block1, &[]);
+        pos.insert_block(block1);
+        pos.ins().return_(&[]);
+
+        // Both instructions are estimated at 4 bytes, plus slack for the branch.
+        assert_eq!(func.estimate_size(&*isa), 12);
+    }
+}
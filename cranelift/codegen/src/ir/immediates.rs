@@ -1290,6 +1290,46 @@ mod tests {
         parse_err::<Offset32>("+0x8000_0000", "Offset out of range");
     }
 
+    // Verify that the `Display` output of `x` parses back to `x`.
+    #[track_caller]
+    fn round_trip<T: FromStr + Display + PartialEq + fmt::Debug>(x: T)
+    where
+        <T as FromStr>::Err: fmt::Debug,
+    {
+        assert_eq!(x.to_string().parse::<T>().unwrap(), x);
+    }
+
+    #[test]
+    fn round_trip_limits() {
+        assert_eq!(Imm64(i64::MIN).to_string(), "-9223372036854775808");
+        assert_eq!(Imm64(i64::MAX).to_string(), "0x7fff_ffff_ffff_ffff");
+        for x in [i64::MIN, i64::MIN + 1, -1, 0, i64::MAX] {
+            round_trip(Imm64(x));
+        }
+
+        assert_eq!(Uimm64(u64::MAX).to_string(), "0xffff_ffff_ffff_ffff");
+        for x in [0, 1 << 63, u64::MAX] {
+            round_trip(Uimm64(x));
+        }
+
+        assert_eq!(Uimm32(u32::MAX).to_string(), "0xffff_ffff");
+        parse_err::<Uimm32>("0x1_0000_0000", "Uimm32 out of range");
+        parse_err::<Uimm32>("-1", "Uimm32 out of range");
+        for x in [0, 1 << 31, u32::MAX] {
+            round_trip(Uimm32(x));
+        }
+
+        assert_eq!(Offset32(i32::MIN).to_string(), "-0x8000_0000");
+        // A zero offset is omitted from the text format.
+        assert_eq!(Offset32(0).to_string(), "");
+        for x in [i32::MIN, -1, 1, i32::MAX] {
+            round_trip(Offset32(x));
+        }
+        assert_eq!(Offset32(i32::MAX).try_add_i64(1), None);
+        assert_eq!(Offset32(i32::MIN).try_add_i64(-1), None);
+        assert_eq!(Offset32(-8).try_add_i64(20), Some(Offset32(12)));
+    }
+
     #[test]
     fn format_ieee16() {
         assert_eq!(Ieee16::with_bits(0).to_string(), "0.0"); // 0.0
@@ -1958,4 +1998,46 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn round_trip_float_bit_patterns() {
+        // Special values: zeros, infinities, the largest quiet and signaling NaN payloads and
+        // all ones.
+        for bits in [0, 0x8000, 0x7c00, 0xfc00, 0x7fff, 0x7dff, 0xffff] {
+            round_trip(Ieee16::with_bits(bits));
+        }
+        for bits in [
+            0,
+            0x8000_0000,
+            0x7f80_0000,
+            0xff80_0000,
+            0x7fff_ffff,
+            0x7fbf_ffff,
+            !0,
+        ] {
+            round_trip(Ieee32::with_bits(bits));
+        }
+        for bits in [
+            0,
+            1 << 63,
+            0x7ff << 52,
+            0xfff << 52,
+            !0 >> 1,
+            0x7ff7 << 48,
+            !0,
+        ] {
+            round_trip(Ieee64::with_bits(bits));
+        }
+
+        // Random bit patterns, from a fixed xorshift sequence.
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        for _ in 0..10_000 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            round_trip(Ieee16::with_bits(state as u16));
+            round_trip(Ieee32::with_bits(state as u32));
+            round_trip(Ieee64::with_bits(state));
+        }
+    }
 }
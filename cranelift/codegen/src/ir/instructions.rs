@@ -17,12 +17,7 @@ use serde_derive::{Deserialize, Serialize};
 
 use crate::bitset::ScalarBitSet;
 use crate::entity;
-use crate::ir::{
-    self,
-    condcodes::{FloatCC, IntCC},
-    trapcode::TrapCode,
-    types, Block, FuncRef, MemFlags, SigRef, StackSlot, Type, Value,
-};
+use crate::ir::{self, types, Block, FuncRef, MemFlags, SigRef, StackSlot, Type, Value};
 
 /// Some instructions use an external list of argument values because there is not enough space in
 /// the 16-byte `InstructionData` struct. These value lists are stored in a memory pool in
@@ -133,6 +128,13 @@ impl BlockCall {
             values: self.values.deep_clone(pool),
         }
     }
+
+    /// Copy this block call from `src_pool` into `dst_pool`, keeping the same block.
+    pub fn clone_to(&self, src_pool: &ValueListPool, dst_pool: &mut ValueListPool) -> Self {
+        Self {
+            values: self.values.clone_to(src_pool, dst_pool),
+        }
+    }
 }
 
 /// Wrapper for the context needed to display a [BlockCall] value.
@@ -161,7 +163,8 @@ impl<'a> Display for DisplayBlockCall<'a> {
 
 // Include code generated by `cranelift-codegen/meta/src/gen_inst.rs`. This file contains:
 //
-// - The `pub enum InstructionFormat` enum with all the instruction formats.
+// - The `pub enum InstructionFormat` enum with all the instruction formats, and its operand
+//   layout accessors.
 // - The `pub enum InstructionData` enum with all the instruction data fields.
 // - The `pub enum Opcode` definition with all known opcodes,
 // - The `const OPCODE_FORMAT: [InstructionFormat; N]` table.
@@ -182,7 +185,63 @@ impl Display for Opcode {
     }
 }
 
+/// An immediate operand or entity reference of an instruction format, stored directly in its
+/// `InstructionData` variant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FormatImmediate {
+    /// The name of the `InstructionData` field holding the operand.
+    pub member: &'static str,
+    /// The Rust type of the field.
+    pub rust_type: &'static str,
+    /// Is this a reference to an entity, such as a `FuncRef`, rather than an immediate value?
+    pub is_entity: bool,
+}
+
+/// Rewrites the entity references of instructions, see [`InstructionData::map_entity_refs`].
+///
+/// Every method returns its argument unchanged by default, so implementations only override the
+/// methods for the entities they renumber.
+pub trait EntityRefMapper {
+    /// Map a reference to a signature.
+    fn map_sig_ref(&mut self, sig_ref: SigRef) -> SigRef {
+        sig_ref
+    }
+
+    /// Map a reference to an external function.
+    fn map_func_ref(&mut self, func_ref: FuncRef) -> FuncRef {
+        func_ref
+    }
+
+    /// Map a reference to a jump table.
+    fn map_jump_table(&mut self, table: ir::JumpTable) -> ir::JumpTable {
+        table
+    }
+
+    /// Map a reference to a global value.
+    fn map_global_value(&mut self, global_value: ir::GlobalValue) -> ir::GlobalValue {
+        global_value
+    }
+
+    /// Map a reference to a sized stack slot.
+    fn map_stack_slot(&mut self, stack_slot: StackSlot) -> StackSlot {
+        stack_slot
+    }
+
+    /// Map a reference to a dynamic stack slot.
+    fn map_dynamic_stack_slot(
+        &mut self,
+        dynamic_stack_slot: ir::DynamicStackSlot,
+    ) -> ir::DynamicStackSlot {
+        dynamic_stack_slot
+    }
+}
+
 impl Opcode {
+    /// Look up an opcode by its name in the textual IR, such as `iadd_imm`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        name.parse().ok()
+    }
+
     /// Get the instruction format for this opcode.
     pub fn format(self) -> InstructionFormat {
         OPCODE_FORMAT[self as usize - 1]
@@ -201,6 +260,41 @@ impl Opcode {
     pub fn is_safepoint(self) -> bool {
         self.is_call() && !self.is_return()
     }
+
+    /// Is this opcode free of side effects?
+    ///
+    /// A pure instruction doesn't access memory, can't trap, doesn't call, branch or return, and
+    /// has no other side effects, so it can be moved or removed as long as its arguments and
+    /// results stay available.
+    pub fn is_pure(self) -> bool {
+        !self.can_load() && !crate::inst_predicates::trivially_has_side_effects(self)
+    }
+
+    /// Can the two operands of this opcode be swapped without changing its result?
+    ///
+    /// This is conservative: `false` is returned for any opcode that isn't known to be
+    /// commutative, including comparisons, whose condition code would have to change as well.
+    pub fn is_commutative(self) -> bool {
+        match self {
+            Self::Iadd
+            | Self::Imul
+            | Self::Umulhi
+            | Self::Smulhi
+            | Self::UaddSat
+            | Self::SaddSat
+            | Self::Umin
+            | Self::Umax
+            | Self::Smin
+            | Self::Smax
+            | Self::AvgRound
+            | Self::Band
+            | Self::Bor
+            | Self::Bxor
+            | Self::Fadd
+            | Self::Fmul => true,
+            _ => false,
+        }
+    }
 }
 
 // This trait really belongs in cranelift-reader where it is used by the `.clif` file parser, but since
@@ -304,14 +398,23 @@ impl Default for VariableArgs {
 impl InstructionData {
     /// Get the destinations of this instruction, if it's a branch.
     ///
-    /// `br_table` returns the empty slice.
+    /// Each `BlockCall` holds a destination block and the arguments passed to its parameters,
+    /// after the ones that the branch defines itself (see
+    /// `DataFlowGraph::num_implicit_block_args`). For `br_table`, the default destination comes
+    /// first, followed by the entries of the jump table. For `try_call`, the normal destination
+    /// comes first, followed by the exception destination. Non-branch instructions return the
+    /// empty slice.
     pub fn branch_destination<'a>(&'a self, jump_tables: &'a ir::JumpTables) -> &'a [BlockCall] {
         match self {
             Self::Jump {
                 ref destination, ..
             } => std::slice::from_ref(destination),
             Self::Brif { blocks, .. } => blocks.as_slice(),
-            Self::BranchTable { table, .. } => jump_tables.get(*table).unwrap().all_branches(),
+            Self::BranchTable { table, .. }
+            | Self::TryCall { table, .. }
+            | Self::TryCallIndirect { table, .. } => {
+                jump_tables.get(*table).unwrap().all_branches()
+            }
             _ => {
                 debug_assert!(!self.opcode().is_branch());
                 &[]
@@ -321,7 +424,7 @@ impl InstructionData {
 
     /// Get a mutable slice of the destinations of this instruction, if it's a branch.
     ///
-    /// `br_table` returns the empty slice.
+    /// The destinations are in the same order as `branch_destination`.
     pub fn branch_destination_mut<'a>(
         &'a mut self,
         jump_tables: &'a mut ir::JumpTables,
@@ -332,7 +435,9 @@ impl InstructionData {
                 ..
             } => std::slice::from_mut(destination),
             Self::Brif { blocks, .. } => blocks.as_mut_slice(),
-            Self::BranchTable { table, .. } => {
+            Self::BranchTable { table, .. }
+            | Self::TryCall { table, .. }
+            | Self::TryCallIndirect { table, .. } => {
                 jump_tables.get_mut(*table).unwrap().all_branches_mut()
             }
             _ => {
@@ -361,43 +466,6 @@ impl InstructionData {
         }
     }
 
-    /// If this is a trapping instruction, get its trap code. Otherwise, return
-    /// `None`.
-    pub fn trap_code(&self) -> Option<TrapCode> {
-        match *self {
-            Self::CondTrap { code, .. } | Self::Trap { code, .. } => Some(code),
-            _ => None,
-        }
-    }
-
-    /// If this is a control-flow instruction depending on an integer condition, gets its
-    /// condition.  Otherwise, return `None`.
-    pub fn cond_code(&self) -> Option<IntCC> {
-        match self {
-            &InstructionData::IntCompare { cond, .. }
-            | &InstructionData::IntCompareImm { cond, .. } => Some(cond),
-            _ => None,
-        }
-    }
-
-    /// If this is a control-flow instruction depending on a floating-point condition, gets its
-    /// condition.  Otherwise, return `None`.
-    pub fn fp_cond_code(&self) -> Option<FloatCC> {
-        match self {
-            &InstructionData::FloatCompare { cond, .. } => Some(cond),
-            _ => None,
-        }
-    }
-
-    /// If this is a trapping instruction, get an exclusive reference to its
-    /// trap code. Otherwise, return `None`.
-    pub fn trap_code_mut(&mut self) -> Option<&mut TrapCode> {
-        match self {
-            Self::CondTrap { code, .. } | Self::Trap { code, .. } => Some(code),
-            _ => None,
-        }
-    }
-
     /// If this is an atomic read/modify/write instruction, return its subopcode.
     pub fn atomic_rmw_op(&self) -> Option<ir::AtomicRmwOp> {
         match self {
@@ -446,9 +514,15 @@ impl InstructionData {
         match *self {
             Self::Call {
                 func_ref, ref args, ..
+            }
+            | Self::TryCall {
+                func_ref, ref args, ..
             } => CallInfo::Direct(func_ref, args.as_slice(pool)),
             Self::CallIndirect {
                 sig_ref, ref args, ..
+            }
+            | Self::TryCallIndirect {
+                sig_ref, ref args, ..
             } => CallInfo::Indirect(sig_ref, &args.as_slice(pool)[1..]),
             Self::Ternary {
                 opcode: Opcode::StackSwitch,
@@ -908,6 +982,527 @@ mod tests {
         assert_eq!(mem::size_of::<Opcode>(), mem::size_of::<Option<Opcode>>());
     }
 
+    #[test]
+    fn pure_opcodes() {
+        for opcode in [Opcode::Iadd, Opcode::Fdiv, Opcode::Iconst, Opcode::Select] {
+            assert!(opcode.is_pure(), "{opcode}");
+        }
+        for opcode in [
+            Opcode::Load,
+            Opcode::Store,
+            Opcode::Call,
+            Opcode::Trap,
+            Opcode::Udiv,
+            Opcode::Fence,
+            Opcode::Jump,
+            Opcode::Return,
+        ] {
+            assert!(!opcode.is_pure(), "{opcode}");
+        }
+    }
+
+    #[test]
+    fn commutative_opcodes() {
+        for opcode in [Opcode::Iadd, Opcode::Imul, Opcode::Band, Opcode::Fmul] {
+            assert!(opcode.is_commutative(), "{opcode}");
+        }
+        for opcode in [
+            Opcode::Isub,
+            Opcode::Udiv,
+            Opcode::Ishl,
+            Opcode::Icmp,
+            Opcode::Fsub,
+            Opcode::IaddImm,
+        ] {
+            assert!(!opcode.is_commutative(), "{opcode}");
+        }
+    }
+
+    #[test]
+    fn all_opcodes() {
+        for &opcode in Opcode::all() {
+            assert_eq!(Opcode::from_name(opcode_name(opcode)), Some(opcode));
+
+            // The format describes the same operands as the opcode's constraints.
+            let format = opcode.format();
+            let constraints = opcode.constraints();
+            if opcode.is_commutative() {
+                assert_eq!(format, InstructionFormat::Binary, "{opcode}");
+            }
+            assert_eq!(
+                format.num_value_operands(),
+                constraints.num_fixed_value_arguments(),
+                "{opcode}"
+            );
+        }
+        assert_eq!(Opcode::from_name("iadd_imm"), Some(Opcode::IaddImm));
+        assert_eq!(Opcode::from_name("iadd\0"), None);
+
+        assert_eq!(InstructionFormat::Binary.num_value_operands(), 2);
+        assert!(!InstructionFormat::Binary.has_value_list());
+        assert_eq!(InstructionFormat::Brif.num_block_operands(), 2);
+        assert!(InstructionFormat::Call.has_value_list());
+        assert_eq!(
+            InstructionFormat::BinaryImm64.immediates(),
+            [FormatImmediate {
+                member: "imm",
+                rust_type: "ir::immediates::Imm64",
+                is_entity: false,
+            }]
+        );
+        assert_eq!(
+            InstructionFormat::Call
+                .immediates()
+                .iter()
+                .map(|imm| (imm.member, imm.is_entity))
+                .collect::<Vec<_>>(),
+            [("func_ref", true)]
+        );
+    }
+
+    #[test]
+    fn branch_destinations() {
+        use crate::ir::{JumpTableData, JumpTables};
+        use cranelift_entity::EntityRef;
+
+        let pool = &mut ValueListPool::new();
+        let jump_tables = &mut JumpTables::new();
+        let [block0, block1, block2] = [0, 1, 2].map(Block::new);
+        let [v0, v1] = [0, 1].map(Value::new);
+        let dests = |data: &InstructionData, pool: &ValueListPool, jts: &JumpTables| {
+            data.branch_destination(jts)
+                .iter()
+                .map(|call| (call.block(pool), call.args_slice(pool).to_vec()))
+                .collect::<Vec<_>>()
+        };
+
+        let jump = InstructionData::Jump {
+            opcode: Opcode::Jump,
+            destination: BlockCall::new(block1, &[v0, v1], pool),
+        };
+        assert_eq!(dests(&jump, pool, jump_tables), [(block1, vec![v0, v1])]);
+
+        let brif = InstructionData::Brif {
+            opcode: Opcode::Brif,
+            arg: v0,
+            blocks: [
+                BlockCall::new(block1, &[v1], pool),
+                BlockCall::new(block2, &[], pool),
+            ],
+        };
+        assert_eq!(
+            dests(&brif, pool, jump_tables),
+            [(block1, vec![v1]), (block2, vec![])]
+        );
+
+        let table = jump_tables.push(JumpTableData::new(
+            BlockCall::new(block0, &[], pool),
+            &[
+                BlockCall::new(block2, &[v0], pool),
+                BlockCall::new(block1, &[v1], pool),
+            ],
+        ));
+        let br_table = InstructionData::BranchTable {
+            opcode: Opcode::BrTable,
+            arg: v0,
+            table,
+        };
+        assert_eq!(
+            dests(&br_table, pool, jump_tables),
+            [(block0, vec![]), (block2, vec![v0]), (block1, vec![v1])]
+        );
+
+        let ret = InstructionData::MultiAry {
+            opcode: Opcode::Return,
+            args: ValueList::from_slice(&[v0], pool),
+        };
+        assert!(ret.branch_destination(jump_tables).is_empty());
+
+        // Retargeting a destination leaves its arguments alone.
+        let mut brif = brif;
+        brif.branch_destination_mut(jump_tables)[0].set_block(block0, pool);
+        assert_eq!(
+            dests(&brif, pool, jump_tables),
+            [(block0, vec![v1]), (block2, vec![])]
+        );
+    }
+
+    #[test]
+    fn immediate_accessors() {
+        use crate::ir::condcodes::{FloatCC, IntCC};
+        use crate::ir::immediates::{Ieee16, Ieee32, Ieee64, Imm64, Offset32, Uimm8};
+        use crate::ir::{
+            AtomicRmwOp, Constant, DynamicStackSlot, GlobalValue, Immediate, JumpTable, TrapCode,
+        };
+        use cranelift_entity::EntityRef;
+
+        // Build an instance of `format` along with the names of the accessors
+        // that should find an immediate in it. The match is exhaustive so that
+        // new formats have to be added here.
+        fn sample(
+            format: InstructionFormat,
+            opcode: Opcode,
+            pool: &mut ValueListPool,
+        ) -> (InstructionData, &'static [&'static str]) {
+            let v = Value::new(0);
+            let block = BlockCall::new(Block::new(0), &[], pool);
+            let flags = MemFlags::new();
+            let offset = Offset32::new(8);
+            let code = TrapCode::STACK_OVERFLOW;
+            match format {
+                InstructionFormat::AtomicCas => (
+                    InstructionData::AtomicCas {
+                        opcode,
+                        args: [v; 3],
+                        flags,
+                    },
+                    &[],
+                ),
+                InstructionFormat::AtomicRmw => (
+                    InstructionData::AtomicRmw {
+                        opcode,
+                        args: [v; 2],
+                        flags,
+                        op: AtomicRmwOp::Add,
+                    },
+                    &[],
+                ),
+                InstructionFormat::Binary => (
+                    InstructionData::Binary {
+                        opcode,
+                        args: [v; 2],
+                    },
+                    &[],
+                ),
+                InstructionFormat::BinaryImm64 => (
+                    InstructionData::BinaryImm64 {
+                        opcode,
+                        arg: v,
+                        imm: Imm64::new(5),
+                    },
+                    &["imm64"],
+                ),
+                InstructionFormat::BinaryImm8 => (
+                    InstructionData::BinaryImm8 {
+                        opcode,
+                        arg: v,
+                        imm: Uimm8::from(5),
+                    },
+                    &[],
+                ),
+                InstructionFormat::BranchTable => (
+                    InstructionData::BranchTable {
+                        opcode,
+                        arg: v,
+                        table: JumpTable::new(0),
+                    },
+                    &[],
+                ),
+                InstructionFormat::Brif => (
+                    InstructionData::Brif {
+                        opcode,
+                        arg: v,
+                        blocks: [block; 2],
+                    },
+                    &[],
+                ),
+                InstructionFormat::Call => (
+                    InstructionData::Call {
+                        opcode,
+                        args: ValueList::new(),
+                        func_ref: FuncRef::new(0),
+                    },
+                    &[],
+                ),
+                InstructionFormat::CallIndirect => (
+                    InstructionData::CallIndirect {
+                        opcode,
+                        args: ValueList::new(),
+                        sig_ref: SigRef::new(0),
+                    },
+                    &[],
+                ),
+                InstructionFormat::CondTrap => (
+                    InstructionData::CondTrap {
+                        opcode,
+                        arg: v,
+                        code,
+                    },
+                    &["trap_code"],
+                ),
+                InstructionFormat::DynamicStackLoad => (
+                    InstructionData::DynamicStackLoad {
+                        opcode,
+                        dynamic_stack_slot: DynamicStackSlot::new(0),
+                    },
+                    &[],
+                ),
+                InstructionFormat::DynamicStackStore => (
+                    InstructionData::DynamicStackStore {
+                        opcode,
+                        arg: v,
+                        dynamic_stack_slot: DynamicStackSlot::new(0),
+                    },
+                    &[],
+                ),
+                InstructionFormat::FloatCompare => (
+                    InstructionData::FloatCompare {
+                        opcode,
+                        args: [v; 2],
+                        cond: FloatCC::Equal,
+                    },
+                    &["fp_cond_code"],
+                ),
+                InstructionFormat::FuncAddr => (
+                    InstructionData::FuncAddr {
+                        opcode,
+                        func_ref: FuncRef::new(0),
+                    },
+                    &[],
+                ),
+                // `uadd_overflow_trap` traps too, so it has a trap code.
+                InstructionFormat::IntAddTrap => (
+                    InstructionData::IntAddTrap {
+                        opcode,
+                        args: [v; 2],
+                        code,
+                    },
+                    &["trap_code"],
+                ),
+                InstructionFormat::IntCompare => (
+                    InstructionData::IntCompare {
+                        opcode,
+                        args: [v; 2],
+                        cond: IntCC::Equal,
+                    },
+                    &["cond_code"],
+                ),
+                InstructionFormat::IntCompareImm => (
+                    InstructionData::IntCompareImm {
+                        opcode,
+                        arg: v,
+                        cond: IntCC::Equal,
+                        imm: Imm64::new(5),
+                    },
+                    &["imm64", "cond_code"],
+                ),
+                InstructionFormat::Jump => (
+                    InstructionData::Jump {
+                        opcode,
+                        destination: block,
+                    },
+                    &[],
+                ),
+                InstructionFormat::Load => (
+                    InstructionData::Load {
+                        opcode,
+                        arg: v,
+                        flags,
+                        offset,
+                    },
+                    &["offset32"],
+                ),
+                InstructionFormat::LoadNoOffset => (
+                    InstructionData::LoadNoOffset {
+                        opcode,
+                        arg: v,
+                        flags,
+                    },
+                    &[],
+                ),
+                InstructionFormat::MultiAry => (
+                    InstructionData::MultiAry {
+                        opcode,
+                        args: ValueList::new(),
+                    },
+                    &[],
+                ),
+                InstructionFormat::NullAry => (InstructionData::NullAry { opcode }, &[]),
+                InstructionFormat::Shuffle => (
+                    InstructionData::Shuffle {
+                        opcode,
+                        args: [v; 2],
+                        imm: Immediate::new(0),
+                    },
+                    &[],
+                ),
+                InstructionFormat::StackLoad => (
+                    InstructionData::StackLoad {
+                        opcode,
+                        stack_slot: StackSlot::new(0),
+                        offset,
+                    },
+                    &["offset32"],
+                ),
+                InstructionFormat::StackStore => (
+                    InstructionData::StackStore {
+                        opcode,
+                        arg: v,
+                        stack_slot: StackSlot::new(0),
+                        offset,
+                    },
+                    &["offset32"],
+                ),
+                InstructionFormat::Store => (
+                    InstructionData::Store {
+                        opcode,
+                        args: [v; 2],
+                        flags,
+                        offset,
+                    },
+                    &["offset32"],
+                ),
+                InstructionFormat::StoreNoOffset => (
+                    InstructionData::StoreNoOffset {
+                        opcode,
+                        args: [v; 2],
+                        flags,
+                    },
+                    &[],
+                ),
+                InstructionFormat::Ternary => (
+                    InstructionData::Ternary {
+                        opcode,
+                        args: [v; 3],
+                    },
+                    &[],
+                ),
+                InstructionFormat::TernaryImm8 => (
+                    InstructionData::TernaryImm8 {
+                        opcode,
+                        args: [v; 2],
+                        imm: Uimm8::from(5),
+                    },
+                    &[],
+                ),
+                InstructionFormat::Trap => (InstructionData::Trap { opcode, code }, &["trap_code"]),
+                InstructionFormat::TryCall => (
+                    InstructionData::TryCall {
+                        opcode,
+                        args: ValueList::new(),
+                        func_ref: FuncRef::new(0),
+                        table: JumpTable::new(0),
+                    },
+                    &[],
+                ),
+                InstructionFormat::TryCallIndirect => (
+                    InstructionData::TryCallIndirect {
+                        opcode,
+                        args: ValueList::new(),
+                        sig_ref: SigRef::new(0),
+                        table: JumpTable::new(0),
+                    },
+                    &[],
+                ),
+                InstructionFormat::Unary => (InstructionData::Unary { opcode, arg: v }, &[]),
+                InstructionFormat::UnaryConst => (
+                    InstructionData::UnaryConst {
+                        opcode,
+                        constant_handle: Constant::new(0),
+                    },
+                    &[],
+                ),
+                InstructionFormat::UnaryGlobalValue => (
+                    InstructionData::UnaryGlobalValue {
+                        opcode,
+                        global_value: GlobalValue::new(0),
+                    },
+                    &[],
+                ),
+                InstructionFormat::UnaryIeee16 => (
+                    InstructionData::UnaryIeee16 {
+                        opcode,
+                        imm: Ieee16::with_bits(0x3c00),
+                    },
+                    &["ieee16"],
+                ),
+                InstructionFormat::UnaryIeee32 => (
+                    InstructionData::UnaryIeee32 {
+                        opcode,
+                        imm: Ieee32::with_float(1.0),
+                    },
+                    &["ieee32"],
+                ),
+                InstructionFormat::UnaryIeee64 => (
+                    InstructionData::UnaryIeee64 {
+                        opcode,
+                        imm: Ieee64::with_float(1.0),
+                    },
+                    &["ieee64"],
+                ),
+                InstructionFormat::UnaryImm => (
+                    InstructionData::UnaryImm {
+                        opcode,
+                        imm: Imm64::new(5),
+                    },
+                    &["imm64"],
+                ),
+            }
+        }
+
+        let mut pool = ValueListPool::new();
+        let mut formats = Vec::new();
+        for &opcode in Opcode::all() {
+            let format = opcode.format();
+            if formats.contains(&format) {
+                continue;
+            }
+            formats.push(format);
+
+            let (mut data, expected) = sample(format, opcode, &mut pool);
+            assert_eq!(InstructionFormat::from(&data), format);
+            let has = |name| expected.contains(&name);
+
+            // Each getter finds an immediate exactly when the format has one,
+            // and writes through the `_mut` variant are visible to it.
+            assert_eq!(data.imm64().is_some(), has("imm64"), "{format:?}");
+            if let Some(imm) = data.imm64_mut() {
+                *imm = Imm64::new(-1);
+                assert_eq!(data.imm64(), Some(Imm64::new(-1)));
+            }
+            assert_eq!(data.ieee16().is_some(), has("ieee16"), "{format:?}");
+            if let Some(imm) = data.ieee16_mut() {
+                *imm = Ieee16::with_bits(0x4000);
+                assert_eq!(data.ieee16(), Some(Ieee16::with_bits(0x4000)));
+            }
+            assert_eq!(data.ieee32().is_some(), has("ieee32"), "{format:?}");
+            if let Some(imm) = data.ieee32_mut() {
+                *imm = Ieee32::with_float(2.0);
+                assert_eq!(data.ieee32(), Some(Ieee32::with_float(2.0)));
+            }
+            assert_eq!(data.ieee64().is_some(), has("ieee64"), "{format:?}");
+            if let Some(imm) = data.ieee64_mut() {
+                *imm = Ieee64::with_float(2.0);
+                assert_eq!(data.ieee64(), Some(Ieee64::with_float(2.0)));
+            }
+            assert_eq!(data.offset32().is_some(), has("offset32"), "{format:?}");
+            if let Some(offset) = data.offset32_mut() {
+                *offset = Offset32::new(16);
+                assert_eq!(data.offset32(), Some(Offset32::new(16)));
+                assert_eq!(data.load_store_offset(), Some(16));
+            }
+            assert_eq!(data.cond_code().is_some(), has("cond_code"), "{format:?}");
+            if let Some(cond) = data.cond_code_mut() {
+                *cond = IntCC::NotEqual;
+                assert_eq!(data.cond_code(), Some(IntCC::NotEqual));
+            }
+            assert_eq!(
+                data.fp_cond_code().is_some(),
+                has("fp_cond_code"),
+                "{format:?}"
+            );
+            if let Some(cond) = data.fp_cond_code_mut() {
+                *cond = FloatCC::NotEqual;
+                assert_eq!(data.fp_cond_code(), Some(FloatCC::NotEqual));
+            }
+            assert_eq!(data.trap_code().is_some(), has("trap_code"), "{format:?}");
+            if let Some(code) = data.trap_code_mut() {
+                *code = TrapCode::INTEGER_OVERFLOW;
+                assert_eq!(data.trap_code(), Some(TrapCode::INTEGER_OVERFLOW));
+            }
+        }
+    }
+
     #[test]
     fn instruction_data() {
         use core::mem;
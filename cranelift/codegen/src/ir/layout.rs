@@ -296,7 +296,28 @@ impl Layout {
     pub fn remove_block(&mut self, block: Block) {
         debug_assert!(self.is_block_inserted(block), "block not in the layout");
         debug_assert!(self.first_inst(block).is_none(), "block must be empty.");
+        self.unlink_block(block);
+    }
+
+    /// Move `block` and its instructions to the end of the layout.
+    pub fn move_block_to_end(&mut self, block: Block) {
+        debug_assert!(self.is_block_inserted(block), "block not in the layout");
+        let last = self.last_block.unwrap();
+        if last == block {
+            return;
+        }
+        self.unlink_block(block);
+        {
+            let node = &mut self.blocks[block];
+            node.prev = last.into();
+            node.next = None.into();
+        }
+        self.blocks[last].next = block.into();
+        self.last_block = Some(block);
+    }
 
+    /// Unlink `block` from its neighbors in the layout, leaving its instructions alone.
+    fn unlink_block(&mut self, block: Block) {
         // Clear the `block` node and extract links.
         let prev;
         let next;
@@ -972,6 +993,30 @@ mod tests {
         verify(&mut layout, &[(e1, &[]), (e0, &[]), (e2, &[])]);
     }
 
+    #[test]
+    fn move_block_to_end() {
+        let mut layout = Layout::new();
+        let e0 = Block::new(0);
+        let e1 = Block::new(1);
+        let e2 = Block::new(2);
+        let i0 = Inst::new(0);
+        let i1 = Inst::new(1);
+
+        layout.append_block(e0);
+        layout.append_block(e1);
+        layout.append_block(e2);
+        layout.append_inst(i0, e0);
+        layout.append_inst(i1, e0);
+
+        layout.move_block_to_end(e0);
+        verify(&mut layout, &[(e1, &[]), (e2, &[]), (e0, &[i0, i1])]);
+        layout.move_block_to_end(e0);
+        verify(&mut layout, &[(e1, &[]), (e2, &[]), (e0, &[i0, i1])]);
+        layout.move_block_to_end(e2);
+        verify(&mut layout, &[(e1, &[]), (e0, &[i0, i1]), (e2, &[])]);
+        assert_eq!(layout.entry_block(), Some(e1));
+    }
+
     #[test]
     fn append_inst() {
         let mut layout = Layout::new();
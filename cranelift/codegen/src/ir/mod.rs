@@ -21,6 +21,7 @@ mod memflags;
 mod memtype;
 pub mod pcc;
 mod progpoint;
+mod snapshot;
 mod sourceloc;
 pub mod stackslot;
 mod trapcode;
@@ -32,10 +33,11 @@ use serde_derive::{Deserialize, Serialize};
 
 pub use crate::ir::atomic_rmw_op::AtomicRmwOp;
 pub use crate::ir::builder::{
-    InsertBuilder, InstBuilder, InstBuilderBase, InstInserterBase, ReplaceBuilder,
+    CheckedInsertBuilder, InsertBuilder, InstBuilder, InstBuilderBase, InstInserterBase,
+    ReplaceBuilder,
 };
 pub use crate::ir::constant::{ConstantData, ConstantPool};
-pub use crate::ir::dfg::{BlockData, DataFlowGraph, ValueDef};
+pub use crate::ir::dfg::{BlockData, DataFlowGraph, RewriteError, TypeCheckError, ValueDef};
 pub use crate::ir::dynamic_type::{dynamic_to_fixed, DynamicTypeData, DynamicTypes};
 pub use crate::ir::entities::{
     Block, Constant, DynamicStackSlot, DynamicType, FuncRef, GlobalValue, Immediate, Inst,
@@ -45,7 +47,7 @@ pub use crate::ir::extfunc::{
     AbiParam, ArgumentExtension, ArgumentPurpose, ExtFuncData, Signature,
 };
 pub use crate::ir::extname::{ExternalName, UserExternalName, UserFuncName};
-pub use crate::ir::function::Function;
+pub use crate::ir::function::{DfgStats, Function, MemoryReport, MoveError};
 pub use crate::ir::globalvalue::GlobalValueData;
 pub use crate::ir::instructions::{
     BlockCall, InstructionData, Opcode, ValueList, ValueListPool, VariableArgs,
@@ -58,6 +60,7 @@ pub use crate::ir::memflags::{AliasRegion, Endianness, MemFlags};
 pub use crate::ir::memtype::{MemoryTypeData, MemoryTypeField};
 pub use crate::ir::pcc::{BaseExpr, Expr, Fact, FactContext, PccError, PccResult};
 pub use crate::ir::progpoint::ProgramPoint;
+pub use crate::ir::snapshot::FunctionSnapshot;
 pub use crate::ir::sourceloc::RelSourceLoc;
 pub use crate::ir::sourceloc::SourceLoc;
 pub use crate::ir::stackslot::{
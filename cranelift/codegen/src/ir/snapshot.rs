@@ -0,0 +1,276 @@
+//! Lightweight checkpoints of a function for speculative transformations.
+//!
+//! A pass that wants to try a transformation and keep it only if it pays off can take a
+//! `FunctionSnapshot` first, and call `restore` to roll back if it doesn't. Unlike cloning the
+//! whole function, a snapshot only records the sizes of the entity tables and the value list
+//! pool, and relies on the following rules while it is alive:
+//!
+//! - New entities may be created and changed freely, and new instructions and blocks may be
+//!   inserted anywhere in the layout.
+//! - Instructions that existed when the snapshot was taken may only be replaced or removed from
+//!   the layout through the snapshot's `replace_inst` and `remove_inst` methods, which keep a
+//!   journal of the old state.
+//! - Nothing else that existed when the snapshot was taken may be changed: no new parameters on
+//!   old blocks, no aliasing of old values, no reordering of old instructions.
+//!
+//! Breaking these rules is detected when the snapshot is restored, and causes a panic.
+
+use crate::entity::EntityRef;
+use crate::ir::{Block, Function, Inst, InstructionData};
+use alloc::vec::Vec;
+use core::hash::Hasher;
+use rustc_hash::FxHasher;
+
+/// A change to an instruction that existed when the snapshot was taken.
+enum Mutation {
+    /// The instruction's data was replaced.
+    Replace { inst: Inst, data: InstructionData },
+    /// The instruction was removed from the layout. It was the first instruction in `block` if
+    /// `prev` is `None`.
+    Remove {
+        inst: Inst,
+        block: Block,
+        prev: Option<Inst>,
+    },
+}
+
+/// A checkpoint of a function that can be restored later.
+///
+/// See the module documentation for the changes that can be rolled back.
+pub struct FunctionSnapshot {
+    insts: usize,
+    values: usize,
+    blocks: usize,
+    value_lists: usize,
+    signatures: usize,
+    ext_funcs: usize,
+    jump_tables: usize,
+    immediates: usize,
+    dynamic_types: usize,
+    global_values: usize,
+    memory_types: usize,
+    sized_stack_slots: usize,
+    dynamic_stack_slots: usize,
+    srclocs: usize,
+    journal: Vec<Mutation>,
+    fingerprint: u64,
+}
+
+impl FunctionSnapshot {
+    /// Take a snapshot of `func`.
+    ///
+    /// This doesn't copy any of the function's data, but it does visit every instruction, value
+    /// and block to compute a fingerprint that is checked when restoring.
+    pub fn new(func: &Function) -> Self {
+        let dfg = &func.dfg;
+        let mut snapshot = Self {
+            insts: dfg.num_insts(),
+            values: dfg.num_values(),
+            blocks: dfg.num_blocks(),
+            value_lists: dfg.value_lists.len(),
+            signatures: dfg.signatures.len(),
+            ext_funcs: dfg.ext_funcs.len(),
+            jump_tables: dfg.jump_tables.len(),
+            immediates: dfg.immediates.len(),
+            dynamic_types: dfg.dynamic_types.len(),
+            global_values: func.global_values.len(),
+            memory_types: func.memory_types.len(),
+            sized_stack_slots: func.sized_stack_slots.len(),
+            dynamic_stack_slots: func.dynamic_stack_slots.len(),
+            srclocs: func.srclocs.values().len(),
+            journal: Vec::new(),
+            fingerprint: 0,
+        };
+        snapshot.fingerprint = snapshot.fingerprint(func);
+        snapshot
+    }
+
+    /// Is `inst` older than this snapshot?
+    fn is_old(&self, inst: Inst) -> bool {
+        inst.index() < self.insts
+    }
+
+    /// Replace the data of `inst` with `data`, recording the old data if needed.
+    ///
+    /// The new data must produce the same result types, since the instruction's results are
+    /// not changed. It must not modify any value lists of the old data in place, since those
+    /// are needed to restore it.
+    pub fn replace_inst(&mut self, func: &mut Function, inst: Inst, data: InstructionData) {
+        let old = core::mem::replace(&mut func.dfg.insts[inst], data);
+        if self.is_old(inst) {
+            self.journal.push(Mutation::Replace { inst, data: old });
+        }
+    }
+
+    /// Remove `inst` from the layout, recording its position if needed.
+    pub fn remove_inst(&mut self, func: &mut Function, inst: Inst) {
+        if self.is_old(inst) {
+            let block = func
+                .layout
+                .inst_block(inst)
+                .expect("instruction not in the layout");
+            let prev = func.layout.prev_inst(inst);
+            self.journal.push(Mutation::Remove { inst, block, prev });
+        }
+        func.layout.remove_inst(inst);
+    }
+
+    /// Roll `func` back to the state it was in when this snapshot was taken.
+    ///
+    /// Panics if `func` was changed in a way the snapshot can't undo.
+    pub fn restore(self, func: &mut Function) {
+        // Undo journaled changes in reverse, so that the recorded layout positions are valid
+        // again by the time each removed instruction is reinserted.
+        for mutation in self.journal.iter().rev() {
+            match *mutation {
+                Mutation::Replace { inst, data } => func.dfg.insts[inst] = data,
+                Mutation::Remove { inst, block, prev } => {
+                    let next = match prev {
+                        Some(prev) => func.layout.next_inst(prev),
+                        None => func.layout.first_inst(block),
+                    };
+                    match next {
+                        Some(next) => func.layout.insert_inst(inst, next),
+                        None => func.layout.append_inst(inst, block),
+                    }
+                }
+            }
+        }
+
+        // Remove new instructions and blocks from the layout.
+        let blocks: Vec<Block> = func.layout.blocks().collect();
+        for block in blocks {
+            let is_new_block = block.index() >= self.blocks;
+            let mut next = func.layout.first_inst(block);
+            while let Some(inst) = next {
+                next = func.layout.next_inst(inst);
+                if is_new_block || !self.is_old(inst) {
+                    func.layout.remove_inst(inst);
+                }
+            }
+            if is_new_block {
+                func.layout.remove_block(block);
+            }
+        }
+
+        func.dfg
+            .truncate(self.insts, self.values, self.blocks, self.value_lists);
+        func.dfg.signatures.truncate(self.signatures);
+        func.dfg.ext_funcs.truncate(self.ext_funcs);
+        func.dfg.jump_tables.truncate(self.jump_tables);
+        func.dfg.immediates.truncate(self.immediates);
+        func.dfg.dynamic_types.truncate(self.dynamic_types);
+        func.global_values.truncate(self.global_values);
+        func.global_value_facts.resize(self.global_values);
+        func.memory_types.truncate(self.memory_types);
+        func.sized_stack_slots.truncate(self.sized_stack_slots);
+        func.dynamic_stack_slots.truncate(self.dynamic_stack_slots);
+        // The source location map only grows when locations are set, and whether it is empty
+        // changes how the function is printed, so restore its old size.
+        func.srclocs.resize(self.srclocs);
+
+        assert_eq!(
+            self.fingerprint(func),
+            self.fingerprint,
+            "function was changed in a way that FunctionSnapshot can't restore"
+        );
+    }
+
+    /// Hash the entities covered by this snapshot and their order in the layout.
+    fn fingerprint(&self, func: &Function) -> u64 {
+        let mut hasher = FxHasher::default();
+        func.dfg
+            .hash_entities(self.insts, self.values, self.blocks, &mut hasher);
+        for block in func.layout.blocks() {
+            if block.index() < self.blocks {
+                hasher.write_u32(block.as_u32());
+            }
+            for inst in func.layout.block_insts(block) {
+                if self.is_old(inst) {
+                    hasher.write_u32(inst.as_u32());
+                }
+            }
+        }
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cursor::{Cursor, FuncCursor};
+    use crate::ir::{types, AbiParam, InstBuilder, Signature};
+    use crate::isa::CallConv;
+    use alloc::string::ToString;
+
+    fn sample() -> Function {
+        let mut func = Function::new();
+        let block0 = func.dfg.make_block();
+        let block1 = func.dfg.make_block();
+        let v0 = func.dfg.append_block_param(block0, types::I32);
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let v1 = pos.ins().iadd_imm(v0, 1);
+        let v2 = pos.ins().imul(v1, v0);
+        pos.ins().brif(v2, block1, &[], block1, &[]);
+        pos.insert_block(block1);
+        pos.ins().return_(&[]);
+        func
+    }
+
+    #[test]
+    fn restore() {
+        let mut func = sample();
+        let original = func.display().to_string();
+        let snapshot_len = func.dfg.value_lists.len();
+        let mut snapshot = FunctionSnapshot::new(&func);
+
+        let block0 = func.layout.entry_block().unwrap();
+        let block1 = func.layout.next_block(block0).unwrap();
+        let mut insts = func.layout.block_insts(block0);
+        let iadd = insts.next().unwrap();
+        let imul = insts.next().unwrap();
+        let v0 = func.dfg.block_params(block0)[0];
+
+        // Replace and remove old instructions through the snapshot.
+        let mut data = func.dfg.insts[imul];
+        let dfg = &mut func.dfg;
+        data.map_values(&mut dfg.value_lists, &mut dfg.jump_tables, |_| v0);
+        snapshot.replace_inst(&mut func, imul, data);
+        snapshot.remove_inst(&mut func, iadd);
+
+        // Create lots of new entities.
+        let block2 = func.dfg.make_block();
+        let v3 = func.dfg.append_block_param(block2, types::I64);
+        let mut sig = Signature::new(CallConv::SystemV);
+        sig.params.push(AbiParam::new(types::I64));
+        let sig = func.import_signature(sig);
+        let mut pos = FuncCursor::new(&mut func).at_first_insertion_point(block1);
+        let v4 = pos.ins().iconst(types::I64, 7);
+        pos.ins().call_indirect(sig, v4, &[v4]);
+        pos.goto_bottom(block1);
+        pos.insert_block(block2);
+        let args: Vec<_> = (0..20).map(|_| v3).collect();
+        pos.ins().call_indirect(sig, v3, &args);
+        pos.ins().return_(&[]);
+        assert_ne!(func.display().to_string(), original);
+
+        snapshot.restore(&mut func);
+        assert_eq!(func.display().to_string(), original);
+        assert_eq!(func.dfg.value_lists.len(), snapshot_len);
+        assert_eq!(func.dfg.signatures.len(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "can't restore")]
+    fn unsupported_mutation() {
+        let mut func = sample();
+        let snapshot = FunctionSnapshot::new(&func);
+
+        // Adding a parameter to an existing block isn't journaled.
+        let block0 = func.layout.entry_block().unwrap();
+        func.dfg.append_block_param(block0, types::I64);
+
+        snapshot.restore(&mut func);
+    }
+}
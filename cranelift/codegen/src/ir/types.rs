@@ -313,6 +313,33 @@ impl Type {
         (self.bits() + 7) / 8
     }
 
+    /// Get the number of bits in a lane, or `None` for types without lanes like `INVALID`.
+    ///
+    /// This is `lane_bits` for callers that need to handle the special types explicitly.
+    pub fn bit_width(self) -> Option<u8> {
+        match self.lane_bits() {
+            0 => None,
+            bits => Some(bits as u8),
+        }
+    }
+
+    /// Get the number of bytes in a lane, or `None` for types without lanes like `INVALID`.
+    pub fn byte_width(self) -> Option<u8> {
+        self.bit_width().map(|bits| bits / 8)
+    }
+
+    /// Get the number of bytes in all lanes of this type, which is the same as `byte_width` for
+    /// scalars.
+    ///
+    /// Returns `None` for types without lanes and for dynamic vectors, whose size isn't known.
+    pub fn total_byte_width(self) -> Option<u32> {
+        if self.is_dynamic_vector() {
+            return None;
+        }
+        self.byte_width()
+            .map(|bytes| u32::from(bytes) * self.lane_count())
+    }
+
     /// Get a SIMD vector type with `n` times more lanes than this one.
     ///
     /// If this is a scalar type, this produces a SIMD type with this as a lane type and `n` lanes.
@@ -495,6 +522,31 @@ mod tests {
         assert_eq!(F128.lane_bits(), 128);
     }
 
+    #[test]
+    fn widths() {
+        assert_eq!(INVALID.bit_width(), None);
+        assert_eq!(INVALID.byte_width(), None);
+        assert_eq!(INVALID.total_byte_width(), None);
+
+        assert_eq!(I8.bit_width(), Some(8));
+        assert_eq!(I8.byte_width(), Some(1));
+        assert_eq!(I16.byte_width(), Some(2));
+        assert_eq!(F32.byte_width(), Some(4));
+        assert_eq!(I64.total_byte_width(), Some(8));
+        assert_eq!(I128.bit_width(), Some(128));
+        assert_eq!(F128.byte_width(), Some(16));
+
+        assert_eq!(I32X4.bit_width(), Some(32));
+        assert_eq!(I32X4.byte_width(), Some(4));
+        assert_eq!(I32X4.total_byte_width(), Some(16));
+        assert_eq!(I8X16.total_byte_width(), Some(16));
+        assert_eq!(F64X2.total_byte_width(), Some(I64X2.bytes()));
+
+        let dynamic = I32X4.vector_to_dynamic().unwrap();
+        assert_eq!(dynamic.byte_width(), Some(4));
+        assert_eq!(dynamic.total_byte_width(), None);
+    }
+
     #[test]
     fn typevar_functions() {
         assert_eq!(INVALID.half_width(), None);
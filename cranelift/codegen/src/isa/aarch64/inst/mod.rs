@@ -1102,6 +1102,10 @@ impl MachInst for Inst {
         Inst::Nop4
     }
 
+    fn align_loop_header(offset: CodeOffset, alignment: CodeOffset) -> Option<CodeOffset> {
+        Some(align_to(offset, alignment))
+    }
+
     fn rc_for_type(ty: Type) -> CodegenResult<(&'static [RegClass], &'static [Type])> {
         match ty {
             I8 => Ok((&[RegClass::Int], &[I8])),
@@ -53,6 +53,7 @@ use crate::isa::unwind::{systemv::RegisterMappingError, UnwindInfoKind};
 use crate::machinst::{CompiledCode, CompiledCodeStencil, TextSectionBuilder};
 use crate::settings;
 use crate::settings::Configurable;
+use crate::settings::OptLevel;
 use crate::settings::SetResult;
 use crate::CodegenResult;
 use alloc::{boxed::Box, sync::Arc, vec::Vec};
@@ -85,6 +86,7 @@ mod pulley_shared;
 pub mod unwind;
 
 mod call_conv;
+mod size_estimate;
 mod winch;
 
 /// Returns a builder that can create a corresponding `TargetIsa`
@@ -353,6 +355,31 @@ pub trait TargetIsa: fmt::Display + Send + Sync {
     /// alignment, for performance, required by this ISA.
     fn function_alignment(&self) -> FunctionAlignment;
 
+    /// Estimate the number of bytes of machine code that `inst` is lowered to, without compiling
+    /// it.
+    ///
+    /// This is a cheap guess for heuristics such as inlining and tiering decisions. The default
+    /// implementation doesn't look at the target, and errs on the large side.
+    fn estimate_inst_size(&self, dfg: &ir::DataFlowGraph, inst: ir::Inst) -> u32 {
+        size_estimate::default_inst_size(dfg, inst)
+    }
+
+    /// The number of cycles before the result of an instruction with `opcode` can be used by the
+    /// instructions that depend on it.
+    ///
+    /// This only guides instruction scheduling. The default implementation gives rough latencies
+    /// for a modern out-of-order core.
+    fn instruction_latency(&self, opcode: ir::Opcode) -> u32 {
+        match opcode {
+            ir::Opcode::Udiv | ir::Opcode::Sdiv | ir::Opcode::Urem | ir::Opcode::Srem => 20,
+            ir::Opcode::Fdiv | ir::Opcode::Sqrt => 15,
+            _ if opcode.can_load() => 4,
+            ir::Opcode::Fadd | ir::Opcode::Fsub | ir::Opcode::Fmul | ir::Opcode::Fma => 4,
+            ir::Opcode::Imul | ir::Opcode::Umulhi | ir::Opcode::Smulhi => 3,
+            _ => 1,
+        }
+    }
+
     /// The log2 of the target's page size and alignment.
     ///
     /// Note that this may be an upper-bound that is larger than necessary for
@@ -434,6 +461,19 @@ impl<'a> dyn TargetIsa + 'a {
         }
     }
 
+    /// Get the alignment for functions where performance matters, according to the `opt_level`
+    /// setting.
+    ///
+    /// This is the preferred function alignment, unless `opt_level` is `speed_and_size`, which
+    /// favors smaller code and only uses the minimum alignment.
+    pub fn opt_function_alignment(&self) -> u32 {
+        let alignment = self.function_alignment();
+        match self.flags().opt_level() {
+            OptLevel::SpeedAndSize => alignment.minimum,
+            OptLevel::None | OptLevel::Speed => alignment.preferred,
+        }
+    }
+
     /// Get the pointer type of this ISA.
     pub fn pointer_type(&self) -> ir::Type {
         ir::Type::int(self.pointer_bits() as u16).unwrap()
@@ -454,6 +494,18 @@ impl<'a> dyn TargetIsa + 'a {
         self.pointer_width().bytes()
     }
 
+    /// Does this ISA have vector registers and instructions?
+    pub(crate) fn has_vector_support(&self) -> bool {
+        // Currently RISC-V is the only architecture that may not have vector support.
+        match self.triple().architecture {
+            Architecture::Riscv64(_) => match self.isa_flags().iter().find(|f| f.name == "has_v") {
+                Some(value) => value.as_bool().unwrap_or(false),
+                None => false,
+            },
+            _ => true,
+        }
+    }
+
     /// Get the information needed by frontends producing Cranelift IR.
     pub fn frontend_config(&self) -> TargetFrontendConfig {
         TargetFrontendConfig {
@@ -1129,6 +1129,10 @@ impl MachInst for Inst {
         }
     }
 
+    fn align_loop_header(offset: CodeOffset, alignment: CodeOffset) -> Option<CodeOffset> {
+        Some(align_to(offset, alignment))
+    }
+
     fn rc_for_type(ty: Type) -> CodegenResult<(&'static [RegClass], &'static [Type])> {
         match ty {
             types::I8 => Ok((&[RegClass::Int], &[types::I8])),
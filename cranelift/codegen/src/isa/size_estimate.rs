@@ -0,0 +1,101 @@
+//! Target-independent code size estimates.
+
+use crate::ir::{DataFlowGraph, Inst, InstructionData, Opcode};
+
+/// The size assumed for a single machine instruction.
+const INST_BYTES: u32 = 4;
+
+/// Estimate the number of bytes of machine code that `inst` is lowered to, without knowing
+/// anything about the target.
+///
+/// Most instructions are assumed to become a single machine instruction, and twice as much when
+/// they operate on vectors or values wider than 64 bits. Instructions that are usually lowered to
+/// longer sequences, such as calls, jump tables, constants and arithmetic that needs trap checks,
+/// get larger estimates.
+pub(crate) fn default_inst_size(dfg: &DataFlowGraph, inst: Inst) -> u32 {
+    let opcode = dfg.insts[inst].opcode();
+    let ty = dfg.ctrl_typevar(inst);
+    let bytes = match dfg.insts[inst] {
+        // A bounds check and an indirect jump, followed by the table itself.
+        InstructionData::BranchTable { table, .. } => {
+            4 * INST_BYTES + 4 * dfg.jump_tables[table].all_branches().len() as u32
+        }
+        _ => match opcode {
+            Opcode::Nop => 0,
+            // A 64-bit constant can take up to four instructions to materialize.
+            Opcode::Iconst if ty.bits() > 32 => 4 * INST_BYTES,
+            Opcode::F64const => 4 * INST_BYTES,
+            Opcode::Iconst | Opcode::F32const => 2 * INST_BYTES,
+            // Moving the arguments into place, and then the call itself.
+            _ if opcode.is_call() => (dfg.inst_args(inst).len() as u32 + 1) * INST_BYTES,
+            _ if opcode.can_trap() => 3 * INST_BYTES,
+            _ => INST_BYTES,
+        },
+    };
+    if ty.is_vector() || ty.bits() > 64 {
+        2 * bytes
+    } else {
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cursor::{Cursor, FuncCursor};
+    use crate::ir::{types, AbiParam, ExtFuncData, ExternalName, Function, InstBuilder};
+    use crate::ir::{JumpTableData, Signature};
+    use crate::isa::CallConv;
+
+    #[test]
+    fn default_sizes() {
+        let mut func = Function::new();
+        let block0 = func.dfg.make_block();
+        let block1 = func.dfg.make_block();
+        let v0 = func.dfg.append_block_param(block0, types::I64);
+        let v1 = func.dfg.append_block_param(block0, types::I32X4);
+
+        let mut sig = Signature::new(CallConv::SystemV);
+        sig.params.push(AbiParam::new(types::I64));
+        sig.params.push(AbiParam::new(types::I64));
+        let sig = func.import_signature(sig);
+        let callee = func.import_function(ExtFuncData {
+            name: ExternalName::testcase("callee"),
+            signature: sig,
+            colocated: true,
+        });
+        let def = func.dfg.block_call(block1, &[]);
+        let entries = [def, def];
+        let table = func.create_jump_table(JumpTableData::new(def, &entries));
+        let entries = vec![def; 1000];
+        let large_table = func.create_jump_table(JumpTableData::new(def, &entries));
+
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let nop = pos.ins().nop();
+        let sum = pos.ins().iadd(v0, v0);
+        let vsum = pos.ins().iadd(v1, v1);
+        let small = pos.ins().iconst(types::I32, 1);
+        let large = pos.ins().iconst(types::I64, 1);
+        let call = pos.ins().call(callee, &[v0, v0]);
+        let index = pos.ins().ireduce(types::I32, v0);
+        let br_table = pos.ins().br_table(index, table);
+        let block2 = pos.func.dfg.make_block();
+        pos.insert_block(block2);
+        let large_br_table = pos.ins().br_table(index, large_table);
+        pos.insert_block(block1);
+        pos.ins().return_(&[]);
+
+        let dfg = &func.dfg;
+        let inst = |v| dfg.value_def(v).unwrap_inst();
+        assert_eq!(default_inst_size(dfg, nop), 0);
+        assert_eq!(default_inst_size(dfg, inst(sum)), 4);
+        assert_eq!(default_inst_size(dfg, inst(vsum)), 8);
+        assert_eq!(default_inst_size(dfg, inst(small)), 8);
+        assert_eq!(default_inst_size(dfg, inst(large)), 16);
+        assert_eq!(default_inst_size(dfg, call), 12);
+        assert_eq!(default_inst_size(dfg, br_table), 28);
+        // Large jump tables don't saturate.
+        assert_eq!(default_inst_size(dfg, large_br_table), 4020);
+    }
+}
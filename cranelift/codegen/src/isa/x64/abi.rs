@@ -458,6 +458,10 @@ impl ABIMachineSpec for X64ABIMachineSpec {
         Inst::Rets { rets }
     }
 
+    fn gen_try_call_defs(defs: Vec<ArgPair>) -> Option<Inst> {
+        Some(Inst::TryCallDefs { defs })
+    }
+
     fn gen_add_imm(
         _call_conv: isa::CallConv,
         into_reg: Writable<Reg>,
@@ -879,6 +883,7 @@ impl ABIMachineSpec for X64ABIMachineSpec {
             callee_pop_size,
             callee_conv: call_conv,
             caller_conv: call_conv,
+            try_call_info: None,
         })));
         insts
     }
@@ -914,6 +919,12 @@ impl ABIMachineSpec for X64ABIMachineSpec {
         }
     }
 
+    fn exception_payload_reg(_call_conv_of_callee: isa::CallConv) -> Option<Reg> {
+        // Unwinders hand the exception value to the landing pad in the first
+        // return register.
+        Some(regs::rax())
+    }
+
     fn get_ext_mode(
         _call_conv: isa::CallConv,
         specified: ir::ArgumentExtension,
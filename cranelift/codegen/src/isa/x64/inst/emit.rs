@@ -10,6 +10,7 @@ use crate::isa::x64::encoding::vex::{VexInstruction, VexVectorLength};
 use crate::isa::x64::inst::args::*;
 use crate::isa::x64::inst::*;
 use crate::isa::x64::lower::isle::generated_code::{Atomic128RmwSeqOp, AtomicRmwSeqOp};
+use crate::settings::OptLevel;
 
 /// A small helper to generate a signed conversion instruction.
 fn emit_signed_cvt(
@@ -1056,6 +1057,27 @@ pub(crate) fn emit(
             // `mov` instruction. Currently that's not possible but perhaps one
             // day it may be worth it.
             match amode {
+                // When optimizing for size, adding or subtracting one is
+                // `inc %dst` or `dec %dst`, which is a byte shorter than the
+                // `add` below.
+                Amode::ImmReg {
+                    simm32: simm32 @ (1 | -1),
+                    base,
+                    flags: _,
+                } if base == dst && info.flags.opt_level() == OptLevel::SpeedAndSize => {
+                    let rex_flags = RexFlags::from((*size, dst));
+                    let subopcode = if simm32 == 1 { 0 } else { 1 };
+                    let enc_dst = int_reg_enc(dst);
+                    emit_std_enc_enc(
+                        sink,
+                        LegacyPrefixes::None,
+                        0xFF,
+                        1,
+                        subopcode,
+                        enc_dst,
+                        rex_flags,
+                    );
+                }
                 // If `base == dst` then this is `add $imm, %dst`, so encode
                 // that instead.
                 Amode::ImmReg {
@@ -1642,6 +1664,7 @@ pub(crate) fn emit(
         }
 
         Inst::CallKnown { info: call_info } => {
+            let call_start = sink.cur_offset();
             if let Some(s) = state.take_stack_map() {
                 let offset = sink.cur_offset() + 5;
                 sink.push_user_stack_map(state, offset, s);
@@ -1653,6 +1676,9 @@ pub(crate) fn emit(
             emit_reloc(sink, Reloc::X86CallPCRel4, &call_info.dest, -4);
             sink.put4(0);
             sink.add_call_site();
+            if let Some(try_call) = call_info.try_call_info {
+                sink.add_exception_handler(call_start, try_call.exception);
+            }
 
             // Reclaim the outgoing argument area that was released by the callee, to ensure that
             // StackAMode values are always computed from a consistent SP.
@@ -1665,6 +1691,10 @@ pub(crate) fn emit(
                 )
                 .emit(sink, info, state);
             }
+
+            if let Some(try_call) = call_info.try_call_info {
+                Inst::jmp_known(try_call.continuation).emit(sink, info, state);
+            }
         }
 
         Inst::ReturnCallKnown { info: call_info } => {
@@ -1698,6 +1728,7 @@ pub(crate) fn emit(
         Inst::CallUnknown {
             info: call_info, ..
         } => {
+            let call_start = sink.cur_offset();
             let dest = call_info.dest.clone();
 
             match dest {
@@ -1735,6 +1766,9 @@ pub(crate) fn emit(
             }
 
             sink.add_call_site();
+            if let Some(try_call) = call_info.try_call_info {
+                sink.add_exception_handler(call_start, try_call.exception);
+            }
 
             // Reclaim the outgoing argument area that was released by the callee, to ensure that
             // StackAMode values are always computed from a consistent SP.
@@ -1747,9 +1781,14 @@ pub(crate) fn emit(
                 )
                 .emit(sink, info, state);
             }
+
+            if let Some(try_call) = call_info.try_call_info {
+                Inst::jmp_known(try_call.continuation).emit(sink, info, state);
+            }
         }
 
         Inst::Args { .. } => {}
+        Inst::TryCallDefs { .. } => {}
         Inst::Rets { .. } => {}
 
         Inst::Ret {
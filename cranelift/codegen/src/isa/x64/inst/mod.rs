@@ -120,6 +120,7 @@ impl Inst {
             | Inst::Push64 { .. }
             | Inst::StackProbeLoop { .. }
             | Inst::Args { .. }
+            | Inst::TryCallDefs { .. }
             | Inst::Rets { .. }
             | Inst::Ret { .. }
             | Inst::Setcc { .. }
@@ -675,6 +676,20 @@ impl PrettyPrint for Inst {
             }
         }
 
+        fn pretty_print_try_call(info: Option<TryCallInfo>) -> String {
+            match info {
+                Some(TryCallInfo {
+                    continuation,
+                    exception,
+                }) => format!(
+                    "; jmp {}; catch {}",
+                    continuation.to_string(),
+                    exception.to_string()
+                ),
+                None => String::new(),
+            }
+        }
+
         match self {
             Inst::Nop { len } => format!("{} len={}", ljustify("nop".to_string()), len),
 
@@ -1645,13 +1660,15 @@ impl PrettyPrint for Inst {
 
             Inst::CallKnown { info } => {
                 let op = ljustify("call".to_string());
-                format!("{op} {:?}", info.dest)
+                let try_call = pretty_print_try_call(info.try_call_info);
+                format!("{op} {:?}{try_call}", info.dest)
             }
 
             Inst::CallUnknown { info } => {
                 let dest = info.dest.pretty_print(8);
                 let op = ljustify("call".to_string());
-                format!("{op} *{dest}")
+                let try_call = pretty_print_try_call(info.try_call_info);
+                format!("{op} *{dest}{try_call}")
             }
 
             Inst::ReturnCallKnown { info } => {
@@ -1700,6 +1717,16 @@ impl PrettyPrint for Inst {
                 s
             }
 
+            Inst::TryCallDefs { defs } => {
+                let mut s = "try_call_defs".to_string();
+                for def in defs {
+                    let preg = regs::show_reg(def.preg);
+                    let def = pretty_print_reg(def.vreg.to_reg(), 8);
+                    write!(&mut s, " {def}={preg}").unwrap();
+                }
+                s
+            }
+
             Inst::Rets { rets } => {
                 let mut s = "rets".to_string();
                 for ret in rets {
@@ -2651,6 +2678,14 @@ fn x64_get_operands(inst: &mut Inst, collector: &mut impl OperandVisitor) {
             }
         }
 
+        Inst::TryCallDefs { defs } => {
+            // The call left these values in their registers before any of this block's
+            // instructions, so no other value may be moved into those registers ahead of this one.
+            for ArgPair { vreg, preg } in defs {
+                collector.reg_fixed_early_def(vreg, *preg);
+            }
+        }
+
         Inst::Rets { rets } => {
             // The return value(s) are live-out; we represent this
             // with register uses on the return instruction.
@@ -2750,7 +2785,7 @@ impl MachInst for Inst {
 
     fn is_included_in_clobbers(&self) -> bool {
         match self {
-            &Inst::Args { .. } => false,
+            &Inst::Args { .. } | &Inst::TryCallDefs { .. } => false,
             _ => true,
         }
     }
@@ -2779,6 +2814,10 @@ impl MachInst for Inst {
             &Self::JmpKnown { .. } => MachTerminator::Uncond,
             &Self::JmpCond { .. } => MachTerminator::Cond,
             &Self::JmpTableSeq { .. } => MachTerminator::Indirect,
+            // A `try_call` branches to one of its two destinations, depending on whether the
+            // callee returns or unwinds.
+            &Self::CallKnown { ref info } if info.try_call_info.is_some() => MachTerminator::Cond,
+            &Self::CallUnknown { ref info } if info.try_call_info.is_some() => MachTerminator::Cond,
             // All other cases are boring.
             _ => MachTerminator::None,
         }
@@ -2822,6 +2861,10 @@ impl MachInst for Inst {
         Inst::nop(std::cmp::min(preferred_size, 15) as u8)
     }
 
+    fn align_loop_header(offset: CodeOffset, alignment: CodeOffset) -> Option<CodeOffset> {
+        Some(align_to(offset, alignment))
+    }
+
     fn rc_for_type(ty: Type) -> CodegenResult<(&'static [RegClass], &'static [Type])> {
         match ty {
             types::I8 => Ok((&[RegClass::Int], &[types::I8])),
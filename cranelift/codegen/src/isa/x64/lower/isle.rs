@@ -19,7 +19,7 @@ use crate::isa::x64::lower::emit_vm_call;
 use crate::isa::x64::X64Backend;
 use crate::machinst::isle::*;
 use crate::machinst::{
-    ArgPair, CallInfo, InsnInput, InstOutput, IsTailCall, MachInst, VCodeConstant,
+    ArgPair, CallInfo, InsnInput, InstOutput, IsTailCall, MachInst, TryCallInfo, VCodeConstant,
     VCodeConstantData,
 };
 use alloc::vec::Vec;
@@ -71,6 +71,79 @@ impl Context for IsleContext<'_, '_, MInst, X64Backend> {
     isle_lower_prelude_methods!();
     isle_prelude_caller_methods!(X64CallSite);
 
+    fn gen_try_call(
+        &mut self,
+        sig_ref: SigRef,
+        extname: ExternalName,
+        dist: RelocDistance,
+        args @ (inputs, off): ValueSlice,
+        continuation: MachLabel,
+        exception: MachLabel,
+    ) {
+        let caller_conv = self.lower_ctx.abi().call_conv(self.lower_ctx.sigs());
+        let sig = &self.lower_ctx.dfg().signatures[sig_ref];
+        let caller = X64CallSite::from_func(
+            self.lower_ctx.sigs(),
+            sig_ref,
+            &extname,
+            IsTailCall::No,
+            dist,
+            caller_conv,
+            self.backend.flags().clone(),
+        );
+
+        assert_eq!(
+            inputs.len(&self.lower_ctx.dfg().value_lists) - off,
+            sig.params.len()
+        );
+
+        gen_try_call_common(
+            self.lower_ctx,
+            caller,
+            args,
+            TryCallInfo {
+                continuation,
+                exception,
+            },
+        )
+    }
+
+    fn gen_try_call_indirect(
+        &mut self,
+        sig_ref: SigRef,
+        val: Value,
+        args @ (inputs, off): ValueSlice,
+        continuation: MachLabel,
+        exception: MachLabel,
+    ) {
+        let caller_conv = self.lower_ctx.abi().call_conv(self.lower_ctx.sigs());
+        let ptr = self.put_in_reg(val);
+        let sig = &self.lower_ctx.dfg().signatures[sig_ref];
+        let caller = X64CallSite::from_ptr(
+            self.lower_ctx.sigs(),
+            sig_ref,
+            ptr,
+            IsTailCall::No,
+            caller_conv,
+            self.backend.flags().clone(),
+        );
+
+        assert_eq!(
+            inputs.len(&self.lower_ctx.dfg().value_lists) - off,
+            sig.params.len()
+        );
+
+        gen_try_call_common(
+            self.lower_ctx,
+            caller,
+            args,
+            TryCallInfo {
+                continuation,
+                exception,
+            },
+        )
+    }
+
     #[inline]
     fn operand_size_of_type_32_64(&mut self, ty: Type) -> OperandSize {
         if ty.bits() == 64 {
@@ -56,7 +56,7 @@ pub(crate) fn check(
     match vcode[inst_idx] {
         Inst::Nop { .. } => Ok(()),
 
-        Inst::Args { .. } => {
+        Inst::Args { .. } | Inst::TryCallDefs { .. } => {
             // Defs on the args have "axiomatic facts": we trust the
             // ABI code to pass through the values unharmed, so the
             // facts given to us in the CLIF should still be true.
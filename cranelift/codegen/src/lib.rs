@@ -25,7 +25,7 @@ use hashbrown::{hash_map, HashMap};
 #[cfg(feature = "std")]
 use std::collections::{hash_map, HashMap};
 
-pub use crate::context::Context;
+pub use crate::context::{Context, PassObserver};
 pub use crate::value_label::{LabelValueLoc, ValueLabelsRanges, ValueLocRange};
 pub use crate::verifier::verify_function;
 pub use crate::write::write_function;
@@ -45,8 +45,10 @@ pub mod cfg_printer;
 pub mod cursor;
 pub mod data_value;
 pub mod dbg;
+pub mod dfg_printer;
 pub mod dominator_tree;
 pub mod flowgraph;
+pub mod impl_limits;
 pub mod ir;
 pub mod isa;
 pub mod loop_analysis;
@@ -59,8 +61,8 @@ pub mod write;
 
 pub use crate::entity::packed_option;
 pub use crate::machinst::buffer::{
-    FinalizedMachReloc, FinalizedRelocTarget, MachCallSite, MachSrcLoc, MachTextSectionBuilder,
-    MachTrap, OpenPatchRegion, PatchRegion,
+    FinalizedMachReloc, FinalizedRelocTarget, MachCallSite, MachExceptionHandler, MachSrcLoc,
+    MachTextSectionBuilder, MachTrap, OpenPatchRegion, PatchRegion,
 };
 pub use crate::machinst::{
     CallInfo, CompiledCode, Final, MachBuffer, MachBufferFinalized, MachInst, MachInstEmit,
@@ -69,23 +71,32 @@ pub use crate::machinst::{
 };
 
 mod alias_analysis;
+mod code_sinking;
 mod constant_hash;
 mod context;
 mod ctxhash;
+mod div_const;
 mod egraph;
+mod if_conversion;
 mod inst_predicates;
 mod isle_prelude;
 mod iterators;
 mod legalizer;
+mod licm_sinking;
 mod nan_canonicalization;
 mod opts;
+mod outline_cold;
 mod ranges;
 mod remove_constant_phis;
 mod result;
+mod scheduling;
 mod scoped_hash_map;
+mod simplify_cfg;
+mod taildup;
 mod unionfind;
 mod unreachable_code;
 mod value_label;
+mod vectorize_memory;
 
 #[cfg(feature = "souper-harvest")]
 mod souper_harvest;
@@ -0,0 +1,208 @@
+//! Sinking of loop-invariant loads.
+//!
+//! A load from a loop-invariant address whose result is only used after the loop reads the same
+//! memory on every iteration, and only the last read matters. If nothing in the loop writes to
+//! memory, the load can be moved to the loop's exit block so it is executed once:
+//!
+//! ```plain
+//! block1:
+//!     v2 = load.i32 notrap v0
+//!     v3 = iadd_imm v1, -1
+//!     brif v3, block1(v3), block2
+//! block2:
+//!     return v2
+//! ```
+//!
+//! becomes
+//!
+//! ```plain
+//! block1:
+//!     v3 = iadd_imm v1, -1
+//!     brif v3, block1(v3), block2
+//! block2:
+//!     v2 = load.i32 notrap v0
+//!     return v2
+//! ```
+//!
+//! Only loops with a single exit block, which isn't reachable from outside the loop, are
+//! handled, so the load's result doesn't need to be passed through block parameters. Loads that
+//! can trap are left alone, since the loop may not have reached them before trapping elsewhere
+//! or running forever.
+
+use crate::dominator_tree::DominatorTree;
+use crate::entity::EntitySet;
+use crate::flowgraph::ControlFlowGraph;
+use crate::ir::{Block, Function, Inst, InstructionData, Opcode, ValueDef};
+use crate::loop_analysis::{Loop, LoopAnalysis};
+use crate::{timing, trace};
+use alloc::vec::Vec;
+
+/// Sink the loads in the loops of `func` whose address is loop-invariant and whose result is
+/// only used after the loop. Returns `true` if any load was moved.
+///
+/// `cfg`, `domtree` and `loop_analysis` must be valid, and stay valid since only instructions
+/// are moved.
+pub fn sink_loop_invariant_loads(
+    func: &mut Function,
+    cfg: &ControlFlowGraph,
+    domtree: &DominatorTree,
+    loop_analysis: &LoopAnalysis,
+) -> bool {
+    let _tt = timing::licm_sinking();
+    let mut changed = false;
+    for lp in loop_analysis.loops() {
+        let blocks: Vec<Block> = func
+            .layout
+            .blocks()
+            .filter(|&block| loop_analysis.is_in_loop(block, lp))
+            .collect();
+        let Some(exit) = single_exit(cfg, loop_analysis, lp, &blocks) else {
+            continue;
+        };
+        let insts: Vec<Inst> = blocks
+            .iter()
+            .flat_map(|&block| func.layout.block_insts(block))
+            .collect();
+        if insts
+            .iter()
+            .any(|&inst| may_write_memory(func.dfg.insts[inst].opcode()))
+        {
+            continue;
+        }
+
+        let mut used_in_loop = EntitySet::new();
+        for &inst in &insts {
+            for arg in func.dfg.inst_values(inst) {
+                used_in_loop.insert(func.dfg.resolve_aliases(arg));
+            }
+        }
+        let loads: Vec<Inst> = insts
+            .into_iter()
+            .filter(|&inst| {
+                is_invariant_load(func, loop_analysis, lp, inst)
+                    && !used_in_loop.contains(func.dfg.first_result(inst))
+                    && domtree.dominates(inst, exit, &func.layout)
+            })
+            .collect();
+
+        let first = func.layout.first_inst(exit).unwrap();
+        for load in loads {
+            trace!("Sinking {} from {} to {}", load, lp, exit);
+            func.layout.remove_inst(load);
+            func.layout.insert_inst(load, first);
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Get the only block outside `lp` that its `blocks` branch to, if that block can only be
+/// reached from `lp`.
+fn single_exit(
+    cfg: &ControlFlowGraph,
+    loop_analysis: &LoopAnalysis,
+    lp: Loop,
+    blocks: &[Block],
+) -> Option<Block> {
+    let mut exits = blocks
+        .iter()
+        .flat_map(|&block| cfg.succ_iter(block))
+        .filter(|&succ| !loop_analysis.is_in_loop(succ, lp));
+    let exit = exits.next()?;
+    if exits.any(|other| other != exit)
+        || cfg
+            .pred_iter(exit)
+            .any(|pred| !loop_analysis.is_in_loop(pred.block, lp))
+    {
+        return None;
+    }
+    Some(exit)
+}
+
+fn may_write_memory(opcode: Opcode) -> bool {
+    opcode.can_store() || opcode.is_call() || opcode.other_side_effects()
+}
+
+/// Is `inst` a non-trapping load from an address defined outside `lp`?
+fn is_invariant_load(func: &Function, loop_analysis: &LoopAnalysis, lp: Loop, inst: Inst) -> bool {
+    let InstructionData::Load {
+        opcode: Opcode::Load,
+        arg,
+        flags,
+        ..
+    } = func.dfg.insts[inst]
+    else {
+        return false;
+    };
+    let def_block = match func.dfg.value_def(func.dfg.resolve_aliases(arg)) {
+        ValueDef::Result(def, _) => func.layout.inst_block(def),
+        ValueDef::Param(block, _) => Some(block),
+        ValueDef::Union(..) => None,
+    };
+    flags.notrap() && def_block.is_some_and(|block| !loop_analysis.is_in_loop(block, lp))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cursor::{Cursor, FuncCursor};
+    use crate::ir::{types, AbiParam, InstBuilder, MemFlags};
+    use crate::settings;
+    use crate::verifier::verify_function;
+
+    /// Build a counting loop in `block1` that loads from `v0`, and stores to it if `store`.
+    fn counting_loop(store: bool) -> (Function, Inst, [Block; 3]) {
+        let mut func = Function::new();
+        func.signature.params.push(AbiParam::new(types::I64));
+        func.signature.params.push(AbiParam::new(types::I32));
+        func.signature.returns.push(AbiParam::new(types::I32));
+        let blocks = [(); 3].map(|_| func.dfg.make_block());
+        let [block0, block1, block2] = blocks;
+        let v0 = func.dfg.append_block_param(block0, types::I64);
+        let v1 = func.dfg.append_block_param(block0, types::I32);
+        let v2 = func.dfg.append_block_param(block1, types::I32);
+
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        pos.ins().jump(block1, &[v1]);
+        pos.insert_block(block1);
+        let v3 = pos.ins().load(types::I32, MemFlags::trusted(), v0, 0);
+        let load = pos.func.dfg.value_def(v3).unwrap_inst();
+        if store {
+            pos.ins().store(MemFlags::trusted(), v2, v0, 4);
+        }
+        let v4 = pos.ins().iadd_imm(v2, -1);
+        pos.ins().brif(v4, block1, &[v4], block2, &[]);
+        pos.insert_block(block2);
+        pos.ins().return_(&[v3]);
+
+        (func, load, blocks)
+    }
+
+    fn sink(func: &mut Function) -> bool {
+        let cfg = ControlFlowGraph::with_function(func);
+        let domtree = DominatorTree::with_function(func, &cfg);
+        let mut loop_analysis = LoopAnalysis::new();
+        loop_analysis.compute(func, &cfg, &domtree);
+        sink_loop_invariant_loads(func, &cfg, &domtree, &loop_analysis)
+    }
+
+    #[test]
+    fn sink_load() {
+        let (mut func, load, [_, _, block2]) = counting_loop(false);
+
+        assert!(sink(&mut func));
+        let flags = settings::Flags::new(settings::builder());
+        verify_function(&func, &flags).unwrap();
+        assert_eq!(func.layout.first_inst(block2), Some(load));
+        assert!(!sink(&mut func));
+    }
+
+    #[test]
+    fn keep_load_in_storing_loop() {
+        let (mut func, load, [_, block1, _]) = counting_loop(true);
+
+        assert!(!sink(&mut func));
+        assert_eq!(func.layout.inst_block(load), Some(block1));
+    }
+}
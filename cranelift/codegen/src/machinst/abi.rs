@@ -419,6 +419,14 @@ pub trait ABIMachineSpec {
     /// registers.
     fn gen_rets(rets: Vec<RetPair>) -> Self::I;
 
+    /// Generate a pseudo-instruction that captures, in vregs, the values that
+    /// a `try_call` left in registers for one of its destinations. It is the
+    /// first instruction of the edge block to that destination. Returns `None`
+    /// if this backend doesn't support `try_call`.
+    fn gen_try_call_defs(_defs: Vec<ArgPair>) -> Option<Self::I> {
+        None
+    }
+
     /// Generate an add-with-immediate. Note that even if this uses a scratch
     /// register, it must satisfy two requirements:
     ///
@@ -569,6 +577,13 @@ pub trait ABIMachineSpec {
     /// not to be saved across a call to a callee with the given ABI.
     fn get_regs_clobbered_by_call(call_conv_of_callee: isa::CallConv) -> PRegSet;
 
+    /// Get the register that holds the exception value when a callee with the
+    /// given ABI unwinds to a landing pad, or `None` if this backend doesn't
+    /// support unwinding to a landing pad.
+    fn exception_payload_reg(_call_conv_of_callee: isa::CallConv) -> Option<Reg> {
+        None
+    }
+
     /// Get the needed extension mode, given the mode attached to the argument
     /// in the signature and the calling convention. The input (the attribute in
     /// the signature) specifies what extension type should be done *if* the ABI
@@ -599,6 +614,18 @@ pub struct CallInfo<T> {
     /// caller, if any. (Used for popping stack arguments with the `tail`
     /// calling convention.)
     pub callee_pop_size: u32,
+    /// The destinations of a `try_call`, if this call is one.
+    pub try_call_info: Option<TryCallInfo>,
+}
+
+/// The destinations of a `try_call`, which ends its block: the call continues
+/// at one of these labels rather than falling through.
+#[derive(Clone, Copy, Debug)]
+pub struct TryCallInfo {
+    /// Where the call continues when the callee returns normally.
+    pub continuation: MachLabel,
+    /// The landing pad: where the call continues when the callee unwinds.
+    pub exception: MachLabel,
 }
 
 impl<T> CallInfo<T> {
@@ -613,6 +640,7 @@ impl<T> CallInfo<T> {
             caller_conv: call_conv,
             callee_conv: call_conv,
             callee_pop_size: 0,
+            try_call_info: None,
         }
     }
 
@@ -626,6 +654,7 @@ impl<T> CallInfo<T> {
             caller_conv: self.caller_conv,
             callee_conv: self.callee_conv,
             callee_pop_size: self.callee_pop_size,
+            try_call_info: self.try_call_info,
         }
     }
 }
@@ -2302,54 +2331,7 @@ impl<M: ABIMachineSpec> CallSite<M> {
         ctx: &mut Lower<M::I>,
         idx: usize,
     ) -> (SmallInstVec<M::I>, ValueRegs<Reg>) {
-        let mut insts = smallvec![];
-        let mut into_regs: SmallVec<[Reg; 2]> = smallvec![];
-        let ret = ctx.sigs().rets(self.sig)[idx].clone();
-        match ret {
-            ABIArg::Slots { ref slots, .. } => {
-                for slot in slots {
-                    match slot {
-                        // Extension mode doesn't matter because we're copying out, not in,
-                        // and we ignore high bits in our own registers by convention.
-                        &ABIArgSlot::Reg { reg, ty, .. } => {
-                            let into_reg = ctx.alloc_tmp(ty).only_reg().unwrap();
-                            self.defs.push(CallRetPair {
-                                vreg: into_reg,
-                                preg: reg.into(),
-                            });
-                            into_regs.push(into_reg.to_reg());
-                        }
-                        &ABIArgSlot::Stack { offset, ty, .. } => {
-                            let into_reg = ctx.alloc_tmp(ty).only_reg().unwrap();
-                            let sig_data = &ctx.sigs()[self.sig];
-                            // The outgoing argument area must always be restored after a call,
-                            // ensuring that the return values will be in a consistent place after
-                            // any call.
-                            let ret_area_base = sig_data.sized_stack_arg_space();
-                            insts.push(M::gen_load_stack(
-                                StackAMode::OutgoingArg(offset + ret_area_base),
-                                into_reg,
-                                ty,
-                            ));
-                            into_regs.push(into_reg.to_reg());
-                        }
-                    }
-                }
-            }
-            ABIArg::StructArg { .. } => {
-                panic!("StructArg not supported in return position");
-            }
-            ABIArg::ImplicitPtrArg { .. } => {
-                panic!("ImplicitPtrArg not supported in return position");
-            }
-        }
-
-        let value_regs = match *into_regs {
-            [a] => ValueRegs::one(a),
-            [a, b] => ValueRegs::two(a, b),
-            _ => panic!("Expected to see one or two slots only from {ret:?}"),
-        };
-        (insts, value_regs)
+        gen_call_retval::<M>(ctx, self.sig, idx, &mut self.defs)
     }
 
     /// Emit the call itself.
@@ -2366,6 +2348,23 @@ impl<M: ABIMachineSpec> CallSite<M> {
     /// This function should only be called once, as it is allowed to re-use
     /// parts of the `CallSite` object in emitting instructions.
     pub fn emit_call(&mut self, ctx: &mut Lower<M::I>) {
+        self.emit_call_inner(ctx, None)
+    }
+
+    /// Emit the call of a `try_call`, which ends the current block and
+    /// continues at one of the labels in `try_call_info`.
+    ///
+    /// The call defines no return values: they are defined at the start of the
+    /// edge block to the continuation instead (see `gen_try_call_defs`), as
+    /// are the exception values at the landing pad. So no `gen_retval` calls
+    /// may precede this one, and all of the callee's caller-saved registers
+    /// are clobbers, return registers included.
+    pub fn emit_try_call(&mut self, ctx: &mut Lower<M::I>, try_call_info: TryCallInfo) {
+        debug_assert!(self.defs.is_empty());
+        self.emit_call_inner(ctx, Some(try_call_info))
+    }
+
+    fn emit_call_inner(&mut self, ctx: &mut Lower<M::I>, try_call_info: Option<TryCallInfo>) {
         let word_type = M::word_type();
         if let Some(i) = ctx.sigs()[self.sig].stack_ret_arg {
             let rd = ctx.alloc_tmp(word_type).only_reg().unwrap();
@@ -2429,6 +2428,7 @@ impl<M: ABIMachineSpec> CallSite<M> {
                 callee_conv: call_conv,
                 caller_conv: self.caller_conv,
                 callee_pop_size,
+                try_call_info,
             },
         )
         .into_iter()
@@ -2438,6 +2438,114 @@ impl<M: ABIMachineSpec> CallSite<M> {
     }
 }
 
+/// Define the `idx`th return value of a call to a callee with signature `sig`
+/// after the call returns, adding the registers it is returned in to `defs`.
+fn gen_call_retval<M: ABIMachineSpec>(
+    ctx: &mut Lower<M::I>,
+    sig: Sig,
+    idx: usize,
+    defs: &mut CallRetList,
+) -> (SmallInstVec<M::I>, ValueRegs<Reg>) {
+    let mut insts = smallvec![];
+    let mut into_regs: SmallVec<[Reg; 2]> = smallvec![];
+    let ret = ctx.sigs().rets(sig)[idx].clone();
+    match ret {
+        ABIArg::Slots { ref slots, .. } => {
+            for slot in slots {
+                match slot {
+                    // Extension mode doesn't matter because we're copying out, not in,
+                    // and we ignore high bits in our own registers by convention.
+                    &ABIArgSlot::Reg { reg, ty, .. } => {
+                        let into_reg = ctx.alloc_tmp(ty).only_reg().unwrap();
+                        defs.push(CallRetPair {
+                            vreg: into_reg,
+                            preg: reg.into(),
+                        });
+                        into_regs.push(into_reg.to_reg());
+                    }
+                    &ABIArgSlot::Stack { offset, ty, .. } => {
+                        let into_reg = ctx.alloc_tmp(ty).only_reg().unwrap();
+                        let sig_data = &ctx.sigs()[sig];
+                        // The outgoing argument area must always be restored after a call,
+                        // ensuring that the return values will be in a consistent place after
+                        // any call.
+                        let ret_area_base = sig_data.sized_stack_arg_space();
+                        insts.push(M::gen_load_stack(
+                            StackAMode::OutgoingArg(offset + ret_area_base),
+                            into_reg,
+                            ty,
+                        ));
+                        into_regs.push(into_reg.to_reg());
+                    }
+                }
+            }
+        }
+        ABIArg::StructArg { .. } => {
+            panic!("StructArg not supported in return position");
+        }
+        ABIArg::ImplicitPtrArg { .. } => {
+            panic!("ImplicitPtrArg not supported in return position");
+        }
+    }
+
+    let value_regs = match *into_regs {
+        [a] => ValueRegs::one(a),
+        [a, b] => ValueRegs::two(a, b),
+        _ => panic!("Expected to see one or two slots only from {ret:?}"),
+    };
+    (insts, value_regs)
+}
+
+/// Define the values that the `try_call` instruction `inst` passes to its
+/// `succ_idx`th destination ahead of the block call arguments: the return
+/// values for the normal destination, or the exception value for the landing
+/// pad. This is emitted at the start of the edge block to that destination,
+/// where the call left those values in registers. Returns the vregs holding
+/// them, in order.
+pub fn gen_try_call_defs<M: ABIMachineSpec>(
+    ctx: &mut Lower<M::I>,
+    inst: ir::Inst,
+    succ_idx: usize,
+) -> CodegenResult<SmallVec<[Reg; 8]>> {
+    let sig_ref = ctx.dfg().call_signature(inst).unwrap();
+    let mut defs = smallvec![];
+    let mut loads: SmallInstVec<M::I> = smallvec![];
+    let mut regs = smallvec![];
+    if succ_idx == 0 {
+        let sig = ctx.sigs().abi_sig_for_sig_ref(sig_ref);
+        // As for other calls, take the *last* returns of the sig: this skips
+        // a StructReturn, if any, that is present.
+        let num_rets = ctx.dfg().signatures[sig_ref].returns.len();
+        let sigdata_num_rets = ctx.sigs().num_rets(sig);
+        for i in (sigdata_num_rets - num_rets)..sigdata_num_rets {
+            let (retval_insts, retval_regs) = gen_call_retval::<M>(ctx, sig, i, &mut defs);
+            loads.extend(retval_insts);
+            regs.extend_from_slice(retval_regs.regs());
+        }
+    } else {
+        let call_conv = ctx.dfg().signatures[sig_ref].call_conv;
+        let preg = M::exception_payload_reg(call_conv)
+            .ok_or_else(|| CodegenError::Unsupported("try_call".into()))?;
+        let vreg = ctx.alloc_tmp(M::word_type()).only_reg().unwrap();
+        defs.push(CallRetPair { vreg, preg });
+        regs.push(vreg.to_reg());
+    }
+
+    if !defs.is_empty() {
+        let defs = defs
+            .into_iter()
+            .map(|CallRetPair { vreg, preg }| ArgPair { vreg, preg })
+            .collect();
+        let inst = M::gen_try_call_defs(defs)
+            .ok_or_else(|| CodegenError::Unsupported("try_call".into()))?;
+        ctx.emit(inst);
+    }
+    for load in loads {
+        ctx.emit(load);
+    }
+    Ok(regs)
+}
+
 #[cfg(test)]
 mod tests {
     use super::SigData;
@@ -85,9 +85,14 @@ pub struct BlockLoweringOrder {
     /// correct. Instead, this set is used to provide `is_cold()`,
     /// which is used by VCode emission to sink the blocks at the last
     /// moment (when we actually emit bytes into the MachBuffer).
+    ///
+    /// Besides the blocks marked cold in the layout, this includes
+    /// every block but the entry that ends in an unconditional trap.
     cold_blocks: FxHashSet<BlockIndex>,
     /// Lowered blocks that are indirect branch targets.
     indirect_branch_targets: FxHashSet<BlockIndex>,
+    /// Lowered blocks that are loop headers, i.e. the targets of back edges.
+    loop_headers: FxHashSet<BlockIndex>,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -161,9 +166,11 @@ impl BlockLoweringOrder {
         let mut block_succ_range = SecondaryMap::with_default(0..0);
 
         let mut indirect_branch_target_clif_blocks = FxHashSet::default();
+        let mut loop_header_clif_blocks = FxHashSet::default();
 
         for block in f.layout.blocks() {
             let start = block_succs.len();
+            let reachable = domtree.is_reachable(block);
             visit_block_succs(f, block, |_, succ, from_table| {
                 block_out_count[block] += 1;
                 block_in_count[succ] += 1;
@@ -172,6 +179,11 @@ impl BlockLoweringOrder {
                 if from_table {
                     indirect_branch_target_clif_blocks.insert(succ);
                 }
+
+                // An edge to a block that dominates its source is a back edge.
+                if reachable && domtree.dominates(succ, block, &f.layout) {
+                    loop_header_clif_blocks.insert(succ);
+                }
             });
 
             // Ensure that blocks terminated by br_table instructions with an empty jump table are
@@ -198,6 +210,15 @@ impl BlockLoweringOrder {
             if block_out_count[block] > 1 {
                 let range = block_succ_range[block].clone();
 
+                // The destinations of a `try_call` always get an edge block of their own: that is
+                // where the values the call passes to them are defined.
+                let is_try_call = f.layout.last_inst(block).is_some_and(|inst| {
+                    matches!(
+                        f.dfg.insts[inst].opcode(),
+                        Opcode::TryCall | Opcode::TryCallIndirect
+                    )
+                });
+
                 // If chaos-mode is enabled in the control plane, iterate over
                 // the successors in an arbitrary order, which should have no
                 // impact on correctness. The order of the blocks is generally
@@ -207,7 +228,7 @@ impl BlockLoweringOrder {
 
                 for (succ_ix, lb) in succs {
                     let succ = lb.orig_block().unwrap();
-                    if block_in_count[succ] > 1 {
+                    if block_in_count[succ] > 1 || is_try_call {
                         // Mutate the successor to be a critical edge, as `block` has multiple
                         // edges leaving it, and `succ` has multiple edges entering it.
                         *lb = LoweredBlock::CriticalEdge {
@@ -231,9 +252,18 @@ impl BlockLoweringOrder {
         // Step 3: build the successor tables given the lowering order. We can't perform this step
         // during the creation of `lowering_order`, as we need `lb_to_bindex` to be fully populated
         // first.
+        let is_cold = |block: Block| {
+            f.layout.is_cold(block)
+                || (Some(block) != f.layout.entry_block()
+                    && f.layout
+                        .last_inst(block)
+                        .map(|inst| f.dfg.insts[inst].opcode())
+                        == Some(Opcode::Trap))
+        };
         let mut lowered_succ_indices = Vec::new();
         let mut cold_blocks = FxHashSet::default();
         let mut indirect_branch_targets = FxHashSet::default();
+        let mut loop_headers = FxHashSet::default();
         let lowered_succ_ranges =
             Vec::from_iter(lowered_order.iter().enumerate().map(|(ix, lb)| {
                 let bindex = BlockIndex::new(ix);
@@ -246,7 +276,7 @@ impl BlockLoweringOrder {
                         lowered_succ_indices
                             .extend(block_succs[range].iter().map(|lb| lb_to_bindex[lb]));
 
-                        if f.layout.is_cold(block) {
+                        if is_cold(block) {
                             cold_blocks.insert(bindex);
                         }
 
@@ -254,6 +284,10 @@ impl BlockLoweringOrder {
                             indirect_branch_targets.insert(bindex);
                         }
 
+                        if loop_header_clif_blocks.contains(&block) {
+                            loop_headers.insert(bindex);
+                        }
+
                         let last = f.layout.last_inst(block).unwrap();
                         let opcode = f.dfg.insts[last].opcode();
 
@@ -271,7 +305,7 @@ impl BlockLoweringOrder {
                         // Edges inherit indirect branch and cold block metadata from their
                         // successor.
 
-                        if f.layout.is_cold(succ) {
+                        if is_cold(succ) {
                             cold_blocks.insert(bindex);
                         }
 
@@ -292,6 +326,7 @@ impl BlockLoweringOrder {
             lowered_succ_ranges,
             cold_blocks,
             indirect_branch_targets,
+            loop_headers,
         };
 
         trace!("BlockLoweringOrder: {:#?}", result);
@@ -319,6 +354,11 @@ impl BlockLoweringOrder {
     pub fn is_indirect_branch_target(&self, block: BlockIndex) -> bool {
         self.indirect_branch_targets.contains(&block)
     }
+
+    /// Determine whether the given lowered block index is a loop header.
+    pub fn is_loop_header(&self, block: BlockIndex) -> bool {
+        self.loop_headers.contains(&block)
+    }
 }
 
 #[cfg(test)]
@@ -462,4 +502,26 @@ mod test {
         assert!(order.lowered_order[8].in_edge().is_none());
         assert!(order.lowered_order[8].out_edge().is_none());
     }
+
+    #[test]
+    fn test_blockorder_loop_headers() {
+        //   0
+        //   |
+        //   1 <-+
+        //   |\  |
+        //   | 2-+
+        //   |/
+        //   3 <-+
+        //   |   |
+        //   4 --+
+        //
+        // (2 -> 1 and 4 -> 3 are back edges)
+        let order = build_test_func(5, &[(0, 1), (1, 2), (1, 3), (2, 1), (2, 3), (3, 4), (4, 3)]);
+
+        let headers: Vec<u32> = (0..order.lowered_order.len())
+            .filter(|&i| order.is_loop_header(BlockIndex::new(i)))
+            .map(|i| order.lowered_order[i].orig_block().unwrap().as_u32())
+            .collect();
+        assert_eq!(headers, [1, 3]);
+    }
 }
@@ -247,6 +247,9 @@ pub struct MachBuffer<I: VCodeInst> {
     traps: SmallVec<[MachTrap; 16]>,
     /// Any call site records referring to this code.
     call_sites: SmallVec<[MachCallSite; 16]>,
+    /// Any exception handler records referring to this code, with their landing
+    /// pads still as labels.
+    exception_handlers: SmallVec<[MachLabelExceptionHandler; 4]>,
     /// Any source location mappings referring to this code.
     srclocs: SmallVec<[MachSrcLoc<Stencil>; 64]>,
     /// Any user stack maps for this code.
@@ -327,6 +330,7 @@ impl MachBufferFinalized<Stencil> {
             relocs: self.relocs,
             traps: self.traps,
             call_sites: self.call_sites,
+            exception_handlers: self.exception_handlers,
             srclocs: self
                 .srclocs
                 .into_iter()
@@ -357,6 +361,9 @@ pub struct MachBufferFinalized<T: CompilePhase> {
     pub(crate) traps: SmallVec<[MachTrap; 16]>,
     /// Any call site records referring to this code.
     pub(crate) call_sites: SmallVec<[MachCallSite; 16]>,
+    /// The exception table: any exception handler records referring to this
+    /// code.
+    pub(crate) exception_handlers: SmallVec<[MachExceptionHandler; 4]>,
     /// Any source location mappings referring to this code.
     pub(crate) srclocs: SmallVec<[T::MachSrcLocType; 64]>,
     /// Any user stack maps for this code.
@@ -441,6 +448,7 @@ impl<I: VCodeInst> MachBuffer<I> {
             relocs: SmallVec::new(),
             traps: SmallVec::new(),
             call_sites: SmallVec::new(),
+            exception_handlers: SmallVec::new(),
             srclocs: SmallVec::new(),
             user_stack_maps: SmallVec::new(),
             unwind_info: SmallVec::new(),
@@ -1521,6 +1529,16 @@ impl<I: VCodeInst> MachBuffer<I> {
             })
             .collect();
 
+        let exception_handlers = self
+            .exception_handlers
+            .iter()
+            .map(|handler| MachExceptionHandler {
+                start: handler.start,
+                ret_addr: handler.ret_addr,
+                landing_pad: self.resolve_label_offset(handler.landing_pad),
+            })
+            .collect();
+
         let mut srclocs = self.srclocs;
         srclocs.sort_by_key(|entry| entry.start);
 
@@ -1529,6 +1547,7 @@ impl<I: VCodeInst> MachBuffer<I> {
             relocs: finalized_relocs,
             traps: self.traps,
             call_sites: self.call_sites,
+            exception_handlers,
             srclocs,
             user_stack_maps: self.user_stack_maps,
             unwind_info: self.unwind_info,
@@ -1611,6 +1630,17 @@ impl<I: VCodeInst> MachBuffer<I> {
         });
     }
 
+    /// Add an exception handler record for the call that starts at `start` and
+    /// whose return address is the current offset: unwinding out of the call
+    /// continues at `landing_pad`.
+    pub fn add_exception_handler(&mut self, start: CodeOffset, landing_pad: MachLabel) {
+        self.exception_handlers.push(MachLabelExceptionHandler {
+            start,
+            ret_addr: self.data.len() as CodeOffset,
+            landing_pad,
+        });
+    }
+
     /// Add an unwind record at the current offset.
     pub fn add_unwind(&mut self, unwind: UnwindInst) {
         self.unwind_info.push((self.cur_offset(), unwind));
@@ -1742,6 +1772,11 @@ impl<T: CompilePhase> MachBufferFinalized<T> {
     pub fn call_sites(&self) -> &[MachCallSite] {
         &self.call_sites[..]
     }
+
+    /// Get the exception table for this code.
+    pub fn exception_handlers(&self) -> &[MachExceptionHandler] {
+        &self.exception_handlers[..]
+    }
 }
 
 /// Metadata about a constant.
@@ -1908,6 +1943,32 @@ pub struct MachCallSite {
     pub ret_addr: CodeOffset,
 }
 
+/// An exception handler record with its landing pad still a label.
+struct MachLabelExceptionHandler {
+    start: CodeOffset,
+    ret_addr: CodeOffset,
+    landing_pad: MachLabel,
+}
+
+/// An exception handler record resulting from a compilation: an entry in the
+/// exception table, which maps a call that may unwind to the landing pad that
+/// unwinding out of it continues at.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "enable-serde",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub struct MachExceptionHandler {
+    /// The offset of the start of the call instruction, *relative to the
+    /// containing section*.
+    pub start: CodeOffset,
+    /// The offset of the call's return address, *relative to the containing
+    /// section*.
+    pub ret_addr: CodeOffset,
+    /// The offset of the landing pad, *relative to the containing section*.
+    pub landing_pad: CodeOffset,
+}
+
 /// A source-location mapping resulting from a compilation.
 #[derive(PartialEq, Debug, Clone)]
 #[cfg_attr(
@@ -2436,6 +2497,43 @@ mod test {
         assert_eq!(&golden_data[..], &buf.data[..]);
     }
 
+    #[test]
+    fn exception_handlers() {
+        let info = EmitInfo::new(settings::Flags::new(settings::builder()));
+        let mut buf = MachBuffer::new();
+        let mut state = <Inst as MachInstEmit>::State::default();
+        let constants = Default::default();
+
+        buf.reserve_labels_for_blocks(3);
+
+        // A call that continues at block 1 when it returns, and unwinds to block 2.
+        buf.bind_label(label(0), state.ctrl_plane_mut());
+        buf.put4(1);
+        buf.add_exception_handler(0, label(2));
+        let inst = Inst::Jump { dest: target(1) };
+        inst.emit(&mut buf, &info, &mut state);
+
+        // The landing pad is an empty edge block, so its label ends up at block 1.
+        buf.bind_label(label(2), state.ctrl_plane_mut());
+        let inst = Inst::Jump { dest: target(1) };
+        inst.emit(&mut buf, &info, &mut state);
+
+        buf.bind_label(label(1), state.ctrl_plane_mut());
+        buf.put4(2);
+
+        let buf = buf.finish(&constants, state.ctrl_plane_mut());
+
+        assert_eq!(buf.data(), &[1, 0, 0, 0, 2, 0, 0, 0]);
+        assert_eq!(
+            buf.exception_handlers(),
+            &[MachExceptionHandler {
+                start: 0,
+                ret_addr: 4,
+                landing_pad: 4,
+            }]
+        );
+    }
+
     #[test]
     fn metadata_records() {
         let mut buf = MachBuffer::<Inst>::new();
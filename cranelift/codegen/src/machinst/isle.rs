@@ -912,6 +912,19 @@ pub fn gen_call_common<M: ABIMachineSpec>(
     outputs
 }
 
+/// Lower a `try_call` to `caller`, which continues at the labels in
+/// `try_call_info`. Unlike `gen_call_common`, this defines no return values:
+/// the edge blocks to the call's destinations do that.
+pub fn gen_try_call_common<M: ABIMachineSpec>(
+    ctx: &mut Lower<'_, M::I>,
+    mut caller: CallSite<M>,
+    args: ValueSlice,
+    try_call_info: crate::machinst::TryCallInfo,
+) {
+    gen_call_common_args(ctx, &mut caller, args);
+    caller.emit_try_call(ctx, try_call_info);
+}
+
 /// This structure is used to implement the ISLE-generated `Context` trait and
 /// internally has a temporary reference to a machinst `LowerCtx`.
 pub(crate) struct IsleContext<'a, 'b, I, B>
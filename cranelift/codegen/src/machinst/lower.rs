@@ -10,8 +10,8 @@ use crate::inst_predicates::{has_lowering_side_effect, is_constant_64bit};
 use crate::ir::pcc::{Fact, FactContext, PccError, PccResult};
 use crate::ir::{
     ArgumentPurpose, Block, Constant, ConstantData, DataFlowGraph, ExternalName, Function,
-    GlobalValue, GlobalValueData, Immediate, Inst, InstructionData, MemFlags, RelSourceLoc, Type,
-    Value, ValueDef, ValueLabelAssignments, ValueLabelStart,
+    GlobalValue, GlobalValueData, Immediate, Inst, InstructionData, MemFlags, Opcode, RelSourceLoc,
+    Type, Value, ValueDef, ValueLabelAssignments, ValueLabelStart,
 };
 use crate::machinst::valueregs::InvalidSentinel;
 use crate::machinst::{
@@ -423,7 +423,7 @@ impl<'func, I: VCodeInst> Lower<'func, I> {
         let mut sret_param = None;
         for ret in vcode.abi().signature().returns.iter() {
             if ret.purpose == ArgumentPurpose::StructReturn {
-                let entry_bb = f.stencil.layout.entry_block().unwrap();
+                let entry_bb = f.entry_block();
                 for (&param, sig_param) in f
                     .dfg
                     .block_params(entry_bb)
@@ -892,14 +892,12 @@ impl<'func, I: VCodeInst> Lower<'func, I> {
         self.cur_inst = Some(branch);
 
         // Lower the branch in ISLE.
-        backend
-            .lower_branch(self, branch, targets)
-            .unwrap_or_else(|| {
-                panic!(
-                    "should be implemented in ISLE: branch = `{}`",
-                    self.f.dfg.display_inst(branch),
-                )
-            });
+        if backend.lower_branch(self, branch, targets).is_none() {
+            return Err(CodegenError::Unsupported(format!(
+                "should be implemented in ISLE: branch = `{}`",
+                self.f.dfg.display_inst(branch),
+            )));
+        }
         let loc = self.srcloc(branch);
         self.finish_ir_inst(loc);
         // Add block param outputs for current block.
@@ -915,6 +913,13 @@ impl<'func, I: VCodeInst> Lower<'func, I> {
             let inst = opt_inst.expect("lower_branch_blockparam_args called on a critical edge!");
             let succ = succs[succ_idx];
 
+            // The destinations of a `try_call` are always edge blocks, which receive the block
+            // call arguments themselves; see `lower_try_call_edge`.
+            if is_try_call(self.f, inst) {
+                self.vcode.add_succ(succ, &[]);
+                continue;
+            }
+
             // The use of `succ_idx` to index `branch_destination` is valid on the assumption that
             // the traversal order defined in `visit_block_succs` mirrors the order returned by
             // `branch_destination`. If that assumption is violated, the branch targets returned
@@ -932,6 +937,30 @@ impl<'func, I: VCodeInst> Lower<'func, I> {
         }
     }
 
+    /// Lower the edge block to the `succ_idx`th destination of the `try_call` instruction
+    /// `branch`. The call jumps to the edge block without any arguments, so the edge block
+    /// defines the values that the call passes to the destination and then evaluates the block
+    /// call arguments; `args` receives all of them, in the destination's parameter order.
+    fn lower_try_call_edge(
+        &mut self,
+        branch: Inst,
+        succ_idx: usize,
+        args: &mut SmallVec<[Reg; 16]>,
+    ) -> CodegenResult<()> {
+        let defs =
+            crate::machinst::abi::gen_try_call_defs::<I::ABIMachineSpec>(self, branch, succ_idx)?;
+        args.extend_from_slice(&defs);
+
+        let dests = self.f.dfg.insts[branch].branch_destination(&self.f.dfg.jump_tables);
+        let block_call_args = dests[succ_idx].args_slice(&self.f.dfg.value_lists).to_vec();
+        for arg in block_call_args {
+            debug_assert!(self.f.dfg.value_is_real(arg));
+            let regs = self.put_value_in_regs(arg);
+            args.extend_from_slice(regs.regs());
+        }
+        Ok(())
+    }
+
     fn collect_branches_and_targets(
         &self,
         bindex: BlockIndex,
@@ -1001,12 +1030,22 @@ impl<'func, I: VCodeInst> Lower<'func, I> {
                     .expect("Edge block succ must be body block");
 
                 let mut branch_arg_vregs: SmallVec<[Reg; 16]> = smallvec![];
-                for ty in self.f.dfg.block_param_types(orig_succ) {
-                    let regs = self.vregs.alloc(ty)?;
-                    for &reg in regs.regs() {
-                        branch_arg_vregs.push(reg);
-                        let vreg = reg.to_virtual_reg().unwrap();
-                        self.vcode.add_block_param(vreg);
+                match *lb {
+                    LoweredBlock::CriticalEdge { pred, succ_idx, .. }
+                        if is_try_call(self.f, self.f.layout.last_inst(pred).unwrap()) =>
+                    {
+                        let branch = self.f.layout.last_inst(pred).unwrap();
+                        self.lower_try_call_edge(branch, succ_idx as usize, &mut branch_arg_vregs)?;
+                    }
+                    _ => {
+                        for ty in self.f.dfg.block_param_types(orig_succ) {
+                            let regs = self.vregs.alloc(ty)?;
+                            for &reg in regs.regs() {
+                                branch_arg_vregs.push(reg);
+                                let vreg = reg.to_virtual_reg().unwrap();
+                                self.vcode.add_block_param(vreg);
+                            }
+                        }
                     }
                 }
                 self.vcode.add_succ(succ, &branch_arg_vregs[..]);
@@ -1056,6 +1095,14 @@ impl<'func, I: VCodeInst> Lower<'func, I> {
     }
 }
 
+/// Is `inst` a `try_call` or `try_call_indirect`?
+fn is_try_call(f: &Function, inst: Inst) -> bool {
+    matches!(
+        f.dfg.insts[inst].opcode(),
+        Opcode::TryCall | Opcode::TryCallIndirect
+    )
+}
+
 /// Pre-analysis: compute `value_ir_uses`. See comment on
 /// `ValueUseState` for a description of what this analysis
 /// computes.
@@ -1184,7 +1231,7 @@ fn compute_use_states(
 /// multi-result instruction from being forcibly considered `Multiple`-used
 /// regardless of circumstances.
 fn is_value_use_root(f: &Function, inst: Inst) -> bool {
-    f.dfg.inst_results(inst).len() > 1
+    f.dfg.has_multiple_results(inst)
 }
 
 /// Function-level queries.
@@ -1262,7 +1309,7 @@ impl<'func, I: VCodeInst> Lower<'func, I> {
 
     /// Get the number of outputs to the given IR instruction.
     pub fn num_outputs(&self, ir_inst: Inst) -> usize {
-        self.f.dfg.inst_results(ir_inst).len()
+        self.f.dfg.num_results(ir_inst)
     }
 
     /// Get the type for an instruction's input.
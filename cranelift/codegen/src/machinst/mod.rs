@@ -177,6 +177,13 @@ pub trait MachInst: Clone + Debug {
         offset
     }
 
+    /// Align the offset of a loop header to `alignment`, a power of two. The
+    /// gap is filled with NOPs from `gen_nop`. Returns `None` if the backend
+    /// can't fill every gap with NOPs, which is the default.
+    fn align_loop_header(_offset: CodeOffset, _alignment: CodeOffset) -> Option<CodeOffset> {
+        None
+    }
+
     /// What is the worst-case instruction size emitted by this instruction type?
     fn worst_case_size() -> CodeOffset;
 
@@ -390,6 +390,13 @@ pub trait OperandVisitorImpl: OperandVisitor {
         self.reg_fixed(reg.reg.as_mut(), rreg, OperandKind::Def, OperandPos::Late);
     }
 
+    /// Add a register "fixed def" at the start of the instruction, which ties
+    /// a vreg to a particular RealReg and keeps every other vreg out of that
+    /// RealReg for the whole instruction.
+    fn reg_fixed_early_def(&mut self, reg: &mut Writable<impl AsMut<Reg>>, rreg: Reg) {
+        self.reg_fixed(reg.reg.as_mut(), rreg, OperandKind::Def, OperandPos::Early);
+    }
+
     /// Add an operand tying a virtual register to a physical register.
     fn reg_fixed(&mut self, reg: &mut Reg, rreg: Reg, kind: OperandKind, pos: OperandPos) {
         debug_assert!(reg.is_virtual());
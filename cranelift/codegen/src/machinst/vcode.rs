@@ -783,6 +783,24 @@ impl<I: VCodeInst> VCode<I> {
         };
         let mut total_bb_padding = 0;
 
+        // Loop headers are only aligned when optimizing for speed, and the
+        // function has to be aligned at least as much for that to matter.
+        let loop_align = match flags.opt_level() {
+            settings::OptLevel::Speed => 1 << flags.loop_align_log2().min(6),
+            _ => 1,
+        };
+        let loop_align_of = |block: BlockIndex| {
+            if loop_align > 1
+                && self.block_order.is_loop_header(block)
+                && !self.block_order.is_cold(block)
+            {
+                loop_align
+            } else {
+                1
+            }
+        };
+        let mut func_align = 1;
+
         for (block_order_idx, &block) in final_order.iter().enumerate() {
             trace!("emitting block {:?}", block);
 
@@ -790,7 +808,13 @@ impl<I: VCodeInst> VCode<I> {
             state.on_new_block();
 
             // Emit NOPs to align the block.
-            let new_offset = I::align_basic_block(buffer.cur_offset());
+            let mut new_offset = I::align_basic_block(buffer.cur_offset());
+            if loop_align_of(block) > 1 {
+                if let Some(offset) = I::align_loop_header(new_offset, loop_align) {
+                    new_offset = offset;
+                    func_align = loop_align;
+                }
+            }
             while new_offset > buffer.cur_offset() {
                 // Pad with NOPs up to the aligned block offset.
                 let nop = I::gen_nop((new_offset - buffer.cur_offset()) as usize);
@@ -997,6 +1021,8 @@ impl<I: VCodeInst> VCode<I> {
                 let next_block_size = next_block_range.len() as u32;
                 let next_block_ra_insertions = ra_edits_per_block[next_block.index()];
                 I::worst_case_size() * (next_block_size + next_block_ra_insertions)
+                    + loop_align_of(next_block)
+                    - 1
             } else {
                 0
             };
@@ -1068,8 +1094,11 @@ impl<I: VCodeInst> VCode<I> {
             self.compute_value_labels_ranges(regalloc, &inst_offsets[..], func_body_len);
         let frame_size = self.abi.frame_size();
 
+        let mut buffer = buffer.finish(&self.constants, ctrl_plane);
+        buffer.alignment = buffer.alignment.max(func_align);
+
         EmitResult {
-            buffer: buffer.finish(&self.constants, ctrl_plane),
+            buffer,
             bb_offsets,
             bb_edges,
             func_body_len,
@@ -1759,4 +1788,97 @@ mod test {
         // With certain versions of Rust, each `HashMap` in `VCodeConstants` occupied at
         // least 48 bytes, making an empty `VCodeConstants` cost 120 bytes.
     }
+
+    #[test]
+    #[cfg(feature = "x86")]
+    fn align_loop_headers() {
+        use crate::cursor::{Cursor, FuncCursor};
+        use crate::ir::{AbiParam, Function, InstBuilder};
+        use crate::settings::Configurable;
+        use crate::Context;
+
+        let mut func = Function::new();
+        func.signature.params.push(AbiParam::new(types::I32));
+        let [block0, block1, block2] = [(); 3].map(|_| func.dfg.make_block());
+        let v0 = func.dfg.append_block_param(block0, types::I32);
+        let v1 = func.dfg.append_block_param(block1, types::I32);
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        pos.ins().jump(block1, &[v0]);
+        pos.insert_block(block1);
+        let v2 = pos.ins().iadd_imm(v1, -2);
+        pos.ins().brif(v2, block1, &[v2], block2, &[]);
+        pos.insert_block(block2);
+        pos.ins().return_(&[]);
+
+        let compile = |opt_level, loop_align_log2| {
+            let mut builder = settings::builder();
+            builder.set("opt_level", opt_level).unwrap();
+            builder.set("loop_align_log2", loop_align_log2).unwrap();
+            builder.enable("machine_code_cfg_info").unwrap();
+            let isa = crate::isa::lookup_by_name("x86_64")
+                .unwrap()
+                .finish(settings::Flags::new(builder))
+                .unwrap();
+            let mut ctx = Context::for_function(func.clone());
+            let code = ctx.compile(&*isa, &mut Default::default()).unwrap();
+            // The loop header is the target of the only back edge.
+            let (_, header) = *code.bb_edges.iter().find(|(from, to)| to <= from).unwrap();
+            (code.code_buffer().to_vec(), header, code.buffer.alignment)
+        };
+
+        let (code, header, alignment) = compile("speed", "0");
+        assert_eq!((header, alignment), (4, 1));
+
+        // The loop header is padded to 16 bytes. The entry block now ends in a
+        // jump over the padding, which is a 7-byte NOP.
+        let (aligned_code, aligned_header, aligned_alignment) = compile("speed", "4");
+        assert_eq!((aligned_header, aligned_alignment), (16, 16));
+        assert_eq!(aligned_code[..header as usize], code[..header as usize]);
+        assert_eq!(aligned_code[4..9], [0xe9, 7, 0, 0, 0]);
+        assert_eq!(aligned_code[9..16], [0x0f, 0x1f, 0x80, 0, 0, 0, 0]);
+        assert_eq!(aligned_code[16..], code[header as usize..]);
+
+        // Loops are left alone when optimizing for size.
+        assert_eq!(compile("speed_and_size", "4"), (code, header, alignment));
+    }
+
+    #[test]
+    #[cfg(feature = "x86")]
+    fn sink_trap_blocks() {
+        use crate::cursor::{Cursor, FuncCursor};
+        use crate::ir::{AbiParam, Function, InstBuilder, TrapCode};
+        use crate::settings::Configurable;
+        use crate::Context;
+
+        let mut func = Function::new();
+        func.signature.params.push(AbiParam::new(types::I32));
+        let [block0, block1, block2] = [(); 3].map(|_| func.dfg.make_block());
+        let v0 = func.dfg.append_block_param(block0, types::I32);
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        pos.ins().brif(v0, block1, &[], block2, &[]);
+        pos.insert_block(block1);
+        pos.ins().trap(TrapCode::unwrap_user(1));
+        pos.insert_block(block2);
+        pos.ins().return_(&[]);
+
+        let mut builder = settings::builder();
+        builder.enable("machine_code_cfg_info").unwrap();
+        let isa = crate::isa::lookup_by_name("x86_64")
+            .unwrap()
+            .finish(settings::Flags::new(builder))
+            .unwrap();
+        let mut ctx = Context::for_function(func);
+        let code = ctx.compile(&*isa, &mut Default::default()).unwrap();
+
+        // block1 isn't marked cold, but it ends in a trap, so it is emitted
+        // after the return. The entry block used to fall through into it, so
+        // it now branches there explicitly.
+        let bytes = code.code_buffer();
+        let trap = *code.bb_starts.last().unwrap();
+        assert_eq!(bytes[trap as usize..], [0x0f, 0x0b]);
+        assert!(bytes[..trap as usize].ends_with(&[0xc3]));
+        assert!(code.bb_edges.contains(&(code.bb_starts[0], trap)));
+    }
 }
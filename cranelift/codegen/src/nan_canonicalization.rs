@@ -1,6 +1,9 @@
 //! A NaN-canonicalizing rewriting pass. Patch floating point arithmetic
 //! instructions that may return a NaN result with a sequence of operations
 //! that will replace nondeterministic NaN's with a single canonical NaN value.
+//!
+//! Results that are only used by other patched instructions are left alone,
+//! since a NaN operand makes those produce a NaN which is canonicalized there.
 
 use crate::cursor::{Cursor, FuncCursor};
 use crate::ir::condcodes::FloatCC;
@@ -9,25 +12,44 @@ use crate::ir::types::{self};
 use crate::ir::{Function, Inst, InstBuilder, InstructionData, Opcode, Value};
 use crate::opts::MemFlags;
 use crate::timing;
+use cranelift_entity::EntitySet;
 
 /// Perform the NaN canonicalization pass.
 pub fn do_nan_canonicalization(func: &mut Function, has_vector_support: bool) {
     let _tt = timing::canonicalize_nans();
+    let escaping = escaping_values(func);
     let mut pos = FuncCursor::new(func);
     while let Some(_block) = pos.next_block() {
         while let Some(inst) = pos.next_inst() {
-            if is_fp_arith(&mut pos, inst) {
+            if is_fp_arith(pos.func, inst) && escaping.contains(pos.func.dfg.first_result(inst)) {
                 add_nan_canon_seq(&mut pos, inst, has_vector_support);
             }
         }
     }
 }
 
+/// Collect the values that are used by anything other than a floating-point
+/// arithmetic instruction.
+fn escaping_values(func: &Function) -> EntitySet<Value> {
+    let mut escaping = EntitySet::new();
+    for block in func.layout.blocks() {
+        for inst in func.layout.block_insts(block) {
+            if is_fp_arith(func, inst) {
+                continue;
+            }
+            for arg in func.dfg.inst_values(inst) {
+                escaping.insert(func.dfg.resolve_aliases(arg));
+            }
+        }
+    }
+    escaping
+}
+
 /// Returns true/false based on whether the instruction is a floating-point
 /// arithmetic operation. This ignores operations like `fneg`, `fabs`, or
 /// `fcopysign` that only operate on the sign bit of a floating point value.
-fn is_fp_arith(pos: &mut FuncCursor, inst: Inst) -> bool {
-    match pos.func.dfg.insts[inst] {
+fn is_fp_arith(func: &Function, inst: Inst) -> bool {
+    match func.dfg.insts[inst] {
         InstructionData::Unary { opcode, .. } => {
             opcode == Opcode::Ceil
                 || opcode == Opcode::Floor
@@ -124,3 +146,54 @@ fn add_nan_canon_seq(pos: &mut FuncCursor, inst: Inst, has_vector_support: bool)
 
     pos.prev_inst(); // Step backwards so the pass does not skip instructions.
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::AbiParam;
+
+    /// Build `fmul (fadd v0, v1), v1`, which also returns the `fadd` if `return_sum`.
+    fn chain(return_sum: bool) -> Function {
+        let mut func = Function::new();
+        for _ in 0..2 {
+            func.signature.params.push(AbiParam::new(types::F32));
+        }
+        func.signature.returns.push(AbiParam::new(types::F32));
+        if return_sum {
+            func.signature.returns.push(AbiParam::new(types::F32));
+        }
+        let block0 = func.dfg.make_block();
+        let v0 = func.dfg.append_block_param(block0, types::F32);
+        let v1 = func.dfg.append_block_param(block0, types::F32);
+
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let v2 = pos.ins().fadd(v0, v1);
+        let v3 = pos.ins().fmul(v2, v1);
+        if return_sum {
+            pos.ins().return_(&[v3, v2]);
+        } else {
+            pos.ins().return_(&[v3]);
+        }
+        func
+    }
+
+    fn count_selects(func: &Function) -> usize {
+        let block0 = func.layout.entry_block().unwrap();
+        func.layout
+            .block_insts(block0)
+            .filter(|&inst| func.dfg.insts[inst].opcode() == Opcode::Select)
+            .count()
+    }
+
+    #[test]
+    fn skip_redundant_canonicalization() {
+        let mut func = chain(false);
+        do_nan_canonicalization(&mut func, false);
+        assert_eq!(count_selects(&func), 1);
+
+        let mut func = chain(true);
+        do_nan_canonicalization(&mut func, false);
+        assert_eq!(count_selects(&func), 2);
+    }
+}
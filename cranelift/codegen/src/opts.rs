@@ -94,7 +94,7 @@ where
                     self.stack.push(y);
                     continue;
                 }
-                ValueDef::Result(inst, _) if ctx.ctx.func.dfg.inst_results(inst).len() == 1 => {
+                ValueDef::Result(inst, _) if ctx.ctx.func.dfg.num_results(inst) == 1 => {
                     let ty = ctx.ctx.func.dfg.value_type(value);
                     trace!(" -> value of type {}", ty);
                     return Some((ty, ctx.ctx.func.dfg.insts[inst]));
@@ -0,0 +1,103 @@
+//! Move cold blocks to the end of the function.
+//!
+//! Blocks that are rarely executed, such as error paths, pollute the instruction cache when they
+//! sit between hot blocks. This pass marks the given blocks cold and moves every cold block after
+//! all of the hot ones, keeping the relative order of both groups. The entry block is never
+//! marked cold, which the verifier rejects, and never moved.
+//!
+//! Only the layout changes: every block ends in a terminator, so the order of blocks doesn't
+//! affect the semantics of the function. The cold blocks are printed with a `cold` annotation.
+//!
+//! When this pass runs as part of `Context::optimize`, the blocks that end in a `trap` are
+//! outlined along with the blocks that the frontend already marked cold.
+
+use crate::ir::{Block, Function, Opcode};
+use crate::timing;
+use alloc::vec::Vec;
+
+/// Mark `cold_blocks` as cold, and move all of the cold blocks in `func` to the end of its
+/// layout. Returns `true` if a block was marked or moved.
+pub fn outline_cold(func: &mut Function, cold_blocks: &[Block]) -> bool {
+    let _tt = timing::outline_cold();
+    let entry = func.layout.entry_block();
+    let mut changed = false;
+    for &block in cold_blocks {
+        if Some(block) != entry {
+            changed |= !func.layout.is_cold(block);
+            func.layout.set_cold(block);
+        }
+    }
+
+    let is_outlined = |block| Some(block) != entry && func.layout.is_cold(block);
+    // The order only changes if a hot block follows a cold one.
+    changed |= func
+        .layout
+        .blocks()
+        .skip_while(|&block| !is_outlined(block))
+        .any(|block| !is_outlined(block));
+    let cold: Vec<Block> = func
+        .layout
+        .blocks()
+        .filter(|&block| is_outlined(block))
+        .collect();
+    for block in cold {
+        func.layout.move_block_to_end(block);
+    }
+    changed
+}
+
+/// Get the blocks of `func` that end in an unconditional `trap`, in layout order.
+pub fn trapping_blocks(func: &Function) -> Vec<Block> {
+    func.layout
+        .blocks()
+        .filter(|&block| {
+            func.layout
+                .last_inst(block)
+                .is_some_and(|inst| func.dfg.insts[inst].opcode() == Opcode::Trap)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cursor::{Cursor, FuncCursor};
+    use crate::ir::{types, InstBuilder, TrapCode};
+    use alloc::string::ToString;
+
+    #[test]
+    fn outline() {
+        let mut func = Function::new();
+        let blocks: Vec<Block> = (0..5).map(|_| func.dfg.make_block()).collect();
+        let v0 = func.dfg.append_block_param(blocks[0], types::I32);
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(blocks[0]);
+        pos.ins().brif(v0, blocks[1], &[], blocks[2], &[]);
+        pos.insert_block(blocks[1]);
+        pos.ins().trap(TrapCode::unwrap_user(1));
+        pos.insert_block(blocks[2]);
+        pos.ins().brif(v0, blocks[3], &[], blocks[4], &[]);
+        pos.insert_block(blocks[3]);
+        pos.ins().trap(TrapCode::unwrap_user(2));
+        pos.insert_block(blocks[4]);
+        pos.ins().return_(&[]);
+
+        // block3 was already cold, and the entry block stays hot and in place.
+        func.layout.set_cold(blocks[3]);
+        assert_eq!(trapping_blocks(&func), [blocks[1], blocks[3]]);
+        assert!(outline_cold(&mut func, &[blocks[1], blocks[0]]));
+
+        let order: Vec<Block> = func.layout.blocks().collect();
+        assert_eq!(
+            order,
+            [blocks[0], blocks[2], blocks[4], blocks[1], blocks[3]]
+        );
+        let text = func.display().to_string();
+        assert!(text.contains("block0(v0: i32):"), "{text}");
+        assert!(text.contains("block1 cold:"), "{text}");
+        assert!(text.contains("block3 cold:"), "{text}");
+
+        // Outlining again changes nothing.
+        assert!(!outline_cold(&mut func, &[blocks[3]]));
+    }
+}
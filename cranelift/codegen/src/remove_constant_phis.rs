@@ -113,6 +113,10 @@ struct OutEdge<'a> {
     branch_index: u32,
     /// The block that control is transferred to.
     block: Block,
+    /// The number of formals of `block` that the instruction defines itself,
+    /// like the return values of a `try_call` on its normal edge. The
+    /// arguments are passed to the formals after these.
+    implicit: u32,
     /// The arguments to that block.
     ///
     /// These values can be from both groups A and B.
@@ -122,8 +126,9 @@ struct OutEdge<'a> {
 impl<'a> OutEdge<'a> {
     /// Construct a new `OutEdge` for the given instruction.
     ///
-    /// Returns `None` if this is an edge without any block arguments, which
-    /// means we can ignore it for this analysis's purposes.
+    /// Returns `None` if this is an edge without any block arguments or
+    /// implicitly defined formals, which means we can ignore it for this
+    /// analysis's purposes.
     #[inline]
     fn new(
         bump: &'a Bump,
@@ -133,9 +138,10 @@ impl<'a> OutEdge<'a> {
         block: BlockCall,
     ) -> Option<Self> {
         let inst_var_args = block.args_slice(&dfg.value_lists);
+        let implicit = dfg.num_implicit_block_args(inst, branch_index);
 
         // Skip edges without params.
-        if inst_var_args.is_empty() {
+        if inst_var_args.is_empty() && implicit == 0 {
             return None;
         }
 
@@ -143,6 +149,7 @@ impl<'a> OutEdge<'a> {
             inst,
             branch_index: branch_index as u32,
             block: block.block(&dfg.value_lists),
+            implicit: implicit as u32,
             args: bump.alloc_slice_fill_iter(
                 inst_var_args
                     .iter()
@@ -298,7 +305,18 @@ pub fn do_remove_constant_phis(func: &mut Function, domtree: &mut DominatorTree)
                 // dst block does take parameters, so it must have a summary.
                 let dst_summary = &summaries[edge.block];
                 let dst_formals = &dst_summary.formals;
-                assert_eq!(edge.args.len(), dst_formals.len());
+                assert_eq!(edge.implicit as usize + edge.args.len(), dst_formals.len());
+                let (implicit_formals, dst_formals) = dst_formals.split_at(edge.implicit as usize);
+
+                // Formals defined by the branch itself are new values on
+                // this edge, so they can't be replaced by any other value.
+                for formal in implicit_formals {
+                    if state.get(*formal) != AbstractValue::Many {
+                        changed = true;
+                        state.set(*formal, AbstractValue::Many);
+                    }
+                }
+
                 for (formal, actual) in dst_formals.iter().zip(edge.args) {
                     // Find the abstract value for `actual`.  If it is a block
                     // formal parameter then the most recent abstract value is
@@ -391,7 +409,7 @@ pub fn do_remove_constant_phis(func: &mut Function, domtree: &mut DominatorTree)
             old_actuals.extend(block.args_slice(&dfg.value_lists));
 
             // Check that the numbers of arguments make sense.
-            let formals = &summaries[edge.block].formals;
+            let formals = &summaries[edge.block].formals[edge.implicit as usize..];
             assert_eq!(formals.len(), old_actuals.len());
 
             // Filter out redundant block arguments.
@@ -0,0 +1,239 @@
+//! List scheduling of the instructions in each block.
+//!
+//! An out-of-order CPU can only hide the latency of an instruction if there is independent work to
+//! do while it waits for the result. This pass reorders the instructions within each block so that
+//! the ones at the start of long dependency chains are issued first, using the latencies given by
+//! `TargetIsa::instruction_latency`.
+//!
+//! The order is constrained by the dependencies between instructions: an instruction comes after
+//! the ones in the same block that define its arguments, and instructions that access memory, can
+//! trap, or have other side effects keep their original order relative to each other. Terminators
+//! stay at the end of their blocks.
+//!
+//! Egraph elaboration places pure instructions by itself, so this is mostly useful after
+//! optimizations, or when they are disabled.
+
+use crate::entity::SecondaryMap;
+use crate::ir::{Block, Function, Inst, ValueDef};
+use crate::isa::TargetIsa;
+use crate::{timing, trace};
+use alloc::collections::BinaryHeap;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+
+/// Reorder the instructions in each block of `func` to hide their latencies on `isa`. Returns
+/// `true` if any instructions were moved.
+pub fn list_schedule(func: &mut Function, isa: &dyn TargetIsa) -> bool {
+    let _tt = timing::scheduling();
+    let mut dag = Dag::default();
+    let mut changed = false;
+    let mut next_block = func.layout.entry_block();
+    while let Some(block) = next_block {
+        dag.build(func, isa, block);
+        dag.schedule();
+        changed |= dag.emit(func);
+        next_block = func.layout.next_block(block);
+    }
+    changed
+}
+
+/// The dependencies between the instructions of a block.
+///
+/// Instructions are identified by their index in `insts`, and every dependency goes from an
+/// instruction to a later one.
+#[derive(Default)]
+struct Dag {
+    /// The instructions to schedule, in their original order.
+    insts: Vec<Inst>,
+    /// The index of each instruction in `insts`, for the block being scheduled.
+    index: SecondaryMap<Inst, usize>,
+    /// The latency of each instruction.
+    latency: Vec<u32>,
+    /// The instructions that depend on each instruction, and the number of cycles they have to
+    /// wait for it.
+    succs: Vec<Vec<(usize, u32)>>,
+    /// The number of unscheduled dependencies of each instruction.
+    num_preds: Vec<u32>,
+    /// The length in cycles of the longest dependency chain starting at each instruction.
+    height: Vec<u32>,
+    /// The instructions in scheduled order.
+    order: Vec<usize>,
+}
+
+impl Dag {
+    /// Collect the instructions of `block`, except for its terminator, and their dependencies.
+    fn build(&mut self, func: &Function, isa: &dyn TargetIsa, block: Block) {
+        self.insts.clear();
+        self.latency.clear();
+        self.succs.clear();
+        self.num_preds.clear();
+
+        let mut last_ordered = None;
+        for inst in func.layout.block_insts(block) {
+            let opcode = func.dfg.insts[inst].opcode();
+            if opcode.is_terminator() {
+                continue;
+            }
+            let index = self.insts.len();
+            self.insts.push(inst);
+            self.index[inst] = index;
+            self.latency.push(isa.instruction_latency(opcode));
+            self.succs.push(Vec::new());
+            self.num_preds.push(0);
+
+            for arg in func.dfg.inst_values(inst) {
+                let arg = func.dfg.resolve_aliases(arg);
+                if let ValueDef::Result(def, _) = func.dfg.value_def(arg) {
+                    if func.layout.inst_block(def) == Some(block) {
+                        let def = self.index[def];
+                        self.add_edge(def, index, self.latency[def]);
+                    }
+                }
+            }
+            if !opcode.is_pure() {
+                if let Some(prev) = last_ordered {
+                    self.add_edge(prev, index, 1);
+                }
+                last_ordered = Some(index);
+            }
+        }
+    }
+
+    fn add_edge(&mut self, from: usize, to: usize, latency: u32) {
+        self.succs[from].push((to, latency));
+        self.num_preds[to] += 1;
+    }
+
+    /// Compute the order of the instructions in `self.order`.
+    ///
+    /// This simulates issuing one instruction per cycle. Of the instructions whose dependencies
+    /// have produced their results, the one with the longest chain of dependent instructions goes
+    /// first, and ties keep the original order.
+    fn schedule(&mut self) {
+        let n = self.insts.len();
+        self.height.clear();
+        self.height.resize(n, 0);
+        for i in (0..n).rev() {
+            self.height[i] = self.succs[i]
+                .iter()
+                .map(|&(succ, latency)| latency + self.height[succ])
+                .fold(self.latency[i], u32::max);
+        }
+
+        let mut earliest = Vec::new();
+        earliest.resize(n, 0);
+        let mut ready = BinaryHeap::new();
+        let mut waiting = BinaryHeap::new();
+        for i in 0..n {
+            if self.num_preds[i] == 0 {
+                ready.push((self.height[i], Reverse(i)));
+            }
+        }
+
+        self.order.clear();
+        let mut cycle = 0;
+        while self.order.len() < n {
+            while let Some(&Reverse((time, i))) = waiting.peek() {
+                if time > cycle {
+                    break;
+                }
+                waiting.pop();
+                ready.push((self.height[i], Reverse(i)));
+            }
+            let Some((_, Reverse(i))) = ready.pop() else {
+                // Nothing can be issued yet, so skip ahead to the first cycle when something can.
+                let Reverse((time, _)) = *waiting.peek().unwrap();
+                cycle = time;
+                continue;
+            };
+
+            self.order.push(i);
+            for &(succ, latency) in &self.succs[i] {
+                earliest[succ] = earliest[succ].max(cycle + latency);
+                self.num_preds[succ] -= 1;
+                if self.num_preds[succ] == 0 {
+                    waiting.push(Reverse((earliest[succ], succ)));
+                }
+            }
+            cycle += 1;
+        }
+    }
+
+    /// Move the instructions into the scheduled order. Returns `true` if any of them moved.
+    fn emit(&self, func: &mut Function) -> bool {
+        // The instructions that haven't been placed yet are still in their original order after
+        // the placed ones, so each instruction is moved before the first of them. All of its
+        // arguments are defined by placed instructions, and all of its users are unplaced.
+        let mut placed = Vec::new();
+        placed.resize(self.insts.len(), false);
+        let mut first_unplaced = 0;
+        let mut changed = false;
+        for &i in &self.order {
+            if i != first_unplaced {
+                trace!(
+                    "Moving {} before {}",
+                    self.insts[i],
+                    self.insts[first_unplaced]
+                );
+                func.move_inst(self.insts[i], self.insts[first_unplaced])
+                    .expect("The schedule respects the dependencies");
+                changed = true;
+            }
+            placed[i] = true;
+            while first_unplaced < placed.len() && placed[first_unplaced] {
+                first_unplaced += 1;
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cursor::{Cursor, FuncCursor};
+    use crate::ir::{types, AbiParam, InstBuilder, MemFlags};
+    use crate::settings;
+    use crate::verifier::verify_function;
+    use alloc::vec;
+
+    #[test]
+    #[cfg(feature = "x86")]
+    fn schedule_block() {
+        let flags = settings::Flags::new(settings::builder());
+        let isa = crate::isa::lookup_by_name("x86_64")
+            .unwrap()
+            .finish(flags.clone())
+            .unwrap();
+
+        let mut func = Function::new();
+        func.signature.params.push(AbiParam::new(types::I64));
+        func.signature.params.push(AbiParam::new(types::I64));
+        func.signature.returns.push(AbiParam::new(types::I64));
+        let block0 = func.dfg.make_block();
+        let v0 = func.dfg.append_block_param(block0, types::I64);
+        let v1 = func.dfg.append_block_param(block0, types::I64);
+
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let v2 = pos.ins().iadd_imm(v0, 1);
+        let v3 = pos.ins().iadd(v2, v2);
+        let store = pos.ins().store(MemFlags::new(), v1, v0, 0);
+        let v4 = pos.ins().load(types::I64, MemFlags::new(), v0, 0);
+        let v5 = pos.ins().udiv(v4, v1);
+        let v6 = pos.ins().iadd(v3, v5);
+        let ret = pos.ins().return_(&[v6]);
+
+        assert!(list_schedule(&mut func, &*isa));
+        verify_function(&func, &flags).unwrap();
+        let inst = |v| func.dfg.value_def(v).unwrap_inst();
+        // The store and the load start the long chain ending in the division, but the load
+        // can't be moved above the store.
+        assert_eq!(
+            func.layout.block_insts(block0).collect::<Vec<_>>(),
+            vec![store, inst(v4), inst(v2), inst(v3), inst(v5), inst(v6), ret]
+        );
+
+        assert!(!list_schedule(&mut func, &*isa));
+    }
+}
@@ -517,9 +517,23 @@ libcall_call_conv = "isa_default"
 probestack_size_log2 = 12
 probestack_strategy = "outline"
 bb_padding_log2_minus_one = 0
+loop_align_log2 = 0
+max_insts_log2 = 24
+max_blocks_log2 = 24
+max_values_log2 = 24
+max_params_log2 = 16
 regalloc_checker = false
 regalloc_verbose_logs = false
 enable_alias_analysis = true
+enable_simplify_cfg = false
+enable_if_conversion = false
+enable_tail_duplication = false
+enable_div_const_folding = false
+enable_licm_sinking = false
+enable_code_sinking = false
+enable_load_merging = false
+enable_instruction_scheduling = false
+enable_cold_block_outlining = false
 enable_verifier = true
 enable_pcc = false
 is_pic = false
@@ -0,0 +1,226 @@
+//! Control flow graph simplification.
+//!
+//! This pass combines the cleanups that are usually needed together after an optimization has
+//! changed the shape of the CFG:
+//!
+//! - Branches on constants are folded into jumps, and so are `brif`s whose two destinations are
+//!   the same.
+//! - A block that ends in a jump to a block with no other predecessors is merged with it.
+//! - Blocks without parameters that contain nothing but a jump are bypassed and removed.
+//! - Blocks that can't be reached from the entry block are removed.
+//!
+//! These steps enable each other, so they are repeated until nothing changes.
+
+use crate::flowgraph::ControlFlowGraph;
+use crate::ir::{Block, Function, Inst, InstBuilder, InstructionData, Opcode, Value};
+use crate::unreachable_code::remove_unreachable_blocks;
+use crate::{timing, trace};
+use alloc::vec::Vec;
+use smallvec::SmallVec;
+
+/// Simplify the CFG of `func` until it reaches a fixed point.
+///
+/// Returns `true` if anything changed. Any previously computed CFG, dominator tree or loop
+/// analysis is invalid afterwards in that case.
+pub fn simplify_cfg(func: &mut Function) -> bool {
+    let _tt = timing::simplify_cfg();
+    let mut cfg = ControlFlowGraph::new();
+    let mut changed = false;
+    loop {
+        // Remove unreachable blocks first, so the other steps don't count their branches as
+        // predecessors.
+        let mut round = remove_unreachable_blocks(func) > 0;
+        cfg.compute(func);
+        round |= fold_branches(func, &mut cfg);
+        round |= merge_blocks(func, &mut cfg);
+        round |= bypass_empty_blocks(func, &mut cfg);
+        if !round {
+            return changed;
+        }
+        changed = true;
+    }
+}
+
+/// Replace branches with known destinations by jumps.
+fn fold_branches(func: &mut Function, cfg: &mut ControlFlowGraph) -> bool {
+    let mut changed = false;
+    let blocks: Vec<Block> = func.layout.blocks().collect();
+    for block in blocks {
+        let Some(inst) = func.layout.last_inst(block) else {
+            continue;
+        };
+        if fold_branch(func, inst) {
+            cfg.recompute_block(func, block);
+            changed = true;
+        }
+    }
+    changed
+}
+
+fn fold_branch(func: &mut Function, inst: Inst) -> bool {
+    let pool = &func.dfg.value_lists;
+    let taken = match func.dfg.insts[inst] {
+        InstructionData::Brif {
+            arg,
+            blocks: [then_dest, else_dest],
+            ..
+        } => match iconst_value(func, arg) {
+            Some(0) => else_dest,
+            Some(_) => then_dest,
+            None if then_dest.block(pool) == else_dest.block(pool)
+                && then_dest.args_slice(pool) == else_dest.args_slice(pool) =>
+            {
+                then_dest
+            }
+            None => return false,
+        },
+        InstructionData::BranchTable { arg, table, .. } => {
+            let Some(index) = iconst_value(func, arg) else {
+                return false;
+            };
+            let table = &func.dfg.jump_tables[table];
+            usize::try_from(index)
+                .ok()
+                .and_then(|index| table.as_slice().get(index).copied())
+                .unwrap_or(table.default_block())
+        }
+        _ => return false,
+    };
+
+    let dest = taken.block(pool);
+    let args: SmallVec<[Value; 8]> = taken.args_slice(pool).iter().copied().collect();
+    trace!(
+        "Folding {} into a jump to {}",
+        func.dfg.display_inst(inst),
+        dest
+    );
+    func.dfg.replace(inst).jump(dest, &args);
+    true
+}
+
+/// Get the value of `value` if it is defined by an `iconst`, zero-extended from its type.
+fn iconst_value(func: &Function, value: Value) -> Option<u64> {
+    let value = func.dfg.resolve_aliases(value);
+    let inst = func.dfg.value_def(value).inst()?;
+    match func.dfg.insts[inst] {
+        InstructionData::UnaryImm {
+            opcode: Opcode::Iconst,
+            imm,
+        } => {
+            let bits = func.dfg.value_type(value).bits();
+            let imm = imm.bits() as u64;
+            Some(if bits < 64 {
+                imm & ((1 << bits) - 1)
+            } else {
+                imm
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Merge blocks that end in a jump into their successor, if they are its only predecessor.
+fn merge_blocks(func: &mut Function, cfg: &mut ControlFlowGraph) -> bool {
+    let entry = func.layout.entry_block();
+    let mut changed = false;
+    let mut next = entry;
+    while let Some(block) = next {
+        let succ = func
+            .layout
+            .last_inst(block)
+            .filter(|&inst| func.dfg.insts[inst].opcode() == Opcode::Jump)
+            .map(|jump| func.dfg.insts[jump].branch_destination(&func.dfg.jump_tables)[0])
+            .map(|dest| dest.block(&func.dfg.value_lists))
+            .filter(|&succ| {
+                succ != block && Some(succ) != entry && cfg.pred_iter(succ).nth(1).is_none()
+            });
+        match succ {
+            Some(succ) => {
+                merge(func, cfg, block, succ);
+                changed = true;
+                // `block` now ends with the terminator of `succ`, so try again.
+            }
+            None => next = func.layout.next_block(block),
+        }
+    }
+    changed
+}
+
+fn merge(func: &mut Function, cfg: &mut ControlFlowGraph, block: Block, succ: Block) {
+    trace!("Merging {} into {}", succ, block);
+    let jump = func.layout.last_inst(block).unwrap();
+    let dfg = &mut func.dfg;
+    let dest = dfg.insts[jump].branch_destination(&dfg.jump_tables)[0];
+    let args: SmallVec<[Value; 8]> = dest.args_slice(&dfg.value_lists).iter().copied().collect();
+    let params = dfg.detach_block_params(succ);
+    let params: SmallVec<[Value; 8]> = params.as_slice(&dfg.value_lists).iter().copied().collect();
+    for (param, arg) in params.into_iter().zip(args) {
+        dfg.change_to_alias(param, arg);
+    }
+
+    func.layout.remove_inst(jump);
+    while let Some(inst) = func.layout.first_inst(succ) {
+        func.layout.remove_inst(inst);
+        func.layout.append_inst(inst, block);
+    }
+
+    // Drop the edges out of the now empty `succ` before adding them back from `block`.
+    cfg.recompute_block(func, succ);
+    cfg.recompute_block(func, block);
+    func.layout.remove_block(succ);
+}
+
+/// Redirect the predecessors of blocks without parameters that only contain a jump to the
+/// jump's destination, and remove those blocks.
+fn bypass_empty_blocks(func: &mut Function, cfg: &mut ControlFlowGraph) -> bool {
+    let entry = func.layout.entry_block();
+    let mut changed = false;
+    let blocks: Vec<Block> = func.layout.blocks().collect();
+    for block in blocks {
+        if Some(block) == entry || func.dfg.num_block_params(block) != 0 {
+            continue;
+        }
+        let Some(jump) = func.layout.first_inst(block) else {
+            continue;
+        };
+        if func.layout.last_inst(block) != Some(jump)
+            || func.dfg.insts[jump].opcode() != Opcode::Jump
+        {
+            continue;
+        }
+        let dest = func.dfg.insts[jump].branch_destination(&func.dfg.jump_tables)[0];
+        let target = dest.block(&func.dfg.value_lists);
+        let preds: SmallVec<[(Block, Inst); 4]> = cfg
+            .pred_iter(block)
+            .map(|pred| (pred.block, pred.inst))
+            .collect();
+        if target == block || preds.is_empty() || preds.iter().any(|&(pred, _)| pred == block) {
+            continue;
+        }
+
+        // The jump arguments are defined in a block that dominates `block`, so they are also
+        // available in all of its predecessors.
+        trace!("Bypassing {} to reach {}", block, target);
+        let args: SmallVec<[Value; 8]> = dest
+            .args_slice(&func.dfg.value_lists)
+            .iter()
+            .copied()
+            .collect();
+        for (pred, branch) in preds {
+            let dfg = &mut func.dfg;
+            for call in dfg.insts[branch].branch_destination_mut(&mut dfg.jump_tables) {
+                if call.block(&dfg.value_lists) == block {
+                    call.set_block(target, &mut dfg.value_lists);
+                    call.extend(args.iter().copied(), &mut dfg.value_lists);
+                }
+            }
+            cfg.recompute_block(func, pred);
+        }
+
+        func.layout.remove_inst(jump);
+        cfg.recompute_block(func, block);
+        func.layout.remove_block(block);
+        changed = true;
+    }
+    changed
+}
@@ -0,0 +1,175 @@
+//! Tail duplication.
+//!
+//! A small block with several predecessors can be copied into each predecessor that jumps to it
+//! unconditionally. This removes the join point and gives every predecessor a longer
+//! straight-line sequence to work with. Predecessors that reach the block through a conditional
+//! branch or a jump table keep branching to it.
+
+use crate::cursor::{Cursor, FuncCursor};
+use crate::flowgraph::ControlFlowGraph;
+use crate::ir::{Block, Function, Inst, InstructionData, JumpTable, Opcode, Value};
+use crate::{timing, trace};
+use alloc::vec::Vec;
+use rustc_hash::{FxHashMap, FxHashSet};
+use smallvec::SmallVec;
+
+/// The largest block, counting its terminator, that `duplicate_tails` copies.
+pub const MAX_INSTRUCTIONS: usize = 4;
+
+/// Run `duplicate_tail` on every block of `func` with the given `max_instructions`.
+///
+/// `cfg` must be valid on entry, and is kept up to date. Returns `true` if anything was
+/// duplicated.
+pub fn duplicate_tails(
+    func: &mut Function,
+    cfg: &mut ControlFlowGraph,
+    max_instructions: usize,
+) -> bool {
+    let blocks: Vec<Block> = func.layout.blocks().collect();
+    let mut changed = false;
+    for block in blocks {
+        changed |= duplicate_tail(func, cfg, block, max_instructions);
+    }
+    changed
+}
+
+/// Copy the body of `block` into each of its predecessors that ends in a `jump` to it.
+///
+/// Nothing is done unless `block` has at most `max_instructions` instructions, including its
+/// terminator, and more than one predecessor. Since the copies define new values, `block` must
+/// also not define any values that are used outside of it. `block` is removed if no
+/// predecessors are left.
+///
+/// `cfg` must be valid on entry, and is kept up to date. Returns `true` if anything was
+/// duplicated.
+pub fn duplicate_tail(
+    func: &mut Function,
+    cfg: &mut ControlFlowGraph,
+    block: Block,
+    max_instructions: usize,
+) -> bool {
+    let _tt = timing::tail_duplication();
+    if !can_duplicate(func, cfg, block, max_instructions) {
+        return false;
+    }
+
+    let jumps: Vec<(Block, Inst)> = cfg
+        .pred_iter(block)
+        .filter(|pred| func.dfg.insts[pred.inst].opcode() == Opcode::Jump)
+        .map(|pred| (pred.block, pred.inst))
+        .collect();
+    if jumps.is_empty() {
+        return false;
+    }
+
+    let body: Vec<Inst> = func.layout.block_insts(block).collect();
+    for (pred, jump) in jumps {
+        trace!("Duplicating {} into {}", block, pred);
+        copy_body(func, block, &body, jump);
+        cfg.recompute_block(func, pred);
+    }
+
+    if cfg.pred_iter(block).next().is_none() {
+        trace!("Removing {}", block);
+        while let Some(inst) = func.layout.first_inst(block) {
+            func.layout.remove_inst(inst);
+        }
+        cfg.recompute_block(func, block);
+        func.layout.remove_block(block);
+    }
+
+    true
+}
+
+fn can_duplicate(
+    func: &Function,
+    cfg: &ControlFlowGraph,
+    block: Block,
+    max_instructions: usize,
+) -> bool {
+    if func.layout.entry_block() == Some(block)
+        || !func.layout.is_block_inserted(block)
+        || func.layout.block_insts(block).count() > max_instructions
+        || cfg.pred_iter(block).nth(1).is_none()
+        || cfg.succ_iter(block).any(|succ| succ == block)
+    {
+        return false;
+    }
+
+    // Uses of values defined in `block` would need new block parameters to merge the copies.
+    let mut defs: FxHashSet<Value> = func.dfg.block_params(block).iter().copied().collect();
+    for inst in func.layout.block_insts(block) {
+        defs.extend(func.dfg.inst_results(inst));
+    }
+    func.layout
+        .blocks()
+        .filter(|&other| other != block)
+        .flat_map(|other| func.layout.block_insts(other))
+        .all(|inst| {
+            func.dfg
+                .inst_values(inst)
+                .all(|arg| !defs.contains(&func.dfg.resolve_aliases(arg)))
+        })
+}
+
+/// Replace `jump` with copies of the instructions in `body`, the contents of `block`.
+fn copy_body(func: &mut Function, block: Block, body: &[Inst], jump: Inst) {
+    let dest = func.dfg.insts[jump].branch_destination(&func.dfg.jump_tables)[0];
+    let mut values: FxHashMap<Value, Value> = func
+        .dfg
+        .block_params(block)
+        .iter()
+        .copied()
+        .zip(dest.args_slice(&func.dfg.value_lists).iter().copied())
+        .collect();
+
+    let mut pos = FuncCursor::new(func).at_inst(jump);
+    for &inst in body {
+        let copy = pos.func.dfg.clone_inst(inst);
+        // `clone_inst` copies value lists, but not jump tables.
+        if let InstructionData::BranchTable { table, .. }
+        | InstructionData::TryCall { table, .. }
+        | InstructionData::TryCallIndirect { table, .. } = pos.func.dfg.insts[copy]
+        {
+            let new_table = clone_jump_table(pos.func, table);
+            if let InstructionData::BranchTable { table, .. }
+            | InstructionData::TryCall { table, .. }
+            | InstructionData::TryCallIndirect { table, .. } = &mut pos.func.dfg.insts[copy]
+            {
+                *table = new_table;
+            }
+        }
+
+        let args: SmallVec<[Value; 8]> = pos
+            .func
+            .dfg
+            .inst_values(copy)
+            .map(|arg| {
+                let arg = pos.func.dfg.resolve_aliases(arg);
+                values.get(&arg).copied().unwrap_or(arg)
+            })
+            .collect();
+        pos.func.dfg.overwrite_inst_values(copy, args.into_iter());
+
+        for (&old, &new) in pos
+            .func
+            .dfg
+            .inst_results(inst)
+            .iter()
+            .zip(pos.func.dfg.inst_results(copy))
+        {
+            values.insert(old, new);
+        }
+        pos.func.srclocs[copy] = pos.func.srclocs[inst];
+        pos.insert_inst(copy);
+    }
+    pos.remove_inst();
+}
+
+fn clone_jump_table(func: &mut Function, table: JumpTable) -> JumpTable {
+    let mut data = func.dfg.jump_tables[table].clone();
+    for call in data.all_branches_mut() {
+        *call = call.deep_clone(&mut func.dfg.value_lists);
+    }
+    func.create_jump_table(data)
+}
@@ -65,8 +65,17 @@ define_passes! {
     egraph: "Egraph based optimizations",
     gvn: "Global value numbering",
     licm: "Loop invariant code motion",
+    licm_sinking: "Loop invariant load sinking",
+    code_sinking: "Sink instructions to their only use",
     unreachable_code: "Remove unreachable blocks",
     remove_constant_phis: "Remove constant phi-nodes",
+    tail_duplication: "Tail duplication",
+    simplify_cfg: "Simplify the control flow graph",
+    if_conversion: "If conversion",
+    vectorize_memory: "Merge adjacent loads into vector loads",
+    scheduling: "List scheduling",
+    div_const: "Strength reduction of division by constants",
+    outline_cold: "Move cold blocks to the end of the function",
 
     vcode_lower: "VCode lowering",
     vcode_emit: "VCode emission",
@@ -157,6 +166,13 @@ impl PassTimes {
     pub fn total(&self) -> Duration {
         self.pass.iter().map(|p| p.total - p.child).sum()
     }
+
+    /// Returns the time spent in `pass`, including its child passes.
+    pub fn get(&self, pass: Pass) -> Duration {
+        self.pass
+            .get(pass.idx())
+            .map_or(Duration::default(), |p| p.total)
+    }
 }
 
 impl Default for PassTimes {
@@ -293,4 +309,11 @@ mod tests {
         assert_eq!(Pass::None.to_string(), "<no pass>");
         assert_eq!(Pass::regalloc.to_string(), "Register allocation");
     }
+
+    #[test]
+    fn get() {
+        let times = PassTimes::default();
+        assert_eq!(times.get(Pass::regalloc), Duration::default());
+        assert_eq!(times.get(Pass::None), Duration::default());
+    }
 }
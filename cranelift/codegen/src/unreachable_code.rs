@@ -5,8 +5,12 @@ use cranelift_entity::EntitySet;
 use crate::cursor::{Cursor, FuncCursor};
 use crate::dominator_tree::DominatorTree;
 use crate::flowgraph::ControlFlowGraph;
+use crate::result::CodegenResult;
 use crate::timing;
+use crate::verifier::VerifierErrors;
 use crate::{ir, trace};
+use alloc::format;
+use alloc::vec::Vec;
 
 /// Eliminate unreachable code.
 ///
@@ -25,7 +29,10 @@ pub fn eliminate_unreachable_code(
     while let Some(block) = pos.next_block() {
         if domtree.is_reachable(block) {
             let inst = pos.func.layout.last_inst(block).unwrap();
-            if let ir::InstructionData::BranchTable { table, .. } = pos.func.dfg.insts[inst] {
+            if let ir::InstructionData::BranchTable { table, .. }
+            | ir::InstructionData::TryCall { table, .. }
+            | ir::InstructionData::TryCallIndirect { table, .. } = pos.func.dfg.insts[inst]
+            {
                 used_tables.insert(table);
             }
             continue;
@@ -50,9 +57,209 @@ pub fn eliminate_unreachable_code(
         pos.func.layout.remove_block(block);
     }
 
+    clear_unused_jump_tables(func, &used_tables);
+}
+
+/// Remove every block that can't be reached from the entry block, and return how many blocks
+/// were removed.
+///
+/// Unlike `eliminate_unreachable_code`, this finds reachable blocks with its own depth-first
+/// search, so it needs neither the CFG nor the dominator tree, but it doesn't keep them up to
+/// date either. Values defined in removed blocks can't be used by reachable code in valid IR, so
+/// nothing else needs to be rewritten.
+pub fn remove_unreachable_blocks(func: &mut ir::Function) -> usize {
+    let _tt = timing::unreachable_code();
+    let unreachable = unreachable_blocks(func);
+    for &block in &unreachable {
+        empty_block(func, block);
+        func.layout.remove_block(block);
+    }
+    unreachable.len()
+}
+
+/// Remove every block that can't be reached from the entry block, keeping `cfg` up to date, and
+/// return how many blocks were removed.
+///
+/// Jump tables that were only used by the removed blocks are cleared. If a value defined in an
+/// unreachable block is still used by a reachable one, removing the block would leave a dangling
+/// reference, so a verifier error is returned instead and `func` is left unchanged.
+pub fn cleanup_unreachable(
+    func: &mut ir::Function,
+    cfg: &mut ControlFlowGraph,
+) -> CodegenResult<usize> {
+    let _tt = timing::unreachable_code();
+    let unreachable = unreachable_blocks(func);
+    if unreachable.is_empty() {
+        return Ok(0);
+    }
+
+    let mut removed = EntitySet::with_capacity(func.dfg.num_blocks());
+    for &block in &unreachable {
+        removed.insert(block);
+    }
+    let mut errors = VerifierErrors::default();
+    let mut used_tables = EntitySet::with_capacity(func.dfg.jump_tables.len());
+    for block in func
+        .layout
+        .blocks()
+        .filter(|&block| !removed.contains(block))
+    {
+        for inst in func.layout.block_insts(block) {
+            if let ir::InstructionData::BranchTable { table, .. }
+            | ir::InstructionData::TryCall { table, .. }
+            | ir::InstructionData::TryCallIndirect { table, .. } = func.dfg.insts[inst]
+            {
+                used_tables.insert(table);
+            }
+            for arg in func.dfg.inst_values(inst) {
+                let arg = func.dfg.resolve_aliases(arg);
+                let def_block = match func.dfg.value_def(arg) {
+                    ir::ValueDef::Result(def, _) => func.layout.inst_block(def),
+                    ir::ValueDef::Param(def, _) => Some(def),
+                    ir::ValueDef::Union(..) => None,
+                };
+                if let Some(def_block) = def_block.filter(|&b| removed.contains(b)) {
+                    errors.report((
+                        inst,
+                        format!("uses {arg}, which is defined in unreachable {def_block}"),
+                    ));
+                }
+            }
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors.into());
+    }
+
+    for &block in &unreachable {
+        empty_block(func, block);
+        // Once the block is empty, this removes it from its successors' predecessor lists.
+        cfg.recompute_block(func, block);
+        func.layout.remove_block(block);
+    }
+    clear_unused_jump_tables(func, &used_tables);
+    Ok(unreachable.len())
+}
+
+/// Get the blocks in the layout of `func` that can't be reached from its entry block.
+fn unreachable_blocks(func: &ir::Function) -> Vec<ir::Block> {
+    let entry = match func.layout.entry_block() {
+        Some(entry) => entry,
+        None => return Vec::new(),
+    };
+
+    let mut reachable = EntitySet::with_capacity(func.dfg.num_blocks());
+    for block in func.dfg.reachable_blocks(entry, &func.layout) {
+        reachable.insert(block);
+    }
+    func.layout
+        .blocks()
+        .filter(|&block| !reachable.contains(block))
+        .collect()
+}
+
+/// Remove all instructions from the unreachable `block`.
+fn empty_block(func: &mut ir::Function, block: ir::Block) {
+    trace!("Eliminating unreachable {}", block);
+    while let Some(inst) = func.layout.first_inst(block) {
+        trace!(" - {}", func.dfg.display_inst(inst));
+        func.layout.remove_inst(inst);
+    }
+}
+
+fn clear_unused_jump_tables(func: &mut ir::Function, used_tables: &EntitySet<ir::JumpTable>) {
     for (table, jt_data) in func.stencil.dfg.jump_tables.iter_mut() {
         if !used_tables.contains(table) {
             jt_data.clear();
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{types, InstBuilder, JumpTableData};
+    use crate::result::CodegenError;
+
+    #[test]
+    fn remove_unreachable() {
+        let mut func = ir::Function::new();
+        let block0 = func.dfg.make_block();
+        let block1 = func.dfg.make_block();
+        let block2 = func.dfg.make_block();
+        let block3 = func.dfg.make_block();
+        let v0 = func.dfg.append_block_param(block3, types::I32);
+        let mut pos = FuncCursor::new(&mut func);
+
+        // block1 and block2 are only reachable from each other, and branch to live block3.
+        pos.insert_block(block0);
+        let c = pos.ins().iconst(types::I32, 0);
+        pos.ins().jump(block3, &[c]);
+        pos.insert_block(block1);
+        pos.ins().jump(block2, &[]);
+        pos.insert_block(block2);
+        let v1 = pos.ins().iconst(types::I32, 1);
+        pos.ins().brif(v1, block1, &[], block3, &[v1]);
+        pos.insert_block(block3);
+        pos.ins().return_(&[v0]);
+
+        assert_eq!(remove_unreachable_blocks(&mut func), 2);
+        let blocks: Vec<_> = func.layout.blocks().collect();
+        assert_eq!(blocks, [block0, block3]);
+        assert_eq!(remove_unreachable_blocks(&mut func), 0);
+    }
+
+    #[test]
+    fn cleanup_dead_br_table() {
+        let mut func = ir::Function::new();
+        let block0 = func.dfg.make_block();
+        let block1 = func.dfg.make_block();
+        let block2 = func.dfg.make_block();
+        let v0 = func.dfg.append_block_param(block0, types::I32);
+        let def = func.dfg.block_call(block2, &[]);
+        let entries = [func.dfg.block_call(block1, &[])];
+        let table = func.create_jump_table(JumpTableData::new(def, &entries));
+        let mut pos = FuncCursor::new(&mut func);
+
+        // block1 is dead, and is the only user of the jump table.
+        pos.insert_block(block0);
+        pos.ins().jump(block2, &[]);
+        pos.insert_block(block1);
+        pos.ins().br_table(v0, table);
+        pos.insert_block(block2);
+        pos.ins().return_(&[]);
+
+        let mut cfg = ControlFlowGraph::with_function(&func);
+        assert_eq!(cleanup_unreachable(&mut func, &mut cfg).unwrap(), 1);
+        let blocks: Vec<_> = func.layout.blocks().collect();
+        assert_eq!(blocks, [block0, block2]);
+        assert!(func.dfg.jump_tables[table].as_slice().is_empty());
+        assert_eq!(cfg.pred_iter(block2).count(), 1);
+        assert_eq!(cleanup_unreachable(&mut func, &mut cfg).unwrap(), 0);
+    }
+
+    #[test]
+    fn cleanup_rejects_dangling_use() {
+        let mut func = ir::Function::new();
+        let block0 = func.dfg.make_block();
+        let block1 = func.dfg.make_block();
+        let block2 = func.dfg.make_block();
+        let mut pos = FuncCursor::new(&mut func);
+
+        // block2 is reachable, but uses a value defined in dead block1.
+        pos.insert_block(block0);
+        pos.ins().jump(block2, &[]);
+        pos.insert_block(block1);
+        let v0 = pos.ins().iconst(types::I32, 0);
+        pos.ins().jump(block2, &[]);
+        pos.insert_block(block2);
+        pos.ins().return_(&[v0]);
+
+        let mut cfg = ControlFlowGraph::with_function(&func);
+        match cleanup_unreachable(&mut func, &mut cfg) {
+            Err(CodegenError::Verifier(errors)) => assert_eq!(errors.0.len(), 1),
+            result => panic!("expected a verifier error, got {result:?}"),
+        }
+        assert_eq!(func.layout.blocks().count(), 3);
+    }
+}
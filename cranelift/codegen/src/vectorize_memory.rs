@@ -0,0 +1,204 @@
+//! Merging of adjacent scalar loads into vector loads.
+//!
+//! A sequence of scalar loads from consecutive offsets of the same base address reads the same
+//! bytes as a single 128-bit vector load, so it can be replaced by one vector load and a lane
+//! extraction for each of the original results:
+//!
+//! ```plain
+//! v1 = load.f64 v0+8
+//! v2 = fadd v1, v1
+//! v3 = load.f64 v0+16
+//! ```
+//!
+//! becomes
+//!
+//! ```plain
+//! v4 = load.f64x2 v0+8
+//! v1 = extractlane v4, 0
+//! v2 = fadd v1, v1
+//! v3 = extractlane v4, 1
+//! ```
+//!
+//! Only loads within a block are merged, and only if nothing between them can store, trap, call
+//! or have other side effects, so moving the later loads up to the first one can't be observed.
+//! Lane 0 of a vector is at its lowest address only for little-endian loads, so big-endian loads
+//! are left alone.
+
+use crate::cursor::{Cursor, FuncCursor};
+use crate::ir::{types, Endianness, Function, Inst, InstBuilder, InstructionData, MemFlags};
+use crate::ir::{Opcode, Type, Value};
+use crate::isa::TargetIsa;
+use crate::{timing, trace};
+use alloc::vec::Vec;
+
+/// The size of the vectors loads are merged into.
+const VECTOR_BYTES: u32 = 16;
+
+/// Merge runs of adjacent scalar loads in each block of `func` into vector loads. Returns `true`
+/// if any loads were merged.
+///
+/// Nothing is merged if `isa` doesn't support vectors.
+pub fn merge_loads(func: &mut Function, isa: &dyn TargetIsa) -> bool {
+    let _tt = timing::vectorize_memory();
+    if !isa.has_vector_support() {
+        return false;
+    }
+
+    let mut changed = false;
+    let mut pos = FuncCursor::new(func);
+    let mut run: Vec<Inst> = Vec::new();
+    while let Some(_block) = pos.next_block() {
+        run.clear();
+        while let Some(inst) = pos.next_inst() {
+            let Some(load) = ScalarLoad::new(pos.func, isa, inst) else {
+                if !pos.func.dfg.insts[inst].opcode().is_pure() {
+                    run.clear();
+                }
+                continue;
+            };
+
+            let continues_run = run.last().is_some_and(|&last| {
+                let last = ScalarLoad::new(pos.func, isa, last).unwrap();
+                load.follows(&last)
+            });
+            if !continues_run {
+                run.clear();
+            }
+            run.push(inst);
+
+            if run.len() as u32 * load.ty.bytes() == VECTOR_BYTES {
+                merge_run(&mut pos, isa, &run);
+                run.clear();
+                changed = true;
+            }
+        }
+    }
+    changed
+}
+
+/// The parts of a load that can be merged.
+struct ScalarLoad {
+    ty: Type,
+    flags: MemFlags,
+    base: Value,
+    offset: i64,
+}
+
+impl ScalarLoad {
+    /// Get the parts of `inst` if it is a little-endian load that can be merged.
+    fn new(func: &Function, isa: &dyn TargetIsa, inst: Inst) -> Option<Self> {
+        let InstructionData::Load {
+            opcode: Opcode::Load,
+            arg,
+            flags,
+            offset,
+        } = func.dfg.insts[inst]
+        else {
+            return None;
+        };
+        let ty = func.dfg.ctrl_typevar(inst);
+        // The merged load covers more bytes than each scalar load, so it can't keep claiming
+        // their alignment, and proof-carrying code facts would no longer match.
+        if !matches!(ty, types::I32 | types::F32 | types::I64 | types::F64)
+            || flags.aligned()
+            || flags.checked()
+            || flags.endianness(isa.endianness()) != Endianness::Little
+        {
+            return None;
+        }
+        Some(Self {
+            ty,
+            flags,
+            base: func.dfg.resolve_aliases(arg),
+            offset: offset.into(),
+        })
+    }
+
+    /// Does `self` load the bytes right after `prev`, in the same way?
+    fn follows(&self, prev: &Self) -> bool {
+        self.ty == prev.ty
+            && self.flags == prev.flags
+            && self.base == prev.base
+            && self.offset == prev.offset + i64::from(prev.ty.bytes())
+    }
+}
+
+/// Replace the loads in `run` by a vector load before the first one, and a lane extraction for
+/// each of them. `pos` is left at the last load.
+fn merge_run(pos: &mut FuncCursor, isa: &dyn TargetIsa, run: &[Inst]) {
+    let first = ScalarLoad::new(pos.func, isa, run[0]).unwrap();
+    let vector_ty = first.ty.by(run.len() as u32).unwrap();
+    trace!("Merging {} loads into a {} load", run.len(), vector_ty);
+
+    pos.goto_inst(run[0]);
+    let vector = pos
+        .ins()
+        .load(vector_ty, first.flags, first.base, first.offset as i32);
+    for (lane, &inst) in run.iter().enumerate() {
+        pos.func.dfg.replace(inst).extractlane(vector, lane as u8);
+    }
+    pos.goto_inst(*run.last().unwrap());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::AbiParam;
+    use crate::settings;
+    use crate::verifier::verify_function;
+
+    fn function(offsets: &[i32], store_between: bool) -> (Function, Vec<Inst>) {
+        let mut func = Function::new();
+        func.signature.params.push(AbiParam::new(types::I64));
+        let block0 = func.dfg.make_block();
+        let v0 = func.dfg.append_block_param(block0, types::I64);
+        let flags = MemFlags::new().with_endianness(Endianness::Little);
+
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(block0);
+        let mut loads = Vec::new();
+        for &offset in offsets {
+            let value = pos.ins().load(types::F32, flags, v0, offset);
+            loads.push(pos.func.dfg.value_def(value).unwrap_inst());
+            if store_between {
+                pos.ins().store(flags, value, v0, 64);
+            }
+        }
+        pos.ins().return_(&[]);
+        (func, loads)
+    }
+
+    #[test]
+    #[cfg(feature = "x86")]
+    fn merge_adjacent_loads() {
+        let flags = settings::Flags::new(settings::builder());
+        let isa = crate::isa::lookup_by_name("x86_64")
+            .unwrap()
+            .finish(flags.clone())
+            .unwrap();
+
+        let (mut func, loads) = function(&[4, 8, 12, 16, 20], false);
+        assert!(merge_loads(&mut func, &*isa));
+        verify_function(&func, &flags).unwrap();
+        for (lane, &inst) in loads[..4].iter().enumerate() {
+            assert_eq!(
+                func.dfg.insts[inst],
+                InstructionData::BinaryImm8 {
+                    opcode: Opcode::Extractlane,
+                    arg: func.dfg.inst_args(inst)[0],
+                    imm: lane as u8,
+                }
+            );
+        }
+        let vector = func.dfg.inst_args(loads[0])[0];
+        assert_eq!(func.dfg.value_type(vector), types::F32X4);
+        // The fifth load has nothing to be merged with.
+        assert_eq!(func.dfg.insts[loads[4]].opcode(), Opcode::Load);
+
+        // Loads that aren't adjacent, or are separated by stores, are left alone.
+        let (mut func, _) = function(&[4, 8, 16, 20], false);
+        assert!(!merge_loads(&mut func, &*isa));
+        let (mut func, _) = function(&[4, 8, 12, 16], true);
+        assert!(!merge_loads(&mut func, &*isa));
+    }
+}
@@ -68,7 +68,7 @@ use crate::dominator_tree::DominatorTree;
 use crate::entity::SparseSet;
 use crate::flowgraph::{BlockPredecessor, ControlFlowGraph};
 use crate::ir::entities::AnyEntity;
-use crate::ir::instructions::{CallInfo, InstructionFormat, ResolvedConstraint};
+use crate::ir::instructions::{CallInfo, InstructionFormat};
 use crate::ir::{self, ArgumentExtension};
 use crate::ir::{
     types, ArgumentPurpose, Block, Constant, DynamicStackSlot, FuncRef, Function, GlobalValue,
@@ -590,6 +590,26 @@ impl<'a> Verifier<'a> {
                 self.verify_func_ref(inst, func_ref, errors)?;
                 self.verify_value_list(inst, args, errors)?;
             }
+            TryCall {
+                func_ref,
+                table,
+                ref args,
+                ..
+            } => {
+                self.verify_func_ref(inst, func_ref, errors)?;
+                self.verify_try_call_table(inst, table, errors)?;
+                self.verify_value_list(inst, args, errors)?;
+            }
+            TryCallIndirect {
+                sig_ref,
+                table,
+                ref args,
+                ..
+            } => {
+                self.verify_sig_ref(inst, sig_ref, errors)?;
+                self.verify_try_call_table(inst, table, errors)?;
+                self.verify_value_list(inst, args, errors)?;
+            }
             CallIndirect {
                 sig_ref, ref args, ..
             } => {
@@ -840,6 +860,36 @@ impl<'a> Verifier<'a> {
         }
     }
 
+    /// Check the jump table of a `try_call`, which holds the normal block as its default block and
+    /// the exception block as its single entry.
+    fn verify_try_call_table(
+        &self,
+        inst: Inst,
+        table: JumpTable,
+        errors: &mut VerifierErrors,
+    ) -> VerifierStepResult {
+        if !self.func.stencil.dfg.jump_tables.is_valid(table) {
+            return errors.fatal((
+                inst,
+                self.context(inst),
+                format!("invalid jump table reference {table}"),
+            ));
+        }
+        self.verify_jump_table(inst, table, errors)?;
+        let entries = self.func.dfg.jump_tables[table].as_slice().len();
+        if entries != 1 {
+            return errors.fatal((
+                inst,
+                self.context(inst),
+                format!(
+                    "{} needs exactly one exception block, got {entries}",
+                    self.func.dfg.insts[inst].opcode()
+                ),
+            ));
+        }
+        Ok(())
+    }
+
     fn verify_value(
         &self,
         loc_inst: Inst,
@@ -1147,33 +1197,21 @@ impl<'a> Verifier<'a> {
     }
 
     fn typecheck(&self, inst: Inst, errors: &mut VerifierErrors) -> VerifierStepResult {
-        let inst_data = &self.func.dfg.insts[inst];
-        let constraints = inst_data.opcode().constraints();
-
-        let ctrl_type = if let Some(value_typeset) = constraints.ctrl_typeset() {
-            // For polymorphic opcodes, determine the controlling type variable first.
-            let ctrl_type = self.func.dfg.ctrl_typevar(inst);
-
-            if !value_typeset.contains(ctrl_type) {
-                errors.report((
-                    inst,
-                    self.context(inst),
-                    format!(
-                        "has an invalid controlling type {ctrl_type} (allowed set is {value_typeset:?})"
-                    ),
-                ));
-            }
-
-            ctrl_type
-        } else {
-            // Non-polymorphic instructions don't check the controlling type variable, so `Option`
-            // is unnecessary and we can just make it `INVALID`.
-            types::INVALID
+        // For polymorphic opcodes, the result types depend on the controlling type variable.
+        // Non-polymorphic instructions don't check the controlling type variable, so `Option` is
+        // unnecessary and we can just make it `INVALID`.
+        let ctrl_type = match self.func.dfg.insts[inst]
+            .opcode()
+            .constraints()
+            .ctrl_typeset()
+        {
+            Some(_) => self.func.dfg.ctrl_typevar(inst),
+            None => types::INVALID,
         };
 
         // Typechecking instructions is never fatal
         let _ = self.typecheck_results(inst, ctrl_type, errors);
-        let _ = self.typecheck_fixed_args(inst, ctrl_type, errors);
+        self.typecheck_args(inst, errors);
         let _ = self.typecheck_variable_args(inst, errors);
         let _ = self.typecheck_return(inst, errors);
         let _ = self.typecheck_special(inst, errors);
@@ -1222,149 +1260,122 @@ impl<'a> Verifier<'a> {
         Ok(())
     }
 
-    fn typecheck_fixed_args(
-        &self,
-        inst: Inst,
-        ctrl_type: Type,
-        errors: &mut VerifierErrors,
-    ) -> VerifierStepResult {
-        let constraints = self.func.dfg.insts[inst].opcode().constraints();
-
-        for (i, &arg) in self.func.dfg.inst_fixed_args(inst).iter().enumerate() {
-            let arg_type = self.func.dfg.value_type(arg);
-            match constraints.value_argument_constraint(i, ctrl_type) {
-                ResolvedConstraint::Bound(expected_type) => {
-                    if arg_type != expected_type {
-                        errors.report((
-                            inst,
-                            self.context(inst),
-                            format!(
-                                "arg {i} ({arg}) has type {arg_type}, expected {expected_type}"
-                            ),
-                        ));
-                    }
-                }
-                ResolvedConstraint::Free(type_set) => {
-                    if !type_set.contains(arg_type) {
-                        errors.report((
-                            inst,
-                            self.context(inst),
-                            format!(
-                                "arg {i} ({arg}) with type {arg_type} failed to satisfy type set {type_set:?}"
-                            ),
-                        ));
-                    }
-                }
-            }
-        }
-        Ok(())
+    /// Check the controlling type variable and the arguments of `inst`, including the ones it
+    /// passes to a callee or to its branch destinations, with `DataFlowGraph::type_check_inst`.
+    fn typecheck_args(&self, inst: Inst, errors: &mut VerifierErrors) {
+        self.func.dfg.for_each_type_error(inst, |err| {
+            let message = match err {
+                ir::TypeCheckError::CallArgCount { expected, found }
+                | ir::TypeCheckError::BlockArgCount {
+                    expected, found, ..
+                } => format!(
+                    "mismatched argument count for `{}`: got {found}, expected {expected}",
+                    self.func.dfg.display_inst(inst),
+                ),
+                err => err.to_string(),
+            };
+            errors.report((inst, self.context(inst), message));
+        });
     }
 
-    /// Typecheck both instructions that contain variable arguments like calls, and those that
-    /// include references to basic blocks with their arguments.
+    /// Check the parts of calls and branches that aren't covered by `typecheck_args`.
     fn typecheck_variable_args(
         &self,
         inst: Inst,
         errors: &mut VerifierErrors,
     ) -> VerifierStepResult {
-        match &self.func.dfg.insts[inst] {
-            ir::InstructionData::Jump { destination, .. } => {
-                self.typecheck_block_call(inst, destination, errors)?;
-            }
-            ir::InstructionData::Brif {
-                blocks: [block_then, block_else],
-                ..
-            } => {
-                self.typecheck_block_call(inst, block_then, errors)?;
-                self.typecheck_block_call(inst, block_else, errors)?;
-            }
-            ir::InstructionData::BranchTable { table, .. } => {
-                for block in self.func.stencil.dfg.jump_tables[*table].all_branches() {
-                    self.typecheck_block_call(inst, block, errors)?;
-                }
-            }
-            inst => debug_assert!(!inst.opcode().is_branch()),
+        if let ir::InstructionData::TryCall { table, .. }
+        | ir::InstructionData::TryCallIndirect { table, .. } = self.func.dfg.insts[inst]
+        {
+            self.typecheck_try_call_params(inst, table, errors)?;
         }
 
-        match self.func.dfg.insts[inst].analyze_call(&self.func.dfg.value_lists) {
-            CallInfo::Direct(func_ref, args) => {
-                let sig_ref = self.func.dfg.ext_funcs[func_ref].signature;
-                let arg_types = self.func.dfg.signatures[sig_ref]
-                    .params
-                    .iter()
-                    .map(|a| a.value_type);
-                self.typecheck_variable_args_iterator(inst, arg_types, args, errors)?;
-            }
-            CallInfo::Indirect(sig_ref, args) => {
-                let arg_types = self.func.dfg.signatures[sig_ref]
-                    .params
-                    .iter()
-                    .map(|a| a.value_type);
-                self.typecheck_variable_args_iterator(inst, arg_types, args, errors)?;
+        if let CallInfo::Indirect(..) =
+            self.func.dfg.insts[inst].analyze_call(&self.func.dfg.value_lists)
+        {
+            if let Some(isa) = self.isa {
+                let callee = self.func.dfg.inst_args(inst)[0];
+                let callee_type = self.func.dfg.value_type(callee);
+                let pointer_type = isa.pointer_type();
+                if callee_type != pointer_type {
+                    errors.report((
+                        inst,
+                        self.context(inst),
+                        format!(
+                            "callee {callee} has type {callee_type}, which is not the pointer type {pointer_type}"
+                        ),
+                    ));
+                }
             }
-            CallInfo::NotACall => {}
         }
         Ok(())
     }
 
-    fn typecheck_block_call(
+    /// The normal block of a `try_call` receives the return values of the call in its first
+    /// parameters, and the exception block receives the exception value, a pointer-sized integer,
+    /// in its first parameter. The block call arguments are checked by `typecheck_args`.
+    fn typecheck_try_call_params(
         &self,
         inst: Inst,
-        block: &ir::BlockCall,
+        table: ir::JumpTable,
         errors: &mut VerifierErrors,
     ) -> VerifierStepResult {
-        let pool = &self.func.dfg.value_lists;
-        let iter = self
-            .func
-            .dfg
-            .block_params(block.block(pool))
-            .iter()
-            .map(|&v| self.func.dfg.value_type(v));
-        let args = block.args_slice(pool);
-        self.typecheck_variable_args_iterator(inst, iter, args, errors)
-    }
-
-    fn typecheck_variable_args_iterator<I: Iterator<Item = Type>>(
-        &self,
-        inst: Inst,
-        iter: I,
-        variable_args: &[Value],
-        errors: &mut VerifierErrors,
-    ) -> VerifierStepResult {
-        let mut i = 0;
-
-        for expected_type in iter {
-            if i >= variable_args.len() {
-                // Result count mismatch handled below, we want the full argument count first though
-                i += 1;
-                continue;
-            }
-            let arg = variable_args[i];
-            let arg_type = self.func.dfg.value_type(arg);
-            if expected_type != arg_type {
+        let dfg = &self.func.dfg;
+        let pool = &dfg.value_lists;
+        let jt = &dfg.jump_tables[table];
+
+        let block = jt.default_block().block(pool);
+        let params = dfg.block_params(block);
+        let sig_ref = dfg.call_signature(inst).unwrap();
+        let returns = &dfg.signatures[sig_ref].returns;
+        if params.len() < returns.len() {
+            errors.report((
+                inst,
+                self.context(inst),
+                format!(
+                    "normal block {block} has {} parameters, which is too few for the {} return values",
+                    params.len(),
+                    returns.len()
+                ),
+            ));
+        }
+        for (i, (&param, ret)) in params.iter().zip(returns).enumerate() {
+            let param_type = dfg.value_type(param);
+            if param_type != ret.value_type {
                 errors.report((
                     inst,
                     self.context(inst),
                     format!(
-                        "arg {} ({}) has type {}, expected {}",
-                        i, variable_args[i], arg_type, expected_type
+                        "return value {i} has type {}, but {param} in normal block {block} has type {param_type}",
+                        ret.value_type
                     ),
                 ));
             }
-            i += 1;
         }
-        if i != variable_args.len() {
+
+        let block = jt.as_slice()[0].block(pool);
+        let Some(&param) = dfg.block_params(block).first() else {
             return errors.nonfatal((
+                inst,
+                self.context(inst),
+                format!("exception block {block} has no parameter for the exception value"),
+            ));
+        };
+        let param_type = dfg.value_type(param);
+        let ok = match self.isa {
+            Some(isa) => param_type == isa.pointer_type(),
+            None => param_type.is_int() && !param_type.is_vector(),
+        };
+        if !ok {
+            errors.report((
                 inst,
                 self.context(inst),
                 format!(
-                    "mismatched argument count for `{}`: got {}, expected {}",
-                    self.func.dfg.display_inst(inst),
-                    variable_args.len(),
-                    i,
+                    "exception value {param} has type {param_type}, which is not a pointer type"
                 ),
             ));
         }
+
         Ok(())
     }
 
@@ -466,6 +466,18 @@ pub fn write_operands(w: &mut dyn Write, dfg: &DataFlowGraph, inst: Inst) -> fmt
             write!(w, " {}({})", func_ref, DisplayValues(args.as_slice(pool)))?;
             write_user_stack_map_entries(w, dfg, inst)
         }
+        TryCall {
+            func_ref,
+            table,
+            ref args,
+            ..
+        } => {
+            write!(w, " {}({})", func_ref, DisplayValues(args.as_slice(pool)))?;
+            for block in jump_tables[table].all_branches() {
+                write!(w, ", {}", block.display(pool))?;
+            }
+            write_user_stack_map_entries(w, dfg, inst)
+        }
         CallIndirect {
             sig_ref, ref args, ..
         } => {
@@ -479,6 +491,25 @@ pub fn write_operands(w: &mut dyn Write, dfg: &DataFlowGraph, inst: Inst) -> fmt
             )?;
             write_user_stack_map_entries(w, dfg, inst)
         }
+        TryCallIndirect {
+            sig_ref,
+            table,
+            ref args,
+            ..
+        } => {
+            let args = args.as_slice(pool);
+            write!(
+                w,
+                " {}, {}({})",
+                sig_ref,
+                args[0],
+                DisplayValues(&args[1..])
+            )?;
+            for block in jump_tables[table].all_branches() {
+                write!(w, ", {}", block.display(pool))?;
+            }
+            write_user_stack_map_entries(w, dfg, inst)
+        }
         FuncAddr { func_ref, .. } => write!(w, " {func_ref}"),
         StackLoad {
             stack_slot, offset, ..
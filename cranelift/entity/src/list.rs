@@ -1,6 +1,7 @@
 //! Small lists of entity references.
 use crate::packed_option::ReservedValue;
 use crate::EntityRef;
+use alloc::collections::BTreeMap;
 use alloc::vec::Vec;
 use core::marker::PhantomData;
 use core::mem;
@@ -162,6 +163,17 @@ impl<T: EntityRef + ReservedValue> ListPool<T> {
         self.data.capacity()
     }
 
+    /// Get the number of elements currently allocated from this pool, including list length
+    /// headers, unused capacity in size classes, and freed blocks awaiting reuse.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns `true` if nothing has been allocated from this pool since it was last cleared.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
     /// Clear the pool, forgetting about all lists that use it.
     ///
     /// This invalidates any existing entity lists that used this pool to allocate memory.
@@ -173,6 +185,61 @@ impl<T: EntityRef + ReservedValue> ListPool<T> {
         self.free.clear();
     }
 
+    /// Shrink the pool back to `len` elements, a length previously returned by `len()`.
+    ///
+    /// This is a cheap way to roll back allocations: every list allocated since the pool had
+    /// length `len` is invalidated. Lists that existed back then stay valid as long as they
+    /// haven't been modified since, because blocks are never moved by other allocations. Free
+    /// blocks beyond `len` are dropped from the free lists.
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.data.len() {
+            return;
+        }
+        let mut kept = Vec::new();
+        for sclass in 0..self.free.len() {
+            // Walk the free list before truncating, since its links may pass through blocks
+            // that are about to be dropped.
+            kept.clear();
+            let mut head = self.free[sclass];
+            while head > 0 {
+                if head <= len {
+                    kept.push(head);
+                }
+                head = self.data[head].index();
+            }
+            let mut next = 0;
+            for &head in kept.iter().rev() {
+                self.data[head] = T::new(next);
+                next = head;
+            }
+            self.free[sclass] = next;
+        }
+        self.data.truncate(len);
+    }
+
+    /// Compact the pool by copying every live list into fresh storage.
+    ///
+    /// After many lists have been freed, the pool's memory can be badly fragmented. This copies
+    /// the contents of each list in `live_lists` into a new, densely packed data vector, updates
+    /// the lists in place to point into it, and replaces the old storage and its free lists.
+    ///
+    /// Any list not included in `live_lists` is invalidated, just like after `clear()`. Lists
+    /// that alias the same storage keep sharing it after compaction.
+    pub fn compact(&mut self, live_lists: &mut [&mut EntityList<T>]) {
+        let capacity = self.data.len();
+        let old = mem::replace(self, Self::with_capacity(capacity));
+        let mut moved = BTreeMap::new();
+        for list in live_lists.iter_mut() {
+            if list.is_empty() {
+                continue;
+            }
+            let index = *moved
+                .entry(list.index)
+                .or_insert_with(|| EntityList::from_slice(list.as_slice(&old), self).index);
+            list.index = index;
+        }
+    }
+
     /// Read the length of a list field, if it exists.
     fn len_of(&self, list: &EntityList<T>) -> Option<usize> {
         let idx = list.index as usize;
@@ -364,6 +431,11 @@ impl<T: EntityRef + ReservedValue> EntityList<T> {
         }
     }
 
+    /// Copy this list from `src_pool` into a new list in `dst_pool`.
+    pub fn clone_to(&self, src_pool: &ListPool<T>, dst_pool: &mut ListPool<T>) -> Self {
+        Self::from_slice(self.as_slice(src_pool), dst_pool)
+    }
+
     /// Removes all elements from the list.
     ///
     /// The memory used by the list is put back in the pool.
@@ -691,6 +763,76 @@ mod tests {
         assert!(b3a == b1 || b3a == b3);
     }
 
+    #[test]
+    fn compact() {
+        let pool = &mut ListPool::<Inst>::new();
+        let mut lists: Vec<EntityList<Inst>> = (0..10)
+            .map(|i| EntityList::from_iter((0..i).map(Inst::new), pool))
+            .collect();
+
+        // Free every other list to fragment the pool.
+        for list in lists.iter_mut().step_by(2) {
+            list.clear(pool);
+        }
+        let fragmented_len = pool.data.len();
+
+        let alias = lists[5];
+        let mut alias_copy = alias;
+        let mut live: Vec<&mut EntityList<Inst>> = lists.iter_mut().skip(1).step_by(2).collect();
+        live.push(&mut alias_copy);
+        pool.compact(&mut live);
+
+        assert!(pool.data.len() < fragmented_len);
+        for (i, list) in lists.iter().enumerate() {
+            if i % 2 == 0 {
+                assert!(list.is_empty());
+            } else {
+                let expected: Vec<Inst> = (0..i).map(Inst::new).collect();
+                assert_eq!(list.as_slice(pool), &expected[..]);
+            }
+        }
+        // Aliased lists still share storage.
+        assert_eq!(alias_copy, lists[5]);
+
+        // The compacted pool is still usable.
+        lists[1].push(Inst::new(42), pool);
+        assert_eq!(lists[1].as_slice(pool), &[Inst::new(0), Inst::new(42)]);
+        assert_eq!(lists[3].len(pool), 3);
+    }
+
+    #[test]
+    fn pool_truncate() {
+        let pool = &mut ListPool::<Inst>::new();
+        let mut old = EntityList::from_slice(&[Inst::new(1), Inst::new(2)], pool);
+        let mut freed = EntityList::from_slice(&[Inst::new(3)], pool);
+        freed.clear(pool);
+        let len = pool.len();
+
+        // `a` reuses the block freed above, `b` and `big` are allocated beyond `len`.
+        let mut a = EntityList::from_slice(&[Inst::new(4)], pool);
+        let mut b = EntityList::from_slice(&[Inst::new(5)], pool);
+        let big = EntityList::from_iter((0..10).map(Inst::new), pool);
+        assert_eq!(big.len(pool), 10);
+        a.clear(pool);
+        b.clear(pool);
+        assert!(pool.len() > len);
+
+        pool.truncate(len);
+        assert_eq!(pool.len(), len);
+        assert_eq!(old.as_slice(pool), &[Inst::new(1), Inst::new(2)]);
+
+        // The free list still has the block below `len`, but not the one beyond it.
+        let c = EntityList::from_slice(&[Inst::new(6)], pool);
+        assert_eq!(pool.len(), len);
+        let d = EntityList::from_slice(&[Inst::new(7)], pool);
+        assert!(pool.len() > len);
+        assert_eq!(c.as_slice(pool), &[Inst::new(6)]);
+        assert_eq!(d.as_slice(pool), &[Inst::new(7)]);
+
+        old.push(Inst::new(8), pool);
+        assert_eq!(old.len(pool), 3);
+    }
+
     #[test]
     fn empty_list() {
         let pool = &mut ListPool::<Inst>::new();
@@ -886,6 +1028,23 @@ mod tests {
         assert_eq!(list2.as_slice(pool), &[i1, i2, i3]);
     }
 
+    #[test]
+    fn clone_to() {
+        let src = &mut ListPool::<Inst>::new();
+        let dst = &mut ListPool::<Inst>::new();
+
+        let i1 = Inst::new(1);
+        let i2 = Inst::new(2);
+
+        // Occupy the start of `dst` so the copy can't share an index with the original.
+        let other = EntityList::from_slice(&[i2, i2, i2, i2, i2], dst);
+        let list1 = EntityList::from_slice(&[i1, i2], src);
+        let list2 = list1.clone_to(src, dst);
+        assert_eq!(list2.as_slice(dst), &[i1, i2]);
+        assert_eq!(other.as_slice(dst), &[i2; 5]);
+        assert!(EntityList::<Inst>::new().clone_to(src, dst).is_empty());
+    }
+
     #[test]
     fn truncate() {
         let pool = &mut ListPool::<Inst>::new();
@@ -112,6 +112,13 @@ where
         self.elems.clear()
     }
 
+    /// Remove every entry with a key at or beyond `len`, keeping the first `len` entries.
+    ///
+    /// This has no effect if the map has `len` or fewer entries.
+    pub fn truncate(&mut self, len: usize) {
+        self.elems.truncate(len)
+    }
+
     /// Get the key that will be assigned to the next pushed value.
     pub fn next_key(&self) -> K {
         K::new(self.elems.len())
@@ -360,6 +367,20 @@ mod tests {
         assert_eq!(v, [k0, k1]);
     }
 
+    #[test]
+    fn truncate() {
+        let mut m: PrimaryMap<E, usize> = PrimaryMap::new();
+        m.push(12);
+        m.push(33);
+        m.push(45);
+
+        m.truncate(4);
+        assert_eq!(m.len(), 3);
+        m.truncate(1);
+        assert_eq!(m.len(), 1);
+        assert_eq!(m.push(7), E(1));
+    }
+
     #[test]
     fn iter() {
         let mut m: PrimaryMap<E, usize> = PrimaryMap::new();
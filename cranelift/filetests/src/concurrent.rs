@@ -44,8 +44,9 @@ pub struct ConcurrentRunner {
 }
 
 impl ConcurrentRunner {
-    /// Create a new `ConcurrentRunner` with threads spun up.
-    pub fn new() -> Self {
+    /// Create a new `ConcurrentRunner` with threads spun up, running tests with the shared
+    /// `settings`.
+    pub fn new(settings: Arc<[String]>) -> Self {
         let (request_tx, request_rx) = channel();
         let request_mutex = Arc::new(Mutex::new(request_rx));
         let (reply_tx, reply_rx) = channel();
@@ -62,7 +63,14 @@ impl ConcurrentRunner {
             })
             .unwrap_or_else(|| num_cpus::get());
         let handles = (0..num_threads)
-            .map(|num| worker_thread(num, request_mutex.clone(), reply_tx.clone()))
+            .map(|num| {
+                worker_thread(
+                    num,
+                    request_mutex.clone(),
+                    reply_tx.clone(),
+                    settings.clone(),
+                )
+            })
             .collect();
 
         Self {
@@ -130,6 +138,7 @@ fn worker_thread(
     thread_num: usize,
     requests: Arc<Mutex<Receiver<Request>>>,
     replies: Sender<Reply>,
+    settings: Arc<[String]>,
 ) -> thread::JoinHandle<timing::PassTimes> {
     thread::Builder::new()
         .name(format!("worker #{thread_num}"))
@@ -146,7 +155,7 @@ fn worker_thread(
                 // The receiver should always be present for this as long as we have jobs.
                 replies.send(Reply::Starting { jobid }).unwrap();
 
-                let result = catch_unwind(|| runone::run(path.as_path(), None, None))
+                let result = catch_unwind(|| runone::run(path.as_path(), None, None, &settings))
                     .unwrap_or_else(|e| {
                         // The test panicked, leaving us a `Box<Any>`.
                         // Panics are usually strings.
@@ -6,7 +6,7 @@ use cranelift_codegen::ir::{
     ExternalName, Function, InstBuilder, Signature, UserExternalName, UserFuncName,
 };
 use cranelift_codegen::isa::{OwnedTargetIsa, TargetIsa};
-use cranelift_codegen::{ir, settings, CodegenError, Context};
+use cranelift_codegen::{ir, settings, CodegenError, Context, MachTrap};
 use cranelift_control::ControlPlane;
 use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext};
 use cranelift_jit::{JITBuilder, JITModule};
@@ -41,6 +41,9 @@ struct DefinedFunction {
 
     /// JIT [FuncId]
     func_id: FuncId,
+
+    /// The trap sites of the compiled function, relative to its start.
+    traps: Vec<MachTrap>,
 }
 
 /// Compile a test case.
@@ -200,6 +203,7 @@ impl TestFileCompiler {
                     new_name: UserExternalName::new(TESTFILE_NAMESPACE, next_id),
                     signature: func.signature.clone(),
                     func_id,
+                    traps: Vec::new(),
                 });
             }
         };
@@ -256,10 +260,11 @@ impl TestFileCompiler {
 
     /// Defines the body of a function
     pub fn define_function(&mut self, func: Function, ctrl_plane: &mut ControlPlane) -> Result<()> {
+        let name = func.name.clone();
         let defined_func = self
             .defined_functions
-            .get(&func.name)
-            .ok_or(anyhow!("Undeclared function {} found!", &func.name))?;
+            .get(&name)
+            .ok_or(anyhow!("Undeclared function {} found!", &name))?;
 
         self.ctx.func = self.apply_func_rename(func, defined_func)?;
         self.module.define_function_with_control_plane(
@@ -267,6 +272,11 @@ impl TestFileCompiler {
             &mut self.ctx,
             ctrl_plane,
         )?;
+
+        // Keep the trap sites so that traps can be mapped back to their codes.
+        let traps = self.ctx.compiled_code().unwrap().buffer.traps().to_vec();
+        self.defined_functions.get_mut(&name).unwrap().traps = traps;
+
         self.module.clear_context(&mut self.ctx);
         Ok(())
     }
@@ -305,10 +315,19 @@ impl TestFileCompiler {
         // available).
         self.module.finalize_definitions()?;
 
+        let mut trap_sites = HashMap::new();
+        for df in self.defined_functions.values() {
+            let start = self.module.get_finalized_function(df.func_id) as usize;
+            for trap in &df.traps {
+                trap_sites.insert(start + trap.offset as usize, trap.code);
+            }
+        }
+
         Ok(CompiledTestFile {
             module: Some(self.module),
             defined_functions: self.defined_functions,
             trampolines: self.trampolines,
+            trap_sites,
         })
     }
 }
@@ -326,6 +345,9 @@ pub struct CompiledTestFile {
     /// Trampolines available in this [JITModule].
     /// See [TestFileCompiler] for more info.
     trampolines: HashMap<Signature, UserFuncName>,
+
+    /// The trap codes of all trap sites in `module`, by address.
+    trap_sites: HashMap<usize, ir::TrapCode>,
 }
 
 impl CompiledTestFile {
@@ -344,6 +366,7 @@ impl CompiledTestFile {
             func_id: defined_func.func_id,
             func_signature: &defined_func.signature,
             trampoline_id,
+            trap_sites: &self.trap_sites,
         })
     }
 }
@@ -362,6 +385,7 @@ pub struct Trampoline<'a> {
     func_id: FuncId,
     func_signature: &'a Signature,
     trampoline_id: FuncId,
+    trap_sites: &'a HashMap<usize, ir::TrapCode>,
 }
 
 impl<'a> Trampoline<'a> {
@@ -380,40 +404,223 @@ impl<'a> Trampoline<'a> {
         values.collect_returns(&self.func_signature)
     }
 
+    /// Call the target function like [Trampoline::call], but return the code of the trap it
+    /// stops at, if any.
+    ///
+    /// Native code is run in a child process so that a trap only stops the child. This is only
+    /// supported on Linux hosts for now; Pulley bytecode can be run on any host.
+    pub fn call_catching_traps(
+        &self,
+        arguments: &[DataValue],
+    ) -> Result<Result<Vec<DataValue>, ir::TrapCode>> {
+        let mut values = UnboxedValues::make_arguments(arguments, &self.func_signature);
+        let arguments_address = values.as_mut_ptr();
+
+        let function_ptr = self.module.get_finalized_function(self.func_id);
+        let trampoline_ptr = self.module.get_finalized_function(self.trampoline_id);
+
+        let trap = if self.is_pulley() {
+            unsafe { self.call_raw(trampoline_ptr, function_ptr, arguments_address) }
+        } else {
+            let pc = unsafe {
+                subprocess::call(&mut values.0, || {
+                    self.call_raw(trampoline_ptr, function_ptr, arguments_address);
+                })?
+            };
+            pc.map(|pc| (pc, None))
+        };
+
+        match trap {
+            None => Ok(Ok(values.collect_returns(&self.func_signature))),
+            Some((_, Some(code))) => Ok(Err(code)),
+            Some((pc, None)) => match self.trap_sites.get(&pc) {
+                Some(code) => Ok(Err(*code)),
+                None => Err(anyhow!(
+                    "compiled code crashed at {pc:#x}, which isn't a trap site"
+                )),
+            },
+        }
+    }
+
+    fn is_pulley(&self) -> bool {
+        matches!(
+            self.module.isa().triple().architecture,
+            Architecture::Pulley32
+                | Architecture::Pulley64
+                | Architecture::Pulley32be
+                | Architecture::Pulley64be
+        )
+    }
+
+    /// Run the trampoline. If Pulley bytecode traps, returns the address of the trapping
+    /// instruction, along with the trap code for traps that Pulley raises by itself; native traps
+    /// are left to the signal handlers of the process.
     unsafe fn call_raw(
         &self,
         trampoline_ptr: *const u8,
         function_ptr: *const u8,
         arguments_address: *mut u128,
-    ) {
-        match self.module.isa().triple().architecture {
-            // For the pulley target this is pulley bytecode, not machine code,
-            // so run the interpreter.
-            Architecture::Pulley32
-            | Architecture::Pulley64
-            | Architecture::Pulley32be
-            | Architecture::Pulley64be => {
-                let mut state = pulley::Vm::new();
-                state.call(
-                    NonNull::new(trampoline_ptr.cast_mut()).unwrap(),
-                    &[
-                        pulley::XRegVal::new_ptr(function_ptr.cast_mut()).into(),
-                        pulley::XRegVal::new_ptr(arguments_address).into(),
-                    ],
-                    [],
-                );
+    ) -> Option<(usize, Option<ir::TrapCode>)> {
+        // For the pulley target this is pulley bytecode, not machine code,
+        // so run the interpreter.
+        if self.is_pulley() {
+            let mut state = pulley::Vm::new();
+            let done = state.call(
+                NonNull::new(trampoline_ptr.cast_mut()).unwrap(),
+                &[
+                    pulley::XRegVal::new_ptr(function_ptr.cast_mut()).into(),
+                    pulley::XRegVal::new_ptr(arguments_address).into(),
+                ],
+                [],
+            );
+            return match done {
+                pulley::DoneReason::Trap { pc, kind } => {
+                    let code = kind.map(|kind| match kind {
+                        pulley::TrapKind::IntegerOverflow => ir::TrapCode::INTEGER_OVERFLOW,
+                        pulley::TrapKind::DivideByZero => ir::TrapCode::INTEGER_DIVISION_BY_ZERO,
+                        pulley::TrapKind::BadConversionToInteger => {
+                            ir::TrapCode::BAD_CONVERSION_TO_INTEGER
+                        }
+                    });
+                    Some((pc.as_ptr() as usize, code))
+                }
+                _ => None,
+            };
+        }
+
+        // Other targets natively execute this machine code.
+        let callable_trampoline: fn(*const u8, *mut u128) -> () =
+            unsafe { mem::transmute(trampoline_ptr) };
+        callable_trampoline(function_ptr, arguments_address);
+        None
+    }
+}
+
+/// Running native code in a child process, to catch the traps it stops at.
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+mod subprocess {
+    use anyhow::{bail, Result};
+    use std::fs::File;
+    use std::io::{self, Read};
+    use std::os::fd::FromRawFd;
+    use std::sync::atomic::{AtomicI32, Ordering};
+    use std::{mem, ptr, slice};
+
+    /// The message tags the child sends before the returned slots or the trapping address.
+    const RETURNED: u8 = 0;
+    const TRAPPED: u8 = 1;
+
+    /// The write end of the pipe to the parent, in the child.
+    static PIPE: AtomicI32 = AtomicI32::new(-1);
+
+    /// Run `f` in a forked child process, and copy `slots` back from it once `f` returns.
+    ///
+    /// Returns the address of the faulting instruction if `f` crashes instead.
+    ///
+    /// # Safety
+    ///
+    /// The child may only call async-signal-safe functions, so `f` must not allocate or take
+    /// locks.
+    pub unsafe fn call(slots: &mut [u128], f: impl FnOnce()) -> Result<Option<usize>> {
+        let mut fds = [0; 2];
+        if libc::pipe(fds.as_mut_ptr()) != 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        let [read_fd, write_fd] = fds;
+
+        let pid = libc::fork();
+        if pid < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        if pid == 0 {
+            libc::close(read_fd);
+            PIPE.store(write_fd, Ordering::Relaxed);
+            let mut action: libc::sigaction = mem::zeroed();
+            action.sa_sigaction = on_signal as *const () as usize;
+            action.sa_flags = libc::SA_SIGINFO;
+            for signal in [libc::SIGILL, libc::SIGSEGV, libc::SIGBUS, libc::SIGFPE] {
+                libc::sigaction(signal, &action, ptr::null_mut());
             }
 
-            // Other targets natively execute this machine code.
-            _ => {
-                let callable_trampoline: fn(*const u8, *mut u128) -> () =
-                    unsafe { mem::transmute(trampoline_ptr) };
-                callable_trampoline(function_ptr, arguments_address);
+            f();
+
+            let bytes = slice::from_raw_parts(slots.as_ptr().cast::<u8>(), size_of_val(slots));
+            write_all(write_fd, &[RETURNED]);
+            write_all(write_fd, bytes);
+            libc::_exit(0);
+        }
+
+        libc::close(write_fd);
+        let mut pipe = File::from_raw_fd(read_fd);
+        let mut tag = [0];
+        let read = pipe.read(&mut tag);
+        let result = match (read, tag[0]) {
+            (Ok(0), _) => None,
+            (Ok(_), RETURNED) => {
+                let bytes =
+                    slice::from_raw_parts_mut(slots.as_mut_ptr().cast::<u8>(), size_of_val(slots));
+                pipe.read_exact(bytes)?;
+                Some(None)
+            }
+            (Ok(_), _) => {
+                let mut pc = [0; size_of::<usize>()];
+                pipe.read_exact(&mut pc)?;
+                Some(Some(usize::from_ne_bytes(pc)))
             }
+            (Err(e), _) => return Err(e.into()),
+        };
+
+        let mut status = 0;
+        if libc::waitpid(pid, &mut status, 0) != pid {
+            return Err(io::Error::last_os_error().into());
+        }
+        match result {
+            Some(result) => Ok(result),
+            None => bail!("child process running compiled code died (wait status {status:#x})"),
+        }
+    }
+
+    extern "C" fn on_signal(_: libc::c_int, _: *mut libc::siginfo_t, context: *mut libc::c_void) {
+        unsafe {
+            let context = &*context.cast::<libc::ucontext_t>();
+            #[cfg(target_arch = "x86_64")]
+            let pc = context.uc_mcontext.gregs[libc::REG_RIP as usize] as usize;
+            #[cfg(target_arch = "aarch64")]
+            let pc = context.uc_mcontext.pc as usize;
+
+            let write_fd = PIPE.load(Ordering::Relaxed);
+            write_all(write_fd, &[TRAPPED]);
+            write_all(write_fd, &pc.to_ne_bytes());
+            libc::_exit(0);
+        }
+    }
+
+    unsafe fn write_all(fd: libc::c_int, mut bytes: &[u8]) {
+        while !bytes.is_empty() {
+            let written = libc::write(fd, bytes.as_ptr().cast(), bytes.len());
+            if written <= 0 {
+                libc::_exit(1);
+            }
+            bytes = &bytes[written as usize..];
         }
     }
 }
 
+#[cfg(not(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+)))]
+mod subprocess {
+    use anyhow::{bail, Result};
+
+    pub unsafe fn call(_slots: &mut [u128], _f: impl FnOnce()) -> Result<Option<usize>> {
+        bail!("traps in native code can't be caught on this host")
+    }
+}
+
 /// Compilation Error when compiling a function.
 #[derive(Error, Debug)]
 pub enum CompilationError {
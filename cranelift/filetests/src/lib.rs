@@ -21,12 +21,16 @@ mod test_alias_analysis;
 mod test_cat;
 mod test_compile;
 mod test_domtree;
+mod test_if_conversion;
 mod test_interpret;
 mod test_legalizer;
 mod test_optimize;
 mod test_print_cfg;
 mod test_run;
+mod test_run_diff;
 mod test_safepoint;
+mod test_simplify_cfg;
+mod test_tail_duplication;
 mod test_unwind;
 mod test_verifier;
 
@@ -39,8 +43,16 @@ mod test_verifier;
 /// Directories are scanned recursively for test cases ending in `.clif`. These test cases are
 /// executed on background threads.
 ///
-pub fn run(verbose: bool, report_times: bool, files: &[String]) -> anyhow::Result<()> {
-    let mut runner = TestRunner::new(verbose, report_times);
+/// The `settings`, like `opt_level=speed`, are applied to every test file before its own `set`
+/// commands, so settings in a file take precedence.
+///
+pub fn run(
+    verbose: bool,
+    report_times: bool,
+    settings: &[String],
+    files: &[String],
+) -> anyhow::Result<()> {
+    let mut runner = TestRunner::new(verbose, report_times, settings);
 
     for path in files.iter().map(Path::new) {
         if path.is_file() {
@@ -66,7 +78,7 @@ pub fn run_passes(
     target: &str,
     file: &str,
 ) -> anyhow::Result<()> {
-    let mut runner = TestRunner::new(verbose, report_times);
+    let mut runner = TestRunner::new(verbose, report_times, &[]);
 
     let path = Path::new(file);
     if path == Path::new("-") || path.is_file() {
@@ -89,12 +101,16 @@ fn new_subtest(parsed: &TestCommand) -> anyhow::Result<Box<dyn subtest::SubTest>
         "cat" => test_cat::subtest(parsed),
         "compile" => test_compile::subtest(parsed),
         "domtree" => test_domtree::subtest(parsed),
+        "if-conversion" => test_if_conversion::subtest(parsed),
         "interpret" => test_interpret::subtest(parsed),
         "legalizer" => test_legalizer::subtest(parsed),
         "optimize" => test_optimize::subtest(parsed),
         "print-cfg" => test_print_cfg::subtest(parsed),
         "run" => test_run::subtest(parsed),
+        "run-diff" => test_run_diff::subtest(parsed),
         "safepoint" => test_safepoint::subtest(parsed),
+        "simplify-cfg" => test_simplify_cfg::subtest(parsed),
+        "tail-duplication" => test_tail_duplication::subtest(parsed),
         "unwind" => test_unwind::subtest(parsed),
         "verifier" => test_verifier::subtest(parsed),
         _ => anyhow::bail!("unknown test command '{}'", parsed.command),
@@ -9,6 +9,7 @@ use std::error::Error;
 use std::ffi::OsStr;
 use std::fmt::{self, Display};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time;
 
 /// Timeout in seconds when we're not making progress.
@@ -78,11 +79,16 @@ pub struct TestRunner {
     ticks_since_progress: usize,
 
     threads: Option<ConcurrentRunner>,
+
+    // Shared settings applied to every test file.
+    settings: Arc<[String]>,
 }
 
 impl TestRunner {
     /// Create a new blank TestRunner.
-    pub fn new(verbose: bool, report_times: bool) -> Self {
+    ///
+    /// The `settings` are applied to every test file before its own `set` commands.
+    pub fn new(verbose: bool, report_times: bool, settings: &[String]) -> Self {
         Self {
             verbose,
             report_times,
@@ -93,6 +99,7 @@ impl TestRunner {
             errors: 0,
             ticks_since_progress: 0,
             threads: None,
+            settings: settings.into(),
         }
     }
 
@@ -117,7 +124,7 @@ impl TestRunner {
     /// Begin running tests concurrently.
     pub fn start_threads(&mut self) {
         assert!(self.threads.is_none());
-        self.threads = Some(ConcurrentRunner::new());
+        self.threads = Some(ConcurrentRunner::new(self.settings.clone()));
     }
 
     /// Scan any directories pushed so far.
@@ -211,7 +218,7 @@ impl TestRunner {
             } else {
                 // Run test synchronously.
                 self.tests[jobid].state = State::Running;
-                let result = runone::run(self.tests[jobid].path(), None, None);
+                let result = runone::run(self.tests[jobid].path(), None, None, &self.settings);
                 self.finish_job(jobid, result);
             }
             self.new_tests = jobid + 1;
@@ -233,7 +240,12 @@ impl TestRunner {
             targ => Some(targ),
         };
 
-        result = runone::run(self.tests[0].path(), Some(passes), specified_target);
+        result = runone::run(
+            self.tests[0].path(),
+            Some(passes),
+            specified_target,
+            &self.settings,
+        );
         self.finish_job(0, result);
     }
 
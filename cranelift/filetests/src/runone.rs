@@ -16,13 +16,15 @@ use std::path::{Path, PathBuf};
 use std::str::Lines;
 use std::time;
 
-/// Load `path` and run the test in it.
+/// Load `path` and run the test in it, with the shared `settings` applied before the `set`
+/// commands in the file.
 ///
 /// If running this test causes a panic, it will propagate as normal.
 pub fn run(
     path: &Path,
     passes: Option<&[String]>,
     target: Option<&str>,
+    settings: &[String],
 ) -> anyhow::Result<time::Duration> {
     let _tt = timing::process_file();
     info!("---\nFile: {}", path.to_string_lossy());
@@ -34,6 +36,7 @@ pub fn run(
         target,
         passes,
         machine_code_cfg_info: true,
+        settings,
         ..ParseOptions::default()
     };
 
@@ -94,7 +97,11 @@ pub fn run(
             verify_testfile(&testfile, fisa)?;
         }
 
-        test.run_target(&testfile, &mut file_update, file_path.as_ref(), flags, *isa)?;
+        test.run_target(&testfile, &mut file_update, file_path.as_ref(), flags, *isa)
+            .with_context(|| match isa {
+                Some(isa) => format!("test {} failed for target {}", test.name(), isa.triple()),
+                None => format!("test {} failed", test.name()),
+            })?;
     }
 
     Ok(started.elapsed())
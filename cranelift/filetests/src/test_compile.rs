@@ -92,13 +92,27 @@ impl SubTest for TestCompile {
                 }
             };
 
-            let actual = Vec::from_iter(
+            let mut actual = Vec::from_iter(
                 std::iter::once("VCode:")
                     .chain(compiled_code.vcode.as_ref().unwrap().lines())
                     .chain(["", "Disassembled:"])
                     .chain(dis.lines()),
             );
 
+            // Only functions with `try_call`s have an exception table, so leave the section out
+            // of every other test's expectation.
+            let handlers = compiled_code.buffer.exception_handlers();
+            let handlers = Vec::from_iter(handlers.iter().map(|handler| {
+                format!(
+                    "  call {:#x}..{:#x}, landing pad {:#x}",
+                    handler.start, handler.ret_addr, handler.landing_pad
+                )
+            }));
+            if !handlers.is_empty() {
+                actual.extend(["", "Exception table:"]);
+                actual.extend(handlers.iter().map(|line| line.as_str()));
+            }
+
             check_precise_output(&actual, context)
         } else {
             run_filecheck(&vcode, context)
@@ -0,0 +1,42 @@
+//! Test command for testing the if-conversion pass.
+//!
+//! The `test if-conversion` test command runs each function through if-conversion, which
+//! replaces small diamonds in the CFG by `select`s.
+//!
+//! The resulting function is sent to `filecheck`.
+
+use crate::subtest::{run_filecheck, Context, SubTest};
+use cranelift_codegen::ir::Function;
+use cranelift_reader::TestCommand;
+use std::borrow::Cow;
+
+struct TestIfConversion;
+
+pub fn subtest(parsed: &TestCommand) -> anyhow::Result<Box<dyn SubTest>> {
+    assert_eq!(parsed.command, "if-conversion");
+    if !parsed.options.is_empty() {
+        anyhow::bail!("No options allowed on {}", parsed);
+    }
+    Ok(Box::new(TestIfConversion))
+}
+
+impl SubTest for TestIfConversion {
+    fn name(&self) -> &'static str {
+        "if-conversion"
+    }
+
+    fn is_mutating(&self) -> bool {
+        true
+    }
+
+    fn run(&self, func: Cow<Function>, context: &Context) -> anyhow::Result<()> {
+        let mut comp_ctx = cranelift_codegen::Context::for_function(func.into_owned());
+
+        comp_ctx
+            .if_convert(context.flags_or_isa())
+            .map_err(|e| crate::pretty_anyhow_error(&comp_ctx.func, e))?;
+
+        let text = comp_ctx.func.display().to_string();
+        run_filecheck(&text, context)
+    }
+}
@@ -2,6 +2,14 @@
 //!
 //! The `interpret` test command interprets each function on the host machine
 //! using [RunCommand](cranelift_reader::RunCommand)s.
+//!
+//! With `enable_nan_canonicalization`, the functions are interpreted after NaN
+//! canonicalization for the file's single target, so the interpreter checks the
+//! inserted sequences.
+//!
+//! The `fold-divs` option interprets the functions after replacing divisions by
+//! constants with multiplications and shifts, so the same run commands check the
+//! folded sequences. It fails if nothing in the file was folded.
 
 use crate::runone::FileUpdate;
 use crate::subtest::SubTest;
@@ -12,21 +20,29 @@ use cranelift_codegen::ir::{Function, LibCall};
 use cranelift_codegen::isa::TargetIsa;
 use cranelift_codegen::settings::Flags;
 use cranelift_interpreter::environment::FunctionStore;
-use cranelift_interpreter::interpreter::{Interpreter, InterpreterState, LibCallValues};
-use cranelift_interpreter::step::ControlFlow;
-use cranelift_reader::{parse_run_command, Details, TestCommand, TestFile};
+use cranelift_interpreter::interpreter::{
+    Interpreter, InterpreterError, InterpreterState, LibCallValues,
+};
+use cranelift_interpreter::step::{ControlFlow, CraneliftTrap};
+use cranelift_reader::{parse_run_command, Details, TestCommand, TestFile, TestOption};
 use log::{info, trace};
 use smallvec::smallvec;
 use std::borrow::Cow;
 
-struct TestInterpret;
+struct TestInterpret {
+    fold_divs: bool,
+}
 
 pub fn subtest(parsed: &TestCommand) -> anyhow::Result<Box<dyn SubTest>> {
     assert_eq!(parsed.command, "interpret");
-    if !parsed.options.is_empty() {
-        anyhow::bail!("No options allowed on {}", parsed);
+    let mut test = TestInterpret { fold_divs: false };
+    for option in &parsed.options {
+        match option {
+            TestOption::Flag("fold-divs") => test.fold_divs = true,
+            _ => anyhow::bail!("unknown option on {}", parsed),
+        }
     }
-    Ok(Box::new(TestInterpret))
+    Ok(Box::new(test))
 }
 
 impl SubTest for TestInterpret {
@@ -49,16 +65,44 @@ impl SubTest for TestInterpret {
         testfile: &TestFile,
         _: &mut FileUpdate,
         _: &'a str,
-        _: &'a Flags,
-        _: Option<&'a dyn TargetIsa>,
+        flags: &'a Flags,
+        isa: Option<&'a dyn TargetIsa>,
     ) -> anyhow::Result<()> {
+        let mut functions = Vec::with_capacity(testfile.functions.len());
+        let mut folded = false;
+        for (func, details) in &testfile.functions {
+            let mut func = match isa {
+                Some(isa) if flags.enable_nan_canonicalization() => {
+                    let mut ctx = cranelift_codegen::Context::for_function(func.clone());
+                    ctx.canonicalize_nans(isa)
+                        .map_err(|e| crate::pretty_anyhow_error(&ctx.func, e))?;
+                    Cow::Owned(ctx.func)
+                }
+                None if flags.enable_nan_canonicalization() => {
+                    anyhow::bail!("NaN canonicalization needs exactly one target")
+                }
+                _ => Cow::Borrowed(func),
+            };
+            if self.fold_divs {
+                let mut ctx = cranelift_codegen::Context::for_function(func.into_owned());
+                folded |= ctx
+                    .fold_divs_by_constants(flags)
+                    .map_err(|e| crate::pretty_anyhow_error(&ctx.func, e))?;
+                func = Cow::Owned(ctx.func);
+            }
+            functions.push((func, details));
+        }
+        if self.fold_divs && !folded {
+            anyhow::bail!("fold-divs didn't fold any division");
+        }
+
         // We can build the FunctionStore once and reuse it
         let mut func_store = FunctionStore::default();
-        for (func, _) in &testfile.functions {
-            func_store.add(func.name.to_string(), &func);
+        for (func, _) in &functions {
+            func_store.add(func.name.to_string(), func);
         }
 
-        for (func, details) in &testfile.functions {
+        for (func, details) in &functions {
             info!("Test: {}({}) interpreter", self.name(), func.name);
 
             run_test(&func_store, func, details).context(self.name())?;
@@ -83,34 +127,12 @@ fn run_test(func_store: &FunctionStore, func: &Function, details: &Details) -> a
 
             command
                 .run(|func_name, run_args| {
-                    // Rebuild the interpreter state on every run to ensure that we don't accidentally depend on
-                    // some leftover state
-                    let state = InterpreterState::default()
-                        .with_function_store(func_store.clone())
-                        .with_libcall_handler(|libcall: LibCall, args: LibCallValues| {
-                            use LibCall::*;
-                            Ok(smallvec![match (libcall, &args[..]) {
-                                (CeilF32, [DataValue::F32(a)]) => DataValue::F32(a.ceil()),
-                                (CeilF64, [DataValue::F64(a)]) => DataValue::F64(a.ceil()),
-                                (FloorF32, [DataValue::F32(a)]) => DataValue::F32(a.floor()),
-                                (FloorF64, [DataValue::F64(a)]) => DataValue::F64(a.floor()),
-                                (TruncF32, [DataValue::F32(a)]) => DataValue::F32(a.trunc()),
-                                (TruncF64, [DataValue::F64(a)]) => DataValue::F64(a.trunc()),
-                                _ => unreachable!(),
-                            }])
-                        });
-
-                    let mut args = Vec::with_capacity(run_args.len());
-                    args.extend_from_slice(run_args);
-
                     // Because we have stored function names with a leading %, we need to re-add it.
                     let func_name = &format!("%{func_name}");
-                    match Interpreter::new(state).call_by_name(func_name, &args) {
-                        Ok(ControlFlow::Return(results)) => Ok(results.to_vec()),
-                        Ok(e) => {
-                            panic!("Unexpected returned control flow: {e:?}")
-                        }
-                        Err(t) => Err(format!("unexpected trap: {t:?}")),
+                    match interpret(func_store, func_name, run_args) {
+                        Ok(Ok(results)) => Ok(results),
+                        Ok(Err(trap)) => Err(format!("unexpected trap: {trap}")),
+                        Err(e) => Err(format!("unexpected trap: {e:?}")),
                     }
                 })
                 .map_err(|e| anyhow::anyhow!("{}", e))?;
@@ -118,3 +140,35 @@ fn run_test(func_store: &FunctionStore, func: &Function, details: &Details) -> a
     }
     Ok(())
 }
+
+/// Interpret a call of the function named `func_name` in `func_store`.
+///
+/// Returns the results of the call, or the trap it stopped with.
+pub(crate) fn interpret(
+    func_store: &FunctionStore,
+    func_name: &str,
+    args: &[DataValue],
+) -> Result<Result<Vec<DataValue>, CraneliftTrap>, InterpreterError> {
+    // Rebuild the interpreter state on every run to ensure that we don't accidentally depend on
+    // some leftover state
+    let state = InterpreterState::default()
+        .with_function_store(func_store.clone())
+        .with_libcall_handler(|libcall: LibCall, args: LibCallValues| {
+            use LibCall::*;
+            Ok(smallvec![match (libcall, &args[..]) {
+                (CeilF32, [DataValue::F32(a)]) => DataValue::F32(a.ceil()),
+                (CeilF64, [DataValue::F64(a)]) => DataValue::F64(a.ceil()),
+                (FloorF32, [DataValue::F32(a)]) => DataValue::F32(a.floor()),
+                (FloorF64, [DataValue::F64(a)]) => DataValue::F64(a.floor()),
+                (TruncF32, [DataValue::F32(a)]) => DataValue::F32(a.trunc()),
+                (TruncF64, [DataValue::F64(a)]) => DataValue::F64(a.trunc()),
+                _ => unreachable!(),
+            }])
+        });
+
+    match Interpreter::new(state).call_by_name(func_name, args)? {
+        ControlFlow::Return(results) => Ok(Ok(results.to_vec())),
+        ControlFlow::Trap(trap) => Ok(Err(trap)),
+        e => panic!("Unexpected returned control flow: {e:?}"),
+    }
+}
@@ -30,7 +30,7 @@ pub fn subtest(parsed: &TestCommand) -> anyhow::Result<Box<dyn SubTest>> {
 /// Builds a [TargetIsa] for the current host.
 ///
 /// ISA Flags can be overridden by passing [Value]'s via `isa_flags`.
-fn build_host_isa(
+pub(crate) fn build_host_isa(
     infer_native_flags: bool,
     flags: settings::Flags,
     isa_flags: Vec<settings::Value>,
@@ -47,7 +47,7 @@ fn build_host_isa(
 }
 
 /// Checks if the host's ISA is compatible with the one requested by the test.
-fn is_isa_compatible(
+pub(crate) fn is_isa_compatible(
     file_path: &str,
     host: &dyn TargetIsa,
     requested: &dyn TargetIsa,
@@ -130,7 +130,7 @@ fn is_isa_compatible(
     Ok(())
 }
 
-fn compile_testfile(
+pub(crate) fn compile_testfile(
     testfile: &TestFile,
     flags: &Flags,
     isa: &dyn TargetIsa,
@@ -0,0 +1,284 @@
+//! Test command for comparing interpreted and compiled execution of CLIF files
+//!
+//! The `run-diff` test command runs each function both in the interpreter and natively on the
+//! host machine, and checks that both produce the same results, or trap with the same trap code.
+//! Functions are called with the arguments of their `run` and `print` commands, and the results
+//! of `run` commands are checked as well. Functions without any such commands are called with
+//! generated arguments, as long as they only take and return integers and can't trap, loop, or
+//! access memory.
+//!
+//! Invocations that trap in the interpreter are run natively in a child process, which is only
+//! supported on Linux hosts.
+//!
+//! The `canonicalize-nans` option makes any two NaNs of the same type compare equal.
+
+use crate::runone::FileUpdate;
+use crate::subtest::{Context, SubTest};
+use crate::test_interpret::interpret;
+use crate::test_run::{build_host_isa, compile_testfile, is_isa_compatible};
+use cranelift_codegen::data_value::{DataValue, DisplayDataValues};
+use cranelift_codegen::ir::{self, Function};
+use cranelift_codegen::isa::TargetIsa;
+use cranelift_codegen::settings::Flags;
+use cranelift_interpreter::environment::FunctionStore;
+use cranelift_interpreter::step::CraneliftTrap;
+use cranelift_reader::{parse_run_command, Details, RunCommand, TestCommand, TestFile, TestOption};
+use log::{info, trace};
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// The number of pseudo-random argument vectors generated for each function, in addition to the
+/// edge values.
+const RANDOM_INVOCATIONS: usize = 8;
+
+struct TestRunDiff {
+    canonicalize_nans: bool,
+}
+
+pub fn subtest(parsed: &TestCommand) -> anyhow::Result<Box<dyn SubTest>> {
+    assert_eq!(parsed.command, "run-diff");
+    let mut test = TestRunDiff {
+        canonicalize_nans: false,
+    };
+    for option in &parsed.options {
+        match option {
+            TestOption::Flag("canonicalize-nans") => test.canonicalize_nans = true,
+            _ => anyhow::bail!("unknown option on {}", parsed),
+        }
+    }
+    Ok(Box::new(test))
+}
+
+impl SubTest for TestRunDiff {
+    fn name(&self) -> &'static str {
+        "run-diff"
+    }
+
+    fn is_mutating(&self) -> bool {
+        false
+    }
+
+    fn needs_isa(&self) -> bool {
+        true
+    }
+
+    /// Runs the entire subtest for a given target, invokes [Self::run] for running
+    /// individual tests.
+    fn run_target<'a>(
+        &self,
+        testfile: &TestFile,
+        _: &mut FileUpdate,
+        file_path: &'a str,
+        flags: &'a Flags,
+        isa: Option<&'a dyn TargetIsa>,
+    ) -> anyhow::Result<()> {
+        // Disable runtests with pinned reg enabled.
+        // We've had some abi issues that the trampoline isn't quite ready for.
+        if flags.enable_pinned_reg() {
+            return Err(anyhow::anyhow!([
+                "Cannot run runtests with pinned_reg enabled.",
+                "See https://github.com/bytecodealliance/wasmtime/issues/4376 for more info"
+            ]
+            .join("\n")));
+        }
+
+        // Check that the host machine can run this test case (i.e. has all extensions)
+        let host_isa = build_host_isa(true, flags.clone(), vec![]);
+        if let Err(e) = is_isa_compatible(file_path, host_isa.as_ref(), isa.unwrap()) {
+            log::info!("{}", e);
+            return Ok(());
+        }
+
+        // Every function is compiled once, and the compiled code is reused for all of its
+        // invocations.
+        let compiled_testfile = compile_testfile(&testfile, flags, isa.unwrap())?;
+
+        let mut func_store = FunctionStore::default();
+        for (func, _) in &testfile.functions {
+            func_store.add(func.name.to_string(), &func);
+        }
+
+        for (func, details) in &testfile.functions {
+            info!(
+                "Test: {}({}) {}",
+                self.name(),
+                func.name,
+                isa.map_or("-", TargetIsa::name)
+            );
+
+            let trampoline = compiled_testfile.get_trampoline(func).unwrap();
+            let mut invocations = run_commands(func, details)?;
+            if invocations.is_empty() {
+                invocations = generate_args(func)
+                    .into_iter()
+                    .map(|args| (args, None))
+                    .collect();
+            }
+
+            for (args, command) in invocations {
+                let call = format!("{}({})", func.name, DisplayDataValues(&args));
+                trace!("Invocation: {call}");
+                let expected = match interpret(&func_store, &func.name.to_string(), &args) {
+                    Ok(expected) => expected,
+                    Err(e) => anyhow::bail!("{call} failed in the interpreter: {e}"),
+                };
+
+                match (expected, command) {
+                    (Ok(expected), command) => {
+                        if let Some(command) = command {
+                            command
+                                .run(|_, _| Ok(expected.clone()))
+                                .map_err(|e| anyhow::anyhow!("{e}"))?;
+                        }
+
+                        let actual = trampoline.call(&args);
+                        if !self.results_eq(&expected, &actual) {
+                            anyhow::bail!(
+                                "{call}: interpreter returned [{}], but compiled code returned [{}]",
+                                DisplayDataValues(&expected),
+                                DisplayDataValues(&actual)
+                            );
+                        }
+                    }
+                    (Err(trap), Some(command)) => {
+                        anyhow::bail!("{command}: traps in the interpreter: {trap}")
+                    }
+                    (Err(trap), None) => {
+                        let CraneliftTrap::User(expected) = trap else {
+                            anyhow::bail!("{call} traps in the interpreter without a code: {trap}")
+                        };
+                        // A native trap would stop the test runner, so the compiled code runs in
+                        // a child process.
+                        match trampoline.call_catching_traps(&args)? {
+                            Err(actual) if actual == expected => {}
+                            Err(actual) => anyhow::bail!(
+                                "{call}: interpreter trapped with {expected}, but compiled code \
+                                 trapped with {actual}"
+                            ),
+                            Ok(actual) => anyhow::bail!(
+                                "{call}: interpreter trapped with {expected}, but compiled code \
+                                 returned [{}]",
+                                DisplayDataValues(&actual)
+                            ),
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn run(&self, _func: Cow<ir::Function>, _context: &Context) -> anyhow::Result<()> {
+        unreachable!()
+    }
+}
+
+impl TestRunDiff {
+    fn results_eq(&self, expected: &[DataValue], actual: &[DataValue]) -> bool {
+        expected.len() == actual.len()
+            && expected.iter().zip(actual).all(|(a, b)| match (a, b) {
+                (DataValue::F32(a), DataValue::F32(b)) if self.canonicalize_nans => {
+                    (a.is_nan() && b.is_nan()) || a.bits() == b.bits()
+                }
+                (DataValue::F64(a), DataValue::F64(b)) if self.canonicalize_nans => {
+                    (a.is_nan() && b.is_nan()) || a.bits() == b.bits()
+                }
+                (a, b) => a.bitwise_eq(b),
+            })
+    }
+}
+
+/// Collect the arguments of the `run` and `print` commands on `func`, along with the `run`
+/// commands whose results should be checked.
+fn run_commands(
+    func: &Function,
+    details: &Details,
+) -> anyhow::Result<Vec<(Vec<DataValue>, Option<RunCommand>)>> {
+    let mut invocations = Vec::new();
+    for comment in details.comments.iter() {
+        match parse_run_command(comment.text, &func.signature)? {
+            Some(RunCommand::Print(invoke)) => invocations.push((invoke.args, None)),
+            Some(RunCommand::Run(invoke, comparison, expected)) => {
+                let args = invoke.args.clone();
+                invocations.push((args, Some(RunCommand::Run(invoke, comparison, expected))));
+            }
+            None => {}
+        }
+    }
+    Ok(invocations)
+}
+
+/// Generate arguments for `func`, if it's safe to call it with arbitrary ones.
+fn generate_args(func: &Function) -> Vec<Vec<DataValue>> {
+    let sig = &func.signature;
+    let all_ints = sig
+        .params
+        .iter()
+        .chain(&sig.returns)
+        .all(|param| param.value_type.is_int());
+    if !all_ints || !is_straight_line_pure(func) {
+        return Vec::new();
+    }
+
+    let param_args = |value: &dyn Fn(usize) -> i128| -> Vec<DataValue> {
+        sig.params
+            .iter()
+            .enumerate()
+            .map(|(i, param)| DataValue::from_integer(value(i), param.value_type).unwrap())
+            .collect()
+    };
+
+    // Pass the same edge value to all parameters. `from_integer` truncates to the parameter
+    // type, so the minimum and maximum are shifted down to each parameter's width.
+    let bits = |i: usize| 128 - sig.params[i].value_type.bits();
+    let mut invocations = vec![
+        param_args(&|_| 0),
+        param_args(&|_| 1),
+        param_args(&|_| -1),
+        param_args(&|i| i128::MIN >> bits(i)),
+        param_args(&|i| i128::MAX >> bits(i)),
+    ];
+
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    for _ in 0..RANDOM_INVOCATIONS {
+        let values: Vec<i128> = (0..sig.params.len())
+            .map(|_| {
+                (u128::from(xorshift(&mut state)) << 64 | u128::from(xorshift(&mut state))) as i128
+            })
+            .collect();
+        invocations.push(param_args(&|i| values[i]));
+    }
+    invocations
+}
+
+/// Check that `func` can't trap, access memory, call other functions, or loop.
+fn is_straight_line_pure(func: &Function) -> bool {
+    let order: HashMap<_, _> = func
+        .layout
+        .blocks()
+        .enumerate()
+        .map(|(i, block)| (block, i))
+        .collect();
+    func.layout.blocks().all(|block| {
+        func.layout.block_insts(block).all(|inst| {
+            let data = &func.dfg.insts[inst];
+            let opcode = data.opcode();
+            !opcode.can_trap()
+                && !opcode.can_load()
+                && !opcode.can_store()
+                && !opcode.is_call()
+                && data
+                    .branch_destination(&func.dfg.jump_tables)
+                    .iter()
+                    .all(|dest| order[&dest.block(&func.dfg.value_lists)] > order[&block])
+        })
+    })
+}
+
+fn xorshift(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
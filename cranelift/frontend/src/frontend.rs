@@ -3,6 +3,7 @@ use crate::ssa::{SSABuilder, SideEffects};
 use crate::variable::Variable;
 use alloc::vec::Vec;
 use core::fmt::{self, Debug};
+use core::panic::Location;
 use cranelift_codegen::cursor::{Cursor, CursorPosition, FuncCursor};
 use cranelift_codegen::entity::{EntityRef, EntitySet, SecondaryMap};
 use cranelift_codegen::ir;
@@ -16,7 +17,9 @@ use cranelift_codegen::ir::{
 };
 use cranelift_codegen::isa::TargetFrontendConfig;
 use cranelift_codegen::packed_option::PackedOption;
+use cranelift_codegen::settings;
 use cranelift_codegen::traversals::Dfs;
+use cranelift_codegen::{CodegenError, CodegenResult};
 use smallvec::SmallVec;
 
 mod safepoints;
@@ -34,6 +37,9 @@ pub struct FunctionBuilderContext {
     stack_map_vars: EntitySet<Variable>,
     stack_map_values: EntitySet<Value>,
     safepoints: safepoints::SafepointSpiller,
+    /// The location of the `use_var` call that created each value, for diagnostics.
+    #[cfg(debug_assertions)]
+    use_var_locations: SecondaryMap<Value, Option<&'static Location<'static>>>,
 }
 
 /// Temporary object used to build a single Cranelift IR [`Function`].
@@ -75,6 +81,8 @@ impl FunctionBuilderContext {
             stack_map_vars,
             stack_map_values,
             safepoints,
+            #[cfg(debug_assertions)]
+            use_var_locations,
         } = self;
         ssa.clear();
         status.clear();
@@ -82,6 +90,8 @@ impl FunctionBuilderContext {
         stack_map_values.clear();
         stack_map_vars.clear();
         safepoints.clear();
+        #[cfg(debug_assertions)]
+        use_var_locations.clear();
     }
 
     fn is_empty(&self) -> bool {
@@ -148,7 +158,9 @@ impl<'short, 'long> InstBuilderBase<'short> for FuncInstBuilder<'short, 'long> {
                 }
             }
 
-            ir::InstructionData::BranchTable { table, .. } => {
+            ir::InstructionData::BranchTable { table, .. }
+            | ir::InstructionData::TryCall { table, .. }
+            | ir::InstructionData::TryCallIndirect { table, .. } => {
                 let pool = &self.builder.func.dfg.value_lists;
 
                 // Unlike all other jumps/branches, jump tables are
@@ -182,6 +194,9 @@ impl<'short, 'long> InstBuilderBase<'short> for FuncInstBuilder<'short, 'long> {
             inst => debug_assert!(!inst.opcode().is_branch()),
         }
 
+        #[cfg(debug_assertions)]
+        self.builder.check_branch_args(inst);
+
         if data.opcode().is_terminator() {
             self.builder.fill_current_block()
         }
@@ -192,7 +207,12 @@ impl<'short, 'long> InstBuilderBase<'short> for FuncInstBuilder<'short, 'long> {
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 /// An error encountered when calling [`FunctionBuilder::try_use_var`].
 pub enum UseVariableError {
+    /// The variable was used (in a call to [`FunctionBuilder::use_var`]) before
+    /// it was declared (in a call to [`FunctionBuilder::declare_var`]).
     UsedBeforeDeclared(Variable),
+    /// Looking up the definition of the variable needed a new parameter on a
+    /// block that already has the maximum number of parameters.
+    ImplLimitExceeded(Variable),
 }
 
 impl fmt::Display for UseVariableError {
@@ -205,6 +225,13 @@ impl fmt::Display for UseVariableError {
                     variable.index()
                 )?;
             }
+            UseVariableError::ImplLimitExceeded(variable) => {
+                write!(
+                    f,
+                    "variable {} needs more block parameters than the implementation limit",
+                    variable.index()
+                )?;
+            }
         }
         Ok(())
     }
@@ -215,6 +242,7 @@ impl std::error::Error for UseVariableError {}
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 /// An error encountered when calling [`FunctionBuilder::try_declare_var`].
 pub enum DeclareVariableError {
+    /// The variable was already declared.
     DeclaredMultipleTimes(Variable),
 }
 
@@ -274,6 +302,132 @@ impl fmt::Display for DefVariableError {
     }
 }
 
+/// Any of the errors returned by the fallible methods of [`FunctionBuilder`], so that language
+/// frontends can propagate them with `?` and report them to their users.
+#[derive(Debug)]
+pub enum FrontendError {
+    /// An error returned by [`FunctionBuilder::try_declare_var`].
+    Declare(DeclareVariableError),
+    /// An error returned by [`FunctionBuilder::try_use_var`].
+    Use(UseVariableError),
+    /// An error returned by [`FunctionBuilder::try_def_var`].
+    Def(DefVariableError),
+    /// An error returned by [`FunctionBuilder::try_finalize`].
+    Finalize(FinalizeError),
+    /// The function exceeds an implementation limit; see
+    /// [`FunctionBuilder::check_impl_limits`], [`FunctionBuilder::try_append_block_param`] and
+    /// [`FunctionBuilder::try_seal_block`]. The error from `cranelift-codegen` is kept.
+    ImplLimitExceeded(CodegenError),
+}
+
+impl fmt::Display for FrontendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrontendError::Declare(error) => write!(f, "{error}"),
+            FrontendError::Use(error) => write!(f, "{error}"),
+            FrontendError::Def(error) => write!(f, "{error}"),
+            FrontendError::Finalize(error) => write!(f, "{error}"),
+            FrontendError::ImplLimitExceeded(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for FrontendError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FrontendError::ImplLimitExceeded(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<DeclareVariableError> for FrontendError {
+    fn from(error: DeclareVariableError) -> Self {
+        FrontendError::Declare(error)
+    }
+}
+
+impl From<UseVariableError> for FrontendError {
+    fn from(error: UseVariableError) -> Self {
+        FrontendError::Use(error)
+    }
+}
+
+impl From<DefVariableError> for FrontendError {
+    fn from(error: DefVariableError) -> Self {
+        FrontendError::Def(error)
+    }
+}
+
+impl From<FinalizeError> for FrontendError {
+    fn from(error: FinalizeError) -> Self {
+        FrontendError::Finalize(error)
+    }
+}
+
+/// An error encountered when calling [`FunctionBuilder::try_finalize`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FinalizeError {
+    /// The blocks that were never sealed.
+    pub unsealed_blocks: Vec<Block>,
+    /// The block parameters added by [`FunctionBuilder::use_var`] to the unsealed blocks, which
+    /// never got arguments from their predecessors.
+    pub placeholders: Vec<Placeholder>,
+    /// The variables that were used without being defined, and the blocks where they were
+    /// initialized to zero instead.
+    pub undefined_variables: Vec<(Variable, Block)>,
+}
+
+/// A block parameter standing in for the value of a variable in a block that was never sealed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placeholder {
+    /// The block the parameter belongs to.
+    pub block: Block,
+    /// The variable whose value the parameter stands in for.
+    pub var: Variable,
+    /// The block parameter.
+    pub param: Value,
+    /// The location of the [`FunctionBuilder::use_var`] call that created the parameter. This
+    /// is only recorded when debug assertions are enabled.
+    pub location: Option<&'static Location<'static>>,
+}
+
+impl FinalizeError {
+    fn is_empty(&self) -> bool {
+        self.unsealed_blocks.is_empty() && self.undefined_variables.is_empty()
+    }
+}
+
+impl fmt::Display for FinalizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FunctionBuilder finalized, but:")?;
+        for &block in &self.unsealed_blocks {
+            write!(f, "\n  block {block} is not sealed")?;
+            for placeholder in self.placeholders.iter().filter(|p| p.block == block) {
+                write!(
+                    f,
+                    "\n    {} is a placeholder for variable {}",
+                    placeholder.param,
+                    placeholder.var.index()
+                )?;
+                if let Some(location) = placeholder.location {
+                    write!(f, ", created by `use_var` at {location}")?;
+                }
+            }
+        }
+        for &(var, block) in &self.undefined_variables {
+            write!(
+                f,
+                "\n  variable {} is used before it is defined, and set to zero in {block}",
+                var.index()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for FinalizeError {}
+
 /// This module allows you to create a function in Cranelift IR in a straightforward way, hiding
 /// all the complexity of its internal representation.
 ///
@@ -381,8 +535,21 @@ impl<'a> FunctionBuilder<'a> {
     /// created. Forgetting to call this method on every block will cause inconsistencies in the
     /// produced functions.
     pub fn seal_block(&mut self, block: Block) {
-        let side_effects = self.func_ctx.ssa.seal_block(block, self.func);
-        self.handle_ssa_side_effects(side_effects);
+        if let Err(error) = self.try_seal_block(block) {
+            panic!("{error}");
+        }
+    }
+
+    /// Like [`seal_block`](Self::seal_block), but returns an error instead of panicking if the
+    /// variables used in `block` need more block parameters than the implementation limit.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FrontendError::ImplLimitExceeded`]. The function can't be completed after that,
+    /// so the [`FunctionBuilderContext`] is reset to be reused for the next function.
+    pub fn try_seal_block(&mut self, block: Block) -> Result<(), FrontendError> {
+        let result = self.func_ctx.ssa.seal_block(block, self.func);
+        self.handle_ssa_result(result)
     }
 
     /// Effectively calls [seal_block](Self::seal_block) on all unsealed blocks in the function.
@@ -392,8 +559,29 @@ impl<'a> FunctionBuilder<'a> {
     /// function can be used at the end of translating all blocks to ensure
     /// that everything is sealed.
     pub fn seal_all_blocks(&mut self) {
-        let side_effects = self.func_ctx.ssa.seal_all_blocks(self.func);
-        self.handle_ssa_side_effects(side_effects);
+        if let Err(error) = self.try_seal_all_blocks() {
+            panic!("{error}");
+        }
+    }
+
+    /// Like [`seal_all_blocks`](Self::seal_all_blocks), but returns an error instead of
+    /// panicking, like [`try_seal_block`](Self::try_seal_block).
+    pub fn try_seal_all_blocks(&mut self) -> Result<(), FrontendError> {
+        let result = self.func_ctx.ssa.seal_all_blocks(self.func);
+        self.handle_ssa_result(result)
+    }
+
+    /// Make room for the variables numbered below `count`.
+    ///
+    /// Variables are stored in tables indexed by their number, which grow as new variables are
+    /// declared. Frontends that know how many variables a function has, such as the number of
+    /// locals of a wasm function, can call this first to size the tables once. Declaring the
+    /// variables is still required.
+    pub fn reserve_vars(&mut self, count: usize) {
+        if self.func_ctx.types.values().len() < count {
+            self.func_ctx.types.resize(count);
+        }
+        self.func_ctx.ssa.reserve_vars(count);
     }
 
     /// Declares the type of a variable.
@@ -447,6 +635,11 @@ impl<'a> FunctionBuilder<'a> {
 
     /// Returns the Cranelift IR necessary to use a previously defined user
     /// variable, returning an error if this is not possible.
+    ///
+    /// If the error is [`UseVariableError::ImplLimitExceeded`], the function can't be
+    /// completed, and the [`FunctionBuilderContext`] is reset to be reused for the next
+    /// function.
+    #[track_caller]
     pub fn try_use_var(&mut self, var: Variable) -> Result<Value, UseVariableError> {
         // Assert that we're about to add instructions to this block using the definition of the
         // given variable. ssa.use_var is the only part of this crate which can add block parameters
@@ -454,23 +647,29 @@ impl<'a> FunctionBuilder<'a> {
         // called, then we enforce a strict separation between user parameters and SSA parameters.
         self.ensure_inserted_block();
 
+        #[cfg(debug_assertions)]
+        let first_new_value = self.func.dfg.num_values();
         let (val, side_effects) = {
-            let ty = *self
-                .func_ctx
-                .types
-                .get(var)
+            let ty = self
+                .var_type(var)
                 .ok_or(UseVariableError::UsedBeforeDeclared(var))?;
-            debug_assert_ne!(
-                ty,
-                types::INVALID,
-                "variable {var:?} is used but its type has not been declared"
-            );
             self.func_ctx
                 .ssa
                 .use_var(self.func, var, ty, self.position.unwrap())
+                .map_err(|_| {
+                    self.func_ctx.clear();
+                    UseVariableError::ImplLimitExceeded(var)
+                })?
         };
         self.handle_ssa_side_effects(side_effects);
 
+        // Remember where the block parameters this may have added come from, to point at them if
+        // their blocks are never sealed.
+        #[cfg(debug_assertions)]
+        for index in first_new_value..self.func.dfg.num_values() {
+            self.func_ctx.use_var_locations[Value::new(index)] = Some(Location::caller());
+        }
+
         // If the variable was declared as needing stack maps, then propagate
         // that requirement to all values derived from using the variable.
         if self.func_ctx.stack_map_vars.contains(var) {
@@ -482,9 +681,13 @@ impl<'a> FunctionBuilder<'a> {
 
     /// Returns the Cranelift IR value corresponding to the utilization at the current program
     /// position of a previously defined user variable.
+    #[track_caller]
     pub fn use_var(&mut self, var: Variable) -> Value {
-        self.try_use_var(var).unwrap_or_else(|_| {
-            panic!("variable {var:?} is used but its type has not been declared")
+        self.try_use_var(var).unwrap_or_else(|error| match error {
+            UseVariableError::UsedBeforeDeclared(_) => {
+                panic!("variable {var:?} is used but its type has not been declared")
+            }
+            UseVariableError::ImplLimitExceeded(_) => panic!("{error}"),
         })
     }
 
@@ -492,10 +695,8 @@ impl<'a> FunctionBuilder<'a> {
     /// an error if the value supplied does not match the type the variable was
     /// declared to have.
     pub fn try_def_var(&mut self, var: Variable, val: Value) -> Result<(), DefVariableError> {
-        let var_ty = *self
-            .func_ctx
-            .types
-            .get(var)
+        let var_ty = self
+            .var_type(var)
             .ok_or(DefVariableError::DefinedBeforeDeclared(var))?;
         if var_ty != self.func.dfg.value_type(val) {
             return Err(DefVariableError::TypeMismatch(var, val));
@@ -516,7 +717,11 @@ impl<'a> FunctionBuilder<'a> {
         self.try_def_var(var, val)
             .unwrap_or_else(|error| match error {
                 DefVariableError::TypeMismatch(var, val) => {
-                    panic!("declared type of variable {var:?} doesn't match type of value {val}");
+                    panic!(
+                        "declared type {} of variable {var:?} doesn't match type {} of value {val}",
+                        self.func_ctx.types[var],
+                        self.func.dfg.value_type(val)
+                    );
                 }
                 DefVariableError::DefinedBeforeDeclared(var) => {
                     panic!("variable {var:?} is used but its type has not been declared");
@@ -524,6 +729,15 @@ impl<'a> FunctionBuilder<'a> {
             })
     }
 
+    /// Get the declared type of `var`, if it was declared.
+    fn var_type(&self, var: Variable) -> Option<Type> {
+        self.func_ctx
+            .types
+            .get(var)
+            .copied()
+            .filter(|&ty| ty != types::INVALID)
+    }
+
     /// Set label for [`Value`]
     ///
     /// This will not do anything unless
@@ -692,12 +906,12 @@ impl<'a> FunctionBuilder<'a> {
         // Check that all the `Block`s are filled and sealed.
         #[cfg(debug_assertions)]
         {
+            let error = self.finalize_error();
+            if !error.unsealed_blocks.is_empty() {
+                panic!("{error}");
+            }
             for block in self.func_ctx.status.keys() {
                 if !self.is_pristine(block) {
-                    assert!(
-                        self.func_ctx.ssa.is_sealed(block),
-                        "FunctionBuilder finalized, but block {block} is not sealed",
-                    );
                     assert!(
                         self.is_filled(block),
                         "FunctionBuilder finalized, but block {block} is not filled",
@@ -728,6 +942,66 @@ impl<'a> FunctionBuilder<'a> {
         // for translation another function.
         self.func_ctx.clear();
     }
+
+    /// Declare that translation of the current function is complete, like
+    /// [`finalize`](Self::finalize), but check that every block was sealed and
+    /// every variable was defined before being used first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error listing every block that isn't sealed, and every
+    /// variable that was used without being defined. Such uses are allowed by
+    /// [`finalize`](Self::finalize), which initializes the variables to zero,
+    /// because they are expected in unreachable code.
+    ///
+    /// The [`FunctionBuilderContext`] is reset either way.
+    pub fn try_finalize(self) -> Result<(), FinalizeError> {
+        let error = self.finalize_error();
+        if error.is_empty() {
+            self.finalize();
+            Ok(())
+        } else {
+            self.func_ctx.clear();
+            Err(error)
+        }
+    }
+
+    /// Check that the function built so far is within the implementation limits configured
+    /// in `flags`, so that an oversized function can be rejected before it is compiled.
+    ///
+    /// These are the same checks that `Context::compile` does with the `max_*_log2` settings.
+    pub fn check_impl_limits(&self, flags: &settings::Flags) -> Result<(), FrontendError> {
+        cranelift_codegen::impl_limits::check_impl_limits(self.func, flags)
+            .map_err(FrontendError::ImplLimitExceeded)
+    }
+
+    /// Collect the blocks that aren't sealed and the variables that were used
+    /// without being defined.
+    fn finalize_error(&self) -> FinalizeError {
+        let mut error = FinalizeError::default();
+        for block in self.func_ctx.status.keys() {
+            if self.is_pristine(block) || self.func_ctx.ssa.is_sealed(block) {
+                continue;
+            }
+            error.unsealed_blocks.push(block);
+            for (var, param) in self.func_ctx.ssa.pending_params(block, self.func) {
+                #[cfg(debug_assertions)]
+                let location = self.func_ctx.use_var_locations[param];
+                #[cfg(not(debug_assertions))]
+                let location = None;
+                error.placeholders.push(Placeholder {
+                    block,
+                    var,
+                    param,
+                    location,
+                });
+            }
+        }
+        error
+            .undefined_variables
+            .extend_from_slice(self.func_ctx.ssa.undefined_uses());
+        error
+    }
 }
 
 /// All the functions documented in the previous block are write-only and help you build a valid
@@ -761,6 +1035,24 @@ impl<'a> FunctionBuilder<'a> {
         self.func.dfg.append_block_param(block, ty)
     }
 
+    /// Like [`append_block_param`](Self::append_block_param), but returns
+    /// [`FrontendError::ImplLimitExceeded`] instead of panicking if `block` already has the
+    /// maximum number of parameters.
+    pub fn try_append_block_param(
+        &mut self,
+        block: Block,
+        ty: Type,
+    ) -> Result<Value, FrontendError> {
+        debug_assert!(
+            self.is_pristine(block),
+            "You can't add block parameters after adding any instruction"
+        );
+        self.func
+            .dfg
+            .try_append_block_param(block, ty)
+            .map_err(FrontendError::ImplLimitExceeded)
+    }
+
     /// Returns the result values of an instruction.
     pub fn inst_results(&self, inst: Inst) -> &[Value] {
         self.func.dfg.inst_results(inst)
@@ -797,6 +1089,20 @@ impl<'a> FunctionBuilder<'a> {
                 .has_any_predecessors(self.position.unwrap())
     }
 
+    /// Returns `true` if a block in the layout, other than the entry block, has no predecessors
+    /// declared.
+    ///
+    /// This is a cheap way to find out whether the function has unreachable blocks without
+    /// computing its control flow graph: a group of unreachable blocks always has one without
+    /// predecessors, unless the group is a cycle that is never entered.
+    pub fn has_blocks_without_predecessors(&self) -> bool {
+        self.func
+            .layout
+            .blocks()
+            .skip(1)
+            .any(|block| !self.func_ctx.ssa.has_any_predecessors(block))
+    }
+
     /// Returns `true` if and only if no instructions have been added since the last call to
     /// [`switch_to_block`](Self::switch_to_block).
     fn is_pristine(&self, block: Block) -> bool {
@@ -1175,6 +1481,45 @@ impl<'a> FunctionBuilder<'a> {
             .declare_block_predecessor(dest_block, jump_inst);
     }
 
+    /// Panic if the arguments that the branch `inst` passes to its destinations don't match their
+    /// parameters.
+    ///
+    /// The number of arguments isn't checked, since it may not match yet: parameters can be
+    /// appended to a block after branches to it were inserted, and the SSA builder only adds the
+    /// arguments for the parameters it appends when the block is sealed. Destinations that
+    /// haven't been created are left to the verifier.
+    #[cfg(debug_assertions)]
+    fn check_branch_args(&self, inst: Inst) {
+        let dfg = &self.func.dfg;
+        for &call in dfg.insts[inst].branch_destination(&dfg.jump_tables) {
+            if !dfg.block_is_valid(call.block(&dfg.value_lists)) {
+                continue;
+            }
+            match dfg.type_check_block_call(call) {
+                Ok(()) => {}
+                Err(ir::TypeCheckError::BlockArgCount { .. }) => {}
+                Err(err) => panic!("ill-typed {}: {err}", dfg.insts[inst].opcode()),
+            }
+        }
+    }
+
+    /// Handle the side effects of a fallible SSA builder call, or reset the context if it failed.
+    fn handle_ssa_result(
+        &mut self,
+        result: CodegenResult<SideEffects>,
+    ) -> Result<(), FrontendError> {
+        match result {
+            Ok(side_effects) => {
+                self.handle_ssa_side_effects(side_effects);
+                Ok(())
+            }
+            Err(error) => {
+                self.func_ctx.clear();
+                Err(FrontendError::ImplLimitExceeded(error))
+            }
+        }
+    }
+
     fn handle_ssa_side_effects(&mut self, side_effects: SideEffects) {
         for modified_block in side_effects.instructions_added_to_blocks {
             if self.is_pristine(modified_block) {
@@ -1188,18 +1533,21 @@ impl<'a> FunctionBuilder<'a> {
 mod tests {
     use super::greatest_divisible_power_of_two;
     use crate::frontend::{
-        DeclareVariableError, DefVariableError, FunctionBuilder, FunctionBuilderContext,
-        UseVariableError,
+        DeclareVariableError, DefVariableError, FrontendError, FunctionBuilder,
+        FunctionBuilderContext, UseVariableError,
     };
     use crate::Variable;
     use alloc::string::ToString;
     use cranelift_codegen::entity::EntityRef;
     use cranelift_codegen::ir::condcodes::IntCC;
     use cranelift_codegen::ir::{types::*, UserFuncName};
-    use cranelift_codegen::ir::{AbiParam, Function, InstBuilder, MemFlags, Signature, Value};
+    use cranelift_codegen::ir::{
+        AbiParam, Block, Function, InstBuilder, MemFlags, Signature, Value,
+    };
     use cranelift_codegen::isa::{CallConv, TargetFrontendConfig, TargetIsa};
-    use cranelift_codegen::settings;
+    use cranelift_codegen::settings::{self, Configurable};
     use cranelift_codegen::verifier::verify_function;
+    use cranelift_codegen::CodegenError;
     use target_lexicon::PointerWidth;
 
     fn sample_function(lazy_seal: bool) {
@@ -1311,6 +1659,25 @@ mod tests {
         sample_function(true)
     }
 
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(
+        expected = "ill-typed brif: arg 0 (v2) has type i64, expected i32 (passed to block1)"
+    )]
+    fn ill_typed_branch_args() {
+        let mut fn_ctx = FunctionBuilderContext::new();
+        let mut func = Function::new();
+        let mut builder = FunctionBuilder::new(&mut func, &mut fn_ctx);
+
+        let block0 = builder.create_block();
+        let block1 = builder.create_block();
+        builder.append_block_param(block1, I32);
+        builder.switch_to_block(block0);
+        let cond = builder.ins().iconst(I8, 1);
+        let arg = builder.ins().iconst(I64, 0);
+        builder.ins().brif(cond, block1, &[arg], block1, &[arg]);
+    }
+
     #[track_caller]
     fn check(func: &Function, expected_ir: &str) {
         let expected_ir = expected_ir.trim();
@@ -1931,6 +2298,284 @@ block0:
         }
     }
 
+    #[test]
+    fn reserve_vars() {
+        let mut fn_ctx = FunctionBuilderContext::new();
+        let mut func = Function::new();
+        let mut builder = FunctionBuilder::new(&mut func, &mut fn_ctx);
+        builder.reserve_vars(10);
+
+        let block0 = builder.create_block();
+        builder.switch_to_block(block0);
+        builder.seal_block(block0);
+
+        // Reserved variables still have to be declared.
+        let x = Variable::from_u32(5);
+        assert_eq!(
+            builder.try_use_var(x),
+            Err(UseVariableError::UsedBeforeDeclared(x))
+        );
+        let value = builder.ins().iconst(I32, 0);
+        assert_eq!(
+            builder.try_def_var(x, value),
+            Err(DefVariableError::DefinedBeforeDeclared(x))
+        );
+
+        builder.declare_var(x, I32);
+        builder.def_var(x, value);
+        assert_eq!(builder.use_var(x), value);
+        builder.ins().return_(&[]);
+        builder.finalize();
+    }
+
+    #[test]
+    fn frontend_error() {
+        fn define(builder: &mut FunctionBuilder, var: Variable) -> Result<(), FrontendError> {
+            builder.try_declare_var(var, I64)?;
+            let value = builder.ins().iconst(I32, 0);
+            builder.try_def_var(var, value)?;
+            Ok(())
+        }
+
+        let mut fn_ctx = FunctionBuilderContext::new();
+        let mut func = Function::new();
+        let mut builder = FunctionBuilder::new(&mut func, &mut fn_ctx);
+        let block0 = builder.create_block();
+        builder.switch_to_block(block0);
+
+        let x = Variable::from_u32(0);
+        let error = define(&mut builder, x).unwrap_err();
+        assert!(matches!(
+            error,
+            FrontendError::Def(DefVariableError::TypeMismatch(var, _)) if var == x
+        ));
+        assert!(matches!(
+            define(&mut builder, x),
+            Err(FrontendError::Declare(
+                DeclareVariableError::DeclaredMultipleTimes(var)
+            )) if var == x
+        ));
+        assert_eq!(
+            FrontendError::from(UseVariableError::UsedBeforeDeclared(x)).to_string(),
+            "variable 0 was used before it was defined"
+        );
+    }
+
+    #[test]
+    fn check_impl_limits() {
+        let mut fn_ctx = FunctionBuilderContext::new();
+        let mut func = Function::new();
+        let mut builder = FunctionBuilder::new(&mut func, &mut fn_ctx);
+        let block0 = builder.create_block();
+        for _ in 0..4 {
+            builder.try_append_block_param(block0, I32).unwrap();
+        }
+        builder.switch_to_block(block0);
+        builder.seal_block(block0);
+        builder.ins().return_(&[]);
+
+        let flags = |max_params_log2: &str| {
+            let mut b = settings::builder();
+            b.set("max_params_log2", max_params_log2).unwrap();
+            settings::Flags::new(b)
+        };
+        assert!(builder.check_impl_limits(&flags("3")).is_ok());
+        assert!(matches!(
+            builder.check_impl_limits(&flags("2")),
+            Err(FrontendError::ImplLimitExceeded(
+                CodegenError::ImplLimitExceeded
+            ))
+        ));
+        builder.finalize();
+    }
+
+    /// Give `block` as many parameters as it can have, so the SSA builder can't add any.
+    fn fill_block_params(builder: &mut FunctionBuilder, block: Block) {
+        while builder.try_append_block_param(block, I32).is_ok() {}
+    }
+
+    #[test]
+    fn use_var_too_many_block_params() {
+        // Using a variable in an unsealed block adds a parameter for it right away, which used to
+        // panic when the block was full.
+        let mut fn_ctx = FunctionBuilderContext::new();
+        let mut func = Function::new();
+        let mut builder = FunctionBuilder::new(&mut func, &mut fn_ctx);
+        let block0 = builder.create_block();
+        fill_block_params(&mut builder, block0);
+        builder.switch_to_block(block0);
+
+        let x = Variable::from_u32(0);
+        builder.declare_var(x, I32);
+        assert_eq!(
+            builder.try_use_var(x),
+            Err(UseVariableError::ImplLimitExceeded(x))
+        );
+
+        // The context was reset, so it can be used for the next function.
+        let mut func = Function::new();
+        let _ = FunctionBuilder::new(&mut func, &mut fn_ctx);
+    }
+
+    #[test]
+    fn seal_block_too_many_block_params() {
+        // Sealing `block1` looks up `x` in its predecessor `block0`, which needs a parameter for
+        // it since it isn't sealed.
+        let mut fn_ctx = FunctionBuilderContext::new();
+        let mut func = Function::new();
+        let mut builder = FunctionBuilder::new(&mut func, &mut fn_ctx);
+        let block0 = builder.create_block();
+        let block1 = builder.create_block();
+        fill_block_params(&mut builder, block0);
+        builder.switch_to_block(block0);
+        builder.ins().jump(block1, &[]);
+
+        builder.switch_to_block(block1);
+        let x = Variable::from_u32(0);
+        builder.declare_var(x, I32);
+        let value = builder.use_var(x);
+        builder.ins().return_(&[value]);
+        assert!(matches!(
+            builder.try_seal_block(block1),
+            Err(FrontendError::ImplLimitExceeded(
+                CodegenError::ImplLimitExceeded
+            ))
+        ));
+
+        let mut func = Function::new();
+        let _ = FunctionBuilder::new(&mut func, &mut fn_ctx);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "declared type i64 of variable var0 doesn't match type i32 of value v0"
+    )]
+    fn def_var_type_mismatch() {
+        let mut fn_ctx = FunctionBuilderContext::new();
+        let mut func = Function::new();
+        let mut builder = FunctionBuilder::new(&mut func, &mut fn_ctx);
+        let block0 = builder.create_block();
+        builder.switch_to_block(block0);
+
+        let x = Variable::from_u32(0);
+        builder.declare_var(x, I64);
+        let value = builder.ins().iconst(I32, 0);
+        builder.def_var(x, value);
+    }
+
+    #[test]
+    fn try_finalize_unsealed() {
+        let mut fn_ctx = FunctionBuilderContext::new();
+        let mut func = Function::new();
+        let mut builder = FunctionBuilder::new(&mut func, &mut fn_ctx);
+        let x = Variable::from_u32(0);
+        builder.declare_var(x, I32);
+
+        let block0 = builder.create_block();
+        let block1 = builder.create_block();
+        let block2 = builder.create_block();
+        builder.switch_to_block(block0);
+        builder.seal_block(block0);
+        let zero = builder.ins().iconst(I32, 0);
+        builder.def_var(x, zero);
+        builder.ins().jump(block1, &[]);
+
+        // Neither block1 nor block2 is ever sealed.
+        builder.switch_to_block(block1);
+        let (param, line) = (builder.use_var(x), line!());
+        builder.ins().jump(block2, &[]);
+        builder.switch_to_block(block2);
+        builder.ins().return_(&[]);
+
+        let error = builder.try_finalize().unwrap_err();
+        assert_eq!(error.unsealed_blocks, [block1, block2]);
+        assert_eq!(error.placeholders.len(), 1);
+        let placeholder = error.placeholders[0];
+        assert_eq!(
+            (placeholder.block, placeholder.var, placeholder.param),
+            (block1, x, param)
+        );
+        assert!(error.undefined_variables.is_empty());
+        let message = error.to_string();
+        assert!(message.contains("block block1 is not sealed"), "{message}");
+        assert!(message.contains("block block2 is not sealed"), "{message}");
+        assert!(
+            message.contains(&format!("{param} is a placeholder for variable 0")),
+            "{message}"
+        );
+
+        #[cfg(debug_assertions)]
+        {
+            let location = placeholder.location.unwrap();
+            assert!(location.file().ends_with("frontend.rs"));
+            assert_eq!(location.line(), line);
+            assert!(message.contains(&format!("created by `use_var` at {location}")));
+        }
+        #[cfg(not(debug_assertions))]
+        let _ = line;
+
+        // The context can be reused.
+        let mut func = Function::new();
+        let builder = FunctionBuilder::new(&mut func, &mut fn_ctx);
+        builder.try_finalize().unwrap();
+    }
+
+    #[test]
+    fn has_blocks_without_predecessors() {
+        let mut fn_ctx = FunctionBuilderContext::new();
+        let mut func = Function::new();
+        let mut builder = FunctionBuilder::new(&mut func, &mut fn_ctx);
+
+        let block0 = builder.create_block();
+        let block1 = builder.create_block();
+        let block2 = builder.create_block();
+        builder.switch_to_block(block0);
+        builder.seal_block(block0);
+        assert!(!builder.has_blocks_without_predecessors());
+        builder.ins().jump(block1, &[]);
+
+        builder.switch_to_block(block1);
+        builder.seal_block(block1);
+        builder.ins().return_(&[]);
+        assert!(!builder.has_blocks_without_predecessors());
+
+        // Nothing jumps to block2.
+        builder.switch_to_block(block2);
+        builder.seal_block(block2);
+        builder.ins().return_(&[]);
+        assert!(builder.has_blocks_without_predecessors());
+        builder.finalize();
+    }
+
+    #[test]
+    fn try_finalize_undefined_variable() {
+        let mut fn_ctx = FunctionBuilderContext::new();
+        let mut func = Function::new();
+        let mut builder = FunctionBuilder::new(&mut func, &mut fn_ctx);
+        let x = Variable::from_u32(0);
+        let y = Variable::from_u32(1);
+        builder.declare_var(x, I32);
+        builder.declare_var(y, I64);
+
+        let block0 = builder.create_block();
+        builder.switch_to_block(block0);
+        builder.seal_block(block0);
+        let zero = builder.ins().iconst(I32, 0);
+        builder.def_var(x, zero);
+        builder.use_var(x);
+        builder.use_var(y);
+        builder.ins().return_(&[]);
+
+        let error = builder.try_finalize().unwrap_err();
+        assert!(error.unsealed_blocks.is_empty());
+        assert_eq!(error.undefined_variables, [(y, block0)]);
+        assert_eq!(
+            error.to_string(),
+            "FunctionBuilder finalized, but:\n  \
+             variable 1 is used before it is defined, and set to zero in block0"
+        );
+    }
+
     #[test]
     fn test_builder_with_iconst_and_negative_constant() {
         let sig = Signature::new(CallConv::SystemV);
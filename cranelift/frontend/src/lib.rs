@@ -169,7 +169,10 @@ use hashbrown::{HashMap, HashSet};
 #[cfg(feature = "std")]
 use std::collections::{HashMap, HashSet};
 
-pub use crate::frontend::{FuncInstBuilder, FunctionBuilder, FunctionBuilderContext};
+pub use crate::frontend::{
+    DeclareVariableError, DefVariableError, FinalizeError, FrontendError, FuncInstBuilder,
+    FunctionBuilder, FunctionBuilderContext, Placeholder, UseVariableError,
+};
 pub use crate::switch::Switch;
 pub use crate::variable::Variable;
 
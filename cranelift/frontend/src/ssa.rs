@@ -16,6 +16,7 @@ use cranelift_codegen::ir::immediates::{Ieee32, Ieee64};
 use cranelift_codegen::ir::types::{F32, F64, I128, I64};
 use cranelift_codegen::ir::{Block, Function, Inst, InstBuilder, Type, Value};
 use cranelift_codegen::packed_option::PackedOption;
+use cranelift_codegen::CodegenResult;
 
 /// Structure containing the data relevant the construction of SSA for a given function.
 ///
@@ -57,6 +58,10 @@ pub struct SSABuilder {
 
     /// Storage for predecessor definitions.
     inst_pool: ListPool<Inst>,
+
+    /// Variables that were used without being defined, and the blocks where they were
+    /// initialized to zero instead.
+    undefined_uses: Vec<(Variable, Block)>,
 }
 
 /// Side effects of a `use_var` or a `seal_block` method call.
@@ -110,6 +115,7 @@ impl SSABuilder {
         self.ssa_blocks.clear();
         self.variable_pool.clear();
         self.inst_pool.clear();
+        self.undefined_uses.clear();
         debug_assert!(self.calls.is_empty());
         debug_assert!(self.results.is_empty());
         debug_assert!(self.side_effects.is_empty());
@@ -122,6 +128,7 @@ impl SSABuilder {
             && self.calls.is_empty()
             && self.results.is_empty()
             && self.side_effects.is_empty()
+            && self.undefined_uses.is_empty()
     }
 }
 
@@ -185,6 +192,14 @@ fn emit_zero(ty: Type, mut cur: FuncCursor) -> Value {
 /// Phi functions.
 ///
 impl SSABuilder {
+    /// Make room for the definitions of the variables numbered below `count`, so that they don't
+    /// have to be allocated one by one as the variables are first defined.
+    pub fn reserve_vars(&mut self, count: usize) {
+        if self.variables.values().len() < count {
+            self.variables.resize(count);
+        }
+    }
+
     /// Declares a new definition of a variable in a given basic block.
     /// The SSA value is passed as an argument because it should be created with
     /// `ir::DataFlowGraph::append_result`.
@@ -199,40 +214,51 @@ impl SSABuilder {
     /// If the variable has never been defined in this blocks or recursively in its predecessors,
     /// this method will silently create an initializer with `iconst` or `fconst`. You are
     /// responsible for making sure that you initialize your variables.
+    ///
+    /// Returns `CodegenError::ImplLimitExceeded` if a block parameter is needed for the variable
+    /// on a block that already has the maximum number of parameters. The function is left
+    /// partially updated in that case.
     pub fn use_var(
         &mut self,
         func: &mut Function,
         var: Variable,
         ty: Type,
         block: Block,
-    ) -> (Value, SideEffects) {
+    ) -> CodegenResult<(Value, SideEffects)> {
         debug_assert!(self.calls.is_empty());
         debug_assert!(self.results.is_empty());
         debug_assert!(self.side_effects.is_empty());
 
         // Prepare the 'calls' and 'results' stacks for the state machine.
-        self.use_var_nonlocal(func, var, ty, block);
-        let value = self.run_state_machine(func, var, ty);
+        let value = self
+            .use_var_nonlocal(func, var, ty, block)
+            .and_then(|()| self.run_state_machine(func, var, ty));
 
         let side_effects = mem::take(&mut self.side_effects);
-        (value, side_effects)
+        Ok((value?, side_effects))
     }
 
     /// Resolve the minimal SSA Value of `var` in `block` by traversing predecessors.
     ///
     /// This function sets up state for `run_state_machine()` but does not execute it.
-    fn use_var_nonlocal(&mut self, func: &mut Function, var: Variable, ty: Type, mut block: Block) {
+    fn use_var_nonlocal(
+        &mut self,
+        func: &mut Function,
+        var: Variable,
+        ty: Type,
+        mut block: Block,
+    ) -> CodegenResult<()> {
         // First, try Local Value Numbering (Algorithm 1 in the paper).
         // If the variable already has a known Value in this block, use that.
         if let Some(val) = self.variables[var][block].expand() {
             self.results.push(val);
-            return;
+            return Ok(());
         }
 
         // Otherwise, use Global Value Numbering (Algorithm 2 in the paper).
         // This resolves the Value with respect to its predecessors.
         // Find the most recent definition of `var`, and the block the definition comes from.
-        let (val, from) = self.find_var(func, var, ty, block);
+        let (val, from) = self.find_var(func, var, ty, block)?;
 
         // The `from` block returned from `find_var` is guaranteed to be on the path we follow by
         // traversing only single-predecessor edges. It might be equal to `block` if there is no
@@ -258,6 +284,7 @@ impl SSABuilder {
             var_defs[block] = PackedOption::from(val);
             block = self.ssa_blocks[block].single_predecessor.unwrap();
         }
+        Ok(())
     }
 
     /// Find the most recent definition of this variable, returning both the definition and the
@@ -289,7 +316,7 @@ impl SSABuilder {
         var: Variable,
         ty: Type,
         mut block: Block,
-    ) -> (Value, Block) {
+    ) -> CodegenResult<(Value, Block)> {
         // Try to find an existing definition along single-predecessor edges first.
         self.visited.clear();
         let var_defs = &mut self.variables[var];
@@ -300,13 +327,13 @@ impl SSABuilder {
             block = pred;
             if let Some(val) = var_defs[block].expand() {
                 self.results.push(val);
-                return (val, block);
+                return Ok((val, block));
             }
         }
 
         // We've promised to return the most recent block where `var` was defined, but we didn't
         // find a usable definition. So create one.
-        let val = func.dfg.append_block_param(block, ty);
+        let val = func.dfg.try_append_block_param(block, ty)?;
         var_defs[block] = PackedOption::from(val);
 
         // Now every predecessor needs to pass its definition of this variable to the newly added
@@ -323,7 +350,7 @@ impl SSABuilder {
                 self.results.push(val);
             }
         }
-        (val, block)
+        Ok((val, block))
     }
 
     /// Declares a new basic block to construct corresponding data for SSA construction.
@@ -374,14 +401,17 @@ impl SSABuilder {
     /// This method modifies the function's `Layout` by adding arguments to the `Block`s to
     /// take into account the Phi function placed by the SSA algorithm.
     ///
-    /// Returns the list of newly created blocks for critical edge splitting.
-    pub fn seal_block(&mut self, block: Block, func: &mut Function) -> SideEffects {
+    /// Returns the list of newly created blocks for critical edge splitting, or
+    /// `CodegenError::ImplLimitExceeded` if looking up the variables used in `block` needs too
+    /// many block parameters.
+    pub fn seal_block(&mut self, block: Block, func: &mut Function) -> CodegenResult<SideEffects> {
         debug_assert!(
             !self.is_sealed(block),
             "Attempting to seal {block} which is already sealed."
         );
-        self.seal_one_block(block, func);
-        mem::take(&mut self.side_effects)
+        let result = self.seal_one_block(block, func);
+        let side_effects = mem::take(&mut self.side_effects);
+        result.map(|()| side_effects)
     }
 
     /// Completes the global value numbering for all unsealed `Block`s in `func`.
@@ -390,24 +420,26 @@ impl SSABuilder {
     /// translation, but for frontends where this is impractical to do, this
     /// function can be used at the end of translating all blocks to ensure
     /// that everything is sealed.
-    pub fn seal_all_blocks(&mut self, func: &mut Function) -> SideEffects {
+    pub fn seal_all_blocks(&mut self, func: &mut Function) -> CodegenResult<SideEffects> {
         // Seal all `Block`s currently in the function. This can entail splitting
         // and creation of new blocks, however such new blocks are sealed on
         // the fly, so we don't need to account for them here.
-        for block in self.ssa_blocks.keys() {
-            self.seal_one_block(block, func);
-        }
-        mem::take(&mut self.side_effects)
+        let result = self
+            .ssa_blocks
+            .keys()
+            .try_for_each(|block| self.seal_one_block(block, func));
+        let side_effects = mem::take(&mut self.side_effects);
+        result.map(|()| side_effects)
     }
 
     /// Helper function for `seal_block` and `seal_all_blocks`.
-    fn seal_one_block(&mut self, block: Block, func: &mut Function) {
+    fn seal_one_block(&mut self, block: Block, func: &mut Function) -> CodegenResult<()> {
         // For each undef var we look up values in the predecessors and create a block parameter
         // only if necessary.
         let mut undef_variables =
             match mem::replace(&mut self.ssa_blocks[block].sealed, Sealed::Yes) {
                 Sealed::No { undef_variables } => undef_variables,
-                Sealed::Yes => return,
+                Sealed::Yes => return Ok(()),
             };
         let ssa_params = undef_variables.len(&self.variable_pool);
 
@@ -440,10 +472,11 @@ impl SSABuilder {
             // self.side_effects may be non-empty here so that callers can
             // accumulate side effects over multiple calls.
             self.begin_predecessors_lookup(val, block);
-            self.run_state_machine(func, var, func.dfg.value_type(val));
+            self.run_state_machine(func, var, func.dfg.value_type(val))?;
         }
 
         undef_variables.clear(&mut self.variable_pool);
+        Ok(())
     }
 
     /// Given the local SSA Value of a Variable in a Block, perform a recursive lookup on
@@ -480,6 +513,7 @@ impl SSABuilder {
     fn finish_predecessors_lookup(
         &mut self,
         func: &mut Function,
+        var: Variable,
         sentinel: Value,
         dest_block: Block,
     ) -> Value {
@@ -517,6 +551,7 @@ impl SSABuilder {
                 self.side_effects
                     .instructions_added_to_blocks
                     .push(dest_block);
+                self.undefined_uses.push((var, dest_block));
                 let zero = emit_zero(
                     func.dfg.value_type(sentinel),
                     FuncCursor::new(func).at_first_insertion_point(dest_block),
@@ -564,6 +599,30 @@ impl SSABuilder {
             .as_slice(&self.inst_pool)
     }
 
+    /// Returns the variables that were used without being defined so far, and the blocks where
+    /// they were initialized to zero instead.
+    pub fn undefined_uses(&self) -> &[(Variable, Block)] {
+        &self.undefined_uses
+    }
+
+    /// Returns the variables whose definitions in `block` still have to be looked up in its
+    /// predecessors once it is sealed, and the block parameters standing in for them.
+    pub fn pending_params<'a>(
+        &'a self,
+        block: Block,
+        func: &'a Function,
+    ) -> impl Iterator<Item = (Variable, Value)> + 'a {
+        let undef_variables = match &self.ssa_blocks[block].sealed {
+            Sealed::No { undef_variables } => undef_variables.as_slice(&self.variable_pool),
+            Sealed::Yes => &[],
+        };
+        // As in `seal_one_block`, the last block parameters are the ones added for
+        // `undef_variables`, in the same order.
+        let params = func.dfg.block_params(block);
+        let params = &params[params.len() - undef_variables.len()..];
+        undef_variables.iter().copied().zip(params.iter().copied())
+    }
+
     /// Returns whether the given Block has any predecessor or not.
     pub fn has_any_predecessors(&self, block: Block) -> bool {
         !self.predecessors(block).is_empty()
@@ -579,22 +638,33 @@ impl SSABuilder {
     /// `use_var` in each predecessor. To avoid risking running out of callstack
     /// space, we keep an explicit stack and use a small state machine rather
     /// than literal recursion.
-    fn run_state_machine(&mut self, func: &mut Function, var: Variable, ty: Type) -> Value {
+    ///
+    /// If a step fails, the remaining steps are dropped so that the builder can be cleared.
+    fn run_state_machine(
+        &mut self,
+        func: &mut Function,
+        var: Variable,
+        ty: Type,
+    ) -> CodegenResult<Value> {
         // Process the calls scheduled in `self.calls` until it is empty.
         while let Some(call) = self.calls.pop() {
             match call {
                 Call::UseVar(branch) => {
                     let block = func.layout.inst_block(branch).unwrap();
-                    self.use_var_nonlocal(func, var, ty, block);
+                    if let Err(error) = self.use_var_nonlocal(func, var, ty, block) {
+                        self.calls.clear();
+                        self.results.clear();
+                        return Err(error);
+                    }
                 }
                 Call::FinishPredecessorsLookup(sentinel, dest_block) => {
-                    let val = self.finish_predecessors_lookup(func, sentinel, dest_block);
+                    let val = self.finish_predecessors_lookup(func, var, sentinel, dest_block);
                     self.results.push(val);
                 }
             }
         }
         debug_assert_eq!(self.results.len(), 1);
-        self.results.pop().unwrap()
+        Ok(self.results.pop().unwrap())
     }
 }
 
@@ -636,27 +706,33 @@ mod tests {
             cur.ins().iconst(I32, 2)
         };
         ssa.def_var(y_var, y_ssa, block0);
-        assert_eq!(ssa.use_var(&mut func, x_var, I32, block0).0, x_ssa);
-        assert_eq!(ssa.use_var(&mut func, y_var, I32, block0).0, y_ssa);
+        assert_eq!(ssa.use_var(&mut func, x_var, I32, block0).unwrap().0, x_ssa);
+        assert_eq!(ssa.use_var(&mut func, y_var, I32, block0).unwrap().0, y_ssa);
 
         let z_var = Variable::new(2);
-        let x_use1 = ssa.use_var(&mut func, x_var, I32, block0).0;
-        let y_use1 = ssa.use_var(&mut func, y_var, I32, block0).0;
+        let x_use1 = ssa.use_var(&mut func, x_var, I32, block0).unwrap().0;
+        let y_use1 = ssa.use_var(&mut func, y_var, I32, block0).unwrap().0;
         let z1_ssa = {
             let mut cur = FuncCursor::new(&mut func).at_bottom(block0);
             cur.ins().iadd(x_use1, y_use1)
         };
         ssa.def_var(z_var, z1_ssa, block0);
-        assert_eq!(ssa.use_var(&mut func, z_var, I32, block0).0, z1_ssa);
+        assert_eq!(
+            ssa.use_var(&mut func, z_var, I32, block0).unwrap().0,
+            z1_ssa
+        );
 
-        let x_use2 = ssa.use_var(&mut func, x_var, I32, block0).0;
-        let z_use1 = ssa.use_var(&mut func, z_var, I32, block0).0;
+        let x_use2 = ssa.use_var(&mut func, x_var, I32, block0).unwrap().0;
+        let z_use1 = ssa.use_var(&mut func, z_var, I32, block0).unwrap().0;
         let z2_ssa = {
             let mut cur = FuncCursor::new(&mut func).at_bottom(block0);
             cur.ins().iadd(x_use2, z_use1)
         };
         ssa.def_var(z_var, z2_ssa, block0);
-        assert_eq!(ssa.use_var(&mut func, z_var, I32, block0).0, z2_ssa);
+        assert_eq!(
+            ssa.use_var(&mut func, z_var, I32, block0).unwrap().0,
+            z2_ssa
+        );
     }
 
     #[test]
@@ -686,7 +762,7 @@ mod tests {
 
         // block0
         ssa.declare_block(block0);
-        ssa.seal_block(block0, &mut func);
+        ssa.seal_block(block0, &mut func).unwrap();
         let x_var = Variable::new(0);
         let x_ssa = {
             let mut cur = FuncCursor::new(&mut func).at_bottom(block0);
@@ -700,30 +776,33 @@ mod tests {
         };
         ssa.def_var(y_var, y_ssa, block0);
         let z_var = Variable::new(2);
-        let x_use1 = ssa.use_var(&mut func, x_var, I32, block0).0;
-        let y_use1 = ssa.use_var(&mut func, y_var, I32, block0).0;
+        let x_use1 = ssa.use_var(&mut func, x_var, I32, block0).unwrap().0;
+        let y_use1 = ssa.use_var(&mut func, y_var, I32, block0).unwrap().0;
         let z1_ssa = {
             let mut cur = FuncCursor::new(&mut func).at_bottom(block0);
             cur.ins().iadd(x_use1, y_use1)
         };
         ssa.def_var(z_var, z1_ssa, block0);
-        let y_use2 = ssa.use_var(&mut func, y_var, I32, block0).0;
+        let y_use2 = ssa.use_var(&mut func, y_var, I32, block0).unwrap().0;
         let brif_block0_block2_block1: Inst = {
             let mut cur = FuncCursor::new(&mut func).at_bottom(block0);
             cur.ins().brif(y_use2, block2, &[], block1, &[])
         };
 
-        assert_eq!(ssa.use_var(&mut func, x_var, I32, block0).0, x_ssa);
-        assert_eq!(ssa.use_var(&mut func, y_var, I32, block0).0, y_ssa);
-        assert_eq!(ssa.use_var(&mut func, z_var, I32, block0).0, z1_ssa);
+        assert_eq!(ssa.use_var(&mut func, x_var, I32, block0).unwrap().0, x_ssa);
+        assert_eq!(ssa.use_var(&mut func, y_var, I32, block0).unwrap().0, y_ssa);
+        assert_eq!(
+            ssa.use_var(&mut func, z_var, I32, block0).unwrap().0,
+            z1_ssa
+        );
 
         // block1
         ssa.declare_block(block1);
         ssa.declare_block_predecessor(block1, brif_block0_block2_block1);
-        ssa.seal_block(block1, &mut func);
+        ssa.seal_block(block1, &mut func).unwrap();
 
-        let x_use2 = ssa.use_var(&mut func, x_var, I32, block1).0;
-        let z_use1 = ssa.use_var(&mut func, z_var, I32, block1).0;
+        let x_use2 = ssa.use_var(&mut func, x_var, I32, block1).unwrap().0;
+        let z_use1 = ssa.use_var(&mut func, z_var, I32, block1).unwrap().0;
         let z2_ssa = {
             let mut cur = FuncCursor::new(&mut func).at_bottom(block1);
             cur.ins().iadd(x_use2, z_use1)
@@ -736,15 +815,18 @@ mod tests {
 
         assert_eq!(x_use2, x_ssa);
         assert_eq!(z_use1, z1_ssa);
-        assert_eq!(ssa.use_var(&mut func, z_var, I32, block1).0, z2_ssa);
+        assert_eq!(
+            ssa.use_var(&mut func, z_var, I32, block1).unwrap().0,
+            z2_ssa
+        );
 
         // block2
         ssa.declare_block(block2);
         ssa.declare_block_predecessor(block2, brif_block0_block2_block1);
         ssa.declare_block_predecessor(block2, jump_block1_block2);
-        ssa.seal_block(block2, &mut func);
-        let x_use3 = ssa.use_var(&mut func, x_var, I32, block2).0;
-        let y_use3 = ssa.use_var(&mut func, y_var, I32, block2).0;
+        ssa.seal_block(block2, &mut func).unwrap();
+        let x_use3 = ssa.use_var(&mut func, x_var, I32, block2).unwrap().0;
+        let y_use3 = ssa.use_var(&mut func, y_var, I32, block2).unwrap().0;
         let y2_ssa = {
             let mut cur = FuncCursor::new(&mut func).at_bottom(block2);
             cur.ins().iadd(x_use3, y_use3)
@@ -821,7 +903,7 @@ mod tests {
 
         // block0
         ssa.declare_block(block0);
-        ssa.seal_block(block0, &mut func);
+        ssa.seal_block(block0, &mut func).unwrap();
         let x_var = Variable::new(0);
         let x1 = {
             let mut cur = FuncCursor::new(&mut func).at_bottom(block0);
@@ -835,8 +917,8 @@ mod tests {
         };
         ssa.def_var(y_var, y1, block0);
         let z_var = Variable::new(2);
-        let x2 = ssa.use_var(&mut func, x_var, I32, block0).0;
-        let y2 = ssa.use_var(&mut func, y_var, I32, block0).0;
+        let x2 = ssa.use_var(&mut func, x_var, I32, block0).unwrap().0;
+        let y2 = ssa.use_var(&mut func, y_var, I32, block0).unwrap().0;
         let z1 = {
             let mut cur = FuncCursor::new(&mut func).at_bottom(block0);
             cur.ins().iadd(x2, y2)
@@ -846,22 +928,22 @@ mod tests {
             let mut cur = FuncCursor::new(&mut func).at_bottom(block0);
             cur.ins().jump(block1, &[])
         };
-        assert_eq!(ssa.use_var(&mut func, x_var, I32, block0).0, x1);
-        assert_eq!(ssa.use_var(&mut func, y_var, I32, block0).0, y1);
+        assert_eq!(ssa.use_var(&mut func, x_var, I32, block0).unwrap().0, x1);
+        assert_eq!(ssa.use_var(&mut func, y_var, I32, block0).unwrap().0, y1);
         assert_eq!(x2, x1);
         assert_eq!(y2, y1);
 
         // block1
         ssa.declare_block(block1);
         ssa.declare_block_predecessor(block1, jump_block0_block1);
-        let z2 = ssa.use_var(&mut func, z_var, I32, block1).0;
-        let y3 = ssa.use_var(&mut func, y_var, I32, block1).0;
+        let z2 = ssa.use_var(&mut func, z_var, I32, block1).unwrap().0;
+        let y3 = ssa.use_var(&mut func, y_var, I32, block1).unwrap().0;
         let z3 = {
             let mut cur = FuncCursor::new(&mut func).at_bottom(block1);
             cur.ins().iadd(z2, y3)
         };
         ssa.def_var(z_var, z3, block1);
-        let y4 = ssa.use_var(&mut func, y_var, I32, block1).0;
+        let y4 = ssa.use_var(&mut func, y_var, I32, block1).unwrap().0;
         assert_eq!(y4, y3);
         let brif_block1_block3_block2 = {
             let mut cur = FuncCursor::new(&mut func).at_bottom(block1);
@@ -871,16 +953,16 @@ mod tests {
         // block2
         ssa.declare_block(block2);
         ssa.declare_block_predecessor(block2, brif_block1_block3_block2);
-        ssa.seal_block(block2, &mut func);
-        let z4 = ssa.use_var(&mut func, z_var, I32, block2).0;
+        ssa.seal_block(block2, &mut func).unwrap();
+        let z4 = ssa.use_var(&mut func, z_var, I32, block2).unwrap().0;
         assert_eq!(z4, z3);
-        let x3 = ssa.use_var(&mut func, x_var, I32, block2).0;
+        let x3 = ssa.use_var(&mut func, x_var, I32, block2).unwrap().0;
         let z5 = {
             let mut cur = FuncCursor::new(&mut func).at_bottom(block2);
             cur.ins().isub(z4, x3)
         };
         ssa.def_var(z_var, z5, block2);
-        let y5 = ssa.use_var(&mut func, y_var, I32, block2).0;
+        let y5 = ssa.use_var(&mut func, y_var, I32, block2).unwrap().0;
         assert_eq!(y5, y3);
         {
             let mut cur = FuncCursor::new(&mut func).at_bottom(block2);
@@ -890,10 +972,10 @@ mod tests {
         // block3
         ssa.declare_block(block3);
         ssa.declare_block_predecessor(block3, brif_block1_block3_block2);
-        ssa.seal_block(block3, &mut func);
-        let y6 = ssa.use_var(&mut func, y_var, I32, block3).0;
+        ssa.seal_block(block3, &mut func).unwrap();
+        let y6 = ssa.use_var(&mut func, y_var, I32, block3).unwrap().0;
         assert_eq!(y6, y3);
-        let x4 = ssa.use_var(&mut func, x_var, I32, block3).0;
+        let x4 = ssa.use_var(&mut func, x_var, I32, block3).unwrap().0;
         assert_eq!(x4, x3);
         let y7 = {
             let mut cur = FuncCursor::new(&mut func).at_bottom(block3);
@@ -907,7 +989,7 @@ mod tests {
 
         // block1 after all predecessors have been visited.
         ssa.declare_block_predecessor(block1, jump_block3_block1);
-        ssa.seal_block(block1, &mut func);
+        ssa.seal_block(block1, &mut func).unwrap();
         assert_eq!(func.dfg.block_params(block1)[0], z2);
         assert_eq!(func.dfg.block_params(block1)[1], y3);
         assert_eq!(func.dfg.resolve_aliases(x3), x1);
@@ -949,10 +1031,10 @@ mod tests {
             cur.ins().iconst(I32, 1)
         };
         ssa.declare_block(block0);
-        ssa.seal_block(block0, &mut func);
+        ssa.seal_block(block0, &mut func).unwrap();
         let x_var = Variable::new(0);
         ssa.def_var(x_var, x1, block0);
-        ssa.use_var(&mut func, x_var, I32, block0).0;
+        ssa.use_var(&mut func, x_var, I32, block0).unwrap().0;
         let br_table = {
             let jump_table = JumpTableData::new(
                 func.dfg.block_call(block2, &[]),
@@ -969,7 +1051,7 @@ mod tests {
         // block1
         ssa.declare_block(block1);
         ssa.declare_block_predecessor(block1, br_table);
-        ssa.seal_block(block1, &mut func);
+        ssa.seal_block(block1, &mut func).unwrap();
         let x2 = {
             let mut cur = FuncCursor::new(&mut func).at_bottom(block1);
             cur.ins().iconst(I32, 2)
@@ -984,8 +1066,8 @@ mod tests {
         ssa.declare_block(block2);
         ssa.declare_block_predecessor(block2, jump_block1_block2);
         ssa.declare_block_predecessor(block2, br_table);
-        ssa.seal_block(block2, &mut func);
-        let x3 = ssa.use_var(&mut func, x_var, I32, block2).0;
+        ssa.seal_block(block2, &mut func).unwrap();
+        let x3 = ssa.use_var(&mut func, x_var, I32, block2).unwrap().0;
         let x4 = {
             let mut cur = FuncCursor::new(&mut func).at_bottom(block2);
             cur.ins().iadd_imm(x3, 1)
@@ -1034,7 +1116,7 @@ mod tests {
         // block0
         ssa.declare_block(block0);
         let x_var = Variable::new(0);
-        ssa.seal_block(block0, &mut func);
+        ssa.seal_block(block0, &mut func).unwrap();
         let x1 = {
             let mut cur = FuncCursor::new(&mut func).at_bottom(block0);
             cur.ins().iconst(I32, 0)
@@ -1060,17 +1142,17 @@ mod tests {
         // block1
         ssa.declare_block(block1);
         ssa.declare_block_predecessor(block1, jump_block0_block1);
-        let z2 = ssa.use_var(&mut func, z_var, I32, block1).0;
+        let z2 = ssa.use_var(&mut func, z_var, I32, block1).unwrap().0;
         assert_eq!(func.dfg.block_params(block1)[0], z2);
-        let x2 = ssa.use_var(&mut func, x_var, I32, block1).0;
+        let x2 = ssa.use_var(&mut func, x_var, I32, block1).unwrap().0;
         assert_eq!(func.dfg.block_params(block1)[1], x2);
         let x3 = {
             let mut cur = FuncCursor::new(&mut func).at_bottom(block1);
             cur.ins().iadd(x2, z2)
         };
         ssa.def_var(x_var, x3, block1);
-        let x4 = ssa.use_var(&mut func, x_var, I32, block1).0;
-        let y3 = ssa.use_var(&mut func, y_var, I32, block1).0;
+        let x4 = ssa.use_var(&mut func, x_var, I32, block1).unwrap().0;
+        let y3 = ssa.use_var(&mut func, y_var, I32, block1).unwrap().0;
         assert_eq!(func.dfg.block_params(block1)[2], y3);
         let y4 = {
             let mut cur = FuncCursor::new(&mut func).at_bottom(block1);
@@ -1082,7 +1164,7 @@ mod tests {
             cur.ins().jump(block1, &[])
         };
         ssa.declare_block_predecessor(block1, jump_block1_block1);
-        ssa.seal_block(block1, &mut func);
+        ssa.seal_block(block1, &mut func).unwrap();
         // At sealing the "z" argument disappear but the remaining "x" and "y" args have to be
         // in the right order.
         assert_eq!(func.dfg.block_params(block1)[1], y3);
@@ -1096,17 +1178,17 @@ mod tests {
         let mut ssa = SSABuilder::default();
         let block0 = func.dfg.make_block();
         ssa.declare_block(block0);
-        ssa.seal_block(block0, &mut func);
+        ssa.seal_block(block0, &mut func).unwrap();
         let i32_var = Variable::new(0);
         let f32_var = Variable::new(1);
         let f64_var = Variable::new(2);
         let i8_var = Variable::new(3);
         let f32x4_var = Variable::new(4);
-        ssa.use_var(&mut func, i32_var, I32, block0);
-        ssa.use_var(&mut func, f32_var, F32, block0);
-        ssa.use_var(&mut func, f64_var, F64, block0);
-        ssa.use_var(&mut func, i8_var, I8, block0);
-        ssa.use_var(&mut func, f32x4_var, F32X4, block0);
+        ssa.use_var(&mut func, i32_var, I32, block0).unwrap();
+        ssa.use_var(&mut func, f32_var, F32, block0).unwrap();
+        ssa.use_var(&mut func, f64_var, F64, block0).unwrap();
+        ssa.use_var(&mut func, i8_var, I8, block0).unwrap();
+        ssa.use_var(&mut func, f32x4_var, F32X4, block0).unwrap();
         assert_eq!(func.dfg.num_block_params(block0), 0);
     }
 
@@ -1118,15 +1200,16 @@ mod tests {
         let mut ssa = SSABuilder::default();
         let block0 = func.dfg.make_block();
         ssa.declare_block(block0);
-        ssa.seal_block(block0, &mut func);
+        ssa.seal_block(block0, &mut func).unwrap();
         let x_var = Variable::new(0);
         assert_eq!(func.dfg.num_block_params(block0), 0);
-        ssa.use_var(&mut func, x_var, I32, block0);
+        ssa.use_var(&mut func, x_var, I32, block0).unwrap();
         assert_eq!(func.dfg.num_block_params(block0), 0);
         assert_eq!(
             func.dfg.insts[func.layout.first_inst(block0).unwrap()].opcode(),
             Opcode::Iconst
         );
+        assert_eq!(ssa.undefined_uses(), [(x_var, block0)]);
     }
 
     #[test]
@@ -1140,9 +1223,13 @@ mod tests {
         ssa.declare_block(block0);
         let x_var = Variable::new(0);
         assert_eq!(func.dfg.num_block_params(block0), 0);
-        ssa.use_var(&mut func, x_var, I32, block0);
+        let (x_param, _) = ssa.use_var(&mut func, x_var, I32, block0).unwrap();
         assert_eq!(func.dfg.num_block_params(block0), 1);
-        ssa.seal_block(block0, &mut func);
+        assert!(ssa.pending_params(block0, &func).eq([(x_var, x_param)]));
+        assert!(ssa.undefined_uses().is_empty());
+        ssa.seal_block(block0, &mut func).unwrap();
+        assert_eq!(ssa.pending_params(block0, &func).count(), 0);
+        assert_eq!(ssa.undefined_uses(), [(x_var, block0)]);
         assert_eq!(func.dfg.num_block_params(block0), 0);
         assert_eq!(
             func.dfg.insts[func.layout.first_inst(block0).unwrap()].opcode(),
@@ -1169,7 +1256,7 @@ mod tests {
 
         // block0
         ssa.declare_block(block0);
-        ssa.seal_block(block0, &mut func);
+        ssa.seal_block(block0, &mut func).unwrap();
         {
             let mut cur = FuncCursor::new(&mut func).at_bottom(block0);
             cur.ins().return_(&[]);
@@ -1180,11 +1267,11 @@ mod tests {
         {
             let mut cur = FuncCursor::new(&mut func).at_bottom(block1);
             let x_var = Variable::new(0);
-            let x_val = ssa.use_var(&mut cur.func, x_var, I32, block1).0;
+            let x_val = ssa.use_var(&mut cur.func, x_var, I32, block1).unwrap().0;
             let brif = cur.ins().brif(x_val, block1, &[], block1, &[]);
             ssa.declare_block_predecessor(block1, brif);
         }
-        ssa.seal_block(block1, &mut func);
+        ssa.seal_block(block1, &mut func).unwrap();
 
         let flags = settings::Flags::new(settings::builder());
         match verify_function(&func, &flags) {
@@ -1221,7 +1308,7 @@ mod tests {
 
         // block0
         ssa.declare_block(block0);
-        ssa.seal_block(block0, &mut func);
+        ssa.seal_block(block0, &mut func).unwrap();
         {
             let mut cur = FuncCursor::new(&mut func).at_bottom(block0);
             cur.ins().return_(&[]);
@@ -1232,7 +1319,7 @@ mod tests {
         let brif = {
             let mut cur = FuncCursor::new(&mut func).at_bottom(block1);
             let x_var = Variable::new(0);
-            let x_val = ssa.use_var(&mut cur.func, x_var, I32, block1).0;
+            let x_val = ssa.use_var(&mut cur.func, x_var, I32, block1).unwrap().0;
             cur.ins().brif(x_val, block2, &[], block1, &[])
         };
 
@@ -1240,7 +1327,7 @@ mod tests {
         ssa.declare_block(block2);
         ssa.declare_block_predecessor(block1, brif);
         ssa.declare_block_predecessor(block2, brif);
-        ssa.seal_block(block2, &mut func);
+        ssa.seal_block(block2, &mut func).unwrap();
         let jump_block2_block1 = {
             let mut cur = FuncCursor::new(&mut func).at_bottom(block2);
             cur.ins().jump(block1, &[])
@@ -1248,7 +1335,7 @@ mod tests {
 
         // seal block1
         ssa.declare_block_predecessor(block1, jump_block2_block1);
-        ssa.seal_block(block1, &mut func);
+        ssa.seal_block(block1, &mut func).unwrap();
         let flags = settings::Flags::new(settings::builder());
         match verify_function(&func, &flags) {
             Ok(()) => {}
@@ -1311,7 +1398,7 @@ mod tests {
         {
             let mut cur = FuncCursor::new(&mut func).at_bottom(block2);
 
-            let _ = ssa.use_var(&mut cur.func, var0, I32, block2).0;
+            let _ = ssa.use_var(&mut cur.func, var0, I32, block2).unwrap().0;
             let var0_iconst = cur.ins().iconst(I32, 1);
             ssa.def_var(var0, var0_iconst, block2);
 
@@ -1320,6 +1407,6 @@ mod tests {
         }
 
         // The sealing algorithm would enter a infinite loop here
-        ssa.seal_all_blocks(&mut func);
+        ssa.seal_all_blocks(&mut func).unwrap();
     }
 }
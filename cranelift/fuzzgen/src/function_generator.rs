@@ -818,7 +818,9 @@ static OPCODE_SIGNATURES: LazyLock<Vec<OpcodeSignature>> = LazyLock::new(|| {
                 | Opcode::Jump
                 | Opcode::Return
                 | Opcode::ReturnCall
-                | Opcode::ReturnCallIndirect => false,
+                | Opcode::ReturnCallIndirect
+                | Opcode::TryCall
+                | Opcode::TryCallIndirect => false,
 
                 // Constants are generated outside of `generate_instructions`
                 Opcode::Iconst => false,
@@ -184,6 +184,15 @@ where
         //   aarch64: https://github.com/bytecodealliance/wasmtime/issues/2735
         let bool_settings = [
             "enable_alias_analysis",
+            "enable_simplify_cfg",
+            "enable_if_conversion",
+            "enable_tail_duplication",
+            "enable_div_const_folding",
+            "enable_licm_sinking",
+            "enable_code_sinking",
+            "enable_load_merging",
+            "enable_instruction_scheduling",
+            "enable_cold_block_outlining",
             "enable_safepoints",
             "unwind_info",
             "preserve_frame_pointers",
@@ -119,10 +119,31 @@ impl<'a> Interpreter<'a> {
                 ControlFlow::Call(called_function, arguments) => {
                     match self.call(called_function, &arguments)? {
                         ControlFlow::Return(rets) => {
-                            self.state
-                                .current_frame_mut()
-                                .set_all(function.dfg.inst_results(inst), rets.to_vec());
-                            maybe_inst = layout.next_inst(inst)
+                            let dfg = &function.dfg;
+                            match dfg.insts[inst].branch_destination(&dfg.jump_tables).first() {
+                                // A `try_call` passes the returned values to its normal block,
+                                // followed by the block call arguments. Unwinding isn't modeled,
+                                // so the exception block is never taken.
+                                Some(normal) => {
+                                    let block = normal.block(&dfg.value_lists);
+                                    let mut values = rets.to_vec();
+                                    values.extend(
+                                        self.state
+                                            .collect_values(normal.args_slice(&dfg.value_lists)),
+                                    );
+                                    trace!("Block: {}", block);
+                                    self.state
+                                        .current_frame_mut()
+                                        .set_all(dfg.block_params(block), values);
+                                    maybe_inst = layout.first_inst(block)
+                                }
+                                None => {
+                                    self.state
+                                        .current_frame_mut()
+                                        .set_all(dfg.inst_results(inst), rets.to_vec());
+                                    maybe_inst = layout.next_inst(inst)
+                                }
+                            }
                         }
                         ControlFlow::Trap(trap) => return Ok(ControlFlow::Trap(trap)),
                         cf => {
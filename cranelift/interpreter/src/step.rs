@@ -357,11 +357,11 @@ where
         Opcode::Trapz => trap_when(!arg(0).into_bool()?, CraneliftTrap::User(trap_code())),
         Opcode::Trapnz => trap_when(arg(0).into_bool()?, CraneliftTrap::User(trap_code())),
         Opcode::Return => ControlFlow::Return(args()),
-        Opcode::Call | Opcode::ReturnCall => {
-            let func_ref = if let InstructionData::Call { func_ref, .. } = inst {
-                func_ref
-            } else {
-                unreachable!()
+        Opcode::Call | Opcode::ReturnCall | Opcode::TryCall => {
+            let func_ref = match inst {
+                InstructionData::Call { func_ref, .. }
+                | InstructionData::TryCall { func_ref, .. } => func_ref,
+                _ => unreachable!(),
             };
 
             let curr_func = state.get_current_function();
@@ -385,14 +385,14 @@ where
             };
 
             let make_control_flow = match inst.opcode() {
-                Opcode::Call => ControlFlow::Call,
+                Opcode::Call | Opcode::TryCall => ControlFlow::Call,
                 Opcode::ReturnCall => ControlFlow::ReturnCall,
                 _ => unreachable!(),
             };
 
             call_func(func, args, make_control_flow)?
         }
-        Opcode::CallIndirect | Opcode::ReturnCallIndirect => {
+        Opcode::CallIndirect | Opcode::ReturnCallIndirect | Opcode::TryCallIndirect => {
             let args = args();
             let addr_dv = DataValue::I64(arg(0).into_int_unsigned()? as i64);
             let addr = Address::try_from(addr_dv.clone()).map_err(StepError::MemoryError)?;
@@ -404,7 +404,7 @@ where
             let call_args: SmallVec<[DataValue; 1]> = SmallVec::from(&args[1..]);
 
             let make_control_flow = match inst.opcode() {
-                Opcode::CallIndirect => ControlFlow::Call,
+                Opcode::CallIndirect | Opcode::TryCallIndirect => ControlFlow::Call,
                 Opcode::ReturnCallIndirect => ControlFlow::ReturnCall,
                 _ => unreachable!(),
             };
@@ -739,8 +739,7 @@ where
             assign_multiple(&[sum, DataValueExt::bool(carry, false, types::I8)?])
         }
         Opcode::UaddOverflowTrap => {
-            let sum = DataValueExt::add(arg(0), arg(1))?;
-            let carry = sum < arg(0) && sum < arg(1);
+            let (sum, carry) = arg(0).uadd_overflow(arg(1))?;
             if carry {
                 ControlFlow::Trap(CraneliftTrap::User(trap_code()))
             } else {
@@ -1316,7 +1315,8 @@ pub enum ControlFlow<'a> {
     /// first instruction of `block42` with the values in `v1` and `v2` filling in the block
     /// parameters.
     ContinueAt(Block, SmallVec<[DataValue; 1]>),
-    /// Indicates a call the given [Function] with the supplied arguments.
+    /// Indicates a call the given [Function] with the supplied arguments. After a `try_call`, the
+    /// interpreter continues at the normal block with the returned values.
     Call(&'a Function, SmallVec<[DataValue; 1]>),
     /// Indicates a tail call to the given [Function] with the supplied arguments.
     ReturnCall(&'a Function, SmallVec<[DataValue; 1]>),
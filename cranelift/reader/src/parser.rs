@@ -21,8 +21,9 @@ use cranelift_codegen::ir::{self, UserExternalNameRef};
 use cranelift_codegen::ir::{
     AbiParam, ArgumentExtension, ArgumentPurpose, Block, Constant, ConstantData, DynamicStackSlot,
     DynamicStackSlotData, DynamicTypeData, ExtFuncData, ExternalName, FuncRef, Function,
-    GlobalValue, GlobalValueData, JumpTableData, MemFlags, MemoryTypeData, MemoryTypeField, Opcode,
-    SigRef, Signature, StackSlot, StackSlotData, StackSlotKind, UserFuncName, Value,
+    GlobalValue, GlobalValueData, JumpTable, JumpTableData, MemFlags, MemoryTypeData,
+    MemoryTypeField, Opcode, SigRef, Signature, StackSlot, StackSlotData, StackSlotKind,
+    UserFuncName, Value,
 };
 use cranelift_codegen::isa::{self, CallConv};
 use cranelift_codegen::packed_option::ReservedValue;
@@ -106,6 +107,9 @@ pub struct ParseOptions<'a> {
     pub unwind_info: bool,
     /// Default for machine_code_cfg_info setting (enabled or disabled).
     pub machine_code_cfg_info: bool,
+    /// Shared settings like `opt_level=speed`, applied before the `set` commands in the file.
+    /// Settings in the file take precedence.
+    pub settings: &'a [String],
 }
 
 impl Default for ParseOptions<'_> {
@@ -116,6 +120,7 @@ impl Default for ParseOptions<'_> {
             default_calling_convention: CallConv::Fast,
             unwind_info: false,
             machine_code_cfg_info: false,
+            settings: &[],
         }
     }
 }
@@ -1084,6 +1089,12 @@ impl<'a> Parser<'a> {
             .set("unwind_info", bool_to_str(options.unwind_info))
             .expect("unwind_info option should be present");
 
+        isaspec::parse_options(
+            options.settings.iter().map(String::as_str),
+            &mut flag_builder,
+            self.loc,
+        )?;
+
         while let Some(Token::Identifier(command)) = self.token() {
             match command {
                 "set" => {
@@ -2837,6 +2848,25 @@ impl<'a> Parser<'a> {
         Ok(dv)
     }
 
+    // Parse the normal and exception destinations of a `try_call` into a jump table.
+    //
+    // try-call-destinations ::= "," Block(normal) [value-list] "," Block(exception) [value-list]
+    fn parse_try_call_destinations(&mut self, ctx: &mut Context) -> ParseResult<JumpTable> {
+        self.match_token(Token::Comma, "expected ',' between operands")?;
+        let block_num = self.match_block("expected normal return block")?;
+        let args = self.parse_opt_value_list()?;
+        let block_normal = ctx.function.dfg.block_call(block_num, &args);
+        self.match_token(Token::Comma, "expected ',' between operands")?;
+        let block_num = self.match_block("expected exception block")?;
+        let args = self.parse_opt_value_list()?;
+        let block_exception = ctx.function.dfg.block_call(block_num, &args);
+        Ok(ctx
+            .function
+            .dfg
+            .jump_tables
+            .push(JumpTableData::new(block_normal, &[block_exception])))
+    }
+
     // Parse the operands following the instruction opcode.
     // This depends on the format of the opcode.
     fn parse_inst_operands(
@@ -3071,6 +3101,20 @@ impl<'a> Parser<'a> {
                     args: args.into_value_list(&[], &mut ctx.function.dfg.value_lists),
                 }
             }
+            InstructionFormat::TryCall => {
+                let func_ref = self.match_fn("expected function reference")?;
+                ctx.check_fn(func_ref, self.loc)?;
+                self.match_token(Token::LPar, "expected '(' before arguments")?;
+                let args = self.parse_value_list()?;
+                self.match_token(Token::RPar, "expected ')' after arguments")?;
+                let table = self.parse_try_call_destinations(ctx)?;
+                InstructionData::TryCall {
+                    opcode,
+                    func_ref,
+                    table,
+                    args: args.into_value_list(&[], &mut ctx.function.dfg.value_lists),
+                }
+            }
             InstructionFormat::CallIndirect => {
                 let sig_ref = self.match_sig("expected signature reference")?;
                 ctx.check_sig(sig_ref, self.loc)?;
@@ -3085,6 +3129,22 @@ impl<'a> Parser<'a> {
                     args: args.into_value_list(&[callee], &mut ctx.function.dfg.value_lists),
                 }
             }
+            InstructionFormat::TryCallIndirect => {
+                let sig_ref = self.match_sig("expected signature reference")?;
+                ctx.check_sig(sig_ref, self.loc)?;
+                self.match_token(Token::Comma, "expected ',' between operands")?;
+                let callee = self.match_value("expected SSA value callee operand")?;
+                self.match_token(Token::LPar, "expected '(' before arguments")?;
+                let args = self.parse_value_list()?;
+                self.match_token(Token::RPar, "expected ')' after arguments")?;
+                let table = self.parse_try_call_destinations(ctx)?;
+                InstructionData::TryCallIndirect {
+                    opcode,
+                    sig_ref,
+                    table,
+                    args: args.into_value_list(&[callee], &mut ctx.function.dfg.value_lists),
+                }
+            }
             InstructionFormat::FuncAddr => {
                 let func_ref = self.match_fn("expected function reference")?;
                 ctx.check_fn(func_ref, self.loc)?;
@@ -3494,6 +3554,83 @@ mod tests {
         assert!(!is_warning);
     }
 
+    #[test]
+    fn dedup_signatures() {
+        use cranelift_codegen::settings;
+        use cranelift_codegen::verifier::verify_function;
+
+        let mut func = Parser::new(
+            "function %f(i64) {
+                sig0 = (i32) -> i32 system_v
+                sig1 = (i32) -> i32 system_v
+                sig2 = (i64) system_v
+                sig3 = (i32) -> i32 system_v
+                fn0 = %g sig1
+                fn1 = %h sig2
+            block0(v0: i64):
+                v1 = iconst.i32 0
+                v2 = call_indirect sig3, v0(v1)
+                call fn1(v0)
+                v3 = call fn0(v2)
+                return
+            }",
+        )
+        .parse_function()
+        .unwrap()
+        .0;
+
+        assert_eq!(func.dfg.dedup_signatures(), 2);
+        verify_function(&func, &settings::Flags::new(settings::builder())).unwrap();
+
+        let [sig0, sig1] = [0, 1].map(SigRef::from_u32);
+        assert_eq!(func.dfg.signatures.len(), 2);
+        assert_eq!(
+            func.dfg.signatures[sig0].to_string(),
+            "(i32) -> i32 system_v"
+        );
+        assert_eq!(func.dfg.signatures[sig1].to_string(), "(i64) system_v");
+        let [fn0, fn1] = [0, 1].map(FuncRef::from_u32);
+        assert_eq!(func.dfg.ext_funcs[fn0].signature, sig0);
+        assert_eq!(func.dfg.ext_funcs[fn1].signature, sig1);
+        let call_indirect = func.layout.first_inst(func.entry_block()).unwrap();
+        let call_indirect = func.layout.next_inst(call_indirect).unwrap();
+        assert_eq!(func.dfg.call_signature(call_indirect), Some(sig0));
+        assert!(!func.display().to_string().contains("sig2"));
+    }
+
+    #[test]
+    fn dedup_signatures_try_call_indirect() {
+        use cranelift_codegen::settings;
+        use cranelift_codegen::verifier::verify_function;
+
+        let mut func = Parser::new(
+            "function %f(i64, i64) -> i64, i64 {
+                sig0 = (i32)
+                sig1 = (i64) -> i64, i64
+                sig2 = (i64) -> i64, i64
+            block0(v0: i64, v1: i64):
+                try_call_indirect sig2, v1(v0), block1(v0), block2
+            block1(v2: i64, v3: i64, v4: i64):
+                v5 = iadd v2, v4
+                return v5, v3
+            block2(v6: i64):
+                return v6, v6
+            }",
+        )
+        .parse_function()
+        .unwrap()
+        .0;
+
+        assert_eq!(func.dedup_signatures(), 1);
+        verify_function(&func, &settings::Flags::new(settings::builder())).unwrap();
+
+        let sig1 = SigRef::from_u32(1);
+        assert_eq!(func.dfg.signatures.len(), 2);
+        let try_call_indirect = func.layout.first_inst(func.entry_block()).unwrap();
+        assert_eq!(func.dfg.call_signature(try_call_indirect), Some(sig1));
+        assert!(!func.display().to_string().contains("sig2"));
+    }
+
     #[test]
     fn duplicate_fn() {
         let ParseError {
@@ -3617,6 +3754,42 @@ mod tests {
         }
     }
 
+    #[test]
+    fn shared_settings() {
+        let shared = [
+            "enable_float=false".to_string(),
+            "opt_level=speed".to_string(),
+        ];
+        let options = ParseOptions {
+            settings: &shared,
+            ..ParseOptions::default()
+        };
+        let tf = parse_test(
+            "set opt_level=none
+             function %foo() system_v {}",
+            options,
+        )
+        .unwrap();
+        match tf.isa_spec {
+            IsaSpec::None(s) => {
+                assert!(!s.enable_float());
+                // The file's own setting takes precedence.
+                assert_eq!(s.opt_level(), settings::OptLevel::None);
+            }
+            _ => panic!("unexpected ISAs"),
+        }
+
+        let shared = ["no_such_setting=1".to_string()];
+        let options = ParseOptions {
+            settings: &shared,
+            ..ParseOptions::default()
+        };
+        let err = parse_test("function %foo() system_v {}", options)
+            .err()
+            .unwrap();
+        assert_eq!(err.message, "unknown setting 'no_such_setting=1'");
+    }
+
     #[test]
     fn user_function_name() {
         // Valid characters in the name:
@@ -3666,6 +3839,32 @@ mod tests {
         assert!(parser.parse_function().is_err());
     }
 
+    #[test]
+    fn function_header_round_trip() {
+        for (header, name) in [
+            (
+                "function %foo(i32, i64) -> i32 system_v {",
+                ir::UserFuncName::testcase("foo"),
+            ),
+            (
+                "function u0:7(i32, i64) -> i32 system_v {",
+                ir::UserFuncName::user(0, 7),
+            ),
+        ] {
+            let text = format!("{header}\nblock0(v0: i32, v1: i64):\n    return v0\n}}\n");
+            let func = Parser::new(&text).parse_function().unwrap().0;
+            assert_eq!(func.name, name);
+            assert_eq!(func.signature.params.len(), 2);
+            assert_eq!(func.signature.returns[0].value_type, I32);
+            assert_eq!(func.signature.call_conv, CallConv::SystemV);
+
+            let printed = func.display().to_string();
+            assert_eq!(printed.lines().next(), Some(header));
+            let reparsed = Parser::new(&printed).parse_function().unwrap().0;
+            assert_eq!(reparsed.display().to_string(), printed);
+        }
+    }
+
     #[test]
     fn change_default_calling_convention() {
         let code = "function %test() {
@@ -3927,4 +4126,35 @@ mod tests {
         assert!(func.layout.is_cold(Block::from_u32(1)));
         assert!(!func.layout.is_cold(Block::from_u32(2)));
     }
+
+    #[test]
+    fn parse_try_call() {
+        let code = "function %test(i32, i64) -> i32 fast {
+    sig0 = (i32) -> i32 fast
+    fn0 = %f sig0
+
+block0(v0: i32, v1: i64):
+    try_call fn0(v0), block1, block2(v0)
+
+block1(v2: i32):
+    try_call_indirect.i64 sig0, v1(v2), block3(v2), block2(v0)
+
+block2(v3: i64, v4: i32):
+    return v4
+
+block3(v5: i32, v6: i32):
+    v7 = iadd v5, v6
+    return v7
+}
+";
+
+        let flags = cranelift_codegen::settings::Flags::new(cranelift_codegen::settings::builder());
+        let func = Parser::new(code).parse_function().unwrap().0;
+        assert_eq!(func.to_string(), code);
+        cranelift_codegen::verify_function(&func, &flags).unwrap();
+
+        // The call results are passed to the normal block, not defined by the instruction.
+        let bad = code.replace("    try_call fn0", "    v8 = try_call fn0");
+        assert!(Parser::new(&bad).parse_function().is_err());
+    }
 }
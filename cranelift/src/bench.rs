@@ -0,0 +1,153 @@
+//! CLI tool to measure how long Cranelift takes to parse, verify and compile IR files, and how
+//! large the IR data structures get while doing so.
+
+use crate::utils::{iterate_files, read_to_string};
+use anyhow::{Context as _, Result};
+use clap::Parser;
+use cranelift_codegen::ir::MemoryReport;
+use cranelift_codegen::isa::{OwnedTargetIsa, TargetIsa};
+use cranelift_codegen::print_errors::{pretty_error, pretty_verifier_error};
+use cranelift_codegen::timing::{self, Pass};
+use cranelift_codegen::Context;
+use cranelift_reader::{parse_sets_and_triple, parse_test, OwnedFlagsOrIsa, ParseOptions};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Measure compile time and IR memory usage of Cranelift IR files
+#[derive(Parser)]
+pub struct Options {
+    /// Number of times to parse and compile each file
+    #[arg(short = 'n', long = "iterations", default_value_t = 10)]
+    iterations: u32,
+
+    /// Print pass timing report
+    #[arg(short = 'T')]
+    report_times: bool,
+
+    /// Configure Cranelift settings
+    #[arg(long = "set")]
+    settings: Vec<String>,
+
+    /// Specify the Cranelift target. Defaults to the target in each file, or the host.
+    #[arg(long = "target")]
+    target: Option<String>,
+
+    /// Specify input files or directories to be used
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+}
+
+/// Wall-clock time spent in each phase, summed over all iterations.
+#[derive(Default)]
+struct PhaseTimes {
+    parse: Duration,
+    verify: Duration,
+    compile: Duration,
+}
+
+pub fn run(options: &Options) -> Result<()> {
+    anyhow::ensure!(
+        options.iterations > 0,
+        "the iteration count must be positive"
+    );
+    let parsed = parse_sets_and_triple(
+        &options.settings,
+        options.target.as_deref().unwrap_or_default(),
+    )?;
+
+    // Don't attribute anything timed before now to the first file.
+    let _ = timing::take_current();
+
+    for path in iterate_files(&options.files) {
+        bench_file(options, &path, &parsed)?;
+    }
+    Ok(())
+}
+
+fn bench_file(options: &Options, path: &Path, parsed: &OwnedFlagsOrIsa) -> Result<()> {
+    let name = path.display();
+    let buffer = read_to_string(path)?;
+    let mut phases = PhaseTimes::default();
+
+    let mut test_file = None;
+    for _ in 0..options.iterations {
+        let start = Instant::now();
+        let file = parse_test(&buffer, ParseOptions::default())
+            .with_context(|| format!("failed to parse {name}"))?;
+        phases.parse += start.elapsed();
+        test_file = Some(file);
+    }
+    let test_file = test_file.unwrap();
+
+    // Prefer the target from the command line, then the one named in the file, and fall back
+    // to the host.
+    let host_isa: OwnedTargetIsa;
+    let isa: &dyn TargetIsa = match parsed {
+        OwnedFlagsOrIsa::Isa(isa) => &**isa,
+        OwnedFlagsOrIsa::Flags(flags) => match test_file.isa_spec.unique_isa() {
+            Some(isa) => isa,
+            None => {
+                host_isa = cranelift_native::builder()
+                    .map_err(|s| anyhow::anyhow!("{}", s))?
+                    .finish(flags.clone())?;
+                &*host_isa
+            }
+        },
+    };
+
+    let mut peak = MemoryReport::default();
+    let mut context = Context::new();
+    for (func, _) in &test_file.functions {
+        peak = peak.max(func.memory_report());
+
+        for _ in 0..options.iterations {
+            context.clear();
+            context.func = func.clone();
+
+            let start = Instant::now();
+            context.verify(isa).map_err(|errors| {
+                anyhow::anyhow!("{}", pretty_verifier_error(&context.func, None, errors))
+            })?;
+            phases.verify += start.elapsed();
+
+            let start = Instant::now();
+            context
+                .compile(isa, &mut Default::default())
+                .map_err(|err| anyhow::anyhow!("{}", pretty_error(&err.func, err.inner)))?;
+            phases.compile += start.elapsed();
+        }
+
+        // Optimization and legalization work in place, so this is the function at its largest.
+        peak = peak.max(context.func.memory_report());
+    }
+
+    let n = options.iterations;
+    println!(
+        "{name}: {} function(s) for {}, {n} iteration(s)",
+        test_file.functions.len(),
+        isa.triple()
+    );
+    println!("  parse:   {:?} per iteration", phases.parse / n);
+    println!("  verify:  {:?} per iteration", phases.verify / n);
+    println!("  compile: {:?} per iteration", phases.compile / n);
+
+    // The backend phases are only measured when the `timing` feature is enabled.
+    let pass_times = timing::take_current();
+    for (phase, pass) in [
+        ("lower:   ", Pass::vcode_lower),
+        ("regalloc:", Pass::regalloc),
+        ("emit:    ", Pass::vcode_emit),
+    ] {
+        println!("    {phase} {:?} per iteration", pass_times.get(pass) / n);
+    }
+    println!("peak IR sizes:");
+    for line in peak.to_string().lines() {
+        println!("  {line}");
+    }
+
+    if options.report_times {
+        print!("{pass_times}");
+    }
+
+    Ok(())
+}
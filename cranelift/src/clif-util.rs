@@ -1,12 +1,14 @@
 use clap::Parser;
 use std::path::PathBuf;
 
+mod bench;
 mod bugpoint;
 mod cat;
 mod compile;
 mod disasm;
 mod interpret;
 mod print_cfg;
+mod print_dfg;
 mod run;
 mod utils;
 
@@ -21,9 +23,11 @@ enum Commands {
     Interpret(interpret::Options),
     Cat(cat::Options),
     PrintCfg(print_cfg::Options),
+    PrintDfg(print_dfg::Options),
     Compile(compile::Options),
     Pass(PassOptions),
     Bugpoint(bugpoint::Options),
+    Bench(bench::Options),
 
     #[cfg(feature = "souper-harvest")]
     SouperHarvest(souper_harvest::Options),
@@ -42,6 +46,11 @@ struct TestOptions {
     #[arg(short = 'T')]
     time_passes: bool,
 
+    /// Apply a setting like `opt_level=speed` to every test. Settings in a test file take
+    /// precedence.
+    #[arg(long = "set")]
+    settings: Vec<String>,
+
     /// Specify an input file to be used. Use '-' for stdin.
     #[arg(required = true)]
     files: Vec<PathBuf>,
@@ -81,8 +90,10 @@ fn main() -> anyhow::Result<()> {
         Commands::Run(r) => run::run(&r)?,
         Commands::Interpret(i) => interpret::run(&i)?,
         Commands::PrintCfg(p) => print_cfg::run(&p)?,
+        Commands::PrintDfg(p) => print_dfg::run(&p)?,
         Commands::Compile(c) => compile::run(&c)?,
         Commands::Bugpoint(b) => bugpoint::run(&b)?,
+        Commands::Bench(b) => bench::run(&b)?,
 
         #[cfg(feature = "souper-harvest")]
         Commands::SouperHarvest(s) => souper_harvest::run(&s)?,
@@ -96,6 +107,7 @@ fn main() -> anyhow::Result<()> {
             cranelift_filetests::run(
                 t.verbose,
                 t.time_passes,
+                &t.settings,
                 &t.files
                     .iter()
                     .map(|f| f.display().to_string())
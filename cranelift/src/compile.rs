@@ -28,6 +28,19 @@ pub struct Options {
     #[arg(short = 'D', long)]
     disasm: bool,
 
+    /// Print statistics about aliases, detached values and value list pool usage after
+    /// compilation
+    #[arg(long)]
+    dfg_stats: bool,
+
+    /// Print the Cranelift IR after every pass
+    #[arg(long)]
+    print_after_all: bool,
+
+    /// Print the Cranelift IR after the given comma-separated passes, e.g. `egraph,legalize`
+    #[arg(long, value_delimiter = ',')]
+    print_after: Vec<String>,
+
     /// Configure Cranelift settings
     #[arg(long = "set")]
     settings: Vec<String>,
@@ -99,6 +112,17 @@ fn handle_module(
         let mut context = Context::new();
         context.func = func;
 
+        if options.print_after_all || !options.print_after.is_empty() {
+            let all = options.print_after_all;
+            let passes = options.print_after.clone();
+            context.set_pass_observer(Box::new(move |pass, func| {
+                if all || passes.iter().any(|p| p == pass) {
+                    println!("; After {pass}:");
+                    println!("{}", func.display());
+                }
+            }));
+        }
+
         // Compile and encode the result to machine code.
         let compiled_code = context
             .compile(isa, &mut Default::default())
@@ -123,6 +147,11 @@ fn handle_module(
             println!("{}", context.func.display());
         }
 
+        if options.dfg_stats {
+            println!("; DFG stats for {}:", context.func.name);
+            print!("{}", context.func.dfg_stats());
+        }
+
         if options.disasm {
             let result = context.compiled_code().unwrap();
             print_all(
@@ -0,0 +1,225 @@
+//! The `print-dfg` sub-command.
+//!
+//! Read a series of Cranelift IR files and print the value dependence graphs of their functions,
+//! either in graphviz format or as JSON for use by external tools.
+
+use crate::utils::read_to_string;
+use anyhow::Result;
+use clap::Parser;
+use cranelift_codegen::dfg_printer::DFGPrinter;
+use cranelift_codegen::ir::{Block, Function, Value};
+use cranelift_reader::parse_functions;
+use serde_derive::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Prints out the value dependence graph in GraphViz Dot format or JSON
+#[derive(Parser)]
+pub struct Options {
+    /// Print a JSON description of the instructions and values instead of a graph
+    #[arg(long)]
+    json: bool,
+
+    /// Only print the given block, e.g. `block3`
+    #[arg(long, value_parser = parse_block)]
+    block: Option<Block>,
+
+    /// Specify an input file to be used. Use '-' for stdin.
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+}
+
+fn parse_block(s: &str) -> Result<Block> {
+    s.strip_prefix("block")
+        .and_then(|n| n.parse().ok())
+        .and_then(Block::with_number)
+        .ok_or_else(|| anyhow::anyhow!("expected a block name like `block3`, got `{s}`"))
+}
+
+/// The JSON description of a function's data flow graph.
+#[derive(Serialize, Deserialize, Debug)]
+struct FunctionJson {
+    name: String,
+    blocks: Vec<BlockJson>,
+    /// Every alias in the function, whether or not it is used.
+    aliases: Vec<AliasJson>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct BlockJson {
+    block: String,
+    params: Vec<ValueJson>,
+    insts: Vec<InstJson>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct InstJson {
+    inst: String,
+    opcode: String,
+    /// The instruction as it would be printed in textual IR.
+    text: String,
+    /// All value operands, including branch arguments, before resolving aliases.
+    operands: Vec<String>,
+    results: Vec<ValueJson>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ValueJson {
+    value: String,
+    #[serde(rename = "type")]
+    ty: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct AliasJson {
+    value: String,
+    original: String,
+}
+
+pub fn run(options: &Options) -> Result<()> {
+    for (i, f) in options.files.iter().enumerate() {
+        if i != 0 {
+            println!();
+        }
+        print_dfg(options, f)?
+    }
+    Ok(())
+}
+
+fn print_dfg(options: &Options, path: &Path) -> Result<()> {
+    let buffer = read_to_string(path)?;
+    let items = parse_functions(&buffer)?;
+
+    if options.json {
+        let funcs: Vec<_> = items
+            .iter()
+            .map(|func| function_json(func, options.block))
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&funcs)?);
+        return Ok(());
+    }
+
+    for (idx, func) in items.into_iter().enumerate() {
+        if idx != 0 {
+            println!();
+        }
+        match options.block {
+            Some(block) => print!("{}", DFGPrinter::with_block(&func, block)),
+            None => print!("{}", DFGPrinter::new(&func)),
+        }
+    }
+
+    Ok(())
+}
+
+fn function_json(func: &Function, only: Option<Block>) -> FunctionJson {
+    let value_json = |value: Value| ValueJson {
+        value: value.to_string(),
+        ty: func.dfg.value_type(value).to_string(),
+    };
+
+    let blocks = func
+        .layout
+        .blocks()
+        .filter(|&block| only.map_or(true, |b| b == block))
+        .map(|block| BlockJson {
+            block: block.to_string(),
+            params: func
+                .dfg
+                .block_params(block)
+                .iter()
+                .map(|&v| value_json(v))
+                .collect(),
+            insts: func
+                .layout
+                .block_insts(block)
+                .map(|inst| InstJson {
+                    inst: inst.to_string(),
+                    opcode: func.dfg.insts[inst].opcode().to_string(),
+                    text: func.dfg.display_inst(inst).to_string(),
+                    operands: func.dfg.inst_values(inst).map(|v| v.to_string()).collect(),
+                    results: func
+                        .dfg
+                        .inst_results(inst)
+                        .iter()
+                        .map(|&v| value_json(v))
+                        .collect(),
+                })
+                .collect(),
+        })
+        .collect();
+
+    let aliases = func
+        .dfg
+        .values()
+        .filter_map(|value| {
+            let original = func.dfg.value_alias_dest_for_serialization(value)?;
+            Some(AliasJson {
+                value: value.to_string(),
+                original: original.to_string(),
+            })
+        })
+        .collect();
+
+    FunctionJson {
+        name: func.name.to_string(),
+        blocks,
+        aliases,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CLIF: &str = "
+        function %f(i32, i64) -> i64 {
+        block0(v0: i32, v1: i64):
+            v2 = iconst.i64 1
+            brif v0, block1(v2), block2
+
+        block1(v3: i64):
+            v4 -> v3
+            v5 = iadd v1, v4
+            return v5
+
+        block2:
+            return v1
+        }
+    ";
+
+    #[test]
+    fn json_round_trip() {
+        let funcs = parse_functions(CLIF).unwrap();
+        let func = &funcs[0];
+
+        let json = serde_json::to_string(&function_json(func, None)).unwrap();
+        let parsed: FunctionJson = serde_json::from_str(&json).unwrap();
+
+        let insts: Vec<_> = parsed.blocks.iter().flat_map(|b| &b.insts).collect();
+        assert_eq!(insts.len(), func.dfg.num_insts());
+        let params: usize = parsed.blocks.iter().map(|b| b.params.len()).sum();
+        assert_eq!(params, 3);
+        let results: usize = insts.iter().map(|i| i.results.len()).sum();
+        assert_eq!(results, 2);
+
+        assert_eq!(insts[1].opcode, "brif");
+        assert_eq!(insts[1].operands, ["v0", "v2"]);
+        assert_eq!(insts[2].operands, ["v1", "v4"]);
+        assert_eq!(parsed.aliases.len(), 1);
+        assert_eq!(parsed.aliases[0].value, "v4");
+        assert_eq!(parsed.aliases[0].original, "v3");
+    }
+
+    #[test]
+    fn json_block_filter() {
+        let funcs = parse_functions(CLIF).unwrap();
+        let block = parse_block("block1").unwrap();
+
+        let json = function_json(&funcs[0], Some(block));
+        assert_eq!(json.blocks.len(), 1);
+        assert_eq!(json.blocks[0].block, "block1");
+        assert_eq!(json.blocks[0].params[0].ty, "i64");
+
+        assert!(parse_block("ebb1").is_err());
+    }
+}
@@ -273,6 +273,15 @@ impl wasmtime_environ::Compiler for Compiler {
             &mut func_env,
         )?;
 
+        // The translator doesn't emit code that it knows is dead, but blocks can still end up
+        // unreachable; remove them so that later passes don't spend time on them. This needs the
+        // CFG, so only do it when the translator saw a block without predecessors.
+        if compiler.cx.func_translator.has_unreachable_blocks() {
+            context
+                .cleanup_unreachable()
+                .map_err(|error| CompileError::Codegen(pretty_error(&context.func, error)))?;
+        }
+
         if let Some(path) = &self.clif_dir {
             use std::io::Write;
 
@@ -976,9 +985,9 @@ impl FunctionCompiler<'_> {
 
         // Give wasm functions, user defined code, a "preferred" alignment
         // instead of the minimum alignment as this can help perf in niche
-        // situations.
+        // situations, unless optimizing for size.
         let preferred_alignment = if body_and_tunables.is_some() {
-            self.compiler.isa.function_alignment().preferred
+            self.compiler.isa.opt_function_alignment()
         } else {
             1
         };
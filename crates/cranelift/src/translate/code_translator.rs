@@ -94,7 +94,7 @@ use std::vec::Vec;
 use wasmparser::{FuncValidator, MemArg, Operator, WasmModuleResources};
 use wasmtime_environ::{
     wasm_unsupported, DataIndex, ElemIndex, FuncIndex, GlobalIndex, MemoryIndex, Signed,
-    TableIndex, TypeConvert, TypeIndex, Unsigned, WasmRefType, WasmResult,
+    TableIndex, TypeConvert, TypeIndex, Unsigned, WasmError, WasmRefType, WasmResult,
 };
 
 /// Given a `Reachability<T>`, unwrap the inner `T` or, when unreachable, set
@@ -138,7 +138,11 @@ pub fn translate_operator(
          *  disappear in the Cranelift Code
          ***********************************************************************************/
         Operator::LocalGet { local_index } => {
-            let val = builder.use_var(Variable::from_u32(*local_index));
+            // Inside a loop, this may need block parameters for the local, which a function
+            // with a huge number of locals can run out of.
+            let val = builder
+                .try_use_var(Variable::from_u32(*local_index))
+                .map_err(|_| WasmError::ImplLimitExceeded)?;
             state.push1(val);
             let label = ValueLabel::from_u32(*local_index);
             builder.set_val_label(val, label);
@@ -354,6 +358,7 @@ pub fn translate_operator(
             );
         }
         Operator::Else => {
+            state.bounds_checks.reset_scope();
             let i = state.control_stack.len() - 1;
             match state.control_stack[i] {
                 ControlStackFrame::If {
@@ -433,6 +438,7 @@ pub fn translate_operator(
         }
         Operator::End => {
             let frame = state.control_stack.pop().unwrap();
+            state.bounds_checks.exit_scope();
             let next_block = frame.following_code();
             let return_count = frame.num_return_values();
             let return_args = state.peekn_mut(return_count);
@@ -448,9 +454,13 @@ pub fn translate_operator(
             builder.switch_to_block(next_block);
             builder.seal_block(next_block);
 
-            // If it is a loop we also have to seal the body loop block
+            // If it is a loop we also have to seal the body loop block. This is the only block
+            // that locals are used in before it is sealed, so it is the only one that needs
+            // block parameters for them while sealing.
             if let ControlStackFrame::Loop { header, .. } = frame {
-                builder.seal_block(header)
+                builder
+                    .try_seal_block(header)
+                    .map_err(|_| WasmError::ImplLimitExceeded)?;
             }
 
             frame.truncate_value_stack_to_original_size(&mut state.stack);
@@ -528,7 +538,7 @@ pub fn translate_operator(
                         frame.set_branched_to_exit();
                         frame.br_destination()
                     };
-                    data.push(builder.func.dfg.block_call(block, &[]));
+                    data.push(block);
                 }
                 let block = {
                     let i = state.control_stack.len() - 1 - (default as usize);
@@ -536,9 +546,7 @@ pub fn translate_operator(
                     frame.set_branched_to_exit();
                     frame.br_destination()
                 };
-                let block = builder.func.dfg.block_call(block, &[]);
-                let jt = builder.create_jump_table(JumpTableData::new(block, &data));
-                builder.ins().br_table(val, jt);
+                translate_br_table_dispatch(builder, val, &data, block);
             } else {
                 // Here we have jump arguments, but Cranelift's br_table doesn't support them
                 // We then proceed to split the edges going out of the br_table
@@ -555,7 +563,7 @@ pub fn translate_operator(
                             *entry.insert(block)
                         }
                     };
-                    data.push(builder.func.dfg.block_call(branch_block, &[]));
+                    data.push(branch_block);
                 }
                 let default_branch_block = match dest_block_map.entry(default as usize) {
                     hash_map::Entry::Occupied(entry) => *entry.get(),
@@ -565,9 +573,7 @@ pub fn translate_operator(
                         *entry.insert(block)
                     }
                 };
-                let default_branch_block = builder.func.dfg.block_call(default_branch_block, &[]);
-                let jt = builder.create_jump_table(JumpTableData::new(default_branch_block, &data));
-                builder.ins().br_table(val, jt);
+                translate_br_table_dispatch(builder, val, &data, default_branch_block);
                 for (depth, dest_block) in dest_block_sequence {
                     builder.switch_to_block(dest_block);
                     builder.seal_block(dest_block);
@@ -2980,6 +2986,7 @@ fn translate_unreachable_operator(
             state.push_block(ir::Block::reserved_value(), 0, 0);
         }
         Operator::Else => {
+            state.bounds_checks.reset_scope();
             let i = state.control_stack.len() - 1;
             match state.control_stack[i] {
                 ControlStackFrame::If {
@@ -3038,6 +3045,7 @@ fn translate_unreachable_operator(
             let stack = &mut state.stack;
             let control_stack = &mut state.control_stack;
             let frame = control_stack.pop().unwrap();
+            state.bounds_checks.exit_scope();
 
             // Pop unused parameters from stack.
             frame.truncate_value_stack_to_original_size(stack);
@@ -3045,7 +3053,9 @@ fn translate_unreachable_operator(
             let reachable_anyway = match frame {
                 // If it is a loop we also have to seal the body loop block
                 ControlStackFrame::Loop { header, .. } => {
-                    builder.seal_block(header);
+                    builder
+                        .try_seal_block(header)
+                        .map_err(|_| WasmError::ImplLimitExceeded)?;
                     // And loops can't have branches to the end.
                     false
                 }
@@ -3188,6 +3198,7 @@ fn prepare_addr(
         Ok(offset) => bounds_checks::bounds_check_and_compute_addr(
             builder,
             environ,
+            &mut state.bounds_checks,
             &heap,
             index,
             offset,
@@ -3233,6 +3244,7 @@ fn prepare_addr(
             bounds_checks::bounds_check_and_compute_addr(
                 builder,
                 environ,
+                &mut state.bounds_checks,
                 &heap,
                 adjusted_index,
                 0,
@@ -3660,6 +3672,79 @@ fn translate_br_if_args(
     (br_destination, inputs)
 }
 
+/// The minimum average length of the runs of identical targets in a `br_table` for it to be
+/// translated to a binary search over the runs instead of a jump table.
+const BR_TABLE_MIN_AVERAGE_RUN: usize = 8;
+
+/// Branch to `targets[index]`, or to `default` if `index` is out of bounds.
+///
+/// This is a jump table unless the targets form long runs of the same block, as in a large
+/// `br_table` with only a few distinct labels. The jump table would mostly repeat the same
+/// entries then, so this does a binary search over the start indices of the runs instead.
+fn translate_br_table_dispatch(
+    builder: &mut FunctionBuilder,
+    index: ir::Value,
+    targets: &[ir::Block],
+    default: ir::Block,
+) {
+    // The start index and target of each run of identical targets.
+    let runs: Vec<(usize, ir::Block)> = targets
+        .iter()
+        .copied()
+        .enumerate()
+        .dedup_by(|(_, a), (_, b)| a == b)
+        .collect();
+    if runs.is_empty() || runs.len() * BR_TABLE_MIN_AVERAGE_RUN > targets.len() {
+        let data: Vec<_> = targets
+            .iter()
+            .map(|&block| builder.func.dfg.block_call(block, &[]))
+            .collect();
+        let default = builder.func.dfg.block_call(default, &[]);
+        let jt = builder.create_jump_table(JumpTableData::new(default, &data));
+        builder.ins().br_table(index, jt);
+        return;
+    }
+
+    let mut pending = Vec::new();
+    let in_bounds = builder.ins().icmp_imm(
+        IntCC::UnsignedLessThan,
+        index,
+        i64::try_from(targets.len()).unwrap(),
+    );
+    let search = br_table_search_block(builder, &runs, &mut pending);
+    builder.ins().brif(in_bounds, search, &[], default, &[]);
+    while let Some((block, runs)) = pending.pop() {
+        builder.switch_to_block(block);
+        builder.seal_block(block);
+        let (low, high) = runs.split_at(runs.len() / 2);
+        let low_block = br_table_search_block(builder, low, &mut pending);
+        let high_block = br_table_search_block(builder, high, &mut pending);
+        let is_high = builder.ins().icmp_imm(
+            IntCC::UnsignedGreaterThanOrEqual,
+            index,
+            i64::try_from(high[0].0).unwrap(),
+        );
+        builder.ins().brif(is_high, high_block, &[], low_block, &[]);
+    }
+}
+
+/// Get the block to branch to for an index that falls in one of `runs`: the target of the run
+/// if there is only one, or a new block searching them, which is added to `pending`.
+fn br_table_search_block<'a>(
+    builder: &mut FunctionBuilder,
+    runs: &'a [(usize, ir::Block)],
+    pending: &mut Vec<(ir::Block, &'a [(usize, ir::Block)])>,
+) -> ir::Block {
+    match runs {
+        [(_, target)] => *target,
+        _ => {
+            let block = builder.create_block();
+            pending.push((block, runs));
+            block
+        }
+    }
+}
+
 /// Determine the returned value type of a WebAssembly operator
 fn type_of(operator: &Operator) -> Type {
     match operator {
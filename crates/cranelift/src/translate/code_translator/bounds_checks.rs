@@ -21,6 +21,7 @@
 
 use super::Reachability;
 use crate::func_environ::FuncEnvironment;
+use crate::translate::state::BoundsChecks;
 use crate::translate::{HeapData, TargetEnvironment};
 use cranelift_codegen::{
     cursor::{Cursor, FuncCursor},
@@ -39,6 +40,8 @@ use Reachability::*;
 pub fn bounds_check_and_compute_addr(
     builder: &mut FunctionBuilder,
     env: &mut FuncEnvironment<'_>,
+    // Explicit bounds checks that dominate this access.
+    bounds_checks: &mut BoundsChecks,
     heap: &HeapData,
     // Dynamic operand indexing into the heap.
     index: ir::Value,
@@ -237,6 +240,28 @@ pub fn bounds_check_and_compute_addr(
         )));
     }
 
+    // Every case below emits an explicit bounds check. Special case for when
+    // a dominating access already checked the same index value with an
+    // `offset + access_size` at least as large as ours: if that check passed,
+    // then ours would pass too, so elide it.
+    //
+    // With Spectre mitigations every access needs its own guarded address,
+    // and with PCC every access needs its own facts, so only do this when
+    // neither is enabled. Otherwise, remember this check for later accesses.
+    if !spectre_mitigations_enabled && !pcc {
+        let checked_index = builder.func.dfg.resolve_aliases(orig_index);
+        if bounds_checks.record_or_elide(bound_gv, checked_index, offset_and_size) {
+            return Ok(Reachable(compute_addr(
+                &mut builder.cursor(),
+                heap,
+                env.pointer_type(),
+                index,
+                offset,
+                None,
+            )));
+        }
+    }
+
     // Special case for when we can rely on virtual memory, the minimum
     // byte size of this memory fits within the memory reservation, and
     // memory isn't allowed to move. In this situation we know that
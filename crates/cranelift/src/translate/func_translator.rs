@@ -24,6 +24,7 @@ use wasmtime_environ::{TypeConvert, WasmResult};
 pub struct FuncTranslator {
     func_ctx: FunctionBuilderContext,
     state: FuncTranslationState,
+    has_unreachable_blocks: bool,
 }
 
 impl FuncTranslator {
@@ -32,6 +33,7 @@ impl FuncTranslator {
         Self {
             func_ctx: FunctionBuilderContext::new(),
             state: FuncTranslationState::new(),
+            has_unreachable_blocks: false,
         }
     }
 
@@ -41,6 +43,21 @@ impl FuncTranslator {
         &mut self.func_ctx
     }
 
+    /// Returns the number of explicit heap bounds checks that were elided in
+    /// the last translated function, because a dominating check covered them.
+    pub fn elided_bounds_checks(&self) -> usize {
+        self.state.bounds_checks.elided
+    }
+
+    /// Returns `true` if the last translated function may have blocks that
+    /// can't be reached from its entry block.
+    ///
+    /// The translator doesn't emit code that it knows is dead, so this is
+    /// normally `false` and the function doesn't need to be cleaned up.
+    pub fn has_unreachable_blocks(&self) -> bool {
+        self.has_unreachable_blocks
+    }
+
     /// Translate a binary WebAssembly function from a `FunctionBody`.
     ///
     /// See [the WebAssembly specification][wasm].
@@ -91,7 +108,12 @@ impl FuncTranslator {
         parse_local_decls(&mut reader, &mut builder, num_params, environ, validator)?;
         parse_function_body(validator, reader, &mut builder, &mut self.state, environ)?;
 
+        self.has_unreachable_blocks = builder.has_blocks_without_predecessors();
         builder.finalize();
+        log::trace!(
+            "elided {} redundant bounds checks",
+            self.elided_bounds_checks()
+        );
         log::trace!("translated Wasm to CLIF:\n{}", func.display());
         Ok(())
     }
@@ -213,6 +235,7 @@ fn declare_locals(
         }
     };
 
+    builder.reserve_vars(*next_local + count as usize);
     for _ in 0..count {
         let local = Variable::new(*next_local);
         builder.declare_var(local, ty);
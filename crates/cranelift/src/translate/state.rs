@@ -240,6 +240,94 @@ pub struct FuncTranslationState {
     // `FuncEnvironment::make_direct_func()`.
     // Stores both the function reference and the number of WebAssembly arguments
     functions: HashMap<FuncIndex, (ir::FuncRef, usize)>,
+
+    /// Explicit heap bounds checks that dominate the code being translated.
+    pub(crate) bounds_checks: BoundsChecks,
+}
+
+/// Explicit heap bounds checks that dominate the code being translated.
+///
+/// Wasm control flow is structured, so a check dominates everything after it
+/// up to the end of the innermost control frame around it: code after that
+/// `end` can be reached by a branch out of the frame from before the check.
+/// The checks are scoped to the control stack accordingly: leaving a frame, or
+/// moving from the consequent of an `if` to its alternative, forgets the checks
+/// emitted in it. Memories never shrink, so nothing in between, such as a
+/// `memory.grow` or a call, can make a dominating check stale.
+#[derive(Default)]
+pub(crate) struct BoundsChecks {
+    // Map from a heap's bound and an index value to the largest
+    // `offset + access_size` that has been checked for them.
+    checked: HashMap<(ir::GlobalValue, Value), u64>,
+
+    // The entries of `checked` that were replaced, along with their previous
+    // values, to restore them when leaving the scope that replaced them.
+    undo: Vec<((ir::GlobalValue, Value), Option<u64>)>,
+
+    // The length of `undo` when each scope on the control stack was entered.
+    scopes: Vec<usize>,
+
+    /// The number of explicit bounds checks that were elided because a
+    /// dominating one covered them.
+    pub(crate) elided: usize,
+}
+
+impl BoundsChecks {
+    /// Record that an access to `index` on the heap bounded by `bound`, with
+    /// the given `offset + access_size`, is checked from now on in the
+    /// current scope, unless a dominating check already covers it. Returns
+    /// `true` if the access's check can be elided because of such a check.
+    pub(crate) fn record_or_elide(
+        &mut self,
+        bound: ir::GlobalValue,
+        index: Value,
+        offset_and_size: u64,
+    ) -> bool {
+        let key = (bound, index);
+        match self.checked.get(&key) {
+            Some(&checked) if offset_and_size <= checked => {
+                self.elided += 1;
+                true
+            }
+            previous => {
+                let previous = previous.copied();
+                self.undo.push((key, previous));
+                self.checked.insert(key, offset_and_size);
+                false
+            }
+        }
+    }
+
+    fn enter_scope(&mut self) {
+        self.scopes.push(self.undo.len());
+    }
+
+    /// Forget the checks emitted since the innermost scope was entered, and
+    /// leave that scope.
+    pub(crate) fn exit_scope(&mut self) {
+        let start = self.scopes.pop().unwrap();
+        for (key, previous) in self.undo.drain(start..).rev() {
+            match previous {
+                Some(checked) => self.checked.insert(key, checked),
+                None => self.checked.remove(&key),
+            };
+        }
+    }
+
+    /// Forget the checks emitted since the innermost scope was entered, but
+    /// stay in that scope. This is used when moving to the alternative of an
+    /// `if`, which its consequent doesn't dominate.
+    pub(crate) fn reset_scope(&mut self) {
+        self.exit_scope();
+        self.enter_scope();
+    }
+
+    fn clear(&mut self) {
+        self.checked.clear();
+        self.undo.clear();
+        self.scopes.clear();
+        self.elided = 0;
+    }
 }
 
 // Public methods that are exposed to non- API consumers.
@@ -262,6 +350,7 @@ impl FuncTranslationState {
             memory_to_heap: HashMap::new(),
             signatures: HashMap::new(),
             functions: HashMap::new(),
+            bounds_checks: BoundsChecks::default(),
         }
     }
 
@@ -273,6 +362,7 @@ impl FuncTranslationState {
         self.memory_to_heap.clear();
         self.signatures.clear();
         self.functions.clear();
+        self.bounds_checks.clear();
     }
 
     /// Initialize the state for compiling a function with the given signature.
@@ -398,6 +488,7 @@ impl FuncTranslationState {
         num_result_types: usize,
     ) {
         debug_assert!(num_param_types <= self.stack.len());
+        self.bounds_checks.enter_scope();
         self.control_stack.push(ControlStackFrame::Block {
             destination: following_code,
             original_stack_size: self.stack.len() - num_param_types,
@@ -416,6 +507,7 @@ impl FuncTranslationState {
         num_result_types: usize,
     ) {
         debug_assert!(num_param_types <= self.stack.len());
+        self.bounds_checks.enter_scope();
         self.control_stack.push(ControlStackFrame::Loop {
             header,
             destination: following_code,
@@ -447,6 +539,7 @@ impl FuncTranslationState {
             self.stack.push(val);
         }
 
+        self.bounds_checks.enter_scope();
         self.control_stack.push(ControlStackFrame::If {
             destination,
             else_data,
@@ -379,11 +379,25 @@ impl Engine {
             | "regalloc_algorithm"
             | "is_pic"
             | "bb_padding_log2_minus_one"
+            | "loop_align_log2"
+            | "max_insts_log2"
+            | "max_blocks_log2"
+            | "max_values_log2"
+            | "max_params_log2"
             | "machine_code_cfg_info"
             | "tls_model" // wasmtime doesn't use tls right now
             | "stack_switch_model" // wasmtime doesn't use stack switching right now
             | "opt_level" // opt level doesn't change semantics
             | "enable_alias_analysis" // alias analysis-based opts don't change semantics
+            | "enable_simplify_cfg" // optimizations don't change semantics
+            | "enable_if_conversion" // optimizations don't change semantics
+            | "enable_tail_duplication" // optimizations don't change semantics
+            | "enable_div_const_folding" // optimizations don't change semantics
+            | "enable_licm_sinking" // optimizations don't change semantics
+            | "enable_code_sinking" // optimizations don't change semantics
+            | "enable_load_merging" // optimizations don't change semantics
+            | "enable_instruction_scheduling" // optimizations don't change semantics
+            | "enable_cold_block_outlining" // optimizations don't change semantics
             | "probestack_size_log2" // probestack above asserted disabled
             | "regalloc" // shouldn't change semantics
             | "enable_incremental_compilation_cache_checks" // shouldn't change semantics
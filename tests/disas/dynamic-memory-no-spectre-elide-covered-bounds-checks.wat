@@ -0,0 +1,236 @@
+;;! target = "x86_64"
+;;! flags = [
+;;!   "-Ccranelift-enable-heap-access-spectre-mitigation=false",
+;;!   "-Ostatic-memory-maximum-size=0",
+;;!   "-Odynamic-memory-guard-size=0xffff",
+;;! ]
+
+(module
+  (memory (export "memory") 0)
+
+  (func (export "loads") (param i32) (result i32 i32 i32)
+    ;; Checks the widest range.
+    local.get 0
+    i32.load offset=8
+    ;; Same index and a narrower range, so no bounds checks are needed.
+    local.get 0
+    i32.load offset=0
+    local.get 0
+    i32.load offset=4
+  )
+
+  ;; The second access uses a different index, so it needs its own bounds
+  ;; check.
+  (func (export "different_indices") (param i32 i32) (result i32 i32)
+    local.get 0
+    i32.load offset=8
+    local.get 1
+    i32.load offset=0
+  )
+
+  ;; The first check dominates the accesses in both arms of the `if` and after
+  ;; it, so none of them need a bounds check.
+  (func (export "dominated") (param i32 i32) (result i32)
+    local.get 0
+    i32.load offset=8
+    drop
+    local.get 1
+    if (result i32)
+      local.get 0
+      i32.load offset=4
+    else
+      local.get 0
+      i32.load offset=0
+    end
+    local.get 0
+    i32.load offset=8
+    i32.add
+  )
+
+  ;; A check in the consequent of an `if` dominates neither its alternative
+  ;; nor the code after the `if`, so those accesses need their own checks.
+  (func (export "not_dominated") (param i32 i32) (result i32)
+    local.get 1
+    if (result i32)
+      local.get 0
+      i32.load offset=8
+    else
+      local.get 0
+      i32.load offset=4
+    end
+    local.get 0
+    i32.load offset=0
+    i32.add
+  )
+)
+
+;; function u0:0(i64 vmctx, i64, i32) -> i32, i32, i32 tail {
+;;     gv0 = vmctx
+;;     gv1 = load.i64 notrap aligned readonly gv0+8
+;;     gv2 = load.i64 notrap aligned gv1+16
+;;     gv3 = vmctx
+;;     gv4 = load.i64 notrap aligned gv3+104
+;;     gv5 = load.i64 notrap aligned checked gv3+96
+;;     stack_limit = gv2
+;;
+;;                                 block0(v0: i64, v1: i64, v2: i32):
+;; @0076                               v6 = uextend.i64 v2
+;; @0076                               v7 = global_value.i64 gv4
+;; @0076                               v8 = icmp ugt v6, v7
+;; @0076                               trapnz v8, heap_oob
+;; @0076                               v9 = global_value.i64 gv5
+;; @0076                               v10 = iadd v9, v6
+;; @0076                               v11 = iconst.i64 8
+;; @0076                               v12 = iadd v10, v11  ; v11 = 8
+;; @0076                               v13 = load.i32 little heap v12
+;; @007b                               v14 = uextend.i64 v2
+;; @007b                               v15 = global_value.i64 gv5
+;; @007b                               v16 = iadd v15, v14
+;; @007b                               v17 = load.i32 little heap v16
+;; @0080                               v18 = uextend.i64 v2
+;; @0080                               v19 = global_value.i64 gv5
+;; @0080                               v20 = iadd v19, v18
+;; @0080                               v21 = iconst.i64 4
+;; @0080                               v22 = iadd v20, v21  ; v21 = 4
+;; @0080                               v23 = load.i32 little heap v22
+;; @0083                               jump block1(v13, v17, v23)
+;;
+;;                                 block1(v3: i32, v4: i32, v5: i32):
+;; @0083                               return v3, v4, v5
+;; }
+;;
+;; function u0:1(i64 vmctx, i64, i32, i32) -> i32, i32 tail {
+;;     gv0 = vmctx
+;;     gv1 = load.i64 notrap aligned readonly gv0+8
+;;     gv2 = load.i64 notrap aligned gv1+16
+;;     gv3 = vmctx
+;;     gv4 = load.i64 notrap aligned gv3+104
+;;     gv5 = load.i64 notrap aligned checked gv3+96
+;;     stack_limit = gv2
+;;
+;;                                 block0(v0: i64, v1: i64, v2: i32, v3: i32):
+;; @0088                               v6 = uextend.i64 v2
+;; @0088                               v7 = global_value.i64 gv4
+;; @0088                               v8 = icmp ugt v6, v7
+;; @0088                               trapnz v8, heap_oob
+;; @0088                               v9 = global_value.i64 gv5
+;; @0088                               v10 = iadd v9, v6
+;; @0088                               v11 = iconst.i64 8
+;; @0088                               v12 = iadd v10, v11  ; v11 = 8
+;; @0088                               v13 = load.i32 little heap v12
+;; @008d                               v14 = uextend.i64 v3
+;; @008d                               v15 = global_value.i64 gv4
+;; @008d                               v16 = icmp ugt v14, v15
+;; @008d                               trapnz v16, heap_oob
+;; @008d                               v17 = global_value.i64 gv5
+;; @008d                               v18 = iadd v17, v14
+;; @008d                               v19 = load.i32 little heap v18
+;; @0090                               jump block1(v13, v19)
+;;
+;;                                 block1(v4: i32, v5: i32):
+;; @0090                               return v4, v5
+;; }
+;;
+;; function u0:2(i64 vmctx, i64, i32, i32) -> i32 tail {
+;;     gv0 = vmctx
+;;     gv1 = load.i64 notrap aligned readonly gv0+8
+;;     gv2 = load.i64 notrap aligned gv1+16
+;;     gv3 = vmctx
+;;     gv4 = load.i64 notrap aligned gv3+104
+;;     gv5 = load.i64 notrap aligned checked gv3+96
+;;     stack_limit = gv2
+;;
+;;                                 block0(v0: i64, v1: i64, v2: i32, v3: i32):
+;;                                     v24 -> v2
+;; @0095                               v5 = uextend.i64 v2
+;; @0095                               v6 = global_value.i64 gv4
+;; @0095                               v7 = icmp ugt v5, v6
+;; @0095                               trapnz v7, heap_oob
+;; @0095                               v8 = global_value.i64 gv5
+;; @0095                               v9 = iadd v8, v5
+;; @0095                               v10 = iconst.i64 8
+;; @0095                               v11 = iadd v9, v10  ; v10 = 8
+;; @0095                               v12 = load.i32 little heap v11
+;; @009b                               brif v3, block2, block4
+;;
+;;                                 block2:
+;; @009f                               v14 = uextend.i64 v2
+;; @009f                               v15 = global_value.i64 gv5
+;; @009f                               v16 = iadd v15, v14
+;; @009f                               v17 = iconst.i64 4
+;; @009f                               v18 = iadd v16, v17  ; v17 = 4
+;; @009f                               v19 = load.i32 little heap v18
+;; @00a2                               jump block3(v19)
+;;
+;;                                 block4:
+;; @00a5                               v20 = uextend.i64 v2
+;; @00a5                               v21 = global_value.i64 gv5
+;; @00a5                               v22 = iadd v21, v20
+;; @00a5                               v23 = load.i32 little heap v22
+;; @00a8                               jump block3(v23)
+;;
+;;                                 block3(v13: i32):
+;; @00ab                               v25 = uextend.i64 v2
+;; @00ab                               v26 = global_value.i64 gv5
+;; @00ab                               v27 = iadd v26, v25
+;; @00ab                               v28 = iconst.i64 8
+;; @00ab                               v29 = iadd v27, v28  ; v28 = 8
+;; @00ab                               v30 = load.i32 little heap v29
+;; @00ae                               v31 = iadd v13, v30
+;; @00af                               jump block1(v31)
+;;
+;;                                 block1(v4: i32):
+;; @00af                               return v4
+;; }
+;;
+;; function u0:3(i64 vmctx, i64, i32, i32) -> i32 tail {
+;;     gv0 = vmctx
+;;     gv1 = load.i64 notrap aligned readonly gv0+8
+;;     gv2 = load.i64 notrap aligned gv1+16
+;;     gv3 = vmctx
+;;     gv4 = load.i64 notrap aligned gv3+104
+;;     gv5 = load.i64 notrap aligned checked gv3+96
+;;     stack_limit = gv2
+;;
+;;                                 block0(v0: i64, v1: i64, v2: i32, v3: i32):
+;;                                     v22 -> v2
+;; @00b4                               brif v3, block2, block4
+;;
+;;                                 block2:
+;; @00b8                               v6 = uextend.i64 v2
+;; @00b8                               v7 = global_value.i64 gv4
+;; @00b8                               v8 = icmp ugt v6, v7
+;; @00b8                               trapnz v8, heap_oob
+;; @00b8                               v9 = global_value.i64 gv5
+;; @00b8                               v10 = iadd v9, v6
+;; @00b8                               v11 = iconst.i64 8
+;; @00b8                               v12 = iadd v10, v11  ; v11 = 8
+;; @00b8                               v13 = load.i32 little heap v12
+;; @00bb                               jump block3(v13)
+;;
+;;                                 block4:
+;; @00be                               v14 = uextend.i64 v2
+;; @00be                               v15 = global_value.i64 gv4
+;; @00be                               v16 = icmp ugt v14, v15
+;; @00be                               trapnz v16, heap_oob
+;; @00be                               v17 = global_value.i64 gv5
+;; @00be                               v18 = iadd v17, v14
+;; @00be                               v19 = iconst.i64 4
+;; @00be                               v20 = iadd v18, v19  ; v19 = 4
+;; @00be                               v21 = load.i32 little heap v20
+;; @00c1                               jump block3(v21)
+;;
+;;                                 block3(v5: i32):
+;; @00c4                               v23 = uextend.i64 v2
+;; @00c4                               v24 = global_value.i64 gv4
+;; @00c4                               v25 = icmp ugt v23, v24
+;; @00c4                               trapnz v25, heap_oob
+;; @00c4                               v26 = global_value.i64 gv5
+;; @00c4                               v27 = iadd v26, v23
+;; @00c4                               v28 = load.i32 little heap v27
+;; @00c7                               v29 = iadd v5, v28
+;; @00c8                               jump block1(v29)
+;;
+;;                                 block1(v4: i32):
+;; @00c8                               return v4
+;; }
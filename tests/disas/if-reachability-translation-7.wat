@@ -0,0 +1,48 @@
+;;! target = "x86_64"
+
+;; Reachable `if` head, a consequent that returns, and a large alternative that
+;; is dead after its first instruction means that no blocks are left behind for
+;; the dead code, and that the following block is unreachable.
+
+(module
+  (func (param i32) (result i32)
+    local.get 0
+    if
+      i32.const 1
+      return
+    else
+      unreachable
+      local.get 0
+      if (result i32)
+        i32.const 2
+      else
+        local.get 0
+        i32.const 3
+        i32.add
+      end
+      drop
+      block
+        loop
+          local.get 0
+          br_table 0 1 0
+        end
+      end
+    end
+    i32.const 0))
+
+;; function u0:0(i64 vmctx, i64, i32) -> i32 tail {
+;;     gv0 = vmctx
+;;     gv1 = load.i64 notrap aligned readonly gv0+8
+;;     gv2 = load.i64 notrap aligned gv1+16
+;;     stack_limit = gv2
+;;
+;;                                 block0(v0: i64, v1: i64, v2: i32):
+;; @001b                               brif v2, block2, block4
+;;
+;;                                 block2:
+;; @001d                               v4 = iconst.i32 1
+;; @001f                               return v4  ; v4 = 1
+;;
+;;                                 block4:
+;; @0021                               trap user11
+;; }